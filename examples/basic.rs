@@ -72,10 +72,11 @@ fn main() -> Result<()> {
     log("Listening for hotkeys... Press Ctrl+C to exit.");
 
     // Listen for hotkey events
-    while let Ok(event) = manager.recv() {
+    for event in manager.events() {
         let state_str = match event.state {
             HotkeyState::Pressed => "PRESSED",
             HotkeyState::Released => "RELEASED",
+            HotkeyState::AllReleased => "ALL RELEASED",
         };
         if let Some(hotkey) = manager.get_hotkey(event.id) {
             log(&format!("[{}] {} (id: {:?})", state_str, hotkey, event.id));
@@ -0,0 +1,44 @@
+//! Example: Rescue an app whose hotkeys block keys they shouldn't
+//!
+//! Suspends every hotkey of a manager built with `rescue_event(true)` (the
+//! `rescue` feature) and has it write its state to a dump file in the temp
+//! directory. Can be shipped as a small standalone tool next to an app.
+//!
+//! Run with: cargo run --example rescue -- <pid>
+//!
+//! On Unix, send the manager's rescue signal instead, e.g. `kill -USR2 <pid>`.
+
+#[cfg(windows)]
+fn main() {
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{OpenEventW, SetEvent, EVENT_MODIFY_STATE};
+
+    let Some(pid) = std::env::args().nth(1).and_then(|pid| pid.parse::<u32>().ok()) else {
+        eprintln!("Usage: rescue <pid>");
+        std::process::exit(2);
+    };
+
+    let name = HSTRING::from(format!("HandyKeysRescue-{pid}"));
+    let event = match unsafe { OpenEventW(EVENT_MODIFY_STATE, false, &name) } {
+        Ok(event) => event,
+        Err(e) => {
+            eprintln!("Process {pid} has no rescue event ({e}). Is it built with rescue_event(true)?");
+            std::process::exit(1);
+        }
+    };
+    let result = unsafe { SetEvent(event) };
+    let _ = unsafe { CloseHandle(event) };
+    match result {
+        Ok(()) => println!("Rescue requested; see handy-keys-rescue-{pid}.txt in the temp directory"),
+        Err(e) => {
+            eprintln!("Failed to signal the rescue event: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("On Unix, send the manager's rescue signal instead, e.g. `kill -USR2 <pid>`");
+}
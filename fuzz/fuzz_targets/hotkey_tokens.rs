@@ -0,0 +1,53 @@
+//! Parse hotkey strings assembled from real modifier and key names
+//!
+//! Random bytes rarely spell "ctrl" or "keypad+", so this target builds inputs
+//! from the parser's vocabulary, mixed with separators, whitespace and raw
+//! text, to reach the interesting cases quickly.
+//!
+//! Run with: cargo fuzz run hotkey_tokens
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use handy_keys::Hotkey;
+use libfuzzer_sys::fuzz_target;
+
+const MODIFIERS: &[&str] = &[
+    "cmd", "command", "meta", "super", "win", "windows", "shift", "ctrl", "control", "opt",
+    "option", "alt", "fn", "function",
+];
+
+const KEYS: &[&str] = &[
+    "a", "K", "0", "num9", "f1", "F20", "space", " ", "enter", "esc", "backspace", "del",
+    "pageup", "left", "DownArrow", "-", "=", "[", "]", "\\", ";", "'", ",", ".", "/", "`",
+    "keypad0", "keypad.", "keypad*", "keypad+", "keypad/", "keypad-", "keypad=", "keypadenter",
+    "keypadclear", "capslock", "numlock", "yen", "§", "kana", "mouse1", "mousex2", "forward", "xbutton1",
+];
+
+#[derive(Arbitrary, Debug)]
+enum Token {
+    Modifier(u8),
+    Key(u8),
+    Plus,
+    Whitespace,
+    Raw(String),
+}
+
+fuzz_target!(|tokens: Vec<Token>| {
+    let mut input = String::new();
+    for token in &tokens {
+        match token {
+            Token::Modifier(i) => input.push_str(MODIFIERS[*i as usize % MODIFIERS.len()]),
+            Token::Key(i) => input.push_str(KEYS[*i as usize % KEYS.len()]),
+            Token::Plus => input.push('+'),
+            Token::Whitespace => input.push(' '),
+            Token::Raw(s) => input.push_str(s),
+        }
+    }
+
+    if let Ok(hotkey) = input.parse::<Hotkey>() {
+        let _ = hotkey.to_string();
+        let _ = hotkey.to_symbol_string();
+        let _ = hotkey.to_accelerator_string();
+    }
+});
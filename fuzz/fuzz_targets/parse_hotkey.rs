@@ -0,0 +1,25 @@
+//! Parse arbitrary strings as hotkeys, keys and modifiers
+//!
+//! Run with: cargo fuzz run parse_hotkey corpus/parse_hotkey seeds/parse_hotkey
+
+#![no_main]
+
+use handy_keys::{Hotkey, Key, Modifiers};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &str| {
+    if let Ok(hotkey) = data.parse::<Hotkey>() {
+        // Everything derived from a parsed hotkey must be infallible
+        let _ = hotkey.to_string();
+        let _ = hotkey.to_lowercase_string();
+        let _ = hotkey.to_handy_string();
+        let _ = hotkey.to_symbol_string();
+        let _ = hotkey.to_accelerator_string();
+        let _ = hotkey.to_key_equivalent();
+        let _ = hotkey.to_physical();
+        assert_eq!(Hotkey::from_slug(&hotkey.to_slug()).unwrap(), hotkey);
+    }
+    let _ = Hotkey::from_slug(data);
+    let _ = data.parse::<Key>();
+    let _ = data.parse::<Modifiers>();
+});
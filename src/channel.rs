@@ -0,0 +1,296 @@
+//! Event channel that can also be awaited
+//!
+//! A thin wrapper around `std::sync::mpsc` whose receiver can be polled from
+//! async code. Every send wakes the task last registered by
+//! [`Receiver::poll_recv`], so no runtime or helper thread is needed. Sends
+//! also set the receiver's [`Readiness`], once it has one.
+//!
+//! A sender given an [`EventSink`](crate::EventSink) delivers to it instead,
+//! and its receiver never gets anything.
+
+use std::sync::mpsc::{self, RecvError, RecvTimeoutError, SendError, TryRecvError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use crate::platform::readiness::Readiness;
+use crate::sink::SharedSink;
+
+/// The task waiting for the next event, if any
+type WakerSlot = Arc<Mutex<Option<Waker>>>;
+
+/// Created the first time the receiver is asked for it
+type ReadinessSlot = Arc<OnceLock<Readiness>>;
+
+/// Create an event channel
+pub(crate) fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = mpsc::channel();
+    let waker = WakerSlot::default();
+    let readiness = ReadinessSlot::default();
+    (
+        Sender {
+            inner: Some(tx),
+            waker: Arc::clone(&waker),
+            readiness: Arc::clone(&readiness),
+            sink: None,
+        },
+        Receiver {
+            inner: rx,
+            waker,
+            readiness,
+        },
+    )
+}
+
+/// Sending half of an event channel
+pub(crate) struct Sender<T> {
+    /// Only `None` while dropping
+    inner: Option<mpsc::Sender<T>>,
+    waker: WakerSlot,
+    readiness: ReadinessSlot,
+    /// Where events go instead of the receiver (if anywhere)
+    sink: Option<SharedSink<T>>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            waker: Arc::clone(&self.waker),
+            readiness: Arc::clone(&self.readiness),
+            sink: self.sink.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Send to `sink` instead of the receiver, if given
+    pub fn with_sink(mut self, sink: Option<SharedSink<T>>) -> Self {
+        self.sink = sink;
+        self
+    }
+
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if let Some(sink) = &self.sink {
+            return sink.send(value).map_err(SendError);
+        }
+        match &self.inner {
+            Some(inner) => inner.send(value)?,
+            None => return Err(SendError(value)),
+        }
+        self.wake();
+        Ok(())
+    }
+
+    fn wake(&self) {
+        if let Some(readiness) = self.readiness.get() {
+            readiness.set();
+        }
+        if let Some(waker) = self.waker.lock().ok().and_then(|mut slot| slot.take()) {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // The last sender going away disconnects the channel, which a waiting
+        // task must see, so wake it only once the channel is updated
+        drop(self.inner.take());
+        self.wake();
+    }
+}
+
+/// Receiving half of an event channel
+pub(crate) struct Receiver<T> {
+    inner: mpsc::Receiver<T>,
+    waker: WakerSlot,
+    readiness: ReadinessSlot,
+}
+
+impl<T> Receiver<T> {
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.inner.recv()
+    }
+
+    /// Receive an event if one is queued, clearing the readiness if none is
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.inner.try_recv() {
+            Err(TryRecvError::Empty) => self.clear_readiness(),
+            result => result,
+        }
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.inner.recv_timeout(timeout)
+    }
+
+    pub fn try_iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.try_recv().ok())
+    }
+
+    /// The readiness set whenever an event is sent, creating it if needed
+    ///
+    /// It stays set until [`try_recv`](Self::try_recv) finds no event, so
+    /// waiting on it never misses one.
+    pub fn readiness(&self) -> crate::error::Result<&Readiness> {
+        if let Some(readiness) = self.readiness.get() {
+            return Ok(readiness);
+        }
+        let _ = self.readiness.set(Readiness::new()?);
+        let readiness = self.readiness.get().expect("readiness was just set");
+        // Events sent before it existed are still queued
+        readiness.set();
+        Ok(readiness)
+    }
+
+    /// Set the readiness if it exists, e.g. for an event taken from the
+    /// channel but kept for later
+    pub fn mark_ready(&self) {
+        if let Some(readiness) = self.readiness.get() {
+            readiness.set();
+        }
+    }
+
+    fn clear_readiness(&self) -> Result<T, TryRecvError> {
+        let Some(readiness) = self.readiness.get() else {
+            return Err(TryRecvError::Empty);
+        };
+        readiness.clear();
+        // An event sent just before clearing must leave it set
+        let result = self.inner.try_recv();
+        if result.is_ok() {
+            readiness.set();
+        }
+        result
+    }
+
+    /// Poll for the next event, `None` once every sender is gone
+    ///
+    /// Only the task that polled last is woken, so events should be awaited
+    /// from one task at a time.
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Some(poll) = self.poll_ready() {
+            return poll;
+        }
+        if let Ok(mut slot) = self.waker.lock() {
+            *slot = Some(cx.waker().clone());
+        }
+        // An event sent before the waker was stored would otherwise be missed
+        self.poll_ready().unwrap_or(Poll::Pending)
+    }
+
+    fn poll_ready(&self) -> Option<Poll<Option<T>>> {
+        match self.try_recv() {
+            Ok(value) => Some(Poll::Ready(Some(value))),
+            Err(TryRecvError::Disconnected) => Some(Poll::Ready(None)),
+            Err(TryRecvError::Empty) => None,
+        }
+    }
+}
+
+/// Stream of the events of a [`Receiver`]
+#[cfg(feature = "async")]
+pub(crate) struct EventStream<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+#[cfg(feature = "async")]
+impl<'a, T> EventStream<'a, T> {
+    pub fn new(receiver: &'a Receiver<T>) -> Self {
+        Self { receiver }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> futures_core::Stream for EventStream<'_, T> {
+    type Item = T;
+
+    fn poll_next(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn sink_replaces_receiver() {
+        let (sink_tx, sink_rx) = std::sync::mpsc::channel();
+        let (tx, rx) = channel();
+        let tx = tx.with_sink(Some(SharedSink::new(sink_tx)));
+
+        tx.send(1).unwrap();
+        assert_eq!(sink_rx.try_recv(), Ok(1));
+        assert!(rx.try_recv().is_err());
+
+        drop(sink_rx);
+        assert!(tx.send(2).is_err());
+    }
+
+    #[test]
+    fn send_wakes_pending_receiver() {
+        let (tx, rx) = channel();
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(rx.poll_recv(&mut cx), Poll::Pending);
+        tx.send(1).unwrap();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(rx.poll_recv(&mut cx), Poll::Ready(Some(1)));
+
+        // Nobody is waiting, so nothing is woken
+        tx.send(2).unwrap();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(rx.poll_recv(&mut cx), Poll::Ready(Some(2)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn readiness_set_until_queue_empty() {
+        let is_ready = |rx: &Receiver<u32>| rx.readiness().unwrap().is_set();
+        let (tx, rx) = channel();
+        tx.send(1).unwrap();
+        // Events sent before the readiness existed count too
+        assert!(is_ready(&rx));
+
+        tx.send(2).unwrap();
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert!(is_ready(&rx));
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert!(is_ready(&rx));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        assert!(!is_ready(&rx));
+
+        // Disconnecting makes it ready so waiters notice
+        drop(tx);
+        assert!(is_ready(&rx));
+    }
+
+    #[test]
+    fn dropping_senders_ends_the_stream() {
+        let (tx, rx) = channel::<u32>();
+        let counter = Arc::new(CountingWaker::default());
+        let waker = Waker::from(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(rx.poll_recv(&mut cx), Poll::Pending);
+        drop(tx);
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(rx.poll_recv(&mut cx), Poll::Ready(None));
+    }
+}
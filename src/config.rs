@@ -0,0 +1,205 @@
+//! Hotkey bindings loaded from a config file
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::manager::HotkeyManager;
+use crate::types::{current_parser, Hotkey, HotkeyId, HotkeyParser, Modifiers};
+
+/// Names of the modifier that is Cmd on macOS and Ctrl elsewhere
+const CMD_OR_CTRL: &[&str] = &["CmdOrCtrl", "cmd_or_ctrl", "CommandOrControl"];
+
+/// The hotkey of one action in a [`HotkeyConfig`]
+///
+/// Either a single hotkey string for every platform, or a table with one per
+/// platform and a `default` for the rest. An action with neither an entry
+/// for the current platform nor a default isn't bound there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Binding {
+    All(String),
+    PerPlatform {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        default: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        macos: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        windows: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        linux: Option<String>,
+    },
+}
+
+impl Binding {
+    /// The hotkey string that applies on this platform, if any
+    pub fn for_current_platform(&self) -> Option<&str> {
+        match self {
+            Binding::All(hotkey) => Some(hotkey),
+            Binding::PerPlatform {
+                default,
+                macos,
+                windows,
+                linux,
+            } => {
+                let platform = if cfg!(target_os = "macos") {
+                    macos
+                } else if cfg!(target_os = "windows") {
+                    windows
+                } else if cfg!(target_os = "linux") {
+                    linux
+                } else {
+                    &None
+                };
+                platform.as_deref().or(default.as_deref())
+            }
+        }
+    }
+}
+
+/// Action names mapped to hotkeys, as stored in an app's config file
+///
+/// Deserializes from a map of action name to [`Binding`] in any serde format,
+/// such as TOML or JSON. Hotkey strings are parsed like `str::parse` does
+/// (with the [default parser](HotkeyParser::set_default), if one is set),
+/// and also accept `CmdOrCtrl` for Cmd on macOS and Ctrl elsewhere.
+///
+/// ```toml
+/// toggle_recording = "CmdOrCtrl+Shift+R"
+/// open_palette = { default = "Ctrl+P", macos = "Cmd+P" }
+/// push_to_talk = { windows = "F13" }
+/// ```
+///
+/// ```no_run
+/// use handy_keys::{HotkeyConfig, HotkeyManager};
+///
+/// let config: HotkeyConfig = serde_json::from_str(r#"{
+///     "toggle_recording": "CmdOrCtrl+Shift+R",
+///     "open_palette": { "default": "Ctrl+P", "macos": "Cmd+P" }
+/// }"#)?;
+///
+/// let manager = HotkeyManager::new()?;
+/// let ids = config.register(&manager)?;
+/// for event in manager.events() {
+///     if event.id == ids["toggle_recording"] {
+///         println!("Toggling recording");
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HotkeyConfig {
+    pub bindings: BTreeMap<String, Binding>,
+}
+
+impl HotkeyConfig {
+    /// Create an empty config
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the hotkey of every action bound on this platform
+    ///
+    /// Returns [`Error::InvalidBinding`] naming the action for a hotkey that
+    /// can't be parsed, or for two actions bound to the same hotkey.
+    pub fn hotkeys(&self) -> Result<BTreeMap<String, Hotkey>> {
+        let cmd_or_ctrl = if cfg!(target_os = "macos") { Modifiers::CMD } else { Modifiers::CTRL };
+        let parser = CMD_OR_CTRL
+            .iter()
+            .fold(current_parser(), |parser: HotkeyParser, name| parser.modifier_alias(name, cmd_or_ctrl));
+
+        let mut hotkeys: BTreeMap<String, Hotkey> = BTreeMap::new();
+        for (action, binding) in &self.bindings {
+            let Some(s) = binding.for_current_platform() else {
+                continue;
+            };
+            let invalid = |e| Error::InvalidBinding(action.clone(), Box::new(e));
+            let hotkey = parser.parse(s).map_err(invalid)?;
+            if let Some((other, _)) = hotkeys.iter().find(|(_, bound)| **bound == hotkey) {
+                return Err(invalid(Error::HotkeyAlreadyRegistered(format!(
+                    "{hotkey} (\"{other}\")"
+                ))));
+            }
+            hotkeys.insert(action.clone(), hotkey);
+        }
+        Ok(hotkeys)
+    }
+
+    /// Register every action bound on this platform, returning their IDs by
+    /// action name
+    ///
+    /// The config is checked as by [`hotkeys`](Self::hotkeys) before anything
+    /// is registered, and each hotkey is labeled with its action name (see
+    /// [`HotkeyManager::set_label`]). If one can't be registered, e.g.
+    /// because the manager already has its combo, the ones registered so far
+    /// are unregistered again and the error names the action.
+    pub fn register(&self, manager: &HotkeyManager) -> Result<HashMap<String, HotkeyId>> {
+        let mut ids: HashMap<String, HotkeyId> = HashMap::new();
+        for (action, hotkey) in self.hotkeys()? {
+            let registered = manager
+                .register(hotkey)
+                .and_then(|id| manager.set_label(id, action.as_str()).map(|()| id));
+            match registered {
+                Ok(id) => {
+                    ids.insert(action, id);
+                }
+                Err(e) => {
+                    for id in ids.into_values() {
+                        let _ = manager.unregister(id);
+                    }
+                    return Err(Error::InvalidBinding(action, Box::new(e)));
+                }
+            }
+        }
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::listener::Backend;
+
+    fn config(json: &str) -> HotkeyConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn per_platform_bindings() {
+        let config = config(
+            r#"{
+                "save": "CmdOrCtrl+S",
+                "palette": { "default": "Ctrl+P", "macos": "Cmd+P" },
+                "talk": { "windows": "F13" }
+            }"#,
+        );
+        let hotkeys = config.hotkeys().unwrap();
+
+        let primary = if cfg!(target_os = "macos") { "Cmd" } else { "Ctrl" };
+        assert_eq!(hotkeys["save"], format!("{primary}+S").parse().unwrap());
+        assert_eq!(hotkeys["palette"], format!("{primary}+P").parse().unwrap());
+        assert_eq!(hotkeys.contains_key("talk"), cfg!(target_os = "windows"));
+    }
+
+    #[test]
+    fn invalid_bindings_name_the_action() {
+        let error = config(r#"{ "save": "Ctrl+Nope" }"#).hotkeys().unwrap_err();
+        assert!(matches!(&error, Error::InvalidBinding(action, _) if action == "save"), "{error}");
+
+        let error = config(r#"{ "a": "Ctrl+K", "b": "ctrl+k" }"#).hotkeys().unwrap_err();
+        assert!(error.to_string().contains("\"a\""), "{error}");
+    }
+
+    #[test]
+    fn register_is_all_or_nothing() {
+        let manager = HotkeyManager::builder().backend(Backend::Headless).build().unwrap();
+        let ids = config(r#"{ "a": "Ctrl+J", "b": "Ctrl+K" }"#).register(&manager).unwrap();
+        assert_eq!(manager.get_label(ids["a"]).as_deref(), Some("a"));
+
+        let error = config(r#"{ "c": "Ctrl+L", "d": "Ctrl+K" }"#).register(&manager).unwrap_err();
+        assert!(matches!(&error, Error::InvalidBinding(action, _) if action == "d"), "{error}");
+        assert_eq!(manager.hotkey_count(), 2);
+    }
+}
@@ -0,0 +1,94 @@
+//! "Click to record" hotkey field for egui (requires the `egui` feature)
+
+use std::time::Duration;
+
+use crate::listener::KeyboardListener;
+use crate::recorder::{Capture, CaptureStep};
+use crate::types::Hotkey;
+
+/// How often to poll the listener while recording
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// A button that records a hotkey when clicked
+///
+/// While recording, key events are captured with a [`KeyboardListener`] and
+/// removed from egui's input, so shortcuts being recorded don't also trigger
+/// the app's own egui shortcuts. Recording is cancelled by pressing Escape,
+/// clicking the field again or clicking elsewhere.
+///
+/// Keep the `HotkeyField` alongside the hotkey in your app state:
+///
+/// ```ignore
+/// let response = self.hotkey_field.ui(ui, &mut self.hotkey);
+/// if response.changed() {
+///     // self.hotkey was recorded
+/// }
+/// ```
+#[derive(Default)]
+pub struct HotkeyField {
+    recording: Option<(KeyboardListener, Capture)>,
+}
+
+impl HotkeyField {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` while the field is waiting for a hotkey
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Show the field, storing a recorded hotkey in `hotkey`
+    ///
+    /// The response is marked changed when a hotkey is recorded.
+    pub fn ui(&mut self, ui: &mut egui::Ui, hotkey: &mut Option<Hotkey>) -> egui::Response {
+        let text = if self.is_recording() {
+            "Press a shortcut…".to_string()
+        } else {
+            hotkey
+                .map(|h| h.to_symbol_string())
+                .unwrap_or_else(|| "Click to record".to_string())
+        };
+        let mut response = ui.add(egui::Button::new(text).selected(self.is_recording()));
+
+        if response.clicked() {
+            self.recording = match self.recording.take() {
+                Some(_) => None,
+                // If the listener can't start (e.g. no permission) the field
+                // stays idle
+                None => KeyboardListener::new()
+                    .ok()
+                    .map(|listener| (listener, Capture::new())),
+            };
+        } else if response.clicked_elsewhere() {
+            self.recording = None;
+        }
+
+        let Some((listener, capture)) = self.recording.as_mut() else {
+            return response;
+        };
+
+        ui.input_mut(|input| {
+            input
+                .events
+                .retain(|event| !matches!(event, egui::Event::Key { .. } | egui::Event::Text(_)))
+        });
+
+        while let Some(event) = listener.try_recv() {
+            match capture.process(&event) {
+                CaptureStep::Pending => continue,
+                CaptureStep::Done(recorded) => {
+                    *hotkey = Some(recorded);
+                    response.mark_changed();
+                }
+                CaptureStep::Cancelled => {}
+            }
+            self.recording = None;
+            return response;
+        }
+
+        ui.ctx().request_repaint_after(POLL_INTERVAL);
+        response
+    }
+}
@@ -0,0 +1,1702 @@
+//! Matching of key events against registered hotkeys
+//!
+//! [`MatchEngine`] turns a stream of [`KeyEvent`]s into [`HotkeyEvent`]s. It
+//! holds no OS resources; [`HotkeyManager`](crate::HotkeyManager) runs one on
+//! the events of its listener.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::platform::state::{Guard, TypingDetector};
+use crate::types::{
+    ConflictKind, Hotkey, HotkeyEvent, HotkeyId, HotkeySequence, HotkeyState, Key, KeyEvent,
+};
+
+/// Which hotkeys fire when several match the keys being typed
+///
+/// A modifier-only hotkey such as `Cmd+Shift` is pressed on the way to typing
+/// any hotkey that includes its modifiers, such as `Cmd+Shift+K`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Precedence {
+    /// Every matching hotkey fires, so typing `Cmd+Shift+K` presses
+    /// `Cmd+Shift` first
+    #[default]
+    AllMatch,
+    /// Only the most specific hotkey fires
+    ///
+    /// A modifier-only hotkey that is part of another registered hotkey waits
+    /// to see whether a key follows. If its modifiers are released without
+    /// one, it's pressed and released right away; otherwise it doesn't fire.
+    /// Such hotkeys therefore can't be held. Modifier-only hotkeys without a
+    /// more specific counterpart fire immediately as usual.
+    MostSpecificWins,
+}
+
+/// A hotkey's callback, see `HotkeyManager::register_with_callback`
+pub(crate) type Callback = Arc<Mutex<Box<dyn FnMut(HotkeyEvent) + Send>>>;
+
+/// Progress of a double-tap hotkey, see `HotkeyManager::register_double_tap`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DoubleTap {
+    /// Longest time between the two taps
+    pub(crate) window: Duration,
+    /// When the first tap went down, if one is waiting for a second
+    pub(crate) first_tap: Option<Instant>,
+    /// The combo currently held down, so its repeats aren't counted as taps
+    pub(crate) held: Option<Hotkey>,
+}
+
+/// Progress through a key sequence, see `HotkeyManager::register_sequence`
+#[derive(Debug, Clone)]
+pub(crate) struct SequenceProgress {
+    pub(crate) sequence: HotkeySequence,
+    /// Longest time between two chords
+    pub(crate) timeout: Duration,
+    /// How many chords have been typed so far
+    pub(crate) typed: usize,
+    /// When the last chord was typed
+    pub(crate) last_chord: Option<Instant>,
+    /// The chord currently held down, so its repeats aren't counted
+    pub(crate) held: Option<Hotkey>,
+}
+
+impl SequenceProgress {
+    pub(crate) fn new(sequence: HotkeySequence, timeout: Duration) -> Self {
+        Self {
+            sequence,
+            timeout,
+            typed: 0,
+            last_chord: None,
+            held: None,
+        }
+    }
+
+    /// The chord the sequence is waiting for, if it's in progress
+    fn awaited(&self) -> Option<Hotkey> {
+        (self.typed > 0).then(|| self.sequence.chords()[self.typed])
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.typed = 0;
+        self.last_chord = None;
+    }
+}
+
+/// Matches key events against hotkeys, without any OS listener
+///
+/// The matching behind [`HotkeyManager`](crate::HotkeyManager), for replaying
+/// recorded [`KeyEvent`]s, testing, or frontends with their own source of
+/// key events. Feed it events in order, and it returns the hotkey events they
+/// cause. It has no side effects and never looks at the clock unless asked
+/// to: pass each event's time to [`process_event_at`](Self::process_event_at)
+/// for results that only depend on the input.
+///
+/// # Examples
+/// ```
+/// use handy_keys::{HotkeyState, Key, KeyEvent, MatchEngine, Modifiers};
+///
+/// let mut engine = MatchEngine::new();
+/// let id = engine.register("Ctrl+K".parse()?)?;
+///
+/// let event = KeyEvent {
+///     modifiers: Modifiers::CTRL,
+///     key: Some(Key::K),
+///     is_key_down: true,
+///     changed_modifier: None,
+///     sides: Modifiers::empty(),
+///     hook: None,
+/// };
+/// let events = engine.process_event(&event);
+/// assert_eq!((events[0].id, events[0].state), (id, HotkeyState::Pressed));
+/// # Ok::<(), handy_keys::Error>(())
+/// ```
+pub struct MatchEngine {
+    /// The combos of each registered hotkey, the first being the main one
+    pub(crate) hotkeys: HashMap<HotkeyId, Vec<Hotkey>>,
+    pub(crate) next_id: u32,
+    /// Track which hotkeys are currently pressed, and through which combo
+    /// (with its index)
+    pub(crate) pressed_hotkeys: HashMap<HotkeyId, (usize, Hotkey)>,
+    /// Callbacks of hotkeys that have one, instead of the event channel
+    pub(crate) callbacks: HashMap<HotkeyId, Callback>,
+    /// Events waiting for the event loop to pass them to their callback
+    pub(crate) pending_callbacks: Vec<(HotkeyEvent, Callback)>,
+    /// While set, no hotkey is pressed or blocked, see `HotkeyManager::suspend`
+    pub(crate) suspended: bool,
+    pub(crate) precedence: Precedence,
+    /// Modifier-only hotkeys held back under [`Precedence::MostSpecificWins`]
+    /// or because they're exclusive, fired if their modifiers are released
+    /// before any other key down
+    pub(crate) pending_taps: HashMap<HotkeyId, (usize, Hotkey)>,
+    /// Combos set aside by `HotkeyManager::register_pending`, neither
+    /// matched nor blocked until committed
+    pub(crate) reserved: HashMap<HotkeyId, Vec<Hotkey>>,
+    /// Hotkeys that are matched but never blocked, see
+    /// `HotkeyManager::register_passthrough`
+    pub(crate) passthrough: HashSet<HotkeyId>,
+    /// Modifier-only hotkeys that don't fire if a key is typed while their
+    /// modifiers are held, see `HotkeyManager::register_exclusive`
+    pub(crate) exclusive: HashSet<HotkeyId>,
+    /// Hotkeys that only fire when tapped twice
+    pub(crate) double_taps: HashMap<HotkeyId, DoubleTap>,
+    /// Key sequences, registered in `hotkeys` under their first chord
+    pub(crate) sequences: HashMap<HotkeyId, SequenceProgress>,
+    /// Later chords of in-progress sequences that are being blocked
+    pub(crate) sequence_blocks: HashSet<Hotkey>,
+    /// See `HotkeyManagerBuilder::repair_lost_releases`
+    pub(crate) lost_release_gap: Option<Duration>,
+    /// When the key of each pressed hotkey last went down, repeats included
+    pub(crate) last_key_downs: HashMap<HotkeyId, Instant>,
+    /// Presses released by [`Self::release_lost`]
+    pub(crate) lost_releases: u64,
+    /// Hotkeys only matched while their guard allows, see
+    /// `HotkeyManager::register_guarded`
+    pub(crate) guards: HashMap<HotkeyId, Guard>,
+    /// Hotkeys that also match with extra modifiers held, see
+    /// `HotkeyManager::register_loose`
+    pub(crate) loose: HashSet<HotkeyId>,
+    /// Names of hotkeys for diagnostics, see `HotkeyManager::set_label`
+    pub(crate) labels: HashMap<HotkeyId, Arc<str>>,
+    /// See `HotkeyManagerBuilder::all_released_events`
+    pub(crate) report_all_released: bool,
+    /// Released hotkeys some of whose keys are still held, with the combo
+    /// (and its index) they were pressed as
+    pub(crate) releasing: HashMap<HotkeyId, (usize, Hotkey)>,
+    /// Keys currently down, not counting modifiers
+    pub(crate) held_keys: HashSet<Key>,
+    /// See `HotkeyManagerBuilder::suppress_while_typing`
+    pub(crate) typing: Option<TypingDetector>,
+}
+
+impl MatchEngine {
+    /// Create an engine with no hotkeys
+    pub fn new() -> Self {
+        Self {
+            hotkeys: HashMap::new(),
+            next_id: 0,
+            pressed_hotkeys: HashMap::new(),
+            callbacks: HashMap::new(),
+            pending_callbacks: Vec::new(),
+            suspended: false,
+            precedence: Precedence::default(),
+            pending_taps: HashMap::new(),
+            reserved: HashMap::new(),
+            passthrough: HashSet::new(),
+            exclusive: HashSet::new(),
+            double_taps: HashMap::new(),
+            sequences: HashMap::new(),
+            sequence_blocks: HashSet::new(),
+            lost_release_gap: None,
+            last_key_downs: HashMap::new(),
+            lost_releases: 0,
+            guards: HashMap::new(),
+            loose: HashSet::new(),
+            labels: HashMap::new(),
+            report_all_released: false,
+            releasing: HashMap::new(),
+            held_keys: HashSet::new(),
+            typing: None,
+        }
+    }
+
+    /// The combos of every hotkey that should be blocked
+    pub(crate) fn blocked_combos(&self) -> impl Iterator<Item = Hotkey> + '_ {
+        self.hotkeys
+            .iter()
+            .filter(|(id, _)| !self.passthrough.contains(id))
+            .flat_map(|(_, combos)| combos.iter().copied())
+    }
+
+    /// Fail if any of `combos` is already registered or reserved
+    pub(crate) fn check_available(&self, combos: &[Hotkey]) -> Result<()> {
+        for (id, existing) in self.hotkeys.iter().chain(&self.reserved) {
+            if let Some(hotkey) = combos.iter().find(|hotkey| existing.contains(hotkey)) {
+                return Err(Error::HotkeyAlreadyRegistered(self.describe(hotkey, *id)));
+            }
+        }
+        Ok(())
+    }
+
+    /// `combo` registered as `id`, with the hotkey's label if it has one
+    fn describe(&self, combo: impl std::fmt::Display, id: HotkeyId) -> String {
+        match self.labels.get(&id) {
+            Some(label) => format!("{combo} (id: {id:?}, \"{label}\")"),
+            None => format!("{combo} (id: {id:?})"),
+        }
+    }
+
+    /// Fail if `sequence` is already registered, or its first chord is
+    /// registered as a hotkey
+    ///
+    /// Sequences may share their first chord, like `Ctrl+K Ctrl+B` and
+    /// `Ctrl+K Ctrl+C`.
+    pub(crate) fn check_sequence_available(&self, sequence: &HotkeySequence) -> Result<()> {
+        for (id, existing) in self.hotkeys.iter().chain(&self.reserved) {
+            let taken = match self.sequences.get(id) {
+                Some(progress) => progress.sequence == *sequence,
+                None => existing.contains(&sequence.first()),
+            };
+            if taken {
+                return Err(Error::HotkeyAlreadyRegistered(self.describe(sequence, *id)));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn allocate_id(&mut self) -> HotkeyId {
+        let id = HotkeyId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// Whether the modifier-only `hotkey` must wait for its modifiers to be
+    /// released before it fires
+    fn holds_back(&self, id: HotkeyId, hotkey: &Hotkey) -> bool {
+        hotkey.key.is_none()
+            && (self.exclusive.contains(&id)
+                || (self.precedence == Precedence::MostSpecificWins
+                    && self.has_more_specific(id, hotkey)))
+    }
+
+    /// Whether another registered hotkey includes the modifier-only `hotkey`
+    fn has_more_specific(&self, id: HotkeyId, hotkey: &Hotkey) -> bool {
+        self.hotkeys
+            .iter()
+            .filter(|(&other, _)| other != id)
+            .flat_map(|(_, combos)| combos)
+            .any(|combo| hotkey.conflict_with(combo) == Some(ConflictKind::Prefix))
+    }
+
+    /// An event of hotkey `id`, with its label
+    fn event(&self, id: HotkeyId, state: HotkeyState, hotkey: Hotkey, alias: usize) -> HotkeyEvent {
+        HotkeyEvent {
+            id,
+            state,
+            hotkey,
+            alias,
+            label: self.labels.get(&id).cloned(),
+        }
+    }
+
+    /// Queue the events of hotkeys with a callback, returning the others
+    pub(crate) fn route(&mut self, events: Vec<HotkeyEvent>) -> Vec<HotkeyEvent> {
+        events
+            .into_iter()
+            .filter(|event| match self.callbacks.get(&event.id) {
+                Some(callback) => {
+                    self.pending_callbacks.push((event.clone(), Arc::clone(callback)));
+                    false
+                }
+                None => true,
+            })
+            .collect()
+    }
+
+    /// Register a hotkey, failing if it's already registered
+    pub fn register(&mut self, hotkey: Hotkey) -> Result<HotkeyId> {
+        self.check_available(&[hotkey])?;
+        let id = self.allocate_id();
+        self.hotkeys.insert(id, vec![hotkey]);
+        Ok(id)
+    }
+
+    /// Stop matching a hotkey, returning the Released event that ends its
+    /// press if it was pressed
+    pub fn unregister(&mut self, id: HotkeyId) -> Result<Option<HotkeyEvent>> {
+        self.remove(id)
+            .map(|(_, release)| release)
+            .ok_or(Error::HotkeyNotFound(id))
+    }
+
+    /// The hotkey registered as `id`
+    pub fn hotkey(&self, id: HotkeyId) -> Option<Hotkey> {
+        self.hotkeys.get(&id).and_then(|combos| combos.first()).copied()
+    }
+
+    /// Set which hotkeys fire when several match, see [`Precedence`]
+    pub fn set_precedence(&mut self, precedence: Precedence) {
+        self.precedence = precedence;
+    }
+
+    /// Process a key event that happened just now and return any matching
+    /// hotkey events
+    pub fn process_event(&mut self, event: &KeyEvent) -> Vec<HotkeyEvent> {
+        self.process_event_at(event, Instant::now())
+    }
+
+    /// Process a key event that happened at `now`
+    ///
+    /// Events must be passed in order, with times that don't go backwards.
+    /// Only double taps and sequences depend on the times.
+    pub fn process_event_at(&mut self, event: &KeyEvent, now: Instant) -> Vec<HotkeyEvent> {
+        let mut results = Vec::new();
+        // Typing is judged by the keystrokes before this one
+        let typing = self.typing.as_ref().is_some_and(|typing| typing.is_typing(now));
+        if let Some(detector) = &mut self.typing {
+            detector.record(event, now);
+        }
+        if let Some(key) = event.key {
+            if event.is_key_down {
+                self.held_keys.insert(key);
+            } else {
+                self.held_keys.remove(&key);
+            }
+        }
+
+        // Release pressed hotkeys whose combo this event breaks. This runs for
+        // every event, since a missed key up can leave the modifiers of a key
+        // event out of sync with the combo.
+        let to_release: Vec<HotkeyId> = self
+            .pressed_hotkeys
+            .iter()
+            .filter(|(_, (_, hotkey))| breaks_combo(hotkey, event))
+            .map(|(&id, _)| id)
+            .collect();
+
+        for id in to_release {
+            if let Some((alias, hotkey)) = self.pressed_hotkeys.remove(&id) {
+                results.push(self.event(id, HotkeyState::Released, hotkey, alias));
+            }
+        }
+        if event.is_key_down {
+            self.release_lost(event, now, &mut results);
+        }
+
+        // Any key down means a held-back hotkey was on the way to another
+        // combo, while releasing its modifiers completes the tap
+        if event.is_key_down {
+            self.pending_taps.clear();
+        } else {
+            let taps: Vec<HotkeyId> = self
+                .pending_taps
+                .iter()
+                .filter(|(_, (_, hotkey))| breaks_combo(hotkey, event))
+                .map(|(&id, _)| id)
+                .collect();
+            for id in taps {
+                if let Some((alias, hotkey)) = self.pending_taps.remove(&id) {
+                    for state in [HotkeyState::Pressed, HotkeyState::Released] {
+                        results.push(self.event(id, state, hotkey, alias));
+                    }
+                }
+            }
+        }
+
+        if self.report_all_released {
+            self.finish_releases(event, &mut results);
+        }
+
+        for tap in self.double_taps.values_mut() {
+            if tap.held.is_some_and(|hotkey| breaks_combo(&hotkey, event)) {
+                tap.held = None;
+            }
+        }
+        for progress in self.sequences.values_mut() {
+            if progress.held.is_some_and(|hotkey| breaks_combo(&hotkey, event)) {
+                progress.held = None;
+            }
+        }
+
+        // As the hook decided, so a key it let through never presses a hotkey
+        let excluded = event.is_key_down && event.hook.is_some_and(|hook| hook.excluded);
+        // Also true when blocking hotkeys were registered after the hook saw
+        // the key, which mustn't press them now that the app got it
+        let let_through = event.hook.and_then(|hook| hook.blocked) == Some(false);
+        // A chord that continues a sequence belongs to it alone
+        if event.is_key_down
+            && !self.suspended
+            && !excluded
+            && !self.advance_sequences(event, let_through, now, &mut results)
+        {
+            // Check for hotkeys that should be pressed
+            let held = event.sided_modifiers();
+            let mut to_press: Vec<(HotkeyId, usize, Hotkey)> = self
+                .hotkeys
+                .iter()
+                .filter(|(id, _)| !self.pressed_hotkeys.contains_key(id))
+                .filter(|(id, _)| !let_through || self.passthrough.contains(id))
+                .filter(|(id, _)| self.guards.get(id).is_none_or(Guard::allows))
+                .filter_map(|(&id, combos)| {
+                    let loose = self.loose.contains(&id);
+                    combos
+                        .iter()
+                        .enumerate()
+                        .find(|(_, hotkey)| {
+                            hotkey.key == event.key
+                                && match loose {
+                                    true => held.contains(hotkey.modifiers),
+                                    false => hotkey.modifiers.matched_by(held),
+                                }
+                        })
+                        .map(|(alias, &hotkey)| (id, alias, hotkey))
+                })
+                .filter(|(_, _, hotkey)| !(typing && TypingDetector::suppresses(hotkey)))
+                .collect();
+            // A hotkey for the exact combo takes precedence over loose ones,
+            // and one that requires the sides held over one for either side
+            let rank = |hotkey: &Hotkey| {
+                let exact = hotkey.modifiers.without_sides() == event.modifiers;
+                (exact, hotkey.modifiers.side_bits().bits().count_ones())
+            };
+            if let Some(best) = to_press.iter().map(|(_, _, hotkey)| rank(hotkey)).max() {
+                to_press.retain(|(_, _, hotkey)| rank(hotkey) == best);
+            }
+
+            // Typing any other key breaks off a double tap
+            if event.key.is_some() {
+                for (id, tap) in self.double_taps.iter_mut() {
+                    if !to_press.iter().any(|(pressed, _, _)| pressed == id) {
+                        tap.first_tap = None;
+                    }
+                }
+            }
+
+            for (id, alias, hotkey) in to_press {
+                if !self.completes_double_tap(id, hotkey, now) || !self.begin_sequence(id, hotkey, now) {
+                    continue;
+                }
+                if self.holds_back(id, &hotkey) {
+                    self.pending_taps.insert(id, (alias, hotkey));
+                    continue;
+                }
+                self.pressed_hotkeys.insert(id, (alias, hotkey));
+                self.releasing.remove(&id);
+                self.last_key_downs.insert(id, now);
+                results.push(self.event(id, HotkeyState::Pressed, hotkey, alias));
+            }
+        }
+
+        results
+    }
+
+    /// Wait for every key of the hotkeys released in `results` to come up,
+    /// and send [`HotkeyState::AllReleased`] for those whose keys all have
+    fn finish_releases(&mut self, event: &KeyEvent, results: &mut Vec<HotkeyEvent>) {
+        for released in results.iter().filter(|e| e.state == HotkeyState::Released) {
+            self.releasing.insert(released.id, (released.alias, released.hotkey));
+        }
+        let done: Vec<HotkeyId> = self
+            .releasing
+            .iter()
+            .filter(|(_, (_, hotkey))| {
+                !event.modifiers.intersects(hotkey.modifiers)
+                    && hotkey.key.is_none_or(|key| !self.held_keys.contains(&key))
+            })
+            .map(|(&id, _)| id)
+            .collect();
+        for id in done {
+            if let Some((alias, hotkey)) = self.releasing.remove(&id) {
+                results.push(self.event(id, HotkeyState::AllReleased, hotkey, alias));
+            }
+        }
+    }
+
+    /// Release pressed hotkeys that `event` presses again after a key up was
+    /// lost, see `HotkeyManagerBuilder::repair_lost_releases`
+    fn release_lost(&mut self, event: &KeyEvent, now: Instant, results: &mut Vec<HotkeyEvent>) {
+        self.last_key_downs.retain(|id, _| self.pressed_hotkeys.contains_key(id));
+        let lost: Vec<HotkeyId> = self
+            .pressed_hotkeys
+            .iter()
+            .filter(|(_, (_, hotkey))| {
+                hotkey.key.is_some()
+                    && hotkey.key == event.key
+                    && hotkey.modifiers.matched_by(event.sided_modifiers())
+            })
+            .map(|(&id, _)| id)
+            .filter(|id| {
+                let last = self.last_key_downs.insert(*id, now);
+                // Key repeat keeps arriving well within the gap
+                self.lost_release_gap
+                    .zip(last)
+                    .is_some_and(|(gap, last)| now.duration_since(last) > gap)
+            })
+            .collect();
+        for id in lost {
+            if let Some((alias, hotkey)) = self.pressed_hotkeys.remove(&id) {
+                self.lost_releases += 1;
+                results.push(self.event(id, HotkeyState::Released, hotkey, alias));
+            }
+        }
+    }
+
+    /// Record a tap of `hotkey`, returning whether it should be pressed
+    ///
+    /// Always true for hotkeys that aren't double-tap ones. Autorepeat while
+    /// the first tap is held doesn't count as a second tap.
+    fn completes_double_tap(&mut self, id: HotkeyId, hotkey: Hotkey, now: Instant) -> bool {
+        let Some(tap) = self.double_taps.get_mut(&id) else {
+            return true;
+        };
+        if tap.held.is_some() {
+            return false;
+        }
+        tap.held = Some(hotkey);
+        match tap.first_tap.take() {
+            Some(first) if now.duration_since(first) <= tap.window => true,
+            _ => {
+                tap.first_tap = Some(now);
+                false
+            }
+        }
+    }
+
+    /// Feed a key down to the sequences in progress, pressing the ones it
+    /// completes
+    ///
+    /// Sequences it doesn't continue start over, as do blocking ones if the
+    /// hook `let_through` the key down. Returns whether any sequence took it.
+    fn advance_sequences(
+        &mut self,
+        event: &KeyEvent,
+        let_through: bool,
+        now: Instant,
+        results: &mut Vec<HotkeyEvent>,
+    ) -> bool {
+        if event.key.is_none() {
+            return false;
+        }
+        let is_chord = |hotkey: &Hotkey| {
+            hotkey.modifiers.matched_by(event.sided_modifiers()) && hotkey.key == event.key
+        };
+        let mut advanced = false;
+        let mut completed = Vec::new();
+        for (&id, progress) in self.sequences.iter_mut() {
+            let Some(awaited) = progress.awaited() else {
+                continue;
+            };
+            // Autorepeat of the chord just typed
+            if progress.held.as_ref().is_some_and(is_chord) {
+                advanced = true;
+                continue;
+            }
+            let in_time = progress
+                .last_chord
+                .is_some_and(|last| now.duration_since(last) <= progress.timeout);
+            let blocked = !let_through || self.passthrough.contains(&id);
+            if !in_time || !is_chord(&awaited) || !blocked {
+                progress.reset();
+                continue;
+            }
+            advanced = true;
+            progress.held = Some(awaited);
+            progress.typed += 1;
+            progress.last_chord = Some(now);
+            if progress.typed == progress.sequence.chords().len() {
+                progress.reset();
+                completed.push((id, awaited));
+            }
+        }
+        for (id, hotkey) in completed {
+            self.pressed_hotkeys.insert(id, (0, hotkey));
+            self.releasing.remove(&id);
+            results.push(self.event(id, HotkeyState::Pressed, hotkey, 0));
+        }
+        advanced
+    }
+
+    /// Start the sequence `id` with its first chord, returning whether it
+    /// should be pressed right away
+    ///
+    /// Always true for hotkeys that aren't sequences, and for one-chord
+    /// sequences.
+    fn begin_sequence(&mut self, id: HotkeyId, hotkey: Hotkey, now: Instant) -> bool {
+        let Some(progress) = self.sequences.get_mut(&id) else {
+            return true;
+        };
+        if progress.sequence.chords().len() == 1 {
+            return true;
+        }
+        if progress.held.is_none() {
+            progress.held = Some(hotkey);
+            progress.typed = 1;
+            progress.last_chord = Some(now);
+        }
+        false
+    }
+
+    /// Give up on sequences whose next chord is overdue, and block exactly
+    /// the chords that in-progress sequences are waiting for
+    pub(crate) fn sync_sequence_blocking(&mut self, blocking: &mut HashSet<Hotkey>, now: Instant) {
+        for progress in self.sequences.values_mut() {
+            if progress
+                .last_chord
+                .is_some_and(|last| now.duration_since(last) > progress.timeout)
+            {
+                progress.reset();
+            }
+        }
+        let awaited: HashSet<Hotkey> = if self.suspended {
+            HashSet::new()
+        } else {
+            self.sequences
+                .iter()
+                .filter(|(id, _)| !self.passthrough.contains(id))
+                .filter_map(|(_, progress)| progress.awaited())
+                .collect()
+        };
+        if awaited == self.sequence_blocks {
+            return;
+        }
+        for chord in self.sequence_blocks.difference(&awaited) {
+            if self.suspended || !self.blocked_combos().any(|combo| combo == *chord) {
+                blocking.remove(chord);
+            }
+        }
+        blocking.extend(awaited.difference(&self.sequence_blocks).copied());
+        self.sequence_blocks = awaited;
+    }
+
+    /// Remove a hotkey, returning its combos and, if it was pressed, the
+    /// Released event that ends the press
+    ///
+    /// For a hotkey with a callback, the Released event is queued for the
+    /// callback instead of returned.
+    pub(crate) fn remove(&mut self, id: HotkeyId) -> Option<(Vec<Hotkey>, Option<HotkeyEvent>)> {
+        let combos = self.hotkeys.remove(&id)?;
+        self.pending_taps.remove(&id);
+        self.releasing.remove(&id);
+        let release = self
+            .pressed_hotkeys
+            .remove(&id)
+            .map(|(alias, hotkey)| self.event(id, HotkeyState::Released, hotkey, alias));
+        let release = self.route(release.into_iter().collect()).pop();
+        self.callbacks.remove(&id);
+        self.passthrough.remove(&id);
+        self.exclusive.remove(&id);
+        self.double_taps.remove(&id);
+        self.sequences.remove(&id);
+        self.guards.remove(&id);
+        self.loose.remove(&id);
+        self.labels.remove(&id);
+        Some((combos, release))
+    }
+
+    /// Stop matching and blocking hotkeys, releasing the pressed ones
+    pub(crate) fn suspend(&mut self, blocking: &mut HashSet<Hotkey>) -> Vec<HotkeyEvent> {
+        self.suspended = true;
+        blocking.clear();
+        self.release_all()
+    }
+
+    /// Describe the state for a rescue dump, see [`crate::rescue`]
+    #[cfg(feature = "rescue")]
+    pub(crate) fn dump(&self, blocking: &HashSet<Hotkey>) -> String {
+        use std::fmt::Write;
+
+        let mut dump = String::new();
+        let _ = writeln!(dump, "suspended: {}", self.suspended);
+        let _ = writeln!(dump, "precedence: {:?}", self.precedence);
+        let mut ids: Vec<&HotkeyId> = self.hotkeys.keys().collect();
+        ids.sort_by_key(|id| id.0);
+        let _ = writeln!(dump, "hotkeys:");
+        for id in ids {
+            let combos: Vec<String> = self.hotkeys[id].iter().map(Hotkey::to_string).collect();
+            let _ = write!(dump, "  {}: {}", id.0, combos.join(", "));
+            if let Some(label) = self.labels.get(id) {
+                let _ = write!(dump, " \"{label}\"");
+            }
+            if self.passthrough.contains(id) {
+                dump.push_str(" (passthrough)");
+            }
+            if self.exclusive.contains(id) {
+                dump.push_str(" (exclusive)");
+            }
+            if self.loose.contains(id) {
+                dump.push_str(" (loose)");
+            }
+            if let Some(tap) = self.double_taps.get(id) {
+                let _ = write!(dump, " (double tap within {:?})", tap.window);
+            }
+            if let Some(progress) = self.sequences.get(id) {
+                let _ = write!(dump, " (sequence {})", progress.sequence);
+            }
+            if self.guards.contains_key(id) {
+                dump.push_str(" (guarded)");
+            }
+            if self.callbacks.contains_key(id) {
+                dump.push_str(" (callback)");
+            }
+            if let Some((_, hotkey)) = self.pressed_hotkeys.get(id) {
+                let _ = write!(dump, " (pressed as {})", hotkey);
+            }
+            dump.push('\n');
+        }
+        let mut blocked: Vec<String> = blocking.iter().map(Hotkey::to_string).collect();
+        blocked.sort();
+        let _ = writeln!(dump, "blocking: {}", blocked.join(", "));
+        dump
+    }
+
+    /// Release every pressed hotkey, e.g. after key ups were lost during sleep
+    pub fn release_all(&mut self) -> Vec<HotkeyEvent> {
+        self.pending_taps.clear();
+        // Key ups may have been lost too
+        self.releasing.clear();
+        self.held_keys.clear();
+        for tap in self.double_taps.values_mut() {
+            tap.first_tap = None;
+            tap.held = None;
+        }
+        for progress in self.sequences.values_mut() {
+            progress.reset();
+            progress.held = None;
+        }
+        let pressed: Vec<_> = self.pressed_hotkeys.drain().collect();
+        pressed
+            .into_iter()
+            .map(|(id, (alias, hotkey))| self.event(id, HotkeyState::Released, hotkey, alias))
+            .collect()
+    }
+}
+
+impl Default for MatchEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether `event` ends a press of `hotkey`
+///
+/// A press ends once one of the hotkey's modifiers is no longer held, whatever
+/// event reveals it, or when its key is released. Modifier-only hotkeys end
+/// when any modifier is released.
+fn breaks_combo(hotkey: &Hotkey, event: &KeyEvent) -> bool {
+    if !event.sided_modifiers().contains(hotkey.modifiers) {
+        return true;
+    }
+    !event.is_key_down && event.key == hotkey.key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{HookDecision, Key, Modifiers};
+
+    fn make_key_event(modifiers: Modifiers, key: Option<Key>, is_key_down: bool) -> KeyEvent {
+        KeyEvent {
+            modifiers,
+            key,
+            is_key_down,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        }
+    }
+
+    fn make_modifier_event(modifiers: Modifiers, is_key_down: bool, changed: Modifiers) -> KeyEvent {
+        KeyEvent {
+            modifiers,
+            key: None,
+            is_key_down,
+            changed_modifier: Some(changed),
+            sides: Modifiers::empty(),
+            hook: None,
+        }
+    }
+
+    mod matching {
+        use super::*;
+
+        #[test]
+        fn register_and_lookup_hotkey() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+
+            let id = HotkeyId(state.next_id);
+            state.next_id += 1;
+            state.hotkeys.insert(id, vec![hotkey]);
+
+            assert_eq!(state.hotkeys.get(&id), Some(&vec![hotkey]));
+            assert_eq!(state.hotkeys.len(), 1);
+        }
+
+        #[test]
+        fn hotkey_press_generates_event() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![hotkey]);
+
+            // Simulate Cmd+K key down
+            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, id);
+            assert_eq!(results[0].state, HotkeyState::Pressed);
+            assert_eq!(results[0].hotkey, hotkey);
+            assert!(!results[0].is_alias());
+            assert!(state.pressed_hotkeys.contains_key(&id));
+        }
+
+        #[test]
+        fn hotkey_release_generates_event() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![hotkey]);
+
+            // Press first
+            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            state.process_event(&event);
+
+            // Then release the key
+            let event = make_key_event(Modifiers::CMD, Some(Key::K), false);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, id);
+            assert_eq!(results[0].state, HotkeyState::Released);
+            assert!(!state.pressed_hotkeys.contains_key(&id));
+        }
+
+        #[test]
+        fn no_duplicate_press_events() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![hotkey]);
+
+            // Press once
+            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            let results = state.process_event(&event);
+            assert_eq!(results.len(), 1);
+
+            // Press again (key repeat) - should not generate another event
+            let results = state.process_event(&event);
+            assert_eq!(results.len(), 0);
+        }
+
+        #[test]
+        fn events_carry_labels() {
+            let mut state = MatchEngine::new();
+            let labelled = state.register("Cmd+K".parse().unwrap()).unwrap();
+            state.register("Cmd+J".parse().unwrap()).unwrap();
+            state.labels.insert(labelled, "Toggle Recording".into());
+
+            let pressed = state.process_event(&make_key_event(Modifiers::CMD, Some(Key::K), true));
+            assert_eq!(pressed[0].label.as_deref(), Some("Toggle Recording"));
+            let released = state.release_all();
+            assert_eq!(released[0].label.as_deref(), Some("Toggle Recording"));
+
+            let other = state.process_event(&make_key_event(Modifiers::CMD, Some(Key::J), true));
+            assert_eq!(other[0].label, None);
+        }
+
+        #[test]
+        fn modifier_release_triggers_hotkey_release() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![hotkey]);
+
+            // Press Cmd+K
+            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            state.process_event(&event);
+            assert!(state.pressed_hotkeys.contains_key(&id));
+
+            // Release Cmd (while K is still held) - modifier event
+            let event = make_modifier_event(Modifiers::empty(), false, Modifiers::CMD);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Released);
+            assert!(!state.pressed_hotkeys.contains_key(&id));
+        }
+
+        #[test]
+        fn wrong_modifiers_dont_trigger() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            state.hotkeys.insert(HotkeyId(0), vec![hotkey]);
+
+            // Press Shift+K instead of Cmd+K
+            let event = make_key_event(Modifiers::SHIFT, Some(Key::K), true);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 0);
+        }
+
+        #[test]
+        fn register_and_unregister_through_public_api() {
+            let mut engine = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let id = engine.register(hotkey).unwrap();
+            assert!(engine.register(hotkey).is_err());
+            assert_eq!(engine.hotkey(id), Some(hotkey));
+
+            let start = Instant::now();
+            let pressed = engine.process_event_at(&make_key_event(Modifiers::CMD, Some(Key::K), true), start);
+            assert_eq!(pressed.len(), 1);
+
+            let release = engine.unregister(id).unwrap().unwrap();
+            assert_eq!((release.id, release.state), (id, HotkeyState::Released));
+            assert!(matches!(engine.unregister(id), Err(Error::HotkeyNotFound(_))));
+            assert!(engine
+                .process_event_at(&make_key_event(Modifiers::CMD, Some(Key::K), true), start)
+                .is_empty());
+        }
+
+        #[test]
+        fn release_all_releases_pressed_hotkeys() {
+            let mut state = MatchEngine::new();
+            let id = HotkeyId(0);
+            state
+                .hotkeys
+                .insert(id, vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+
+            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            state.process_event(&event);
+
+            let results = state.release_all();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, id);
+            assert_eq!(results[0].state, HotkeyState::Released);
+            assert!(state.release_all().is_empty());
+        }
+
+        #[test]
+        fn removing_pressed_hotkey_releases_it() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![hotkey]);
+
+            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            state.process_event(&event);
+
+            let (combos, release) = state.remove(id).unwrap();
+            assert_eq!(combos, [hotkey]);
+            let release = release.unwrap();
+            assert_eq!(release.id, id);
+            assert_eq!(release.state, HotkeyState::Released);
+            assert!(state.pressed_hotkeys.is_empty());
+            assert!(state.release_all().is_empty());
+        }
+
+        #[test]
+        fn removing_idle_hotkey_emits_nothing() {
+            let mut state = MatchEngine::new();
+            let id = HotkeyId(0);
+            state
+                .hotkeys
+                .insert(id, vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+
+            let (_, release) = state.remove(id).unwrap();
+            assert!(release.is_none());
+            assert!(state.remove(id).is_none());
+        }
+
+        #[test]
+        fn callback_hotkeys_are_queued_not_returned() {
+            let mut state = MatchEngine::new();
+            let with_callback = HotkeyId(0);
+            let plain = HotkeyId(1);
+            state.hotkeys.insert(with_callback, vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+            state.hotkeys.insert(plain, vec![Hotkey::new(Modifiers::CMD, Key::J).unwrap()]);
+            let callback: Callback = Arc::new(Mutex::new(Box::new(|_| {})));
+            state.callbacks.insert(with_callback, callback);
+
+            let events = state.process_event(&make_key_event(Modifiers::CMD, Some(Key::K), true));
+            assert!(state.route(events).is_empty());
+            assert_eq!(state.pending_callbacks.len(), 1);
+
+            let events = state.process_event(&make_key_event(Modifiers::CMD, Some(Key::J), true));
+            let events = state.route(events);
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].id, plain);
+            assert_eq!(state.pending_callbacks.len(), 1);
+        }
+
+        #[test]
+        fn removing_callback_hotkey_queues_release_and_drops_callback() {
+            let mut state = MatchEngine::new();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+            let callback: Callback = Arc::new(Mutex::new(Box::new(|_| {})));
+            state.callbacks.insert(id, Arc::clone(&callback));
+
+            let events = state.process_event(&make_key_event(Modifiers::CMD, Some(Key::K), true));
+            state.route(events);
+            state.pending_callbacks.clear();
+
+            let (_, release) = state.remove(id).unwrap();
+            assert!(release.is_none());
+            assert!(state.callbacks.is_empty());
+            assert_eq!(state.pending_callbacks.len(), 1);
+            assert_eq!(state.pending_callbacks[0].0.state, HotkeyState::Released);
+
+            // Only the queued event still holds the callback
+            state.pending_callbacks.clear();
+            assert_eq!(Arc::strong_count(&callback), 1);
+        }
+
+        #[test]
+        fn suspended_hotkeys_not_pressed() {
+            let mut state = MatchEngine::new();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+            let press = make_key_event(Modifiers::CMD, Some(Key::K), true);
+
+            state.process_event(&press);
+            state.suspended = true;
+            // The press in progress can still end normally
+            let results = state.process_event(&make_key_event(Modifiers::CMD, Some(Key::K), false));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Released);
+
+            assert!(state.process_event(&press).is_empty());
+
+            state.suspended = false;
+            assert_eq!(state.process_event(&press).len(), 1);
+        }
+
+        #[test]
+        fn most_specific_wins_holds_back_prefix() {
+            let mut state = MatchEngine {
+                precedence: Precedence::MostSpecificWins,
+                ..MatchEngine::new()
+            };
+            let prefix = HotkeyId(0);
+            let specific = HotkeyId(1);
+            let cmd_shift = Modifiers::CMD | Modifiers::SHIFT;
+            state.hotkeys.insert(prefix, vec![Hotkey::new(cmd_shift, None).unwrap()]);
+            state.hotkeys.insert(specific, vec![Hotkey::new(cmd_shift, Key::K).unwrap()]);
+            let hold = make_modifier_event(cmd_shift, true, Modifiers::SHIFT);
+            let release = make_modifier_event(Modifiers::CMD, false, Modifiers::SHIFT);
+
+            // Typing Cmd+Shift+K only presses the specific hotkey
+            assert!(state.process_event(&hold).is_empty());
+            let results = state.process_event(&make_key_event(cmd_shift, Some(Key::K), true));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, specific);
+            state.process_event(&make_key_event(cmd_shift, Some(Key::K), false));
+            assert!(state.process_event(&release).is_empty());
+
+            // Tapping Cmd+Shift alone presses and releases the prefix
+            assert!(state.process_event(&hold).is_empty());
+            let results = state.process_event(&release);
+            let states: Vec<_> = results.iter().map(|e| (e.id, e.state)).collect();
+            assert_eq!(
+                states,
+                [(prefix, HotkeyState::Pressed), (prefix, HotkeyState::Released)]
+            );
+        }
+
+        #[test]
+        fn most_specific_wins_fires_lone_prefix_immediately() {
+            let mut state = MatchEngine {
+                precedence: Precedence::MostSpecificWins,
+                ..MatchEngine::new()
+            };
+            let cmd_shift = Modifiers::CMD | Modifiers::SHIFT;
+            state.hotkeys.insert(HotkeyId(0), vec![Hotkey::new(cmd_shift, None).unwrap()]);
+            state.hotkeys.insert(HotkeyId(1), vec![Hotkey::new(Modifiers::CTRL, Key::K).unwrap()]);
+
+            let results = state.process_event(&make_modifier_event(cmd_shift, true, Modifiers::SHIFT));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Pressed);
+        }
+
+        #[test]
+        fn loose_hotkey_matches_extra_modifiers() {
+            let mut state = MatchEngine::new();
+            let ctrl_c = Hotkey::new(Modifiers::CTRL, Key::C).unwrap();
+            state.hotkeys.insert(HotkeyId(0), vec![ctrl_c]);
+            state.loose.insert(HotkeyId(0));
+            let ctrl_shift = Modifiers::CTRL | Modifiers::SHIFT;
+
+            let results = state.process_event(&make_key_event(ctrl_shift, Some(Key::C), true));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Pressed);
+            assert_eq!(results[0].hotkey, ctrl_c);
+            // Letting go of Shift keeps it held, letting go of Ctrl doesn't
+            let shift_up = make_modifier_event(Modifiers::CTRL, false, Modifiers::SHIFT);
+            assert!(state.process_event(&shift_up).is_empty());
+            let results = state.process_event(&make_key_event(Modifiers::empty(), None, false));
+            assert_eq!(results[0].state, HotkeyState::Released);
+
+            // An exact hotkey takes precedence
+            let exact = Hotkey::new(ctrl_shift, Key::C).unwrap();
+            state.hotkeys.insert(HotkeyId(1), vec![exact]);
+            let results = state.process_event(&make_key_event(ctrl_shift, Some(Key::C), true));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, HotkeyId(1));
+            // Without extra modifiers it's matched as usual
+            state.release_all();
+            let results = state.process_event(&make_key_event(Modifiers::CTRL, Some(Key::C), true));
+            assert_eq!(results[0].id, HotkeyId(0));
+        }
+
+        #[test]
+        fn sided_hotkey_requires_its_side() {
+            let mut state = MatchEngine::new();
+            state.hotkeys.insert(HotkeyId(0), vec!["RightAlt+K".parse().unwrap()]);
+            let right_alt_k = |sides| KeyEvent {
+                sides,
+                hook: None,
+                ..make_key_event(Modifiers::OPT, Some(Key::K), true)
+            };
+
+            assert!(state.process_event(&right_alt_k(Modifiers::L_OPT)).is_empty());
+            assert!(state.process_event(&right_alt_k(Modifiers::empty())).is_empty());
+            assert_eq!(state.process_event(&right_alt_k(Modifiers::R_OPT)).len(), 1);
+            state.release_all();
+
+            // It takes precedence over a hotkey for either side
+            state.hotkeys.insert(HotkeyId(1), vec!["Alt+K".parse().unwrap()]);
+            let results = state.process_event(&right_alt_k(Modifiers::R_OPT));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, HotkeyId(0));
+            state.release_all();
+            assert_eq!(state.process_event(&right_alt_k(Modifiers::L_OPT))[0].id, HotkeyId(1));
+        }
+
+        #[test]
+        fn exclusive_hotkey_cancelled_by_key() {
+            let mut state = MatchEngine::new();
+            let id = HotkeyId(0);
+            let cmd_shift = Modifiers::CMD | Modifiers::SHIFT;
+            state.hotkeys.insert(id, vec![Hotkey::new(cmd_shift, None).unwrap()]);
+            state.exclusive.insert(id);
+            let hold = make_modifier_event(cmd_shift, true, Modifiers::SHIFT);
+            let release = make_modifier_event(Modifiers::CMD, false, Modifiers::SHIFT);
+
+            // Typing Cmd+Shift+S cancels it, even with nothing else registered
+            assert!(state.process_event(&hold).is_empty());
+            assert!(state
+                .process_event(&make_key_event(cmd_shift, Some(Key::S), true))
+                .is_empty());
+            state.process_event(&make_key_event(cmd_shift, Some(Key::S), false));
+            assert!(state.process_event(&release).is_empty());
+
+            // Releasing the modifiers alone fires it
+            assert!(state.process_event(&hold).is_empty());
+            let states: Vec<_> = state
+                .process_event(&release)
+                .iter()
+                .map(|e| e.state)
+                .collect();
+            assert_eq!(states, [HotkeyState::Pressed, HotkeyState::Released]);
+        }
+
+        #[test]
+        fn double_tap_fires_on_second_tap_within_window() {
+            let mut state = MatchEngine::new();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![Hotkey::new(Modifiers::SHIFT, None).unwrap()]);
+            state.double_taps.insert(
+                id,
+                DoubleTap {
+                    window: Duration::from_millis(300),
+                    first_tap: None,
+                    held: None,
+                },
+            );
+            let down = make_modifier_event(Modifiers::SHIFT, true, Modifiers::SHIFT);
+            let up = make_modifier_event(Modifiers::empty(), false, Modifiers::SHIFT);
+            let start = Instant::now();
+            let ms = |n| start + Duration::from_millis(n);
+
+            // First tap, with autorepeat while held
+            assert!(state.process_event_at(&down, ms(0)).is_empty());
+            assert!(state.process_event_at(&down, ms(50)).is_empty());
+            assert!(state.process_event_at(&up, ms(100)).is_empty());
+
+            // Second tap presses and releases it
+            let results = state.process_event_at(&down, ms(200));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Pressed);
+            let results = state.process_event_at(&up, ms(250));
+            assert_eq!(results[0].state, HotkeyState::Released);
+
+            // Too slow
+            assert!(state.process_event_at(&down, ms(1000)).is_empty());
+            state.process_event_at(&up, ms(1050));
+            assert!(state.process_event_at(&down, ms(1400)).is_empty());
+            state.process_event_at(&up, ms(1450));
+
+            // Another key in between starts over
+            let key = make_key_event(Modifiers::empty(), Some(Key::A), true);
+            state.process_event_at(&key, ms(1500));
+            assert!(state.process_event_at(&down, ms(1550)).is_empty());
+        }
+
+        fn state_with_sequences(sequences: &[&str]) -> MatchEngine {
+            let mut state = MatchEngine::new();
+            for (i, sequence) in sequences.iter().enumerate() {
+                let sequence: HotkeySequence = sequence.parse().unwrap();
+                state.hotkeys.insert(HotkeyId(i as u32), vec![sequence.first()]);
+                state.sequences.insert(
+                    HotkeyId(i as u32),
+                    SequenceProgress::new(sequence, Duration::from_secs(1)),
+                );
+            }
+            state
+        }
+
+        #[test]
+        fn sequence_fires_on_last_chord() {
+            let mut state = state_with_sequences(&["Ctrl+K Ctrl+B"]);
+            let ctrl_k = make_key_event(Modifiers::CTRL, Some(Key::K), true);
+            let ctrl_b = make_key_event(Modifiers::CTRL, Some(Key::B), true);
+            let start = Instant::now();
+            let ms = |n| start + Duration::from_millis(n);
+
+            assert!(state.process_event_at(&ctrl_k, ms(0)).is_empty());
+            // Autorepeat of the first chord doesn't break it off
+            assert!(state.process_event_at(&ctrl_k, ms(30)).is_empty());
+            state.process_event_at(&make_key_event(Modifiers::CTRL, Some(Key::K), false), ms(50));
+            let results = state.process_event_at(&ctrl_b, ms(100));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Pressed);
+            assert_eq!(results[0].hotkey, Hotkey::new(Modifiers::CTRL, Key::B).unwrap());
+            let results =
+                state.process_event_at(&make_key_event(Modifiers::CTRL, Some(Key::B), false), ms(150));
+            assert_eq!(results[0].state, HotkeyState::Released);
+
+            // Ctrl+B alone, another key in between, or too late don't fire
+            assert!(state.process_event_at(&ctrl_b, ms(200)).is_empty());
+            state.process_event_at(&ctrl_k, ms(300));
+            state.process_event_at(&make_key_event(Modifiers::CTRL, Some(Key::J), true), ms(350));
+            assert!(state.process_event_at(&ctrl_b, ms(400)).is_empty());
+            state.process_event_at(&ctrl_k, ms(500));
+            assert!(state.process_event_at(&ctrl_b, ms(2000)).is_empty());
+        }
+
+        #[test]
+        fn sequences_share_first_chord() {
+            let mut state = state_with_sequences(&["Ctrl+K Ctrl+B", "Ctrl+K Ctrl+C"]);
+            assert!(state
+                .check_sequence_available(&"Ctrl+K Ctrl+D".parse().unwrap())
+                .is_ok());
+            assert!(state
+                .check_sequence_available(&"Ctrl+K Ctrl+C".parse().unwrap())
+                .is_err());
+            assert!(state
+                .check_available(&[Hotkey::new(Modifiers::CTRL, Key::K).unwrap()])
+                .is_err());
+
+            state.process_event(&make_key_event(Modifiers::CTRL, Some(Key::K), true));
+            state.process_event(&make_key_event(Modifiers::CTRL, Some(Key::K), false));
+            let results = state.process_event(&make_key_event(Modifiers::CTRL, Some(Key::C), true));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, HotkeyId(1));
+        }
+
+        #[test]
+        fn later_chords_blocked_only_while_awaited() {
+            let mut state = state_with_sequences(&["Ctrl+K Ctrl+B"]);
+            let mut blocking = HashSet::from([Hotkey::new(Modifiers::CTRL, Key::K).unwrap()]);
+            let ctrl_b = Hotkey::new(Modifiers::CTRL, Key::B).unwrap();
+            let start = Instant::now();
+
+            state.process_event_at(&make_key_event(Modifiers::CTRL, Some(Key::K), true), start);
+            state.sync_sequence_blocking(&mut blocking, start);
+            assert!(blocking.contains(&ctrl_b));
+
+            // Given up once the timeout passes
+            state.sync_sequence_blocking(&mut blocking, start + Duration::from_secs(2));
+            assert!(!blocking.contains(&ctrl_b));
+            assert_eq!(blocking.len(), 1);
+        }
+
+        #[test]
+        fn lost_release_repaired_after_gap() {
+            let mut state = MatchEngine {
+                lost_release_gap: Some(Duration::from_secs(2)),
+                ..MatchEngine::new()
+            };
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+            let press = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            let start = Instant::now();
+            let secs = |n: f32| start + Duration::from_secs_f32(n);
+
+            assert_eq!(state.process_event_at(&press, secs(0.0)).len(), 1);
+            // Key repeat, slowest after the initial delay
+            assert!(state.process_event_at(&press, secs(1.5)).is_empty());
+            assert!(state.process_event_at(&press, secs(1.6)).is_empty());
+
+            // The key up was lost, and K is pressed again later
+            let results = state.process_event_at(&press, secs(10.0));
+            let states: Vec<_> = results.iter().map(|e| e.state).collect();
+            assert_eq!(states, [HotkeyState::Released, HotkeyState::Pressed]);
+            assert_eq!(state.lost_releases, 1);
+        }
+
+        #[test]
+        fn lost_releases_ignored_by_default() {
+            let mut state = MatchEngine::new();
+            state.hotkeys.insert(HotkeyId(0), vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+            let press = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            let start = Instant::now();
+
+            state.process_event_at(&press, start);
+            assert!(state
+                .process_event_at(&press, start + Duration::from_secs(60))
+                .is_empty());
+        }
+
+        #[test]
+        fn simple_hotkeys_suppressed_while_typing() {
+            let mut state = MatchEngine {
+                typing: Some(TypingDetector::new(3, Duration::from_secs(1))),
+                ..MatchEngine::new()
+            };
+            state.hotkeys.insert(HotkeyId(0), vec![Hotkey::new(Modifiers::empty(), Key::F1).unwrap()]);
+            state.hotkeys.insert(HotkeyId(1), vec![Hotkey::new(Modifiers::CTRL, Key::F1).unwrap()]);
+            let start = Instant::now();
+            let at = |ms| start + Duration::from_millis(ms);
+            for (i, key) in [Key::H, Key::I, Key::Space].into_iter().enumerate() {
+                let ms = i as u64 * 100;
+                state.process_event_at(&make_key_event(Modifiers::empty(), Some(key), true), at(ms));
+                state.process_event_at(&make_key_event(Modifiers::empty(), Some(key), false), at(ms + 50));
+            }
+
+            let f1 = make_key_event(Modifiers::empty(), Some(Key::F1), true);
+            assert!(state.process_event_at(&f1, at(400)).is_empty());
+            state.process_event_at(&make_key_event(Modifiers::empty(), Some(Key::F1), false), at(450));
+            let ctrl_f1 = make_key_event(Modifiers::CTRL, Some(Key::F1), true);
+            assert_eq!(state.process_event_at(&ctrl_f1, at(500)).len(), 1);
+            state.process_event_at(&make_key_event(Modifiers::empty(), Some(Key::F1), false), at(550));
+
+            assert_eq!(state.process_event_at(&f1, at(3000)).len(), 1);
+        }
+
+        #[test]
+        fn all_released_waits_for_modifiers() {
+            let mut state = MatchEngine {
+                report_all_released: true,
+                ..MatchEngine::new()
+            };
+            let hotkey = Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, Key::K).unwrap();
+            state.hotkeys.insert(HotkeyId(0), vec![hotkey]);
+            let states = |results: Vec<HotkeyEvent>| -> Vec<HotkeyState> {
+                results.iter().map(|event| event.state).collect()
+            };
+
+            state.process_event(&make_key_event(Modifiers::CMD | Modifiers::SHIFT, Some(Key::K), true));
+            let release = make_key_event(Modifiers::CMD | Modifiers::SHIFT, Some(Key::K), false);
+            assert_eq!(states(state.process_event(&release)), [HotkeyState::Released]);
+            assert!(state.process_event(&make_key_event(Modifiers::CMD, None, false)).is_empty());
+            assert_eq!(
+                states(state.process_event(&make_key_event(Modifiers::empty(), None, false))),
+                [HotkeyState::AllReleased]
+            );
+        }
+
+        #[test]
+        fn guarded_hotkey_pressed_only_while_allowed() {
+            use std::sync::atomic::{AtomicBool, Ordering};
+
+            let mut state = MatchEngine::new();
+            let allowed = Arc::new(AtomicBool::new(false));
+            let guard_allowed = Arc::clone(&allowed);
+            state.hotkeys.insert(HotkeyId(0), vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+            state
+                .guards
+                .insert(HotkeyId(0), Guard::new(move || guard_allowed.load(Ordering::SeqCst)));
+            let press = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            let release = make_key_event(Modifiers::CMD, Some(Key::K), false);
+
+            assert!(state.process_event(&press).is_empty());
+            state.process_event(&release);
+
+            allowed.store(true, Ordering::SeqCst);
+            state.guards[&HotkeyId(0)].refresh();
+            assert_eq!(state.process_event(&press).len(), 1);
+        }
+
+        #[test]
+        fn nothing_pressed_where_the_hook_saw_an_excluded_app() {
+            let mut state = MatchEngine::new();
+            state.hotkeys.insert(HotkeyId(0), vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+            let press = |excluded| KeyEvent {
+                hook: Some(HookDecision { blocked: None, excluded }),
+                ..make_key_event(Modifiers::CMD, Some(Key::K), true)
+            };
+            let release = make_key_event(Modifiers::CMD, Some(Key::K), false);
+
+            assert!(state.process_event(&press(true)).is_empty());
+            state.process_event(&release);
+            assert_eq!(state.process_event(&press(false)).len(), 1);
+        }
+
+        #[test]
+        fn keys_the_hook_let_through_press_no_blocking_hotkey() {
+            let mut state = MatchEngine::new();
+            let blocking = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let passthrough = Hotkey::new(Modifiers::CMD, Key::J).unwrap();
+            state.hotkeys.insert(HotkeyId(0), vec![blocking]);
+            state.hotkeys.insert(HotkeyId(1), vec![passthrough]);
+            state.passthrough.insert(HotkeyId(1));
+            let press = |hotkey: Hotkey, blocked| KeyEvent {
+                hook: Some(HookDecision { blocked, excluded: false }),
+                ..make_key_event(hotkey.modifiers, hotkey.key, true)
+            };
+            let release = |hotkey: Hotkey| make_key_event(hotkey.modifiers, hotkey.key, false);
+
+            // Registered after the hook let the key through
+            assert!(state.process_event(&press(blocking, Some(false))).is_empty());
+            state.process_event(&release(blocking));
+            assert_eq!(state.process_event(&press(blocking, Some(true))).len(), 1);
+            state.process_event(&release(blocking));
+            // A listener that doesn't block decides nothing
+            assert_eq!(state.process_event(&press(blocking, None)).len(), 1);
+            assert_eq!(state.process_event(&press(passthrough, Some(false))).len(), 1);
+        }
+
+        #[test]
+        fn reserved_combos_taken_but_not_matched() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let id = state.allocate_id();
+            state.reserved.insert(id, vec![hotkey]);
+
+            assert!(matches!(
+                state.check_available(&[hotkey]),
+                Err(Error::HotkeyAlreadyRegistered(_))
+            ));
+            assert!(state
+                .process_event(&make_key_event(Modifiers::CMD, Some(Key::K), true))
+                .is_empty());
+
+            state.reserved.remove(&id);
+            assert!(state.check_available(&[hotkey]).is_ok());
+        }
+
+        #[test]
+        fn passthrough_hotkeys_not_blocked() {
+            let mut state = MatchEngine::new();
+            let blocked = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let observed = Hotkey::new(Modifiers::CMD, Key::C).unwrap();
+            state.hotkeys.insert(HotkeyId(0), vec![blocked]);
+            state.hotkeys.insert(HotkeyId(1), vec![observed]);
+            state.passthrough.insert(HotkeyId(1));
+
+            assert_eq!(state.blocked_combos().collect::<Vec<_>>(), [blocked]);
+            // Still matched
+            let results = state.process_event(&make_key_event(Modifiers::CMD, Some(Key::C), true));
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, HotkeyId(1));
+        }
+
+        #[test]
+        #[cfg(feature = "rescue")]
+        fn rescue_dump_describes_hotkeys() {
+            let mut state = MatchEngine::new();
+            state.hotkeys.insert(HotkeyId(0), vec![Hotkey::new(Modifiers::CMD, Key::K).unwrap()]);
+            state.hotkeys.insert(HotkeyId(1), vec![Hotkey::new(Modifiers::CMD, Key::C).unwrap()]);
+            state.passthrough.insert(HotkeyId(1));
+            state.process_event(&make_key_event(Modifiers::CMD, Some(Key::K), true));
+            let mut blocking: HashSet<Hotkey> = state.blocked_combos().collect();
+
+            let dump = state.dump(&blocking);
+            let released = state.suspend(&mut blocking);
+
+            assert!(dump.contains("suspended: false"));
+            assert!(dump.contains("  1: Cmd+C (passthrough)\n"));
+            assert!(dump.contains("(pressed as Cmd+K)"));
+            assert!(dump.contains("blocking: Cmd+K\n"));
+            assert!(state.suspended);
+            assert!(blocking.is_empty());
+            assert_eq!(released.len(), 1);
+        }
+
+        #[test]
+        fn modifier_only_hotkey() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, None).unwrap();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![hotkey]);
+
+            // Press Cmd+Shift (no key)
+            let event = make_modifier_event(Modifiers::CMD | Modifiers::SHIFT, true, Modifiers::SHIFT);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Pressed);
+        }
+
+        #[test]
+        fn multiple_hotkeys_same_key() {
+            let mut state = MatchEngine::new();
+
+            // Cmd+K and Ctrl+K
+            let hotkey1 = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
+            let hotkey2 = Hotkey::new(Modifiers::CTRL, Key::K).unwrap();
+            let id1 = HotkeyId(0);
+            let id2 = HotkeyId(1);
+            state.hotkeys.insert(id1, vec![hotkey1]);
+            state.hotkeys.insert(id2, vec![hotkey2]);
+
+            // Press Cmd+K
+            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, id1);
+
+            // Press Ctrl+K (release Cmd first)
+            state.pressed_hotkeys.clear();
+            let event = make_key_event(Modifiers::CTRL, Some(Key::K), true);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, id2);
+        }
+
+        #[test]
+        fn aliases_trigger_same_id() {
+            let mut state = MatchEngine::new();
+            let main = Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, Key::Num4).unwrap();
+            let alias = Hotkey::new(Modifiers::empty(), Key::F13).unwrap();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![main, alias]);
+
+            let event = make_key_event(Modifiers::empty(), Some(Key::F13), true);
+            let results = state.process_event(&event);
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].id, id);
+            assert_eq!(results[0].hotkey, alias);
+            assert_eq!(results[0].alias, 1);
+            assert!(results[0].is_alias());
+
+            // Releasing the main combo's key doesn't release the alias
+            let event = make_key_event(Modifiers::empty(), Some(Key::Num4), false);
+            assert!(state.process_event(&event).is_empty());
+
+            let event = make_key_event(Modifiers::empty(), Some(Key::F13), false);
+            let results = state.process_event(&event);
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Released);
+            assert_eq!(results[0].hotkey, alias);
+        }
+
+        #[test]
+        fn key_only_hotkey() {
+            let mut state = MatchEngine::new();
+            let hotkey = Hotkey::new(Modifiers::empty(), Key::F1).unwrap();
+            let id = HotkeyId(0);
+            state.hotkeys.insert(id, vec![hotkey]);
+
+            // Press F1 with no modifiers
+            let event = make_key_event(Modifiers::empty(), Some(Key::F1), true);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].state, HotkeyState::Pressed);
+
+            // F1 with modifiers should NOT trigger
+            state.pressed_hotkeys.clear();
+            let event = make_key_event(Modifiers::CMD, Some(Key::F1), true);
+            let results = state.process_event(&event);
+
+            assert_eq!(results.len(), 0);
+        }
+
+        #[test]
+        fn press_and_release_transitions() {
+            use HotkeyState::{Pressed, Released};
+            const NONE: Modifiers = Modifiers::empty();
+            const CMD: Modifiers = Modifiers::CMD;
+            const CMD_SHIFT: Modifiers = Modifiers::CMD.union(Modifiers::SHIFT);
+
+            type Step = (Modifiers, Option<Key>, bool);
+            let cases: &[(&str, &str, &[Step], &[HotkeyState])] = &[
+                ("key released", "Cmd+K",
+                    &[(CMD, Some(Key::K), true), (CMD, Some(Key::K), false)],
+                    &[Pressed, Released]),
+                ("key repeat", "Cmd+K",
+                    &[(CMD, Some(Key::K), true), (CMD, Some(Key::K), true)],
+                    &[Pressed]),
+                ("modifier released first", "Cmd+K",
+                    &[(CMD, Some(Key::K), true), (NONE, None, false)],
+                    &[Pressed, Released]),
+                ("other key up after modifier lost", "Cmd+K",
+                    &[(CMD, Some(Key::K), true), (NONE, Some(Key::J), false)],
+                    &[Pressed, Released]),
+                ("other key down after modifier lost", "Cmd+K",
+                    &[(CMD, Some(Key::K), true), (NONE, Some(Key::J), true)],
+                    &[Pressed, Released]),
+                ("other key up with modifier held", "Cmd+K",
+                    &[(CMD, Some(Key::K), true), (CMD, Some(Key::J), false)],
+                    &[Pressed]),
+                ("extra modifier pressed", "Cmd+K",
+                    &[(CMD, Some(Key::K), true), (CMD_SHIFT, None, true)],
+                    &[Pressed]),
+                ("pressed again after release", "Cmd+K",
+                    &[(CMD, Some(Key::K), true), (CMD, Some(Key::K), false), (CMD, Some(Key::K), true)],
+                    &[Pressed, Released, Pressed]),
+                ("key-only hotkey with modifier added", "F1",
+                    &[(NONE, Some(Key::F1), true), (Modifiers::SHIFT, None, true), (Modifiers::SHIFT, Some(Key::F1), false)],
+                    &[Pressed, Released]),
+                ("modifier-only hotkey", "Cmd+Shift",
+                    &[(CMD_SHIFT, None, true), (CMD, None, false)],
+                    &[Pressed, Released]),
+                ("modifier-only hotkey, extra modifier released", "Cmd+Shift",
+                    &[(CMD_SHIFT, None, true), (CMD_SHIFT | Modifiers::OPT, None, true), (CMD_SHIFT, None, false)],
+                    &[Pressed, Released]),
+                ("modifier-only hotkey, key typed", "Cmd+Shift",
+                    &[(CMD_SHIFT, None, true), (CMD_SHIFT, Some(Key::T), true), (CMD_SHIFT, Some(Key::T), false)],
+                    &[Pressed]),
+            ];
+
+            for (name, hotkey, steps, expected) in cases {
+                let mut state = MatchEngine::new();
+                state.hotkeys.insert(HotkeyId(0), vec![hotkey.parse().unwrap()]);
+                let states: Vec<HotkeyState> = steps
+                    .iter()
+                    .flat_map(|&(modifiers, key, down)| {
+                        state.process_event(&make_key_event(modifiers, key, down))
+                    })
+                    .map(|event| event.state)
+                    .collect();
+                assert_eq!(&states, expected, "{name}");
+            }
+        }
+    }
+    mod properties {
+        use super::*;
+        use crate::channel::channel;
+        use crate::platform::state::{ListenerConfig, ListenerState};
+        use crate::test_support::{arb_hotkey, arb_key, arb_modifiers};
+        use proptest::prelude::*;
+
+        proptest! {
+            /// A key down triggers a registered hotkey exactly when the
+            /// listener would block it, so blocked events are never lost
+            #[test]
+            fn manager_matches_what_listener_blocks(
+                hotkey in arb_hotkey(),
+                modifiers in arb_modifiers(),
+                key in proptest::option::of(arb_key()),
+                use_hotkey in any::<bool>(),
+            ) {
+                let event = if use_hotkey {
+                    make_key_event(hotkey.modifiers, hotkey.key, true)
+                } else {
+                    make_key_event(modifiers, key, true)
+                };
+
+                let mut state = MatchEngine::new();
+                state.hotkeys.insert(HotkeyId(0), vec![hotkey]);
+                let pressed = !state.process_event(&event).is_empty();
+
+                let (tx, _rx) = channel();
+                let config = ListenerConfig {
+                    blocking_hotkeys: Some(Arc::new(Mutex::new(HashSet::from([hotkey])))),
+                    ..Default::default()
+                };
+                let listener_state = ListenerState::new(tx, &config);
+                let blocked = listener_state.should_block(event.modifiers, event.key);
+
+                prop_assert_eq!(pressed, blocked);
+            }
+        }
+    }
+}
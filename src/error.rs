@@ -1,4 +1,4 @@
-use crate::types::HotkeyId;
+use crate::types::{HotkeyId, Key};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -12,6 +12,9 @@ pub enum Error {
     #[error("Failed to create event tap: {0}")]
     EventTapCreationFailed(String),
 
+    #[error("Failed to grab input devices: {0}")]
+    GrabFailed(String),
+
     #[error("Failed to create run loop source")]
     RunLoopSourceCreationFailed,
 
@@ -45,6 +48,33 @@ pub enum Error {
     #[error("Unknown modifier: {0}")]
     UnknownModifier(String),
 
+    #[error("Key has no scancode: {0}")]
+    NoScancode(Key),
+
+    #[error("No key types {0:?} in the active keyboard layout")]
+    NoKeyForChar(char),
+
+    #[error("Hotkey can't be used as a menu accelerator: {0}")]
+    NotAnAccelerator(String),
+
+    #[error("Invalid binding for \"{0}\": {1}")]
+    InvalidBinding(String, #[source] Box<Error>),
+
+    #[error("Invalid FFI value: {0}")]
+    InvalidFfiValue(String),
+
+    #[error("Invalid value for {0}: {1}")]
+    InvalidEnvVar(&'static str, String),
+
+    #[error("Failed to set thread priority: {0}")]
+    ThreadPriority(String),
+
+    #[error("Not available to managers sharing a listener: {0}")]
+    SharedListener(String),
+
+    #[error("handy-keys is being unloaded")]
+    Unloading,
+
     #[error("Internal error: Mutex poisoned")]
     MutexPoisoned,
 }
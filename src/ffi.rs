@@ -0,0 +1,250 @@
+//! `#[repr(C)]` mirrors of the event types, for C APIs and shared-memory IPC
+//!
+//! The mirrors use only fixed-size integers, so they can cross language and
+//! process boundaries, and can be converted back and forth with `From` and
+//! `TryFrom`.
+//!
+//! # Stability
+//!
+//! - Keys are identified by their stable [`Key::code`], or [`KEY_NONE`]. Codes
+//!   never change and are never reused. New keys get new codes, which older
+//!   versions reject when converting back.
+//! - [`Modifiers`] bits never change.
+//! - The layout of the mirror types only changes in a semver-breaking release.
+//!   Reserved fields are always zero when written, and ignored when read.
+
+use crate::error::{Error, Result};
+use crate::types::{Hotkey, HotkeyEvent, HotkeyId, HotkeyState, Key, KeyEvent, Modifiers};
+
+/// Key code meaning no key, e.g. for a modifier-only hotkey
+pub const KEY_NONE: u16 = 0;
+
+/// Key code of [`Key::Other`], whose platform keycode is stored separately
+pub const KEY_OTHER: u16 = 0xFFFF;
+
+/// [`HotkeyState::Pressed`] in [`FfiHotkeyEvent::state`]
+pub const STATE_PRESSED: u8 = 0;
+
+/// [`HotkeyState::Released`] in [`FfiHotkeyEvent::state`]
+pub const STATE_RELEASED: u8 = 1;
+
+/// [`HotkeyState::AllReleased`] in [`FfiHotkeyEvent::state`]
+pub const STATE_ALL_RELEASED: u8 = 2;
+
+/// A [`Hotkey`], 12 bytes
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FfiHotkey {
+    /// [`Modifiers`] bits
+    pub modifiers: u32,
+    /// Platform keycode when `key` is [`KEY_OTHER`], otherwise 0
+    pub platform_code: u32,
+    /// Stable key code
+    pub key: u16,
+    pub reserved: u16,
+}
+
+/// A [`KeyEvent`], 16 bytes
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FfiKeyEvent {
+    /// [`Modifiers`] bits, with the side flags of [`KeyEvent::sides`]
+    pub modifiers: u32,
+    /// [`Modifiers`] bits of the modifier that changed, 0 for key events
+    pub changed_modifier: u32,
+    /// Platform keycode when `key` is [`KEY_OTHER`], otherwise 0
+    pub platform_code: u32,
+    /// Stable key code
+    pub key: u16,
+    /// 1 for a key down, 0 for a key up
+    pub is_key_down: u8,
+    pub reserved: u8,
+}
+
+/// A [`HotkeyEvent`], 24 bytes
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FfiHotkeyEvent {
+    /// See [`HotkeyId::as_u32`]
+    pub id: u32,
+    /// See [`HotkeyEvent::alias`]
+    pub alias: u32,
+    pub hotkey: FfiHotkey,
+    /// [`STATE_PRESSED`], [`STATE_RELEASED`] or [`STATE_ALL_RELEASED`]
+    pub state: u8,
+    pub reserved: [u8; 3],
+}
+
+/// Split a key into its stable code and platform keycode
+fn encode_key(key: Option<Key>) -> (u16, u32) {
+    match key {
+        None => (KEY_NONE, 0),
+        Some(Key::Other(code)) => (KEY_OTHER, code),
+        Some(key) => (key.code(), 0),
+    }
+}
+
+fn decode_key(code: u16, platform_code: u32) -> Result<Option<Key>> {
+    match code {
+        KEY_NONE => Ok(None),
+        KEY_OTHER => Ok(Some(Key::Other(platform_code))),
+        code => Key::from_code(code)
+            .map(Some)
+            .ok_or_else(|| Error::UnknownKey(format!("code {code:#06X}"))),
+    }
+}
+
+fn decode_modifiers(bits: u32) -> Result<Modifiers> {
+    Modifiers::from_bits(bits).ok_or_else(|| Error::UnknownModifier(format!("bits {bits:#X}")))
+}
+
+impl From<Hotkey> for FfiHotkey {
+    fn from(hotkey: Hotkey) -> Self {
+        let (key, platform_code) = encode_key(hotkey.key);
+        Self {
+            modifiers: hotkey.modifiers.bits(),
+            platform_code,
+            key,
+            reserved: 0,
+        }
+    }
+}
+
+impl TryFrom<FfiHotkey> for Hotkey {
+    type Error = Error;
+
+    fn try_from(hotkey: FfiHotkey) -> Result<Self> {
+        Hotkey::new(
+            decode_modifiers(hotkey.modifiers)?,
+            decode_key(hotkey.key, hotkey.platform_code)?,
+        )
+    }
+}
+
+impl From<KeyEvent> for FfiKeyEvent {
+    fn from(event: KeyEvent) -> Self {
+        let (key, platform_code) = encode_key(event.key);
+        Self {
+            modifiers: event.sided_modifiers().bits(),
+            changed_modifier: event.changed_modifier.map_or(0, |m| m.bits()),
+            platform_code,
+            key,
+            is_key_down: event.is_key_down.into(),
+            reserved: 0,
+        }
+    }
+}
+
+impl TryFrom<FfiKeyEvent> for KeyEvent {
+    type Error = Error;
+
+    fn try_from(event: FfiKeyEvent) -> Result<Self> {
+        let changed_modifier = match event.changed_modifier {
+            0 => None,
+            bits => Some(decode_modifiers(bits)?),
+        };
+        let modifiers = decode_modifiers(event.modifiers)?;
+        Ok(KeyEvent {
+            modifiers: modifiers.without_sides(),
+            key: decode_key(event.key, event.platform_code)?,
+            is_key_down: event.is_key_down != 0,
+            changed_modifier,
+            sides: modifiers.sides_of(modifiers),
+            hook: None,
+        })
+    }
+}
+
+impl From<HotkeyEvent> for FfiHotkeyEvent {
+    fn from(event: HotkeyEvent) -> Self {
+        Self {
+            id: event.id.as_u32(),
+            alias: u32::try_from(event.alias).unwrap_or(u32::MAX),
+            hotkey: event.hotkey.into(),
+            state: match event.state {
+                HotkeyState::Pressed => STATE_PRESSED,
+                HotkeyState::Released => STATE_RELEASED,
+                HotkeyState::AllReleased => STATE_ALL_RELEASED,
+            },
+            reserved: [0; 3],
+        }
+    }
+}
+
+impl TryFrom<FfiHotkeyEvent> for HotkeyEvent {
+    type Error = Error;
+
+    fn try_from(event: FfiHotkeyEvent) -> Result<Self> {
+        let state = match event.state {
+            STATE_PRESSED => HotkeyState::Pressed,
+            STATE_RELEASED => HotkeyState::Released,
+            STATE_ALL_RELEASED => HotkeyState::AllReleased,
+            state => {
+                return Err(Error::InvalidFfiValue(format!("hotkey state {state}")));
+            }
+        };
+        Ok(HotkeyEvent {
+            id: HotkeyId(event.id),
+            state,
+            hotkey: event.hotkey.try_into()?,
+            alias: event.alias as usize,
+            label: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{arb_hotkey, arb_key, arb_modifiers, arb_sided_modifiers};
+    use proptest::prelude::*;
+    use std::mem::{align_of, size_of};
+
+    #[test]
+    fn layout_is_fixed() {
+        assert_eq!((size_of::<FfiHotkey>(), align_of::<FfiHotkey>()), (12, 4));
+        assert_eq!((size_of::<FfiKeyEvent>(), align_of::<FfiKeyEvent>()), (16, 4));
+        assert_eq!((size_of::<FfiHotkeyEvent>(), align_of::<FfiHotkeyEvent>()), (24, 4));
+    }
+
+    #[test]
+    fn stable_key_codes() {
+        let hotkey = FfiHotkey::from(Hotkey::new(Modifiers::CTRL, Key::K).unwrap());
+        assert_eq!((hotkey.modifiers, hotkey.key), (1 << 2, 0x000B));
+        assert_eq!(FfiHotkey::from(Hotkey::new(Modifiers::CMD, None).unwrap()).key, KEY_NONE);
+        assert!(decode_key(0x7FFE, 0).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn hotkeys_roundtrip(hotkey in arb_hotkey()) {
+            prop_assert_eq!(Hotkey::try_from(FfiHotkey::from(hotkey)).unwrap(), hotkey);
+        }
+
+        #[test]
+        fn key_events_roundtrip(
+            modifiers in arb_sided_modifiers(),
+            key in proptest::option::of(arb_key()),
+            is_key_down in any::<bool>(),
+            changed in proptest::option::of(arb_modifiers()),
+        ) {
+            let event = KeyEvent {
+                modifiers: modifiers.without_sides(),
+                key,
+                is_key_down,
+                changed_modifier: changed.filter(|m| !m.is_empty()),
+                sides: modifiers.sides_of(modifiers),
+                hook: None,
+            };
+            prop_assert_eq!(KeyEvent::try_from(FfiKeyEvent::from(event)).unwrap(), event);
+        }
+
+        #[test]
+        fn hotkey_events_roundtrip(hotkey in arb_hotkey(), id in any::<u32>(), alias in 0usize..8) {
+            let event = HotkeyEvent { id: HotkeyId(id), state: HotkeyState::Released, hotkey, alias, label: None };
+            let roundtrip = HotkeyEvent::try_from(FfiHotkeyEvent::from(event.clone())).unwrap();
+            prop_assert_eq!((roundtrip.id, roundtrip.state, roundtrip.hotkey, roundtrip.alias),
+                (event.id, event.state, event.hotkey, event.alias));
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Hotkeys bound to an app's own action type
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::manager::HotkeyManager;
+use crate::types::{Hotkey, HotkeyId, HotkeyState};
+
+/// A [`HotkeyManager`] that reports actions instead of hotkey IDs
+///
+/// Each hotkey is registered with a value of the app's action type, usually
+/// an enum, and [`recv`](Self::recv) yields that action with the hotkey's
+/// state, so apps don't keep their own map from [`HotkeyId`] to action.
+/// [`bindings`](Self::bindings) lists every hotkey with its action, which
+/// serializes with serde when the action does, and
+/// [`register_bindings`](Self::register_bindings) registers such a list
+/// again, e.g. when loading saved settings.
+///
+/// ```no_run
+/// use handy_keys::{HotkeyMap, HotkeyState};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum Action {
+///     ToggleRecording,
+///     OpenPalette,
+/// }
+///
+/// let keymap = HotkeyMap::new()?;
+/// keymap.register("Ctrl+Alt+R".parse()?, Action::ToggleRecording)?;
+/// keymap.register("Ctrl+Shift+P".parse()?, Action::OpenPalette)?;
+///
+/// for (action, state) in keymap.events() {
+///     if state == HotkeyState::Pressed {
+///         println!("{action:?}");
+///     }
+/// }
+/// # Ok::<(), handy_keys::Error>(())
+/// ```
+pub struct HotkeyMap<A> {
+    manager: HotkeyManager,
+    /// Every registered hotkey with its action, in registration order
+    bindings: Mutex<Vec<(HotkeyId, Hotkey, A)>>,
+    sender: Sender<(A, HotkeyState)>,
+    receiver: Receiver<(A, HotkeyState)>,
+}
+
+impl<A: Clone + Send + 'static> HotkeyMap<A> {
+    /// Create a map with a new [`HotkeyManager`]
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_manager(HotkeyManager::new()?))
+    }
+
+    /// Create a map around a manager with non-default settings, see
+    /// [`HotkeyManager::builder`]
+    ///
+    /// Hotkeys the manager already has keep delivering their events through
+    /// it, not the map.
+    pub fn with_manager(manager: HotkeyManager) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            manager,
+            bindings: Mutex::new(Vec::new()),
+            sender,
+            receiver,
+        }
+    }
+
+    /// The manager the hotkeys are registered with, e.g. to
+    /// [`suspend`](HotkeyManager::suspend) them
+    pub fn manager(&self) -> &HotkeyManager {
+        &self.manager
+    }
+
+    /// Register `hotkey`, reporting its events as `action`
+    ///
+    /// Fails like [`HotkeyManager::register`]. Several hotkeys may share an
+    /// action.
+    pub fn register(&self, hotkey: Hotkey, action: A) -> Result<HotkeyId> {
+        let mut bindings = self.bindings.lock().map_err(|_| Error::MutexPoisoned)?;
+        let sender = self.sender.clone();
+        let reported = action.clone();
+        let id = self.manager.register_with_callback(hotkey, move |event| {
+            let _ = sender.send((reported.clone(), event.state));
+        })?;
+        bindings.push((id, hotkey, action));
+        Ok(id)
+    }
+
+    /// Register each hotkey with its action, carrying on past the ones that
+    /// fail
+    ///
+    /// Returns a result per binding, in order, like
+    /// [`HotkeyManager::register_many`].
+    pub fn register_bindings(&self, bindings: &[(Hotkey, A)]) -> Vec<Result<HotkeyId>> {
+        bindings
+            .iter()
+            .map(|(hotkey, action)| self.register(*hotkey, action.clone()))
+            .collect()
+    }
+
+    /// Unregister a hotkey by its ID, returning its action
+    ///
+    /// If the hotkey is pressed, a final
+    /// [`HotkeyState::Released`] is still reported for its action.
+    pub fn unregister(&self, id: HotkeyId) -> Result<A> {
+        let mut bindings = self.bindings.lock().map_err(|_| Error::MutexPoisoned)?;
+        let index = bindings
+            .iter()
+            .position(|(bound, _, _)| *bound == id)
+            .ok_or(Error::HotkeyNotFound(id))?;
+        self.manager.unregister(id)?;
+        Ok(bindings.remove(index).2)
+    }
+
+    /// Get the action of a registered hotkey
+    pub fn action(&self, id: HotkeyId) -> Option<A> {
+        let bindings = self.bindings.lock().ok()?;
+        bindings
+            .iter()
+            .find(|(bound, _, _)| *bound == id)
+            .map(|(_, _, action)| action.clone())
+    }
+
+    /// Every registered hotkey with its action, in registration order
+    pub fn bindings(&self) -> Vec<(Hotkey, A)> {
+        let Ok(bindings) = self.bindings.lock() else {
+            return Vec::new();
+        };
+        bindings
+            .iter()
+            .map(|(_, hotkey, action)| (*hotkey, action.clone()))
+            .collect()
+    }
+
+    /// Blocking receive for the action of a hotkey event
+    pub fn recv(&self) -> Result<(A, HotkeyState)> {
+        self.receiver.recv().map_err(|_| Error::EventLoopNotRunning)
+    }
+
+    /// Receive the action of a hotkey event, waiting up to `timeout`
+    ///
+    /// Returns [`Error::Timeout`] if none came in time.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<(A, HotkeyState)> {
+        self.receiver.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => Error::Timeout,
+            RecvTimeoutError::Disconnected => Error::EventLoopNotRunning,
+        })
+    }
+
+    /// Non-blocking receive for the action of a hotkey event
+    pub fn try_recv(&self) -> Option<(A, HotkeyState)> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Blocking iterator over the actions of hotkey events
+    pub fn events(&self) -> impl Iterator<Item = (A, HotkeyState)> + '_ {
+        std::iter::from_fn(|| self.recv().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::listener::Backend;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Action {
+        Record,
+        Palette,
+    }
+
+    fn keymap() -> HotkeyMap<Action> {
+        let manager = HotkeyManager::builder().backend(Backend::Headless).build().unwrap();
+        HotkeyMap::with_manager(manager)
+    }
+
+    #[test]
+    fn bindings_roundtrip_through_serde() {
+        let keymap = keymap();
+        keymap.register("Ctrl+Alt+R".parse().unwrap(), Action::Record).unwrap();
+        let palette = keymap.register("Ctrl+Shift+P".parse().unwrap(), Action::Palette).unwrap();
+        assert_eq!(keymap.action(palette), Some(Action::Palette));
+
+        let json = serde_json::to_string(&keymap.bindings()).unwrap();
+        let restored = self::keymap();
+        let bindings: Vec<(Hotkey, Action)> = serde_json::from_str(&json).unwrap();
+        assert!(restored.register_bindings(&bindings).iter().all(Result::is_ok));
+        assert_eq!(restored.bindings(), keymap.bindings());
+    }
+
+    #[test]
+    fn unregister_returns_the_action() {
+        let keymap = keymap();
+        let id = keymap.register("Ctrl+Alt+R".parse().unwrap(), Action::Record).unwrap();
+        assert!(keymap.register("Ctrl+Alt+R".parse().unwrap(), Action::Palette).is_err());
+
+        assert_eq!(keymap.unregister(id).unwrap(), Action::Record);
+        assert!(matches!(keymap.unregister(id), Err(Error::HotkeyNotFound(_))));
+        assert!(keymap.bindings().is_empty() && keymap.manager().hotkey_count() == 0);
+        assert!(keymap.try_recv().is_none());
+    }
+}
@@ -0,0 +1,139 @@
+//! Version and capability report for embedding in bug reports
+
+use serde::{Deserialize, Serialize};
+
+use crate::listener::Backend;
+use crate::platform::{env, frontmost};
+
+/// What handy-keys can do in this process, see [`runtime_info`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeInfo {
+    /// Version of the handy-keys crate
+    pub version: String,
+    /// Operating system, as in [`std::env::consts::OS`]
+    pub os: String,
+    /// Backend selected by the environment, or the default if none is
+    ///
+    /// A backend chosen in code with a builder isn't reflected here.
+    pub backend: Backend,
+    /// Whether `HANDY_KEYS_NO_BLOCKING` turns off blocking
+    pub blocking_disabled: bool,
+    pub capabilities: Capabilities,
+    /// Whether accessibility permission is granted, on macOS only
+    pub accessibility: Option<bool>,
+    /// Cargo features handy-keys was built with
+    pub features: Vec<String>,
+}
+
+/// Platform capabilities, which differ between operating systems and backends
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Registered hotkeys can be kept from reaching other apps
+    ///
+    /// False on Wayland, where compositors may not let events be blocked.
+    pub blocking: bool,
+    /// Listeners remove their OS hooks when stopped, so
+    /// [`prepare_unload`](crate::prepare_unload) can succeed
+    pub hook_removal: bool,
+    /// Held modifiers can be read from the OS to fix stuck ones
+    pub modifier_query: bool,
+    /// [`HookDiagnostics`](crate::HookDiagnostics) includes hook latency
+    pub hook_latency: bool,
+    /// [`frontmost_app`](crate::frontmost_app) works, so hotkeys can be
+    /// scoped to an app
+    pub app_scoping: bool,
+}
+
+/// Describe this build of handy-keys and what it can do here
+///
+/// Meant for host apps' diagnostics and bug reports. Doesn't install any
+/// hooks.
+pub fn runtime_info() -> RuntimeInfo {
+    let backend = env::backend().ok().flatten().unwrap_or_default();
+    let accessibility = if cfg!(target_os = "macos") {
+        Some(accessibility_granted())
+    } else {
+        None
+    };
+
+    RuntimeInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        backend,
+        blocking_disabled: env::blocking_disabled(),
+        capabilities: capabilities(backend),
+        accessibility,
+        features: enabled_features().map(str::to_string).collect(),
+    }
+}
+
+fn capabilities(backend: Backend) -> Capabilities {
+    if backend == Backend::Headless {
+        return Capabilities {
+            blocking: false,
+            hook_removal: true,
+            modifier_query: false,
+            hook_latency: false,
+            app_scoping: false,
+        };
+    }
+    let linux = cfg!(target_os = "linux");
+    Capabilities {
+        blocking: !(linux && is_wayland()),
+        hook_removal: !linux,
+        modifier_query: !linux,
+        hook_latency: cfg!(target_os = "windows"),
+        app_scoping: frontmost::SUPPORTED,
+    }
+}
+
+fn is_wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|session| session == "wayland")
+}
+
+#[cfg(target_os = "macos")]
+fn accessibility_granted() -> bool {
+    crate::check_accessibility()
+}
+
+#[cfg(not(target_os = "macos"))]
+fn accessibility_granted() -> bool {
+    true
+}
+
+fn enabled_features() -> impl Iterator<Item = &'static str> {
+    [
+        ("async", cfg!(feature = "async")),
+        ("egui", cfg!(feature = "egui")),
+        ("rescue", cfg!(feature = "rescue")),
+        ("winit", cfg!(feature = "winit")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_build_and_platform() {
+        let info = runtime_info();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.os, std::env::consts::OS);
+        assert_eq!(info.features.contains(&"async".to_string()), cfg!(feature = "async"));
+        assert_eq!(info.accessibility.is_some(), cfg!(target_os = "macos"));
+
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(serde_json::from_str::<RuntimeInfo>(&json).unwrap(), info);
+    }
+
+    #[test]
+    fn headless_backend_cant_block() {
+        let capabilities = capabilities(Backend::Headless);
+        assert!(!capabilities.blocking);
+        assert!(capabilities.hook_removal);
+    }
+}
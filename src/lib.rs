@@ -12,6 +12,8 @@
 //! - **String parsing**: Parse hotkeys from strings like `"Ctrl+Alt+Space"`
 //! - **Hotkey recording**: Low-level [`KeyboardListener`] for implementing
 //!   "record a hotkey" UI flows
+//! - **Macro recording**: Capture timed key/mouse sequences as a serializable [`Macro`]
+//! - **Cheat sheets**: Render a [`Keymap`] of your hotkeys as Markdown or HTML
 //! - **Serde support**: All types implement `Serialize`/`Deserialize`
 //!
 //! # Quick Start
@@ -33,7 +35,7 @@
 //!     println!("Registered hotkeys: {:?}, {:?}", id, id2);
 //!
 //!     // Wait for hotkey events
-//!     while let Ok(event) = manager.recv() {
+//!     for event in manager.events() {
 //!         println!("Hotkey triggered: {:?}", event.id);
 //!     }
 //!
@@ -51,7 +53,7 @@
 //! let listener = KeyboardListener::new()?;
 //!
 //! // Listen for key events
-//! while let Ok(event) = listener.recv() {
+//! for event in listener.events() {
 //!     if event.is_key_down {
 //!         if let Ok(hotkey) = event.as_hotkey() {
 //!             println!("User pressed: {}", hotkey);
@@ -92,17 +94,61 @@
 //!
 //! Uses [rdev](https://crates.io/crates/rdev). On Wayland, hotkey blocking may not
 //! work due to compositor restrictions.
+//!
+//! ## Headless (CI and containers)
+//!
+//! Set `HANDY_KEYS_HEADLESS=1` to run without a display server or permissions.
+//! Listeners and managers start without installing OS hooks and never receive
+//! events, and `check_accessibility()` returns `true`.
 
+mod channel;
+mod config;
+mod engine;
+#[cfg(feature = "egui")]
+mod egui_widget;
+#[cfg(feature = "winit")]
+mod winit_interop;
 mod error;
+pub mod ffi;
+mod hotkey_map;
+mod info;
 mod listener;
 mod manager;
 mod platform;
+mod recorder;
+mod shared_hook;
+mod sink;
+#[cfg(feature = "rescue")]
+mod rescue;
+#[cfg(test)]
+mod test_support;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod types;
 
+pub use config::{Binding, HotkeyConfig};
+pub use engine::{MatchEngine, Precedence};
 pub use error::{Error, Result};
-pub use listener::{BlockingHotkeys, KeyboardListener};
-pub use manager::HotkeyManager;
-pub use types::{Hotkey, HotkeyEvent, HotkeyId, HotkeyState, Key, KeyEvent, Modifiers};
+pub use hotkey_map::HotkeyMap;
+pub use info::{runtime_info, Capabilities, RuntimeInfo};
+pub use listener::{
+    Backend, BlockingHotkeys, CaptureSession, HookDiagnostics, KeyMapping, KeyboardListener, KeyboardListenerBuilder, SystemEvent,
+    TapLocation, TapPlacement, ThreadPriority, SUPPRESS_ALL_TIMEOUT,
+};
+pub use manager::{HotkeyManager, HotkeyManagerBuilder, PendingRegistration, RegisteredHotkey};
+pub use platform::frontmost::frontmost_app;
+pub use platform::threads::prepare_unload;
+pub use recorder::HotkeyRecorder;
+pub use sink::EventSink;
+pub use types::{
+    CanonicalizeOptions, Conflict, ConflictKind, DisplayStyle, HookDecision, Hotkey, HotkeyEvent, HotkeyId, HotkeyParser, HotkeySequence, HotkeyState, Key, KeyEquivalent, KeyEvent, Keymap, KeymapEntry,
+    Macro, MacroRecorder, MacroStep, Modifiers, PhysicalHotkey, Scancode,
+};
+
+#[cfg(feature = "egui")]
+pub use egui_widget::HotkeyField;
+#[cfg(feature = "winit")]
+pub use winit_interop::WinitKeyEvent;
 
 #[cfg(target_os = "macos")]
 pub use platform::macos::{check_accessibility, open_accessibility_settings};
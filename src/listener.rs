@@ -9,17 +9,32 @@
 //! - **Windows**: Uses low-level keyboard hooks. Clean thread shutdown.
 //! - **Linux**: Uses rdev. On Wayland, blocking may not work due to
 //!   compositor restrictions. Thread cleanup is limited.
+//! - **Headless**: With `HANDY_KEYS_HEADLESS=1`, no OS hooks are installed and
+//!   no events are produced, for CI and containers.
 
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{Receiver, TryRecvError};
-use std::sync::Arc;
-use std::thread::JoinHandle;
-use std::time::Duration;
+use std::sync::mpsc::{Sender, TryRecvError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
+use crate::channel::Receiver;
 use crate::error::{Error, Result};
-use crate::types::KeyEvent;
+use crate::platform::env;
+use crate::platform::state::{
+    BlockingMembers, ExcludedApps, HotkeyGuards, ListenerConfig, LooseHotkeys, SharedDiagnostics, SuppressAll,
+    TypingDetector,
+};
+use crate::platform::threads::Handle;
+use crate::sink::{EventSink, SharedSink};
+use crate::types::{Key, KeyEvent, Macro, MacroRecorder};
 
-pub use crate::platform::state::BlockingHotkeys;
+pub use crate::platform::env::Backend;
+pub use crate::platform::state::{
+    BlockingHotkeys, HookDiagnostics, KeyMapping, SystemEvent, TapLocation, TapPlacement, ThreadPriority,
+};
+
+/// How long [`KeyboardListener::set_suppress_all`] lasts unless renewed
+pub const SUPPRESS_ALL_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Platform-agnostic Keyboard Listener
 ///
@@ -27,9 +42,17 @@ pub use crate::platform::state::BlockingHotkeys;
 /// registered hotkeys.
 pub struct KeyboardListener {
     event_receiver: Receiver<KeyEvent>,
-    _thread_handle: Option<JoinHandle<()>>,
+    _thread_handle: Option<Handle>,
     running: Arc<AtomicBool>,
     blocking_hotkeys: Option<BlockingHotkeys>,
+    suppress_all: SuppressAll,
+    diagnostics: SharedDiagnostics,
+    /// See [`KeyboardListenerBuilder::coalesce_modifiers`]
+    coalesce_window: Option<Duration>,
+    /// An event received while coalescing that wasn't merged
+    pending: Mutex<Option<KeyEvent>>,
+    /// Why the Linux input grab stopped, once it has
+    grab_failure: Arc<OnceLock<String>>,
 }
 
 impl KeyboardListener {
@@ -39,54 +62,101 @@ impl KeyboardListener {
     ///
     /// On macOS, this will check for accessibility permissions and fail if not granted.
     pub fn new() -> Result<Self> {
-        Self::new_internal(None)
+        Self::builder().build()
+    }
+
+    /// Create a builder for a KeyboardListener with non-default settings
+    pub fn builder() -> KeyboardListenerBuilder {
+        KeyboardListenerBuilder::default()
     }
 
     /// Create a new KeyboardListener with blocking support
     ///
     /// Events matching hotkeys in the provided set will be blocked from reaching
     /// other applications. The set can be modified after creation to add/remove
-    /// hotkeys dynamically.
+    /// hotkeys dynamically. Mouse buttons are blocked like keys, so a hotkey
+    /// such as `Ctrl+MouseX1` consumes the click. Scroll-wheel events always
+    /// pass through, as no [`Key`](crate::Key) stands for the wheel.
     ///
     /// Note: On Wayland, blocking may not work due to compositor restrictions.
     pub fn new_with_blocking(blocking_hotkeys: BlockingHotkeys) -> Result<Self> {
-        Self::new_internal(Some(blocking_hotkeys))
+        Self::builder().blocking(blocking_hotkeys).build()
     }
 
-    fn new_internal(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<Self> {
+    fn new_internal(mut config: ListenerConfig) -> Result<Self> {
+        let backend = env::backend()?.unwrap_or(config.backend);
+        let suppress_all = Arc::clone(&config.suppress_all);
+        let diagnostics = Arc::clone(&config.diagnostics);
+        let coalesce_window = config.coalesce_window;
+        if env::blocking_disabled() {
+            // The listener keeps the caller's settings, but the hooks never see them
+            config.blocking_hotkeys = None;
+            config.suppress_all = SuppressAll::default();
+        }
+        if backend == Backend::Headless {
+            use crate::platform::headless;
+            let state = headless::spawn(config)?;
+            return Ok(KeyboardListener {
+                event_receiver: state.event_receiver,
+                _thread_handle: state.thread_handle,
+                running: state.running,
+                blocking_hotkeys: state.blocking_hotkeys,
+                suppress_all,
+                diagnostics,
+                coalesce_window,
+                pending: Mutex::new(None),
+                grab_failure: Arc::default(),
+            });
+        }
+
         #[cfg(target_os = "macos")]
         {
             use crate::platform::macos::listener;
-            let state = listener::spawn(blocking_hotkeys)?;
+            let state = listener::spawn(config)?;
             Ok(KeyboardListener {
                 event_receiver: state.event_receiver,
                 _thread_handle: state.thread_handle,
                 running: state.running,
                 blocking_hotkeys: state.blocking_hotkeys,
+                suppress_all,
+                diagnostics,
+                coalesce_window,
+                pending: Mutex::new(None),
+                grab_failure: Arc::default(),
             })
         }
 
         #[cfg(target_os = "windows")]
         {
             use crate::platform::windows::listener;
-            let state = listener::spawn(blocking_hotkeys)?;
+            let state = listener::spawn(config)?;
             Ok(KeyboardListener {
                 event_receiver: state.event_receiver,
                 _thread_handle: state.thread_handle,
                 running: state.running,
                 blocking_hotkeys: state.blocking_hotkeys,
+                suppress_all,
+                diagnostics,
+                coalesce_window,
+                pending: Mutex::new(None),
+                grab_failure: Arc::default(),
             })
         }
 
         #[cfg(target_os = "linux")]
         {
             use crate::platform::linux::listener;
-            let state = listener::spawn(blocking_hotkeys)?;
+            let state = listener::spawn(config)?;
             Ok(KeyboardListener {
                 event_receiver: state.event_receiver,
                 _thread_handle: state.thread_handle,
                 running: state.running,
                 blocking_hotkeys: state.blocking_hotkeys,
+                suppress_all,
+                diagnostics,
+                coalesce_window,
+                pending: Mutex::new(None),
+                grab_failure: state.grab_failure,
             })
         }
     }
@@ -96,35 +166,548 @@ impl KeyboardListener {
         self.blocking_hotkeys.as_ref()
     }
 
+    /// Block every key from reaching other applications, or stop doing so
+    ///
+    /// Useful while a "record hotkey" dialog is open, so the combo being
+    /// recorded doesn't also trigger something in the focused app. Events are
+    /// still delivered to this listener. Modifier keys and mouse buttons are
+    /// never blocked, so modifiers can't get stuck.
+    ///
+    /// As a safety net, suppression ends by itself after
+    /// [`SUPPRESS_ALL_TIMEOUT`]. Call this again to extend it.
+    ///
+    /// Note: On Wayland, blocking may not work due to compositor restrictions.
+    pub fn set_suppress_all(&self, suppress: bool) {
+        if let Ok(mut until) = self.suppress_all.lock() {
+            *until = suppress.then(|| Instant::now() + SUPPRESS_ALL_TIMEOUT);
+        }
+    }
+
+    /// Receive and block every key for up to `duration`
+    ///
+    /// For interactions that take over the keyboard for a moment, like
+    /// recording a hotkey or a "press any key to cancel" prompt. Keys are
+    /// blocked as with [`set_suppress_all`](Self::set_suppress_all) until the
+    /// returned session is dropped or `duration` is over, whichever comes
+    /// first. Durations over [`SUPPRESS_ALL_TIMEOUT`] are shortened to it, so
+    /// a forgotten session can't keep the keyboard for long.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use handy_keys::{Key, KeyboardListener};
+    ///
+    /// let listener = KeyboardListener::new()?;
+    /// let mut session = listener.capture_exclusive(Duration::from_secs(5));
+    /// let cancelled = session.any(|event| event.key == Some(Key::Escape));
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn capture_exclusive(&self, duration: Duration) -> CaptureSession<'_> {
+        let deadline = Instant::now() + duration.min(SUPPRESS_ALL_TIMEOUT);
+        if let Ok(mut until) = self.suppress_all.lock() {
+            *until = Some(deadline);
+        }
+        CaptureSession {
+            listener: self,
+            deadline,
+        }
+    }
+
+    /// How well the OS hook is keeping up, see [`HookDiagnostics`]
+    ///
+    /// Only measured on Windows; always empty elsewhere.
+    pub fn hook_diagnostics(&self) -> HookDiagnostics {
+        self.diagnostics.lock().map(|d| *d).unwrap_or_default()
+    }
+
     /// Blocking receive for key events
     ///
-    /// Blocks until a key event is received or the listener stops.
+    /// Blocks until a key event is received or the listener stops. Returns
+    /// [`Error::GrabFailed`] if the listener stopped because it lost, or
+    /// never got, the Linux input grab.
     pub fn recv(&self) -> Result<KeyEvent> {
-        self.event_receiver
-            .recv()
-            .map_err(|_| Error::EventLoopNotRunning)
+        let event = match self.take_pending() {
+            Some(event) => event,
+            None => self.event_receiver.recv().map_err(|_| self.stopped())?,
+        };
+        Ok(self.coalesce(event, true))
     }
 
     /// Blocking receive with timeout
     ///
     /// Blocks until a key event is received, the timeout expires, or the listener stops.
+    /// Stopping reports [`Error::GrabFailed`] as [`recv`](Self::recv) does.
     pub fn recv_timeout(&self, timeout: Duration) -> Result<KeyEvent> {
-        self.event_receiver.recv_timeout(timeout).map_err(|e| match e {
-            std::sync::mpsc::RecvTimeoutError::Timeout => Error::Timeout,
-            std::sync::mpsc::RecvTimeoutError::Disconnected => Error::EventLoopNotRunning,
-        })
+        let event = match self.take_pending() {
+            Some(event) => event,
+            None => self.event_receiver.recv_timeout(timeout).map_err(|e| match e {
+                std::sync::mpsc::RecvTimeoutError::Timeout => Error::Timeout,
+                std::sync::mpsc::RecvTimeoutError::Disconnected => self.stopped(),
+            })?,
+        };
+        Ok(self.coalesce(event, true))
+    }
+
+    /// Blocking iterator over key events
+    ///
+    /// Yields events as [`recv`](Self::recv) receives them, ending once the
+    /// listener stops.
+    pub fn events(&self) -> impl Iterator<Item = KeyEvent> + '_ {
+        std::iter::from_fn(|| self.recv().ok())
+    }
+
+    /// Record a macro from incoming key events
+    ///
+    /// Blocks and records every event until `stop_key` is pressed. The events
+    /// of the stop key itself are not included in the macro.
+    pub fn record_macro(&self, stop_key: Key) -> Result<Macro> {
+        let mut recorder = MacroRecorder::new();
+        loop {
+            let event = self.recv()?;
+            if event.key == Some(stop_key) {
+                if event.is_key_down {
+                    return Ok(recorder.finish());
+                }
+                continue;
+            }
+            recorder.record(event);
+        }
     }
 
     /// Non-blocking receive for key events
     ///
     /// Returns `Some(event)` if an event is available, `None` otherwise.
     pub fn try_recv(&self) -> Option<KeyEvent> {
-        match self.event_receiver.try_recv() {
-            Ok(event) => Some(event),
-            Err(TryRecvError::Empty) => None,
-            Err(TryRecvError::Disconnected) => None,
+        let event = match self.take_pending() {
+            Some(event) => event,
+            None => match self.event_receiver.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => return None,
+            },
+        };
+        Some(self.coalesce(event, false))
+    }
+
+    /// The error for a receive on a listener that stopped
+    fn stopped(&self) -> Error {
+        match self.grab_failure.get() {
+            Some(failure) => Error::GrabFailed(failure.clone()),
+            None => Error::EventLoopNotRunning,
         }
     }
+
+    /// Take every key event received so far, without blocking
+    ///
+    /// Meant for frame-based loops (games, immediate-mode UIs) that check for
+    /// events once per frame. Modifier changes are coalesced as with
+    /// [`try_recv`](Self::try_recv).
+    pub fn drain(&self) -> Vec<KeyEvent> {
+        std::iter::from_fn(|| self.try_recv()).collect()
+    }
+
+    /// File descriptor that becomes readable when key events are queued
+    ///
+    /// For event loops built on `poll`, `epoll` or mio, to wait for key events
+    /// alongside sockets without a dedicated thread. It stays readable until
+    /// [`try_recv`](Self::try_recv) finds nothing, so take every event (e.g.
+    /// with [`drain`](Self::drain)) each time it's ready. Also readable once
+    /// the listener stops. Don't read from it yourself.
+    #[cfg(unix)]
+    pub fn ready_fd(&self) -> Result<std::os::fd::BorrowedFd<'_>> {
+        Ok(self.event_receiver.readiness()?.fd())
+    }
+
+    /// Event that is signaled when key events are queued
+    ///
+    /// For event loops built on `WaitForMultipleObjects`, to wait for key events
+    /// alongside other handles without a dedicated thread. It stays signaled
+    /// until [`try_recv`](Self::try_recv) finds nothing, so take every event
+    /// (e.g. with [`drain`](Self::drain)) each time it's signaled. Also
+    /// signaled once the listener stops. Don't reset it yourself.
+    #[cfg(windows)]
+    pub fn ready_handle(&self) -> Result<std::os::windows::io::BorrowedHandle<'_>> {
+        Ok(self.event_receiver.readiness()?.handle())
+    }
+
+    fn take_pending(&self) -> Option<KeyEvent> {
+        self.pending.lock().ok().and_then(|mut pending| pending.take())
+    }
+
+    /// Merge the modifier changes following `event` into it, if coalescing
+    ///
+    /// With `wait` set, waits for changes until the coalesce window after
+    /// `event` ends; otherwise only merges changes already received. The first
+    /// event that can't be merged is kept for the next receive.
+    fn coalesce(&self, mut event: KeyEvent, wait: bool) -> KeyEvent {
+        let Some(window) = self.coalesce_window else {
+            return event;
+        };
+        let deadline = Instant::now() + window;
+        while is_modifier_change(&event) {
+            let next = if wait {
+                self.event_receiver
+                    .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    .ok()
+            } else {
+                self.event_receiver.try_recv().ok()
+            };
+            let Some(next) = next else {
+                break;
+            };
+            if !merge_modifier_change(&mut event, &next) {
+                if let Ok(mut pending) = self.pending.lock() {
+                    *pending = Some(next);
+                }
+                self.event_receiver.mark_ready();
+                break;
+            }
+        }
+        event
+    }
+
+    /// Stop the listener, waiting up to `timeout` for its OS hooks to be removed
+    ///
+    /// Dropping the listener also stops it, but waits as long as it takes and
+    /// can't report problems. Call this to stop early, for example before an
+    /// updater replaces the binary. Returns [`Error::Timeout`] if the hook
+    /// thread is still running after `timeout`; it keeps shutting down in the
+    /// background.
+    ///
+    /// Note: On Linux, rdev's hook can't be removed. The listener stops
+    /// delivering and blocking events right away and this returns `Ok`.
+    pub fn shutdown(mut self, timeout: Duration) -> Result<()> {
+        self.running.store(false, Ordering::SeqCst);
+
+        match self._thread_handle.take() {
+            // rdev::grab() never returns, so there's nothing to wait for on Linux
+            Some(handle) if cfg!(any(target_os = "macos", target_os = "windows")) => {
+                handle.join_timeout(timeout)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Wait for the next key event without blocking a thread
+    ///
+    /// Works with any async runtime. Returns an error once the listener stops.
+    #[cfg(feature = "async")]
+    pub async fn recv_async(&self) -> Result<KeyEvent> {
+        std::future::poll_fn(|cx| self.poll_event(cx))
+            .await
+            .ok_or(Error::EventLoopNotRunning)
+    }
+
+    /// Poll for the next event, coalescing only changes already received
+    #[cfg(feature = "async")]
+    fn poll_event(&self, cx: &mut std::task::Context<'_>) -> std::task::Poll<Option<KeyEvent>> {
+        if let Some(event) = self.take_pending() {
+            return std::task::Poll::Ready(Some(self.coalesce(event, false)));
+        }
+        self.event_receiver
+            .poll_recv(cx)
+            .map(|event| event.map(|event| self.coalesce(event, false)))
+    }
+
+    /// Stream of key events, ending when the listener stops
+    ///
+    /// Works with any async runtime. Events are shared with
+    /// [`recv`](Self::recv), so consume them from one place at a time.
+    #[cfg(feature = "async")]
+    pub fn events_stream(&self) -> impl futures_core::Stream<Item = KeyEvent> + '_ {
+        EventStream { listener: self }
+    }
+}
+
+/// Stream of a [`KeyboardListener`]'s events
+#[cfg(feature = "async")]
+struct EventStream<'a> {
+    listener: &'a KeyboardListener,
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for EventStream<'_> {
+    type Item = KeyEvent;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<KeyEvent>> {
+        self.listener.poll_event(cx)
+    }
+}
+
+/// Whether `event` only reports a modifier being pressed or released
+fn is_modifier_change(event: &KeyEvent) -> bool {
+    event.key.is_none() && event.changed_modifier.is_some()
+}
+
+/// Merge `next` into `event` if both change modifiers in the same direction
+///
+/// The merged event carries the final modifiers and every modifier changed.
+fn merge_modifier_change(event: &mut KeyEvent, next: &KeyEvent) -> bool {
+    if !is_modifier_change(event)
+        || !is_modifier_change(next)
+        || event.is_key_down != next.is_key_down
+    {
+        return false;
+    }
+    event.modifiers = next.modifiers;
+    event.changed_modifier = event.changed_modifier.zip(next.changed_modifier).map(|(a, b)| a | b);
+    true
+}
+
+/// Keys received while a listener blocks all of them, see
+/// [`KeyboardListener::capture_exclusive`]
+///
+/// Iterates over key events until the session's time is up or the listener
+/// stops. Keys pass through to other applications again once it's dropped.
+pub struct CaptureSession<'a> {
+    listener: &'a KeyboardListener,
+    deadline: Instant,
+}
+
+impl CaptureSession<'_> {
+    /// Time left before keys pass through again
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+impl Iterator for CaptureSession<'_> {
+    type Item = KeyEvent;
+
+    fn next(&mut self) -> Option<KeyEvent> {
+        let remaining = self.remaining();
+        if remaining.is_zero() {
+            return None;
+        }
+        self.listener.recv_timeout(remaining).ok()
+    }
+}
+
+impl Drop for CaptureSession<'_> {
+    fn drop(&mut self) {
+        self.listener.set_suppress_all(false);
+    }
+}
+
+/// Builder for a [`KeyboardListener`]
+///
+/// ```no_run
+/// use handy_keys::KeyboardListener;
+/// use std::time::Duration;
+///
+/// let listener = KeyboardListener::builder()
+///     .modifier_timeout(Duration::from_secs(10 * 60))
+///     .build()?;
+/// # Ok::<(), handy_keys::Error>(())
+/// ```
+#[derive(Default)]
+pub struct KeyboardListenerBuilder {
+    config: ListenerConfig,
+}
+
+impl KeyboardListenerBuilder {
+    /// Block events matching hotkeys in the provided set
+    ///
+    /// See [`KeyboardListener::new_with_blocking`].
+    pub fn blocking(mut self, blocking_hotkeys: BlockingHotkeys) -> Self {
+        self.config.blocking_hotkeys = Some(blocking_hotkeys);
+        self
+    }
+
+    /// Release modifiers that appear held for longer than `timeout` without any other input
+    ///
+    /// A missed key up can leave a modifier looking held, which breaks hotkey
+    /// matching until the key is pressed again. With a timeout set, the
+    /// listener resyncs stuck modifiers from the OS and emits a release event
+    /// for each one it drops.
+    ///
+    /// Note: On Linux, the OS modifier state can't be queried, so stuck
+    /// modifiers are assumed released.
+    pub fn modifier_timeout(mut self, timeout: Duration) -> Self {
+        self.config.modifier_timeout = Some(timeout);
+        self
+    }
+
+    /// Choose how key events are identified as a [`Key`]
+    ///
+    /// See [`KeyMapping`]. Only affects Windows.
+    pub fn key_mapping(mut self, key_mapping: KeyMapping) -> Self {
+        self.config.key_mapping = key_mapping;
+        self
+    }
+
+    /// Ignore input for `delay` after the listener starts
+    ///
+    /// Keystrokes already in flight at startup, such as the Enter key up from
+    /// launching the app, are neither delivered nor blocked during the delay.
+    pub fn arm_delay(mut self, delay: Duration) -> Self {
+        self.config.arm_delay = delay;
+        self
+    }
+
+    /// Drop key downs that repeat the same key within `window`
+    ///
+    /// Some faulty keyboards send a key down twice, microseconds apart. With a
+    /// window set (a few milliseconds is enough), the repeat and its key up
+    /// aren't delivered, though they're still blocked like the original.
+    /// Autorepeat is much slower and unaffected. Off by default.
+    pub fn dedupe_window(mut self, window: Duration) -> Self {
+        self.config.dedupe_window = window;
+        self
+    }
+
+    /// Reinstall the OS hooks every `interval`
+    ///
+    /// On Windows, hooks installed later by other apps (AutoHotkey, antivirus
+    /// software) run before ours and can delay events or keep them from
+    /// arriving. Reinstalling puts our hook back at the head of the chain.
+    /// Check [`KeyboardListener::hook_diagnostics`] to see if it's needed.
+    /// Only affects Windows.
+    pub fn hook_reinstall_interval(mut self, interval: Duration) -> Self {
+        self.config.hook_reinstall_interval = Some(interval);
+        self
+    }
+
+    /// Choose where the event tap runs relative to other apps' taps
+    ///
+    /// See [`TapPlacement`]. Only affects macOS.
+    pub fn tap_placement(mut self, placement: TapPlacement) -> Self {
+        self.config.tap_placement = placement;
+        self
+    }
+
+    /// Choose which stage of event delivery the event tap observes
+    ///
+    /// See [`TapLocation`]. Only affects macOS.
+    pub fn tap_location(mut self, location: TapLocation) -> Self {
+        self.config.tap_location = location;
+        self
+    }
+
+    /// Deliver events to `sink` instead of the listener's own channel
+    ///
+    /// See [`EventSink`]. [`recv`](KeyboardListener::recv) and the other
+    /// receiving methods then get nothing, and modifier changes aren't
+    /// coalesced.
+    pub fn event_sink(mut self, sink: impl EventSink<KeyEvent> + 'static) -> Self {
+        self.config.sink = Some(SharedSink::new(sink));
+        self
+    }
+
+    /// Set the scheduling priority of the thread running the OS hook
+    ///
+    /// See [`ThreadPriority`]. Building the listener fails with
+    /// [`Error::ThreadPriority`] if the OS doesn't allow it.
+    pub fn thread_priority(mut self, priority: ThreadPriority) -> Self {
+        self.config.thread_priority = priority;
+        self
+    }
+
+    /// Deliver modifiers pressed (or released) together as a single event
+    ///
+    /// By default, pressing Cmd+Shift delivers one event for Cmd and another
+    /// for Cmd+Shift. With a window set, modifier changes in the same
+    /// direction that arrive within `window` of the first are merged into one
+    /// event with the final modifiers, and `changed_modifier` holding all of
+    /// the modifiers that changed. Receiving a modifier change waits up to
+    /// `window` for more; [`try_recv`](KeyboardListener::try_recv) and the
+    /// async methods only merge changes already received. A few tens of
+    /// milliseconds covers modifiers pressed at once.
+    pub fn coalesce_modifiers(mut self, window: Duration) -> Self {
+        self.config.coalesce_window = Some(window);
+        self
+    }
+
+    /// Choose where events come from, see [`Backend`]
+    ///
+    /// The `HANDY_KEYS_BACKEND` and `HANDY_KEYS_HEADLESS` environment
+    /// variables take precedence.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.config.backend = backend;
+        self
+    }
+
+    /// Record diagnostics where the caller can read them
+    pub(crate) fn diagnostics(mut self, diagnostics: SharedDiagnostics) -> Self {
+        self.config.diagnostics = diagnostics;
+        self
+    }
+
+    /// Only block guarded hotkeys while their guard allows it
+    pub(crate) fn guards(mut self, guards: HotkeyGuards) -> Self {
+        self.config.guards = Some(guards);
+        self
+    }
+
+    /// Also block blocking hotkeys in `loose` with extra modifiers held
+    pub(crate) fn loose(mut self, loose: LooseHotkeys) -> Self {
+        self.config.loose = Some(loose);
+        self
+    }
+
+    /// Also block the hotkeys of `members`
+    pub(crate) fn members(mut self, members: BlockingMembers) -> Self {
+        self.config.members = Some(members);
+        self
+    }
+
+    /// Block nothing while one of `apps` is frontmost
+    pub(crate) fn excluded_apps(mut self, apps: ExcludedApps) -> Self {
+        self.config.excluded_apps = apps;
+        self
+    }
+
+    /// Don't block simple hotkeys while `typing` detects typing
+    pub(crate) fn typing(mut self, typing: Option<TypingDetector>) -> Self {
+        self.config.typing = typing;
+        self
+    }
+
+    /// Report keycodes that have no [`Key`] variant to `sender`
+    ///
+    /// Such keys are always delivered as [`Key::Other`] events. With a sender
+    /// set, each distinct keycode is also sent once, rate limited, so apps can
+    /// log which codes an unusual keyboard produces. Keep the receiving end
+    /// drained or dropped.
+    ///
+    /// ```no_run
+    /// use handy_keys::KeyboardListener;
+    /// use std::sync::mpsc;
+    ///
+    /// let (sender, unknown_keys) = mpsc::channel();
+    /// let listener = KeyboardListener::builder()
+    ///     .report_unknown_keys(sender)
+    ///     .build()?;
+    /// for code in unknown_keys.try_iter() {
+    ///     eprintln!("unmapped keycode {code:#X}");
+    /// }
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn report_unknown_keys(mut self, sender: Sender<u32>) -> Self {
+        self.config.unknown_keys = Some(sender);
+        self
+    }
+
+    /// Report [`SystemEvent`]s, like the system waking from sleep or the Linux
+    /// session going inactive after a VT switch, to `sender`
+    ///
+    /// Lets apps show that hotkeys are paused rather than silently not
+    /// firing, or resync their own state after a wake. Keep the receiving
+    /// end drained or dropped.
+    pub fn report_system_events(mut self, sender: Sender<SystemEvent>) -> Self {
+        self.config.system_events = Some(sender);
+        self
+    }
+
+    /// Create the KeyboardListener
+    ///
+    /// On macOS, this will check for accessibility permissions and fail if not granted.
+    pub fn build(self) -> Result<KeyboardListener> {
+        KeyboardListener::new_internal(self.config)
+    }
 }
 
 impl Drop for KeyboardListener {
@@ -136,7 +719,75 @@ impl Drop for KeyboardListener {
         // because rdev::grab() blocks indefinitely.
         #[cfg(any(target_os = "macos", target_os = "windows"))]
         if let Some(handle) = self._thread_handle.take() {
-            let _ = handle.join();
+            handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Modifiers;
+
+    fn modifier_event(modifiers: Modifiers, changed: Modifiers, is_key_down: bool) -> KeyEvent {
+        KeyEvent {
+            modifiers,
+            key: None,
+            is_key_down,
+            changed_modifier: Some(changed),
+            sides: Modifiers::empty(),
+            hook: None,
         }
     }
+
+    #[test]
+    fn capture_session_blocks_until_dropped() {
+        let listener = KeyboardListener::builder().backend(Backend::Headless).build().unwrap();
+        let suppressing = || listener.suppress_all.lock().unwrap().is_some();
+
+        let session = listener.capture_exclusive(Duration::from_secs(60));
+        assert!(session.remaining() <= SUPPRESS_ALL_TIMEOUT);
+        assert!(suppressing());
+        drop(session);
+        assert!(!suppressing());
+
+        let start = Instant::now();
+        assert_eq!(listener.capture_exclusive(Duration::from_millis(50)).count(), 0);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert!(!suppressing());
+    }
+
+    #[test]
+    fn modifiers_pressed_together_merge() {
+        let mut event = modifier_event(Modifiers::CMD, Modifiers::CMD, true);
+        let next = modifier_event(Modifiers::CMD | Modifiers::SHIFT, Modifiers::SHIFT, true);
+
+        assert!(merge_modifier_change(&mut event, &next));
+        assert_eq!(
+            event,
+            modifier_event(
+                Modifiers::CMD | Modifiers::SHIFT,
+                Modifiers::CMD | Modifiers::SHIFT,
+                true
+            )
+        );
+    }
+
+    #[test]
+    fn opposite_changes_and_keys_dont_merge() {
+        let mut event = modifier_event(Modifiers::CMD | Modifiers::SHIFT, Modifiers::SHIFT, true);
+        let release = modifier_event(Modifiers::CMD, Modifiers::SHIFT, false);
+        assert!(!merge_modifier_change(&mut event, &release));
+
+        let key = KeyEvent {
+            modifiers: Modifiers::CMD | Modifiers::SHIFT,
+            key: Some(crate::types::Key::K),
+            is_key_down: true,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        };
+        assert!(!merge_modifier_change(&mut event, &key));
+        assert_eq!(event.changed_modifier, Some(Modifiers::SHIFT));
+    }
 }
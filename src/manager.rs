@@ -1,96 +1,392 @@
 //! Platform-agnostic hotkey manager built on top of KeyboardListener
 
-use std::collections::{HashMap, HashSet};
-use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
-use std::sync::{Arc, Mutex};
-use std::thread::{self, JoinHandle};
-
+use std::collections::HashSet;
+use std::sync::mpsc::TryRecvError;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use crate::channel::{channel, Receiver, Sender};
+use crate::engine::{Callback, DoubleTap, MatchEngine, Precedence, SequenceProgress};
 use crate::error::{Error, Result};
-use crate::listener::{BlockingHotkeys, KeyboardListener};
-use crate::types::{Hotkey, HotkeyEvent, HotkeyId, HotkeyState, KeyEvent};
-
-/// Internal state shared between the manager and the processing thread
-struct ManagerState {
-    hotkeys: HashMap<HotkeyId, Hotkey>,
-    next_id: u32,
-    /// Track which hotkeys are currently pressed
-    pressed_hotkeys: HashSet<HotkeyId>,
+use crate::listener::{
+    Backend, BlockingHotkeys, HookDiagnostics, KeyMapping, KeyboardListener, SystemEvent,
+    TapLocation, TapPlacement, ThreadPriority,
+};
+use crate::platform::frontmost;
+use crate::platform::state::{
+    ExcludedApps, Guard, HotkeyGuards, LooseHotkeys, SharedDiagnostics, TypingDetector,
+    WakeDetector,
+};
+use crate::platform::threads::{self, Handle};
+use crate::recorder::{HotkeyRecorder, RecordingTap};
+use crate::shared_hook::Membership;
+use crate::sink::SharedSink;
+#[cfg(feature = "rescue")]
+use crate::rescue::Rescue;
+use crate::types::{Conflict, Hotkey, HotkeyEvent, HotkeyId, HotkeySequence, KeyEvent};
+
+/// How [`HotkeyManager::register_combos`] registers a hotkey
+#[derive(Default)]
+struct ComboOptions {
+    callback: Option<Callback>,
+    block: bool,
+    exclusive: bool,
+    loose: bool,
+    double_tap: Option<Duration>,
+    sequence: Option<(HotkeySequence, Duration)>,
+    guard: Option<Guard>,
+}
+
+/// Settings for the listeners a manager creates
+#[derive(Debug, Clone, Default)]
+struct ListenerOptions {
+    key_mapping: KeyMapping,
+    arm_delay: Duration,
+    dedupe_window: Duration,
+    hook_reinstall_interval: Option<Duration>,
+    tap_placement: TapPlacement,
+    tap_location: TapLocation,
+    thread_priority: ThreadPriority,
+    backend: Backend,
+    /// How long the event thread waits for input before checking whether it
+    /// should stop (if not the default)
+    poll_interval: Option<Duration>,
+    thread_name: Option<String>,
+    /// See [`HotkeyManagerBuilder::report_system_events`]
+    system_events: Option<std::sync::mpsc::Sender<SystemEvent>>,
+    /// Watched by the event thread, see [`crate::rescue`]
+    #[cfg(feature = "rescue")]
+    rescue: Option<Arc<Rescue>>,
+    /// Shared by every listener the manager creates
+    diagnostics: SharedDiagnostics,
+    /// Guards of blocked combos, shared with every listener
+    guards: HotkeyGuards,
+    /// Blocked combos that are also blocked with extra modifiers, shared with
+    /// every listener
+    loose: LooseHotkeys,
+    /// Shared with the manager's state and every listener
+    excluded_apps: ExcludedApps,
+    /// See [`HotkeyManagerBuilder::suppress_while_typing`]
+    typing: Option<TypingDetector>,
+    /// See [`HotkeyManagerBuilder::shared_listener`]
+    shared: bool,
+    /// Set while a [`HotkeyRecorder`] is open
+    recording: RecordingTap,
 }
 
-impl ManagerState {
-    fn new() -> Self {
-        Self {
-            hotkeys: HashMap::new(),
-            next_id: 0,
-            pressed_hotkeys: HashSet::new(),
+impl ListenerOptions {
+    /// Fail if the settings can't apply to a hook shared with other managers
+    ///
+    /// Excluded apps and typing are decided by the hook, once for every
+    /// manager attached to it, so no manager can have its own.
+    fn check_shareable(&self) -> Result<()> {
+        if self.typing.is_some() {
+            return Err(Error::SharedListener("suppress_while_typing".to_string()));
         }
+        if !self.excluded_apps.is_empty() {
+            return Err(Error::SharedListener("set_excluded_apps".to_string()));
+        }
+        Ok(())
     }
+}
 
-    /// Process a key event and return any matching hotkey events
-    fn process_event(&mut self, event: &KeyEvent) -> Vec<HotkeyEvent> {
-        let mut results = Vec::new();
+/// Where a worker's key events come from
+enum KeySource {
+    /// A listener of the manager's own
+    Own(KeyboardListener),
+    /// The hook shared by managers built with
+    /// [`HotkeyManagerBuilder::shared_listener`]
+    Shared(Membership),
+}
 
-        if event.is_key_down {
-            // Check for hotkeys that should be pressed
-            let to_press: Vec<HotkeyId> = self
-                .hotkeys
-                .iter()
-                .filter(|(&id, hotkey)| {
-                    hotkey.modifiers == event.modifiers
-                        && hotkey.key == event.key
-                        && !self.pressed_hotkeys.contains(&id)
-                })
-                .map(|(&id, _)| id)
-                .collect();
+impl KeySource {
+    fn recv_timeout(&self, timeout: Duration) -> Result<KeyEvent> {
+        match self {
+            KeySource::Own(listener) => listener.recv_timeout(timeout),
+            KeySource::Shared(membership) => membership.recv_timeout(timeout),
+        }
+    }
+}
 
-            for id in to_press {
-                self.pressed_hotkeys.insert(id);
-                results.push(HotkeyEvent {
-                    id,
-                    state: HotkeyState::Pressed,
-                });
-            }
+/// A running keyboard listener and the thread matching its events
+///
+/// Dropping the worker stops the thread, which in turn drops the listener and
+/// uninstalls the OS hooks.
+struct Worker {
+    thread_handle: Option<Handle>,
+    running: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl Worker {
+    fn spawn(
+        state: &Arc<Mutex<MatchEngine>>,
+        blocking_hotkeys: &BlockingHotkeys,
+        options: &ListenerOptions,
+        sender: &Sender<HotkeyEvent>,
+    ) -> Result<Self> {
+        let source = Self::source(blocking_hotkeys, options)?;
+        let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+        let thread_state = Arc::clone(state);
+        let thread_sender = sender.clone();
+        let thread_running = Arc::clone(&running);
+        let thread_blocking = Arc::clone(blocking_hotkeys);
+        let thread_options = options.clone();
+
+        let handle = threads::spawn_named(&running, options.thread_name.clone(), move || {
+            HotkeyManager::event_loop(
+                source,
+                thread_state,
+                thread_blocking,
+                thread_sender,
+                thread_running,
+                thread_options,
+            );
+        })?;
+
+        Ok(Self {
+            thread_handle: Some(handle),
+            running,
+        })
+    }
+
+    /// Install the OS hooks, or join the shared ones
+    fn source(blocking_hotkeys: &BlockingHotkeys, options: &ListenerOptions) -> Result<KeySource> {
+        let mut builder = KeyboardListener::builder()
+            .key_mapping(options.key_mapping)
+            .arm_delay(options.arm_delay)
+            .dedupe_window(options.dedupe_window)
+            .tap_placement(options.tap_placement)
+            .tap_location(options.tap_location)
+            .thread_priority(options.thread_priority)
+            .backend(options.backend)
+            .diagnostics(Arc::clone(&options.diagnostics));
+        if let Some(interval) = options.hook_reinstall_interval {
+            builder = builder.hook_reinstall_interval(interval);
+        }
+        if let Some(sender) = &options.system_events {
+            builder = builder.report_system_events(sender.clone());
+        }
+        let source = if options.shared {
+            // The hook decides exclusion and typing for every manager on it
+            options.check_shareable()?;
+            KeySource::Shared(Membership::join(
+                builder,
+                blocking_hotkeys,
+                &options.guards,
+                &options.loose,
+            )?)
         } else {
-            // Check for hotkeys that should be released
-            // A hotkey is released when either its key is released or its modifiers change
-            let to_release: Vec<HotkeyId> = self
-                .hotkeys
-                .iter()
-                .filter(|(&id, hotkey)| {
-                    self.pressed_hotkeys.contains(&id)
-                        && (hotkey.key == event.key
-                            || (event.key.is_none() && !event.modifiers.contains(hotkey.modifiers)))
-                })
-                .map(|(&id, _)| id)
-                .collect();
+            let builder = builder
+                .excluded_apps(options.excluded_apps.clone())
+                .typing(options.typing.clone())
+                .blocking(blocking_hotkeys.clone())
+                .guards(Arc::clone(&options.guards))
+                .loose(Arc::clone(&options.loose));
+            KeySource::Own(builder.build()?)
+        };
+        Ok(source)
+    }
+}
 
-            for id in to_release {
-                self.pressed_hotkeys.remove(&id);
-                results.push(HotkeyEvent {
-                    id,
-                    state: HotkeyState::Released,
-                });
+impl Worker {
+    /// Stop the thread, waiting up to `timeout` for it to remove the OS hooks
+    fn shutdown(mut self, timeout: Duration) -> Result<()> {
+        self.running
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        match self.thread_handle.take() {
+            Some(handle) if !handle.is_current() => {
+                handle.unpark();
+                handle.join_timeout(timeout)
             }
+            _ => Ok(()),
         }
+    }
+}
 
-        results
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.running
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        // Join the thread to ensure clean shutdown, unless a callback running
+        // on it is stopping the worker, in which case it exits by itself
+        if let Some(handle) = self.thread_handle.take() {
+            if !handle.is_current() {
+                handle.unpark();
+                handle.join();
+            }
+        }
+    }
+}
+
+/// Where a manager keeps its [`Worker`], while it has one
+///
+/// An idle manager stops its worker outside the lock, since joining the
+/// thread waits for callbacks that may call back into the manager. Until the
+/// worker has stopped, new ones wait, so two listeners never run at once.
+#[derive(Default)]
+struct WorkerSlot {
+    state: Mutex<SlotState>,
+    /// Notified when a worker has stopped
+    stopped: Condvar,
+}
+
+#[derive(Default)]
+struct SlotState {
+    worker: Option<Worker>,
+    /// The thread of the worker being stopped, if one is
+    stopping: Option<ThreadId>,
+}
+
+impl WorkerSlot {
+    fn lock(&self) -> Result<MutexGuard<'_, SlotState>> {
+        self.state.lock().map_err(|_| Error::MutexPoisoned)
+    }
+
+    /// Lock the slot once no worker is being stopped
+    ///
+    /// A callback on the thread of the worker being stopped doesn't wait,
+    /// since that thread only exits once the callback returns.
+    fn lock_settled(&self) -> Result<MutexGuard<'_, SlotState>> {
+        let current = thread::current().id();
+        self.stopped
+            .wait_while(self.lock()?, |state| state.stopping.is_some_and(|id| id != current))
+            .map_err(|_| Error::MutexPoisoned)
+    }
+
+    /// Stop the worker if `idle`, checked under the lock, says so
+    #[cfg_attr(target_os = "linux", allow(dead_code))]
+    fn stop_if(&self, idle: impl FnOnce() -> bool) {
+        let Ok(mut state) = self.lock_settled() else {
+            return;
+        };
+        if !idle() {
+            return;
+        }
+        let Some(worker) = state.worker.take() else {
+            return;
+        };
+        state.stopping = worker.thread_handle.as_ref().map(Handle::id);
+        drop(state);
+        // Dropped outside the lock: this joins the worker thread
+        drop(worker);
+        if let Ok(mut state) = self.lock() {
+            state.stopping = None;
+        }
+        self.stopped.notify_all();
+    }
+}
+
+/// Background thread that keeps retrying to install the OS hooks
+///
+/// Used by deferred managers while the listener can't be created yet, e.g.
+/// because the macOS accessibility permission hasn't been granted.
+struct Activator {
+    thread_handle: Option<Handle>,
+    running: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl Activator {
+    /// How often to retry installing the hooks
+    const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+    fn spawn(
+        state: &Arc<Mutex<MatchEngine>>,
+        blocking_hotkeys: &BlockingHotkeys,
+        options: ListenerOptions,
+        sender: &Sender<HotkeyEvent>,
+        worker: &Arc<WorkerSlot>,
+    ) -> Result<Self> {
+        let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+        let state = Arc::clone(state);
+        let blocking_hotkeys = Arc::clone(blocking_hotkeys);
+        let sender = sender.clone();
+        let worker = Arc::clone(worker);
+        let thread_running = Arc::clone(&running);
+
+        let handle = threads::spawn(&running, move || {
+            while thread_running.load(std::sync::atomic::Ordering::SeqCst) {
+                // Parked rather than asleep, so dropping the activator
+                // doesn't wait out the interval
+                thread::park_timeout(Self::RETRY_INTERVAL);
+                if !thread_running.load(std::sync::atomic::Ordering::SeqCst) {
+                    return;
+                }
+
+                let Ok(mut slot) = worker.lock_settled() else {
+                    return;
+                };
+                // Checked under the worker lock, so a manager shutting down
+                // never misses a worker installed here
+                if slot.worker.is_some() || !thread_running.load(std::sync::atomic::Ordering::SeqCst) {
+                    return;
+                }
+                let is_idle = state.lock().map(|s| s.hotkeys.is_empty()).unwrap_or(true);
+                if is_idle {
+                    continue;
+                }
+                if let Ok(spawned) = Worker::spawn(&state, &blocking_hotkeys, &options, &sender) {
+                    slot.worker = Some(spawned);
+                    return;
+                }
+            }
+        })?;
+
+        Ok(Self {
+            thread_handle: Some(handle),
+            running,
+        })
+    }
+
+    fn is_finished(&self) -> bool {
+        self.thread_handle.as_ref().is_none_or(Handle::is_finished)
+    }
+}
+
+impl Drop for Activator {
+    fn drop(&mut self) {
+        self.running
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        if let Some(handle) = self.thread_handle.take() {
+            handle.unpark();
+            handle.join();
+        }
     }
 }
 
 /// Platform-agnostic Hotkey Manager
 ///
 /// This manager wraps a `KeyboardListener` and filters events against
-/// registered hotkeys, emitting `HotkeyEvent`s when matches occur.
+/// registered hotkeys with a [`MatchEngine`], emitting `HotkeyEvent`s when
+/// matches occur.
 ///
 /// Registered hotkeys are blocked from reaching other applications.
 /// Note: On Linux/Wayland, blocking may not work due to compositor restrictions.
+///
+/// Once the last hotkey is unregistered, the OS hooks are uninstalled and the
+/// idle manager costs nothing. They are reinstalled on the next
+/// [`register`](Self::register). A new manager installs them right away, so a
+/// missing permission fails building it; build it with
+/// [`lazy`](HotkeyManagerBuilder::lazy) to start idle instead.
+/// Note: On Linux, rdev can't stop a running grab, so the hooks stay installed.
 pub struct HotkeyManager {
-    state: Arc<Mutex<ManagerState>>,
+    state: Arc<Mutex<MatchEngine>>,
+    event_sender: Sender<HotkeyEvent>,
     event_receiver: Receiver<HotkeyEvent>,
-    _thread_handle: Option<JoinHandle<()>>,
-    running: Arc<std::sync::atomic::AtomicBool>,
+    /// Retries installing the hooks for a deferred manager (declared before
+    /// `worker` so it is stopped first on drop)
+    activator: Mutex<Option<Activator>>,
+    /// The active listener, or none while idle
+    worker: Arc<WorkerSlot>,
     /// Shared set of hotkeys to block
     blocking_hotkeys: BlockingHotkeys,
+    /// Keep registrations pending instead of failing when the hooks can't be installed
+    deferred: bool,
+    /// Register hotkeys as passthrough unless asked otherwise
+    passthrough: bool,
+    listener_options: ListenerOptions,
 }
 
 impl HotkeyManager {
@@ -101,118 +397,974 @@ impl HotkeyManager {
     ///
     /// Note: On Linux/Wayland, blocking may not work due to compositor restrictions.
     pub fn new() -> Result<Self> {
-        let blocking_hotkeys: BlockingHotkeys = Arc::new(Mutex::new(HashSet::new()));
-        let listener = KeyboardListener::new_with_blocking(blocking_hotkeys.clone())?;
+        Self::builder().build()
+    }
 
-        let (tx, rx) = mpsc::channel();
-        let state = Arc::new(Mutex::new(ManagerState::new()));
-        let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
+    /// Create a builder for a HotkeyManager with non-default settings
+    pub fn builder() -> HotkeyManagerBuilder {
+        HotkeyManagerBuilder::default()
+    }
 
-        let thread_state = Arc::clone(&state);
-        let thread_running = Arc::clone(&running);
+    /// Create a HotkeyManager that doesn't require permissions up front
+    ///
+    /// Always succeeds, even when the macOS accessibility permission hasn't
+    /// been granted. Hotkeys registered before the OS hooks can be installed
+    /// are kept pending, and the manager keeps retrying in the background,
+    /// activating them as soon as permission is granted. Use
+    /// [`is_listening`](Self::is_listening) to tell whether hotkeys are live.
+    pub fn new_deferred() -> Result<Self> {
+        Self::builder().deferred(true).build()
+    }
 
-        let handle = thread::spawn(move || {
-            Self::event_loop(listener, thread_state, tx, thread_running);
-        });
+    fn new_internal(options: HotkeyManagerBuilder) -> Result<Self> {
+        #[cfg(feature = "rescue")]
+        let options = options.with_rescue()?;
+        let blocking_hotkeys: BlockingHotkeys = Arc::new(Mutex::new(HashSet::new()));
+        let (tx, rx) = channel();
+        let tx = tx.with_sink(options.sink.clone());
+        let state = Arc::new(Mutex::new(MatchEngine {
+            precedence: options.precedence,
+            lost_release_gap: options.lost_release_gap,
+            report_all_released: options.report_all_released,
+            typing: options.listener.typing.clone(),
+            ..MatchEngine::new()
+        }));
+
+        let worker = if options.lazy || options.deferred {
+            None
+        } else {
+            Some(Worker::spawn(&state, &blocking_hotkeys, &options.listener, &tx)?)
+        };
 
         Ok(Self {
             state,
+            event_sender: tx,
             event_receiver: rx,
-            _thread_handle: Some(handle),
-            running,
+            activator: Mutex::new(None),
+            worker: Arc::new(WorkerSlot {
+                state: Mutex::new(SlotState { worker, stopping: None }),
+                stopped: Condvar::new(),
+            }),
             blocking_hotkeys,
+            deferred: options.deferred,
+            passthrough: options.passthrough,
+            listener_options: options.listener,
         })
     }
 
+    /// How long the event thread waits for input by default, see
+    /// [`HotkeyManagerBuilder::poll_interval`]
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
     /// Event processing loop
+    #[cfg_attr(not(feature = "rescue"), allow(unused_variables))]
     fn event_loop(
-        listener: KeyboardListener,
-        state: Arc<Mutex<ManagerState>>,
+        mut source: KeySource,
+        state: Arc<Mutex<MatchEngine>>,
+        blocking_hotkeys: BlockingHotkeys,
         sender: Sender<HotkeyEvent>,
         running: Arc<std::sync::atomic::AtomicBool>,
+        options: ListenerOptions,
     ) {
-        const RECV_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
-
+        let poll_interval = options.poll_interval.unwrap_or(Self::POLL_INTERVAL);
+        let mut wake_detector = WakeDetector::new();
         while running.load(std::sync::atomic::Ordering::SeqCst) {
+            Self::refresh_guards(&state);
+
+            // Key ups are often lost while the system sleeps, so don't leave
+            // hotkeys pressed across a wake
+            if wake_detector.tick()
+                && !Self::deliver(&state, &blocking_hotkeys, &sender, MatchEngine::release_all)
+            {
+                return;
+            }
+
+            #[cfg(feature = "rescue")]
+            if let Some(rescue) = options.rescue.as_deref().filter(|rescue| rescue.take_request()) {
+                if !Self::rescue(&state, &blocking_hotkeys, &sender, &options, rescue) {
+                    return;
+                }
+            }
+
             // Block until we receive an event or timeout (to check running flag)
-            match listener.recv_timeout(RECV_TIMEOUT) {
+            match source.recv_timeout(poll_interval) {
                 Ok(key_event) => {
-                    if let Ok(mut state) = state.lock() {
-                        let hotkey_events = state.process_event(&key_event);
-                        for event in hotkey_events {
-                            if sender.send(event).is_err() {
-                                // Receiver dropped, exit
-                                return;
-                            }
+                    if let Ok(recording) = options.recording.lock() {
+                        if let Some(recorder) = recording.as_ref() {
+                            let _ = recorder.send(key_event);
                         }
                     }
+                    if !Self::deliver(&state, &blocking_hotkeys, &sender, |state| {
+                        state.process_event(&key_event)
+                    }) {
+                        // Receiver dropped, exit
+                        return;
+                    }
                 }
                 Err(crate::error::Error::Timeout) => {
-                    // No event received, loop continues to check running flag
+                    // No event received, but unregister() may have queued a
+                    // Released event for a callback, or a sequence timed out
+                    if !Self::deliver(&state, &blocking_hotkeys, &sender, |_| Vec::new()) {
+                        return;
+                    }
+                }
+                Err(crate::error::Error::GrabFailed(_)) => {
+                    // Another process holds the input devices (Linux). Key ups
+                    // are lost meanwhile, so release everything and keep
+                    // retrying like a deferred manager until it lets go
+                    if !Self::deliver(&state, &blocking_hotkeys, &sender, MatchEngine::release_all) {
+                        return;
+                    }
+                    thread::park_timeout(Activator::RETRY_INTERVAL);
+                    if !running.load(std::sync::atomic::Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(retried) = Worker::source(&blocking_hotkeys, &options) {
+                        source = retried;
+                    }
                 }
                 Err(_) => {
                     // Listener disconnected, exit
-                    return;
+                    break;
                 }
             }
         }
+
+        // Deliver what unregister() queued just before the worker stopped
+        Self::deliver(&state, &blocking_hotkeys, &sender, |_| Vec::new());
+    }
+
+    /// Run the guards of guarded hotkeys, whose last answers the hooks read
+    ///
+    /// Runs without the state lock, since guards are the app's code.
+    fn refresh_guards(state: &Mutex<MatchEngine>) {
+        let guards: Vec<Guard> = match state.lock() {
+            Ok(state) => state.guards.values().cloned().collect(),
+            Err(_) => return,
+        };
+        for guard in guards {
+            guard.refresh();
+        }
+    }
+
+    /// Deliver the hotkey events produced by `f`, and any queued for callbacks
+    ///
+    /// Events go out on the channel under the state lock, keeping them ordered
+    /// with the ones `unregister()` sends. Callbacks run after the lock is
+    /// released, so they can call back into the manager. Returns `false` once
+    /// the manager (and so the receiver) is gone.
+    ///
+    /// Also updates which chords of key sequences are blocked.
+    fn deliver(
+        state: &Mutex<MatchEngine>,
+        blocking_hotkeys: &BlockingHotkeys,
+        sender: &Sender<HotkeyEvent>,
+        f: impl FnOnce(&mut MatchEngine) -> Vec<HotkeyEvent>,
+    ) -> bool {
+        let pending = {
+            let Ok(mut state) = state.lock() else {
+                return false;
+            };
+            let events = f(&mut state);
+            if let Ok(mut blocking) = blocking_hotkeys.lock() {
+                state.sync_sequence_blocking(&mut blocking, Instant::now());
+            }
+            for event in state.route(events) {
+                if sender.send(event).is_err() {
+                    return false;
+                }
+            }
+            std::mem::take(&mut state.pending_callbacks)
+        };
+
+        for (event, callback) in pending {
+            if let Ok(mut callback) = callback.lock() {
+                callback(event);
+            }
+        }
+        true
+    }
+
+    /// Suspend every hotkey and write the state to the rescue dump
+    #[cfg(feature = "rescue")]
+    fn rescue(
+        state: &Mutex<MatchEngine>,
+        blocking_hotkeys: &BlockingHotkeys,
+        sender: &Sender<HotkeyEvent>,
+        options: &ListenerOptions,
+        rescue: &Rescue,
+    ) -> bool {
+        let mut dump = format!(
+            "handy-keys {} rescue dump (pid {})\n",
+            env!("CARGO_PKG_VERSION"),
+            std::process::id()
+        );
+        let delivered = Self::deliver(state, blocking_hotkeys, sender, |state| {
+            let Ok(mut blocking) = blocking_hotkeys.lock() else {
+                return Vec::new();
+            };
+            dump.push_str(&state.dump(&blocking));
+            state.suspend(&mut blocking)
+        });
+        if let Ok(diagnostics) = options.diagnostics.lock() {
+            dump.push_str(&format!("diagnostics: {:?}\n", *diagnostics));
+        }
+        let _ = rescue.write_dump(&dump);
+        delivered
+    }
+
+    /// Install the OS hooks if they aren't already
+    fn start_listening(&self) -> Result<()> {
+        let mut slot = self.worker.lock_settled()?;
+        if slot.worker.is_none() {
+            slot.worker = Some(Worker::spawn(
+                &self.state,
+                &self.blocking_hotkeys,
+                &self.listener_options,
+                &self.event_sender,
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Keep retrying to install the OS hooks in the background
+    fn start_activator(&self) {
+        let Ok(mut activator) = self.activator.lock() else {
+            return;
+        };
+        if activator.as_ref().is_none_or(Activator::is_finished) {
+            *activator = Activator::spawn(
+                &self.state,
+                &self.blocking_hotkeys,
+                self.listener_options.clone(),
+                &self.event_sender,
+                &self.worker,
+            )
+            .ok();
+        }
+    }
+
+    /// Uninstall the OS hooks if no hotkeys are registered
+    fn stop_listening_if_idle(&self) {
+        // rdev::grab() can't be interrupted, and a second grab would compete
+        // with the idle one, so the Linux listener is kept for reuse
+        // Checked under the worker lock so a concurrent register() can't
+        // have its freshly installed hooks removed
+        #[cfg(not(target_os = "linux"))]
+        self.worker.stop_if(|| self.hotkey_count() == 0 && !self.is_recording());
+    }
+
+    /// Change the registered hotkeys and the blocking set together
+    ///
+    /// Both locks are held for the whole update, so there is no moment where a
+    /// hotkey is matched but not blocked, or blocked but not matched. Events
+    /// the hook handled before the update still carry its decision in
+    /// [`KeyEvent::hook`], which the engine follows.
+    fn update<T>(
+        &self,
+        f: impl FnOnce(&mut MatchEngine, &mut HashSet<Hotkey>) -> Result<T>,
+    ) -> Result<T> {
+        let mut state = self.state.lock().map_err(|_| Error::MutexPoisoned)?;
+        let mut blocking = self
+            .blocking_hotkeys
+            .lock()
+            .map_err(|_| Error::MutexPoisoned)?;
+        f(&mut state, &mut blocking)
     }
 
     /// Register a hotkey and return its unique ID
     ///
     /// Returns an error if the hotkey is already registered.
     pub fn register(&self, hotkey: Hotkey) -> Result<HotkeyId> {
-        let mut state = self.state.lock().map_err(|_| Error::MutexPoisoned)?;
+        self.register_aliases(&[hotkey])
+    }
+
+    /// Register each of `hotkeys` under its own ID, carrying on past the ones
+    /// that fail
+    ///
+    /// Returns a result per hotkey, in order, so loading a config doesn't stop
+    /// at its first bad binding and the app can report which ones failed and
+    /// why. A hotkey listed twice fails the second time like any other
+    /// duplicate.
+    ///
+    /// ```no_run
+    /// use handy_keys::{Hotkey, HotkeyManager};
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// let bindings = ["Ctrl+Alt+Space".parse::<Hotkey>()?, "Cmd+Shift+K".parse()?];
+    /// for (hotkey, result) in bindings.iter().zip(manager.register_many(&bindings)) {
+    ///     if let Err(e) = result {
+    ///         eprintln!("Skipping {hotkey}: {e}");
+    ///     }
+    /// }
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn register_many(&self, hotkeys: &[Hotkey]) -> Vec<Result<HotkeyId>> {
+        hotkeys.iter().map(|hotkey| self.register(*hotkey)).collect()
+    }
+
+    /// Register several combos that all trigger the same hotkey ID
+    ///
+    /// Any of the combos presses the hotkey, and the [`HotkeyEvent`] tells
+    /// which one did (see [`HotkeyEvent::is_alias`]). All of them are blocked.
+    /// [`get_hotkey`](Self::get_hotkey) returns the first combo.
+    ///
+    /// Returns an error if `hotkeys` is empty or any combo is already registered.
+    ///
+    /// ```no_run
+    /// use handy_keys::{Hotkey, HotkeyManager};
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// let screenshot = manager.register_aliases(&[
+    ///     "Cmd+Shift+4".parse::<Hotkey>()?,
+    ///     "F13".parse::<Hotkey>()?,
+    /// ])?;
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn register_aliases(&self, hotkeys: &[Hotkey]) -> Result<HotkeyId> {
+        self.register_combos(
+            hotkeys,
+            ComboOptions {
+                block: !self.passthrough,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register a hotkey whose events are passed to `callback`
+    ///
+    /// The callback runs on the manager's event thread instead of the event
+    /// going to [`recv`](Self::recv), so no receive loop is needed. Keep it
+    /// short, as it delays matching of later events. It may call back into
+    /// the manager, e.g. to unregister its own hotkey. The callback is
+    /// dropped once the hotkey is unregistered and its final events are
+    /// delivered.
+    ///
+    /// ```no_run
+    /// use handy_keys::{Hotkey, HotkeyManager, HotkeyState};
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// manager.register_with_callback("Ctrl+Alt+Space".parse::<Hotkey>()?, |event| {
+    ///     if event.state == HotkeyState::Pressed {
+    ///         println!("pressed");
+    ///     }
+    /// })?;
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn register_with_callback(
+        &self,
+        hotkey: Hotkey,
+        callback: impl FnMut(HotkeyEvent) + Send + 'static,
+    ) -> Result<HotkeyId> {
+        let callback: Callback = Arc::new(Mutex::new(Box::new(callback)));
+        self.register_combos(
+            &[hotkey],
+            ComboOptions {
+                callback: Some(callback),
+                block: !self.passthrough,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register a hotkey that is matched but not blocked
+    ///
+    /// Its events are delivered like any other hotkey's, but the keystroke
+    /// still reaches the focused app, e.g. to count uses of `Cmd+C` without
+    /// keeping it from copying.
+    pub fn register_passthrough(&self, hotkey: Hotkey) -> Result<HotkeyId> {
+        self.register_combos(&[hotkey], ComboOptions::default())
+    }
+
+    /// Register a modifier-only hotkey that doesn't fire if a key is typed
+    /// while its modifiers are held
+    ///
+    /// With `Cmd+Shift` registered this way, typing `Cmd+Shift+S` doesn't
+    /// press it. Since that isn't known until the modifiers are released, the
+    /// hotkey is pressed and released together at that point, so it can't be
+    /// held. A hotkey that has a key is registered as usual.
+    pub fn register_exclusive(&self, hotkey: Hotkey) -> Result<HotkeyId> {
+        self.register_combos(
+            &[hotkey],
+            ComboOptions {
+                block: !self.passthrough,
+                exclusive: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register a hotkey that also fires with extra modifiers held
+    ///
+    /// `Ctrl+C` registered this way is pressed by `Ctrl+Shift+C` and
+    /// `Ctrl+Alt+C` as well, and they're blocked like `Ctrl+C`. A hotkey
+    /// registered for the exact combo takes precedence. The hotkey is
+    /// reported as registered, so [`HotkeyEvent::hotkey`] is `Ctrl+C`.
+    pub fn register_loose(&self, hotkey: Hotkey) -> Result<HotkeyId> {
+        self.register_combos(
+            &[hotkey],
+            ComboOptions {
+                block: !self.passthrough,
+                loose: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Register a hotkey that fires when tapped twice within `window`
+    ///
+    /// Works with modifier-only hotkeys like `Shift` as well as combos like
+    /// `Ctrl+C`. The hotkey is pressed when the second tap goes down and
+    /// released as usual. Typing any other key in between starts over.
+    ///
+    /// Double-tap hotkeys are never blocked, since the first tap can't be
+    /// told apart from an ordinary keystroke until the second arrives.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use handy_keys::{Hotkey, HotkeyManager};
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// let launcher = manager.register_double_tap(
+    ///     "Shift".parse::<Hotkey>()?,
+    ///     Duration::from_millis(300),
+    /// )?;
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn register_double_tap(&self, hotkey: Hotkey, window: Duration) -> Result<HotkeyId> {
+        self.register_combos(
+            &[hotkey],
+            ComboOptions {
+                double_tap: Some(window),
+                ..Default::default()
+            },
+        )
+    }
 
-        // Check if already registered
-        for (id, existing) in &state.hotkeys {
-            if existing == &hotkey {
-                return Err(Error::HotkeyAlreadyRegistered(format!(
-                    "{} (id: {:?})",
-                    hotkey, id
-                )));
+    /// Register a hotkey that is only active while `guard` returns `true`
+    ///
+    /// While the guard returns `false`, the hotkey isn't pressed and the
+    /// keystroke reaches the focused app, e.g. to turn shortcuts off while the
+    /// app shows a modal dialog without unregistering them.
+    ///
+    /// The guard never runs inside the OS hook, which only reads its last
+    /// answer. It runs on the manager's event thread after every key event
+    /// and at least once per [`poll_interval`](HotkeyManagerBuilder::poll_interval),
+    /// so a new answer takes effect by the next keystroke, such as pressing
+    /// the hotkey's modifiers, or within the interval. Keep it quick anyway,
+    /// as hotkey events wait for it.
+    ///
+    /// ```no_run
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use handy_keys::{Hotkey, HotkeyManager};
+    ///
+    /// let modal_open = Arc::new(AtomicBool::new(false));
+    /// let manager = HotkeyManager::new()?;
+    /// let flag = Arc::clone(&modal_open);
+    /// manager.register_guarded("Ctrl+Alt+Space".parse::<Hotkey>()?, move || {
+    ///     !flag.load(Ordering::Relaxed)
+    /// })?;
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn register_guarded(
+        &self,
+        hotkey: Hotkey,
+        guard: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> Result<HotkeyId> {
+        self.register_combos(
+            &[hotkey],
+            ComboOptions {
+                block: !self.passthrough,
+                guard: Some(Guard::new(guard)),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Turn every hotkey off while one of `apps` is the frontmost app
+    ///
+    /// While an excluded app is frontmost, hotkeys are neither pressed nor
+    /// blocked, so keystrokes reach it unchanged, e.g. a virtual machine or
+    /// remote desktop client that forwards them. Apps are identified as in
+    /// [`register_for_app`](Self::register_for_app). Replaces the previous
+    /// list; pass an empty one to exclude no app.
+    ///
+    /// ```no_run
+    /// # let manager = handy_keys::HotkeyManager::new()?;
+    /// manager.set_excluded_apps(&["com.apple.Terminal", "vmware.exe"])?;
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    ///
+    /// Returns an error on Linux, where the frontmost app can't be detected,
+    /// and [`Error::SharedListener`] for a manager built with
+    /// [`shared_listener`](HotkeyManagerBuilder::shared_listener).
+    pub fn set_excluded_apps(&self, apps: &[&str]) -> Result<()> {
+        if self.listener_options.shared && !apps.is_empty() {
+            return Err(Error::SharedListener("set_excluded_apps".to_string()));
+        }
+        if !frontmost::SUPPORTED && !apps.is_empty() {
+            return Err(Error::Platform(
+                "excluding apps isn't supported on this platform".to_string(),
+            ));
+        }
+        self.listener_options
+            .excluded_apps
+            .set(apps.iter().map(|app| app.to_string()).collect());
+        Ok(())
+    }
+
+    /// Register a hotkey that is only active while `app` is the frontmost app
+    ///
+    /// `app` is compared, ignoring case, with what [`frontmost_app`](crate::frontmost_app)
+    /// reports: a bundle identifier such as `com.apple.Safari` on macOS, or an
+    /// executable name such as `Code.exe` on Windows. While another app is
+    /// frontmost, the hotkey isn't pressed and the keystroke passes through,
+    /// as with [`register_guarded`](Self::register_guarded).
+    ///
+    /// Returns an error on Linux, where the frontmost app can't be detected.
+    pub fn register_for_app(&self, hotkey: Hotkey, app: impl Into<String>) -> Result<HotkeyId> {
+        if !frontmost::SUPPORTED {
+            return Err(Error::Platform(
+                "app-scoped hotkeys aren't supported on this platform".to_string(),
+            ));
+        }
+        let app = app.into();
+        self.register_guarded(hotkey, move || frontmost::is_frontmost([&app]))
+    }
+
+    /// Register a key sequence such as `Ctrl+K Ctrl+B`, with at most
+    /// `timeout` between two chords
+    ///
+    /// The hotkey is pressed when the last chord goes down, and its events
+    /// carry that chord. Typing any other key in between starts over.
+    /// [`get_hotkey`](Self::get_hotkey) returns the first chord, which several
+    /// sequences may share but no other hotkey may use.
+    ///
+    /// The first chord is blocked like any hotkey. Later chords are only
+    /// blocked while the sequence is waiting for them, so `Ctrl+B` keeps
+    /// working in other apps otherwise.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use handy_keys::{HotkeyManager, HotkeySequence};
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// let sidebar = manager.register_sequence(
+    ///     "Ctrl+K Ctrl+B".parse::<HotkeySequence>()?,
+    ///     Duration::from_secs(1),
+    /// )?;
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn register_sequence(
+        &self,
+        sequence: HotkeySequence,
+        timeout: Duration,
+    ) -> Result<HotkeyId> {
+        self.register_combos(
+            &[sequence.first()],
+            ComboOptions {
+                block: !self.passthrough,
+                sequence: Some((sequence, timeout)),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Get the key sequence of a hotkey registered with
+    /// [`register_sequence`](Self::register_sequence)
+    ///
+    /// Returns `None` if the ID is not found or isn't a sequence.
+    pub fn get_sequence(&self, id: HotkeyId) -> Option<HotkeySequence> {
+        let state = self.state.lock().ok()?;
+        state.sequences.get(&id).map(|progress| progress.sequence.clone())
+    }
+
+    fn register_combos(&self, hotkeys: &[Hotkey], options: ComboOptions) -> Result<HotkeyId> {
+        let mut combos: Vec<Hotkey> = Vec::with_capacity(hotkeys.len());
+        for hotkey in hotkeys {
+            if !combos.contains(hotkey) {
+                combos.push(*hotkey);
             }
         }
+        if combos.is_empty() {
+            return Err(Error::EmptyHotkey);
+        }
 
-        let id = HotkeyId(state.next_id);
-        state.next_id += 1;
-        state.hotkeys.insert(id, hotkey);
+        let id = self.update(|state, blocking| {
+            match &options.sequence {
+                Some((sequence, _)) => state.check_sequence_available(sequence)?,
+                None => state.check_available(&combos)?,
+            }
+
+            let id = state.allocate_id();
+            if options.block && !state.suspended {
+                blocking.extend(combos.iter().copied());
+            }
+            if let Some(guard) = options.guard {
+                let mut guards = self
+                    .listener_options
+                    .guards
+                    .lock()
+                    .map_err(|_| Error::MutexPoisoned)?;
+                guards.extend(combos.iter().map(|hotkey| (*hotkey, guard.clone())));
+                state.guards.insert(id, guard);
+            }
+            if options.loose {
+                self.listener_options
+                    .loose
+                    .lock()
+                    .map_err(|_| Error::MutexPoisoned)?
+                    .extend(combos.iter().copied());
+                state.loose.insert(id);
+            }
+            state.hotkeys.insert(id, combos);
+            if !options.block {
+                state.passthrough.insert(id);
+            }
+            if options.exclusive {
+                state.exclusive.insert(id);
+            }
+            if let Some(window) = options.double_tap {
+                state.double_taps.insert(
+                    id,
+                    DoubleTap {
+                        window,
+                        first_tap: None,
+                        held: None,
+                    },
+                );
+            }
+            if let Some((sequence, timeout)) = options.sequence {
+                state.sequences.insert(id, SequenceProgress::new(sequence, timeout));
+            }
+            if let Some(callback) = options.callback {
+                state.callbacks.insert(id, callback);
+            }
+            Ok(id)
+        })?;
 
-        // Add to blocking set
-        if let Ok(mut blocking) = self.blocking_hotkeys.lock() {
-            blocking.insert(hotkey);
+        self.start_listening_for(id)
+    }
+
+    /// Make sure the OS hooks are installed now that `id` is registered
+    ///
+    /// Unregisters `id` again if they can't be, unless the manager is deferred.
+    fn start_listening_for(&self, id: HotkeyId) -> Result<HotkeyId> {
+        // Reinstall the hooks if the manager was idle
+        if let Err(e) = self.start_listening() {
+            if self.deferred {
+                // Keep the hotkey pending until the hooks can be installed
+                self.start_activator();
+            } else {
+                let _ = self.unregister(id);
+                return Err(e);
+            }
         }
 
         Ok(id)
     }
 
+    /// Reserve a hotkey without activating it yet
+    ///
+    /// For settings dialogs with "Apply" and "Cancel": the hotkey is checked
+    /// like by [`register`](Self::register), and no other registration can
+    /// take the combo while it's reserved, but it isn't matched or blocked
+    /// until [`PendingRegistration::commit`]. Dropping the reservation without
+    /// committing releases the combo.
+    ///
+    /// ```no_run
+    /// use handy_keys::{Hotkey, HotkeyManager};
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// let pending = manager.register_pending("Ctrl+Alt+Space".parse::<Hotkey>()?)?;
+    /// // ...once the user clicks "Apply"
+    /// let id = pending.commit()?;
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn register_pending(&self, hotkey: Hotkey) -> Result<PendingRegistration<'_>> {
+        let id = self.update(|state, _| {
+            state.check_available(&[hotkey])?;
+            let id = state.allocate_id();
+            state.reserved.insert(id, vec![hotkey]);
+            Ok(id)
+        })?;
+        Ok(PendingRegistration {
+            manager: self,
+            id,
+            hotkey,
+            committed: false,
+        })
+    }
+
     /// Unregister a hotkey by its ID
     ///
+    /// If the hotkey is pressed, a final
+    /// [`HotkeyState::Released`](crate::HotkeyState::Released) event is
+    /// emitted for it (or passed to its callback), so held actions (like
+    /// push-to-talk) are always ended.
+    ///
     /// Returns an error if the hotkey ID is not found.
     pub fn unregister(&self, id: HotkeyId) -> Result<()> {
-        let mut state = self.state.lock().map_err(|_| Error::MutexPoisoned)?;
+        self.update(|state, blocking| {
+            let (combos, release) = state.remove(id).ok_or(Error::HotkeyNotFound(id))?;
+            // Sequences may share their first chord
+            for hotkey in &combos {
+                if !state.blocked_combos().any(|combo| combo == *hotkey) {
+                    blocking.remove(hotkey);
+                }
+            }
+            if let Ok(mut guards) = self.listener_options.guards.lock() {
+                for hotkey in &combos {
+                    guards.remove(hotkey);
+                }
+            }
+            if let Ok(mut loose) = self.listener_options.loose.lock() {
+                for hotkey in &combos {
+                    loose.remove(hotkey);
+                }
+            }
+            state.sync_sequence_blocking(blocking, Instant::now());
+            // Sent under the lock so it can't race with events from the event loop
+            if let Some(release) = release {
+                let _ = self.event_sender.send(release);
+            }
+            Ok(())
+        })?;
 
-        let hotkey = state.hotkeys.remove(&id);
-        if hotkey.is_none() {
-            return Err(Error::HotkeyNotFound(id));
-        }
+        self.stop_listening_if_idle();
+
+        Ok(())
+    }
+
+    /// Find registered hotkeys that overlap with `hotkey`
+    ///
+    /// Besides an identical combo, which [`register`](Self::register) would
+    /// reject, this reports modifier-only hotkeys that fire on the way to
+    /// typing another, like `Cmd+Shift` and `Cmd+Shift+K`. Useful to warn
+    /// about a combo in a settings UI before registering it.
+    ///
+    /// ```no_run
+    /// use handy_keys::{ConflictKind, Hotkey, HotkeyManager};
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// manager.register("Cmd+Shift".parse()?)?;
+    ///
+    /// let conflicts = manager.check_conflicts(&"Cmd+Shift+K".parse::<Hotkey>()?);
+    /// assert_eq!(conflicts[0].kind, ConflictKind::Extends);
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn check_conflicts(&self, hotkey: &Hotkey) -> Vec<Conflict> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+        let mut conflicts: Vec<Conflict> = state
+            .hotkeys
+            .iter()
+            .flat_map(|(&id, combos)| {
+                combos.iter().filter_map(move |registered| {
+                    hotkey.conflict_with(registered).map(|kind| Conflict {
+                        id,
+                        hotkey: *registered,
+                        kind,
+                    })
+                })
+            })
+            .collect();
+        conflicts.sort_by_key(|conflict| conflict.id.0);
+        conflicts
+    }
+
+    /// Stop matching and blocking all hotkeys, keeping their registrations
+    ///
+    /// Useful for a "pause shortcuts" setting. Pressed hotkeys are released
+    /// (emitting [`HotkeyState::Released`](crate::HotkeyState::Released) as
+    /// with [`unregister`](Self::unregister)),
+    /// and keys pass through to other applications until [`resume`](Self::resume).
+    /// Hotkeys registered while suspended stay inactive until then too.
+    pub fn suspend(&self) -> Result<()> {
+        self.update(|state, blocking| {
+            let released = state.suspend(blocking);
+            // Sent under the lock so it can't race with events from the event loop
+            for release in state.route(released) {
+                let _ = self.event_sender.send(release);
+            }
+            Ok(())
+        })
+    }
+
+    /// Resume matching and blocking hotkeys after [`suspend`](Self::suspend)
+    pub fn resume(&self) -> Result<()> {
+        self.update(|state, blocking| {
+            state.suspended = false;
+            blocking.extend(state.blocked_combos());
+            Ok(())
+        })
+    }
+
+    /// Whether hotkeys are suspended, see [`suspend`](Self::suspend)
+    pub fn is_suspended(&self) -> bool {
+        self.state.lock().map(|s| s.suspended).unwrap_or(false)
+    }
 
-        // Remove from blocking set
-        if let Some(hotkey) = hotkey {
-            if let Ok(mut blocking) = self.blocking_hotkeys.lock() {
-                blocking.remove(&hotkey);
+    /// Pause the hotkeys and record a new one from the keyboard
+    ///
+    /// For "change shortcut" UIs: unlike running a separate
+    /// [`KeyboardListener`], the registered hotkeys don't fire (or get
+    /// blocked) while the user types the combo they want. They're suspended
+    /// until the returned [`HotkeyRecorder`] is dropped, which streams the key
+    /// events until the user completes a combination. The OS hooks are
+    /// installed for the recording if they aren't already.
+    ///
+    /// Returns an error if another recording is already in progress, or if
+    /// the OS hooks can't be installed.
+    pub fn begin_recording(&self) -> Result<HotkeyRecorder<'_>> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        {
+            let mut recording = self
+                .listener_options
+                .recording
+                .lock()
+                .map_err(|_| Error::MutexPoisoned)?;
+            if recording.is_some() {
+                return Err(Error::RecordingFailed);
             }
+            *recording = Some(tx);
         }
 
-        Ok(())
+        let resume = !self.is_suspended();
+        let recorder = HotkeyRecorder::new(self, rx, resume);
+        self.suspend()?;
+        self.start_listening()?;
+        Ok(recorder)
+    }
+
+    /// Whether a [`HotkeyRecorder`] is open, see
+    /// [`begin_recording`](Self::begin_recording)
+    pub fn is_recording(&self) -> bool {
+        self.listener_options
+            .recording
+            .lock()
+            .map(|recording| recording.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Stop streaming key events to the recorder, resuming the hotkeys if
+    /// they were active before it
+    pub(crate) fn end_recording(&self, resume: bool) {
+        if let Ok(mut recording) = self.listener_options.recording.lock() {
+            *recording = None;
+        }
+        if resume {
+            let _ = self.resume();
+        }
+        self.stop_listening_if_idle();
     }
 
     /// Get the hotkey definition associated with an ID
     ///
-    /// Returns `None` if the ID is not found.
+    /// For hotkeys registered with [`register_aliases`](Self::register_aliases),
+    /// this is the first combo. Returns `None` if the ID is not found.
     pub fn get_hotkey(&self, id: HotkeyId) -> Option<Hotkey> {
         let state = self.state.lock().ok()?;
-        state.hotkeys.get(&id).copied()
+        state.hotkeys.get(&id)?.first().copied()
+    }
+
+    /// Get every combo that triggers the hotkey with an ID
+    ///
+    /// Returns `None` if the ID is not found.
+    pub fn get_aliases(&self, id: HotkeyId) -> Option<Vec<Hotkey>> {
+        let state = self.state.lock().ok()?;
+        state.hotkeys.get(&id).cloned()
+    }
+
+    /// Name a registered hotkey, e.g. after the action it performs
+    ///
+    /// The label is listed by [`hotkeys`](Self::hotkeys) and included in
+    /// errors about the hotkey, like [`Error::HotkeyAlreadyRegistered`] when
+    /// another registration wants its combo. Events of the hotkey carry it as
+    /// [`label`](HotkeyEvent::label).
+    ///
+    /// Returns an error if the hotkey ID is not found.
+    ///
+    /// ```no_run
+    /// use handy_keys::{Hotkey, HotkeyManager};
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// let id = manager.register("Ctrl+Alt+R".parse::<Hotkey>()?)?;
+    /// manager.set_label(id, "Toggle Recording")?;
+    ///
+    /// for event in manager.events() {
+    ///     println!("{:?}: {:?}", event.label, event.state);
+    /// }
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn set_label(&self, id: HotkeyId, label: impl Into<String>) -> Result<()> {
+        let mut state = self.state.lock().map_err(|_| Error::MutexPoisoned)?;
+        if !state.hotkeys.contains_key(&id) {
+            return Err(Error::HotkeyNotFound(id));
+        }
+        state.labels.insert(id, label.into().into());
+        Ok(())
+    }
+
+    /// Get the label of a hotkey, if it was given one with
+    /// [`set_label`](Self::set_label)
+    pub fn get_label(&self, id: HotkeyId) -> Option<String> {
+        let state = self.state.lock().ok()?;
+        state.labels.get(&id).map(|label| label.to_string())
+    }
+
+    /// List the registered hotkeys, in the order they were registered
+    pub fn hotkeys(&self) -> Vec<RegisteredHotkey> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+        let mut hotkeys: Vec<RegisteredHotkey> = state
+            .hotkeys
+            .iter()
+            .map(|(&id, combos)| RegisteredHotkey {
+                id,
+                hotkey: combos[0],
+                label: state.labels.get(&id).map(|label| label.to_string()),
+            })
+            .collect();
+        hotkeys.sort_by_key(|registered| registered.id.as_u32());
+        hotkeys
+    }
+
+    /// Stop the manager, waiting up to `timeout` for the OS hooks to be removed
+    ///
+    /// Dropping the manager also stops it, but waits as long as it takes and
+    /// can't report problems. Call this to stop early, for example before an
+    /// updater replaces the binary. Returns [`Error::Timeout`] if the hooks
+    /// are still being removed after `timeout`; they keep shutting down in the
+    /// background.
+    ///
+    /// Note: On Linux, rdev's hook can't be removed, see
+    /// [`KeyboardListener::shutdown`].
+    pub fn shutdown(self, timeout: Duration) -> Result<()> {
+        let deadline = std::time::Instant::now() + timeout;
+
+        // Stop a deferred manager's retries first, so no hooks are installed
+        // behind our back. It stops within one retry interval by itself.
+        let activator = self.activator.lock().map_err(|_| Error::MutexPoisoned)?.take();
+        if let Some(activator) = &activator {
+            activator
+                .running
+                .store(false, std::sync::atomic::Ordering::SeqCst);
+        }
+
+        let worker = self.worker.lock_settled()?.worker.take();
+        if let Some(worker) = worker {
+            worker.shutdown(deadline.saturating_duration_since(std::time::Instant::now()))?;
+        }
+        if let Some(mut activator) = activator {
+            if let Some(handle) = activator.thread_handle.take() {
+                handle.join_timeout(deadline.saturating_duration_since(std::time::Instant::now()))?;
+            }
+        }
+        Ok(())
     }
 
     /// Blocking receive for hotkey events
@@ -224,6 +1376,26 @@ impl HotkeyManager {
             .map_err(|_| Error::EventLoopNotRunning)
     }
 
+    /// Blocking iterator over hotkey events
+    ///
+    /// Yields events as [`recv`](Self::recv) receives them, ending once the
+    /// event loop stops.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use handy_keys::HotkeyManager;
+    ///
+    /// let manager = HotkeyManager::new()?;
+    /// manager.register("Ctrl+Alt+Space".parse()?)?;
+    /// for event in manager.events() {
+    ///     println!("Hotkey triggered: {:?}", event.id);
+    /// }
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn events(&self) -> impl Iterator<Item = HotkeyEvent> + '_ {
+        std::iter::from_fn(|| self.recv().ok())
+    }
+
     /// Non-blocking receive for hotkey events
     ///
     /// Returns `Some(event)` if an event is available, `None` otherwise.
@@ -235,6 +1407,82 @@ impl HotkeyManager {
         }
     }
 
+    /// Take every hotkey event received so far, without blocking
+    ///
+    /// Meant for frame-based loops (games, immediate-mode UIs) that check for
+    /// events once per frame. Returns an empty `Vec` if there are none.
+    pub fn drain(&self) -> Vec<HotkeyEvent> {
+        self.event_receiver.try_iter().collect()
+    }
+
+    /// File descriptor that becomes readable when hotkey events are queued
+    ///
+    /// For event loops built on `poll`, `epoll` or mio, to wait for hotkey events
+    /// alongside sockets without a dedicated thread. It stays readable until
+    /// [`try_recv`](Self::try_recv) finds nothing, so take every event (e.g.
+    /// with [`drain`](Self::drain)) each time it's ready. Also readable once
+    /// the event loop stops. Don't read from it yourself.
+    #[cfg(unix)]
+    pub fn ready_fd(&self) -> Result<std::os::fd::BorrowedFd<'_>> {
+        Ok(self.event_receiver.readiness()?.fd())
+    }
+
+    /// Event that is signaled when hotkey events are queued
+    ///
+    /// For event loops built on `WaitForMultipleObjects`, to wait for hotkey events
+    /// alongside other handles without a dedicated thread. It stays signaled
+    /// until [`try_recv`](Self::try_recv) finds nothing, so take every event
+    /// (e.g. with [`drain`](Self::drain)) each time it's signaled. Also
+    /// signaled once the event loop stops. Don't reset it yourself.
+    #[cfg(windows)]
+    pub fn ready_handle(&self) -> Result<std::os::windows::io::BorrowedHandle<'_>> {
+        Ok(self.event_receiver.readiness()?.handle())
+    }
+
+    /// Wait for the next hotkey event without blocking a thread
+    ///
+    /// Works with any async runtime, e.g. in a `tokio::select!` loop.
+    #[cfg(feature = "async")]
+    pub async fn recv_async(&self) -> Result<HotkeyEvent> {
+        std::future::poll_fn(|cx| self.event_receiver.poll_recv(cx))
+            .await
+            .ok_or(Error::EventLoopNotRunning)
+    }
+
+    /// Stream of hotkey events
+    ///
+    /// Works with any async runtime. Events are shared with
+    /// [`recv`](Self::recv), so consume them from one place at a time.
+    #[cfg(feature = "async")]
+    pub fn events_stream(&self) -> impl futures_core::Stream<Item = HotkeyEvent> + '_ {
+        crate::channel::EventStream::new(&self.event_receiver)
+    }
+
+    /// How well the OS hook is keeping up, see [`HookDiagnostics`]
+    ///
+    /// Accumulated over every time the hooks were installed. Only measured
+    /// on Windows; always empty elsewhere.
+    pub fn hook_diagnostics(&self) -> HookDiagnostics {
+        let mut diagnostics = self
+            .listener_options
+            .diagnostics
+            .lock()
+            .map(|d| *d)
+            .unwrap_or_default();
+        if let Ok(state) = self.state.lock() {
+            diagnostics.lost_releases = state.lost_releases;
+        }
+        diagnostics
+    }
+
+    /// Whether the OS hooks are installed and registered hotkeys are live
+    ///
+    /// This is `false` while the manager is idle, and for a deferred manager
+    /// whose hooks couldn't be installed yet.
+    pub fn is_listening(&self) -> bool {
+        self.worker.lock().map(|slot| slot.worker.is_some()).unwrap_or(false)
+    }
+
     /// Get the number of currently registered hotkeys
     pub fn hotkey_count(&self) -> usize {
         let state = if let Ok(s) = self.state.lock() {
@@ -246,208 +1494,505 @@ impl HotkeyManager {
     }
 }
 
-impl Drop for HotkeyManager {
-    fn drop(&mut self) {
-        self.running
-            .store(false, std::sync::atomic::Ordering::SeqCst);
-        // Join the thread to ensure clean shutdown
-        if let Some(handle) = self._thread_handle.take() {
-            let _ = handle.join();
-        }
-    }
+/// A hotkey listed by [`HotkeyManager::hotkeys`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredHotkey {
+    pub id: HotkeyId,
+    /// The hotkey's combo, or its first one if it has aliases or is a
+    /// sequence, like [`HotkeyManager::get_hotkey`]
+    pub hotkey: Hotkey,
+    /// See [`HotkeyManager::set_label`]
+    pub label: Option<String>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::{Key, Modifiers};
+/// A hotkey reserved by [`HotkeyManager::register_pending`]
+///
+/// Dropping it without calling [`commit`](Self::commit) releases the combo.
+#[must_use = "the reservation is released when dropped"]
+pub struct PendingRegistration<'a> {
+    manager: &'a HotkeyManager,
+    id: HotkeyId,
+    hotkey: Hotkey,
+    committed: bool,
+}
 
-    fn make_key_event(modifiers: Modifiers, key: Option<Key>, is_key_down: bool) -> KeyEvent {
-        KeyEvent {
-            modifiers,
-            key,
-            is_key_down,
-            changed_modifier: None,
-        }
+impl PendingRegistration<'_> {
+    /// The ID the hotkey will have once committed
+    pub fn id(&self) -> HotkeyId {
+        self.id
     }
 
-    fn make_modifier_event(modifiers: Modifiers, is_key_down: bool, changed: Modifiers) -> KeyEvent {
-        KeyEvent {
-            modifiers,
-            key: None,
-            is_key_down,
-            changed_modifier: Some(changed),
-        }
+    /// The reserved hotkey
+    pub fn hotkey(&self) -> Hotkey {
+        self.hotkey
     }
 
-    mod manager_state {
-        use super::*;
-
-        #[test]
-        fn register_and_lookup_hotkey() {
-            let mut state = ManagerState::new();
-            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
-
-            let id = HotkeyId(state.next_id);
-            state.next_id += 1;
-            state.hotkeys.insert(id, hotkey);
+    /// Start matching and blocking the hotkey, returning its ID
+    ///
+    /// Fails like [`HotkeyManager::register`] if the OS hooks can't be
+    /// installed, in which case the reservation is released.
+    pub fn commit(mut self) -> Result<HotkeyId> {
+        self.committed = true;
+        let id = self.id;
+        let passthrough = self.manager.passthrough;
+        self.manager.update(|state, blocking| {
+            let combos = state.reserved.remove(&id).ok_or(Error::HotkeyNotFound(id))?;
+            if passthrough {
+                state.passthrough.insert(id);
+            } else if !state.suspended {
+                blocking.extend(combos.iter().copied());
+            }
+            state.hotkeys.insert(id, combos);
+            Ok(())
+        })?;
+        self.manager.start_listening_for(id)
+    }
+}
 
-            assert_eq!(state.hotkeys.get(&id), Some(&hotkey));
-            assert_eq!(state.hotkeys.len(), 1);
+impl Drop for PendingRegistration<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Ok(mut state) = self.manager.state.lock() {
+                state.reserved.remove(&self.id);
+            }
         }
+    }
+}
 
-        #[test]
-        fn hotkey_press_generates_event() {
-            let mut state = ManagerState::new();
-            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
-            let id = HotkeyId(0);
-            state.hotkeys.insert(id, hotkey);
+/// Builder for a [`HotkeyManager`]
+///
+/// ```no_run
+/// use handy_keys::{Hotkey, HotkeyManager};
+///
+/// // Nothing is installed (and no permission is needed) until the first hotkey
+/// let manager = HotkeyManager::builder().lazy(true).build()?;
+///
+/// let hotkey: Hotkey = "Ctrl+Alt+Space".parse()?;
+/// manager.register(hotkey)?;
+/// # Ok::<(), handy_keys::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct HotkeyManagerBuilder {
+    lazy: bool,
+    deferred: bool,
+    precedence: Precedence,
+    lost_release_gap: Option<Duration>,
+    report_all_released: bool,
+    passthrough: bool,
+    sink: Option<SharedSink<HotkeyEvent>>,
+    #[cfg(all(unix, feature = "rescue"))]
+    rescue_signal: Option<i32>,
+    #[cfg(all(windows, feature = "rescue"))]
+    rescue_event: bool,
+    #[cfg(feature = "rescue")]
+    rescue_dump_path: Option<std::path::PathBuf>,
+    listener: ListenerOptions,
+}
 
-            // Simulate Cmd+K key down
-            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
-            let results = state.process_event(&event);
+impl HotkeyManagerBuilder {
+    /// Defer installing the OS hooks until the first [`HotkeyManager::register`]
+    ///
+    /// By default, the hooks are installed when the manager is built, so a
+    /// missing macOS accessibility permission fails [`build`](Self::build).
+    /// With `lazy` set, building always succeeds and the permission error is
+    /// returned by the first `register()` instead, letting apps create the
+    /// manager early and ask for permission at a better moment.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
 
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].id, id);
-            assert_eq!(results[0].state, HotkeyState::Pressed);
-            assert!(state.pressed_hotkeys.contains(&id));
-        }
+    /// Keep registrations pending while the OS hooks can't be installed
+    ///
+    /// Implies [`lazy`](Self::lazy). See [`HotkeyManager::new_deferred`].
+    pub fn deferred(mut self, deferred: bool) -> Self {
+        self.deferred = deferred;
+        self
+    }
 
-        #[test]
-        fn hotkey_release_generates_event() {
-            let mut state = ManagerState::new();
-            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
-            let id = HotkeyId(0);
-            state.hotkeys.insert(id, hotkey);
+    /// Whether registered hotkeys are blocked from reaching other apps
+    ///
+    /// On by default. When off, [`HotkeyManager::register`] and friends
+    /// behave like [`HotkeyManager::register_passthrough`].
+    pub fn blocking(mut self, blocking: bool) -> Self {
+        self.passthrough = !blocking;
+        self
+    }
 
-            // Press first
-            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
-            state.process_event(&event);
+    /// Choose where events come from, see [`Backend`]
+    ///
+    /// The `HANDY_KEYS_BACKEND` and `HANDY_KEYS_HEADLESS` environment
+    /// variables take precedence.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.listener.backend = backend;
+        self
+    }
 
-            // Then release the key
-            let event = make_key_event(Modifiers::CMD, Some(Key::K), false);
-            let results = state.process_event(&event);
+    /// Name the thread that matches events and runs callbacks
+    ///
+    /// Shows up in debuggers, profilers and panic messages.
+    pub fn thread_name(mut self, name: impl Into<String>) -> Self {
+        self.listener.thread_name = Some(name.into());
+        self
+    }
 
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].id, id);
-            assert_eq!(results[0].state, HotkeyState::Released);
-            assert!(!state.pressed_hotkeys.contains(&id));
-        }
+    /// How long the event thread waits for input before checking in
+    ///
+    /// Between events, the thread wakes up this often to notice it should
+    /// stop, deliver releases queued by [`HotkeyManager::unregister`] to
+    /// callbacks, and detect wake from sleep. Shorter intervals make those
+    /// quicker at the cost of more wakeups. Defaults to 100ms; keep it well
+    /// under a second for sleep detection to work.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.listener.poll_interval = Some(interval);
+        self
+    }
 
-        #[test]
-        fn no_duplicate_press_events() {
-            let mut state = ManagerState::new();
-            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
-            let id = HotkeyId(0);
-            state.hotkeys.insert(id, hotkey);
+    /// Suspend all hotkeys when the process receives `signal`
+    ///
+    /// An emergency exit for when blocked keys make the app unusable: run
+    /// `kill -USR2 <pid>` from a terminal (or over SSH) with
+    /// `rescue_signal(libc::SIGUSR2)`, and every hotkey is suspended as with
+    /// [`HotkeyManager::suspend`], so nothing is blocked anymore. The
+    /// manager's state is written to [`rescue_dump_path`](Self::rescue_dump_path)
+    /// for the bug report. Fails to build for signals that can't be handled,
+    /// such as `SIGKILL`.
+    ///
+    /// Requires the `rescue` feature. Unix only.
+    #[cfg(all(unix, feature = "rescue"))]
+    pub fn rescue_signal(mut self, signal: i32) -> Self {
+        self.rescue_signal = Some(signal);
+        self
+    }
 
-            // Press once
-            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
-            let results = state.process_event(&event);
-            assert_eq!(results.len(), 1);
+    /// Suspend all hotkeys when the named event `HandyKeysRescue-<pid>` is set
+    ///
+    /// The Windows counterpart of [`rescue_signal`](Self::rescue_signal), so
+    /// users can recover without Task Manager: setting the event, for example
+    /// with `cargo run --example rescue -- <pid>`, suspends every hotkey as
+    /// with [`HotkeyManager::suspend`] and writes the manager's state to
+    /// [`rescue_dump_path`](Self::rescue_dump_path).
+    ///
+    /// Requires the `rescue` feature. Windows only.
+    #[cfg(all(windows, feature = "rescue"))]
+    pub fn rescue_event(mut self, enabled: bool) -> Self {
+        self.rescue_event = enabled;
+        self
+    }
 
-            // Press again (key repeat) - should not generate another event
-            let results = state.process_event(&event);
-            assert_eq!(results.len(), 0);
-        }
+    /// Where a rescue writes the manager's state
+    ///
+    /// Defaults to `handy-keys-rescue-<pid>.txt` in `$XDG_RUNTIME_DIR`, or in
+    /// the temp directory where that isn't set. The file is replaced on each
+    /// rescue and created readable only by the current user.
+    #[cfg(feature = "rescue")]
+    pub fn rescue_dump_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.rescue_dump_path = Some(path.into());
+        self
+    }
 
-        #[test]
-        fn modifier_release_triggers_hotkey_release() {
-            let mut state = ManagerState::new();
-            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
-            let id = HotkeyId(0);
-            state.hotkeys.insert(id, hotkey);
+    /// Choose which hotkeys fire when several match, see [`Precedence`]
+    pub fn precedence(mut self, precedence: Precedence) -> Self {
+        self.precedence = precedence;
+        self
+    }
 
-            // Press Cmd+K
-            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
-            state.process_event(&event);
-            assert!(state.pressed_hotkeys.contains(&id));
+    /// Release a pressed hotkey before pressing it again when its key goes
+    /// down more than `gap` after it last did
+    ///
+    /// Key ups are sometimes lost (e.g. while a secure desktop or elevated
+    /// window has focus), leaving a hotkey pressed, so the next press looks
+    /// like key repeat and is ignored. With this set, consumers see a
+    /// Released before the new Pressed, keeping pairs balanced. `gap` must be
+    /// longer than the OS key repeat delay, which is at most about 2 seconds;
+    /// held keys repeat faster than that. Repairs are counted in
+    /// [`HookDiagnostics::lost_releases`]. Off by default.
+    pub fn repair_lost_releases(mut self, gap: Duration) -> Self {
+        self.lost_release_gap = Some(gap);
+        self
+    }
 
-            // Release Cmd (while K is still held) - modifier event
-            let event = make_modifier_event(Modifiers::empty(), false, Modifiers::CMD);
-            let results = state.process_event(&event);
+    /// Deliver events to `sink` instead of the manager's own channel
+    ///
+    /// See [`EventSink`](crate::EventSink). [`recv`](HotkeyManager::recv) and
+    /// the other receiving methods then get nothing. Hotkeys registered with
+    /// a callback still call it.
+    ///
+    /// ```no_run
+    /// use handy_keys::HotkeyManager;
+    ///
+    /// let (tx, rx) = std::sync::mpsc::channel();
+    /// let manager = HotkeyManager::builder().event_sink(tx).build()?;
+    /// manager.register("Ctrl+Alt+Space".parse()?)?;
+    /// for event in rx {
+    ///     println!("{:?}", event.id);
+    /// }
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn event_sink(mut self, sink: impl crate::EventSink<HotkeyEvent> + 'static) -> Self {
+        self.sink = Some(SharedSink::new(sink));
+        self
+    }
 
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].state, HotkeyState::Released);
-            assert!(!state.pressed_hotkeys.contains(&id));
-        }
+    /// Ignore simple hotkeys while the user is typing
+    ///
+    /// The user counts as typing once `keys` keystrokes that type text
+    /// (letters, digits, punctuation and Space, alone or with Shift) came
+    /// within `window`. Until that's no longer true, hotkeys with no modifier
+    /// but Shift, such as a bare `F1`, and modifier-only hotkeys are neither
+    /// pressed nor blocked, so dictation and note-taking don't trigger them
+    /// mid-sentence. Hotkeys with other modifiers are unaffected. For
+    /// example, `suppress_while_typing(3, Duration::from_secs(1))` suppresses
+    /// them after 3 keystrokes within a second. Off by default.
+    ///
+    /// Can't be combined with [`shared_listener`](Self::shared_listener):
+    /// building fails with [`Error::SharedListener`].
+    pub fn suppress_while_typing(mut self, keys: usize, window: Duration) -> Self {
+        self.listener.typing = Some(TypingDetector::new(keys, window));
+        self
+    }
 
-        #[test]
-        fn wrong_modifiers_dont_trigger() {
-            let mut state = ManagerState::new();
-            let hotkey = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
-            state.hotkeys.insert(HotkeyId(0), hotkey);
+    /// Send [`HotkeyState::AllReleased`](crate::HotkeyState::AllReleased) once
+    /// every key of a released hotkey is up
+    ///
+    /// [`HotkeyState::Released`](crate::HotkeyState::Released) comes as soon
+    /// as the combo is broken, which
+    /// may be while some of its modifiers are still held. Overlays that should
+    /// stay visible until the user lets go of the combo entirely can wait for
+    /// `AllReleased` instead. Off by default.
+    pub fn all_released_events(mut self, enabled: bool) -> Self {
+        self.report_all_released = enabled;
+        self
+    }
 
-            // Press Shift+K instead of Cmd+K
-            let event = make_key_event(Modifiers::SHIFT, Some(Key::K), true);
-            let results = state.process_event(&event);
+    /// Choose how key events are identified as a [`Key`](crate::Key)
+    ///
+    /// See [`KeyMapping`]. Only affects Windows.
+    pub fn key_mapping(mut self, key_mapping: KeyMapping) -> Self {
+        self.listener.key_mapping = key_mapping;
+        self
+    }
 
-            assert_eq!(results.len(), 0);
-        }
+    /// Ignore input for `delay` after the OS hooks are installed
+    ///
+    /// Keeps hotkeys registered at launch from firing on keystrokes already in
+    /// flight, such as the Enter key up from starting the app from a launcher.
+    /// See [`KeyboardListenerBuilder::arm_delay`](crate::KeyboardListenerBuilder::arm_delay).
+    pub fn arm_delay(mut self, delay: Duration) -> Self {
+        self.listener.arm_delay = delay;
+        self
+    }
 
-        #[test]
-        fn modifier_only_hotkey() {
-            let mut state = ManagerState::new();
-            let hotkey = Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, None).unwrap();
-            let id = HotkeyId(0);
-            state.hotkeys.insert(id, hotkey);
+    /// Reinstall the OS hooks every `interval`
+    ///
+    /// See [`KeyboardListenerBuilder::hook_reinstall_interval`](crate::KeyboardListenerBuilder::hook_reinstall_interval).
+    /// Only affects Windows.
+    pub fn hook_reinstall_interval(mut self, interval: Duration) -> Self {
+        self.listener.hook_reinstall_interval = Some(interval);
+        self
+    }
 
-            // Press Cmd+Shift (no key)
-            let event = make_modifier_event(Modifiers::CMD | Modifiers::SHIFT, true, Modifiers::SHIFT);
-            let results = state.process_event(&event);
+    /// Choose where the event tap runs relative to other apps' taps
+    ///
+    /// See [`TapPlacement`]. Only affects macOS.
+    pub fn tap_placement(mut self, placement: TapPlacement) -> Self {
+        self.listener.tap_placement = placement;
+        self
+    }
 
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].state, HotkeyState::Pressed);
-        }
+    /// Choose which stage of event delivery the event tap observes
+    ///
+    /// See [`TapLocation`]. Only affects macOS.
+    pub fn tap_location(mut self, location: TapLocation) -> Self {
+        self.listener.tap_location = location;
+        self
+    }
 
-        #[test]
-        fn multiple_hotkeys_same_key() {
-            let mut state = ManagerState::new();
+    /// Set the scheduling priority of the thread running the OS hook
+    ///
+    /// See [`ThreadPriority`]. Installing the hooks fails with
+    /// [`Error::ThreadPriority`] if the OS doesn't allow it.
+    pub fn thread_priority(mut self, priority: ThreadPriority) -> Self {
+        self.listener.thread_priority = priority;
+        self
+    }
 
-            // Cmd+K and Ctrl+K
-            let hotkey1 = Hotkey::new(Modifiers::CMD, Key::K).unwrap();
-            let hotkey2 = Hotkey::new(Modifiers::CTRL, Key::K).unwrap();
-            let id1 = HotkeyId(0);
-            let id2 = HotkeyId(1);
-            state.hotkeys.insert(id1, hotkey1);
-            state.hotkeys.insert(id2, hotkey2);
+    /// Report [`SystemEvent`]s of the manager's listener to `sender`
+    ///
+    /// See [`KeyboardListenerBuilder::report_system_events`](crate::KeyboardListenerBuilder::report_system_events).
+    pub fn report_system_events(mut self, sender: std::sync::mpsc::Sender<SystemEvent>) -> Self {
+        self.listener.system_events = Some(sender);
+        self
+    }
 
-            // Press Cmd+K
-            let event = make_key_event(Modifiers::CMD, Some(Key::K), true);
-            let results = state.process_event(&event);
+    /// Attach to an OS hook shared with other managers instead of installing
+    /// one of its own
+    ///
+    /// Each manager installs its own event tap or hook by default, so several
+    /// managers in one process add to the work done for every keystroke (and,
+    /// on Windows, to the hook chain's latency). Managers built with this
+    /// share one hook, which delivers every event to each of them and blocks
+    /// the hotkeys of all of them. The hook is installed with the listener
+    /// settings of the first manager that attaches (its key mapping, arm
+    /// delay, dedupe window, tap placement and location, thread priority and
+    /// hook reinstall interval), reports system events and
+    /// [`hook_diagnostics`](HotkeyManager::hook_diagnostics) to that manager
+    /// only, and is removed once the last one stops listening. Off by default.
+    ///
+    /// The hook decides once for every manager on it whether an app is
+    /// excluded and whether the user is typing, so sharing managers can't
+    /// have their own: [`suppress_while_typing`](Self::suppress_while_typing)
+    /// makes building fail and
+    /// [`set_excluded_apps`](HotkeyManager::set_excluded_apps) returns
+    /// [`Error::SharedListener`].
+    pub fn shared_listener(mut self, shared: bool) -> Self {
+        self.listener.shared = shared;
+        self
+    }
 
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].id, id1);
+    /// Ignore key downs that repeat the same key within `window`
+    ///
+    /// Keeps faulty keyboards that double key downs from pressing hotkeys
+    /// twice. See [`KeyboardListenerBuilder::dedupe_window`](crate::KeyboardListenerBuilder::dedupe_window).
+    pub fn dedupe_window(mut self, window: Duration) -> Self {
+        self.listener.dedupe_window = window;
+        self
+    }
 
-            // Press Ctrl+K (release Cmd first)
-            state.pressed_hotkeys.clear();
-            let event = make_key_event(Modifiers::CTRL, Some(Key::K), true);
-            let results = state.process_event(&event);
+    /// Set up the rescue trigger, if one was asked for
+    #[cfg(feature = "rescue")]
+    fn with_rescue(mut self) -> Result<Self> {
+        #[cfg(unix)]
+        if let Some(signal) = self.rescue_signal {
+            let rescue = Rescue::on_signal(signal, self.rescue_dump_path.take())?;
+            self.listener.rescue = Some(Arc::new(rescue));
+        }
+        #[cfg(windows)]
+        if self.rescue_event {
+            let rescue = Rescue::on_named_event(self.rescue_dump_path.take())?;
+            self.listener.rescue = Some(Arc::new(rescue));
+        }
+        Ok(self)
+    }
 
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].id, id2);
+    /// Create the HotkeyManager
+    pub fn build(self) -> Result<HotkeyManager> {
+        if self.listener.shared {
+            self.listener.check_shareable()?;
         }
+        HotkeyManager::new_internal(self)
+    }
+}
 
-        #[test]
-        fn key_only_hotkey() {
-            let mut state = ManagerState::new();
-            let hotkey = Hotkey::new(Modifiers::empty(), Key::F1).unwrap();
-            let id = HotkeyId(0);
-            state.hotkeys.insert(id, hotkey);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Key, Modifiers};
+
+    #[test]
+    fn sharing_managers_cant_have_their_own_exclusions() {
+        let shared = || HotkeyManager::builder().backend(Backend::Headless).shared_listener(true);
+        let first = shared().build().unwrap();
+        let second = shared().build().unwrap();
+        first.register("Ctrl+K".parse().unwrap()).unwrap();
+        second.register("Ctrl+J".parse().unwrap()).unwrap();
+
+        // One hook decides for both, so neither can exclude an app alone
+        assert!(matches!(first.set_excluded_apps(&["vmware.exe"]), Err(Error::SharedListener(_))));
+        assert!(second.set_excluded_apps(&[]).is_ok());
+        let typing = shared().suppress_while_typing(3, Duration::from_secs(1)).build();
+        assert!(matches!(typing, Err(Error::SharedListener(_))));
+    }
 
-            // Press F1 with no modifiers
-            let event = make_key_event(Modifiers::empty(), Some(Key::F1), true);
-            let results = state.process_event(&event);
+    #[test]
+    fn register_many_reports_each_failure() {
+        let manager = HotkeyManager::builder().backend(Backend::Headless).build().unwrap();
+        let taken = manager.register("Ctrl+K".parse().unwrap()).unwrap();
+        let hotkeys: Vec<Hotkey> = ["Ctrl+J", "Ctrl+K", "Ctrl+L", "Ctrl+J"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let results = manager.register_many(&hotkeys);
+        assert!(results[0].is_ok() && results[2].is_ok());
+        assert!(matches!(results[1], Err(Error::HotkeyAlreadyRegistered(_))));
+        assert!(matches!(results[3], Err(Error::HotkeyAlreadyRegistered(_))));
+        assert_eq!(manager.hotkey_count(), 3);
+        assert_eq!(manager.get_hotkey(taken), Some(hotkeys[1]));
+    }
 
-            assert_eq!(results.len(), 1);
-            assert_eq!(results[0].state, HotkeyState::Pressed);
+    #[test]
+    fn workers_start_once_the_stopping_one_stopped() {
+        let manager = HotkeyManager::builder().backend(Backend::Headless).build().unwrap();
+        manager.worker.stop_if(|| false);
+        assert!(manager.is_listening());
+        manager.worker.stop_if(|| true);
+        assert!(!manager.is_listening());
+        assert_eq!(manager.worker.lock().unwrap().stopping, None);
+
+        let slot = Arc::clone(&manager.worker);
+        let elsewhere = thread::spawn(|| thread::current().id()).join().unwrap();
+        slot.lock().unwrap().stopping = Some(elsewhere);
+        let waiting = thread::spawn(move || slot.lock_settled().is_ok());
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiting.is_finished());
+        manager.worker.lock().unwrap().stopping = None;
+        manager.worker.stopped.notify_all();
+        assert!(waiting.join().unwrap());
+
+        // A callback on the stopping worker's thread doesn't wait for itself
+        manager.worker.lock().unwrap().stopping = Some(thread::current().id());
+        assert!(manager.worker.lock_settled().is_ok());
+    }
 
-            // F1 with modifiers should NOT trigger
-            state.pressed_hotkeys.clear();
-            let event = make_key_event(Modifiers::CMD, Some(Key::F1), true);
-            let results = state.process_event(&event);
+    #[test]
+    fn labels_listed_and_named_in_errors() {
+        let manager = HotkeyManager::builder().backend(Backend::Headless).build().unwrap();
+        let hotkey: Hotkey = "Ctrl+Alt+R".parse().unwrap();
+        let id = manager.register(hotkey).unwrap();
+        let other = manager.register("Ctrl+J".parse().unwrap()).unwrap();
+        manager.set_label(id, "Toggle Recording").unwrap();
+
+        assert_eq!(manager.get_label(id).as_deref(), Some("Toggle Recording"));
+        assert_eq!(manager.get_label(other), None);
+        let listed: Vec<_> = manager.hotkeys().into_iter().map(|h| (h.id, h.label)).collect();
+        assert_eq!(listed, [(id, Some("Toggle Recording".to_string())), (other, None)]);
+        let error = manager.register(hotkey).unwrap_err().to_string();
+        assert!(error.contains("\"Toggle Recording\""), "{error}");
+
+        manager.unregister(id).unwrap();
+        assert_eq!(manager.get_label(id), None);
+        assert!(matches!(manager.set_label(id, "Gone"), Err(Error::HotkeyNotFound(_))));
+    }
 
-            assert_eq!(results.len(), 0);
+    #[test]
+    fn recording_pauses_hotkeys_until_dropped() {
+        let manager = HotkeyManager::builder().backend(Backend::Headless).build().unwrap();
+        let hotkey: Hotkey = "Ctrl+K".parse().unwrap();
+        manager.register(hotkey).unwrap();
+
+        let mut recorder = manager.begin_recording().unwrap();
+        assert!(manager.is_suspended() && manager.is_recording());
+        assert!(matches!(manager.begin_recording(), Err(Error::RecordingFailed)));
+        let tap = manager.listener_options.recording.lock().unwrap().clone().unwrap();
+        for (modifiers, key) in [(Modifiers::CTRL, None), (Modifiers::CTRL, Some(Key::J))] {
+            tap.send(KeyEvent {
+                modifiers,
+                key,
+                is_key_down: true,
+                changed_modifier: None,
+                sides: Modifiers::empty(),
+                hook: None,
+            })
+            .unwrap();
         }
+        assert_eq!(recorder.by_ref().count(), 2);
+        assert_eq!(recorder.hotkey(), Some("Ctrl+J".parse().unwrap()));
+        drop(recorder);
+        assert!(!manager.is_suspended() && !manager.is_recording());
+
+        manager.suspend().unwrap();
+        drop(manager.begin_recording().unwrap());
+        assert!(manager.is_suspended());
     }
 }
@@ -0,0 +1,98 @@
+//! Environment variables that override the host app's settings
+//!
+//! Lets users work around machine-specific problems, and support ask for a
+//! diagnostic run, without the app adding any plumbing. Read whenever a
+//! listener is created, including the ones a manager creates.
+//!
+//! - `HANDY_KEYS_BACKEND`: `native` or `headless`. Takes precedence over
+//!   `HANDY_KEYS_HEADLESS`.
+//! - `HANDY_KEYS_NO_BLOCKING`: when set, no events are blocked, so every key
+//!   still reaches the focused app. Hotkeys still fire.
+
+use std::ffi::OsStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::platform::headless::HEADLESS_ENV;
+
+/// Environment variable that selects the backend
+pub const BACKEND_ENV: &str = "HANDY_KEYS_BACKEND";
+
+/// Environment variable that turns off blocking
+pub const NO_BLOCKING_ENV: &str = "HANDY_KEYS_NO_BLOCKING";
+
+/// Where a listener's key events come from
+///
+/// The `HANDY_KEYS_BACKEND` and `HANDY_KEYS_HEADLESS` environment variables
+/// override the backend chosen by the app.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Backend {
+    /// The platform's OS hooks
+    #[default]
+    Native,
+    /// No OS hooks, permissions or events, for CI and containers
+    Headless,
+}
+
+/// The backend selected by the environment, if any
+pub(crate) fn backend() -> Result<Option<Backend>> {
+    backend_from(
+        std::env::var_os(BACKEND_ENV).as_deref(),
+        std::env::var_os(HEADLESS_ENV).as_deref(),
+    )
+}
+
+fn backend_from(backend: Option<&OsStr>, headless: Option<&OsStr>) -> Result<Option<Backend>> {
+    match backend.filter(|v| !v.is_empty()) {
+        None if is_set(headless) => Ok(Some(Backend::Headless)),
+        None => Ok(None),
+        Some(v) if v.eq_ignore_ascii_case("native") => Ok(Some(Backend::Native)),
+        Some(v) if v.eq_ignore_ascii_case("headless") => Ok(Some(Backend::Headless)),
+        Some(v) => Err(Error::InvalidEnvVar(
+            BACKEND_ENV,
+            format!("{:?} (expected \"native\" or \"headless\")", v),
+        )),
+    }
+}
+
+/// Whether blocking is turned off by the environment
+pub(crate) fn blocking_disabled() -> bool {
+    is_set(std::env::var_os(NO_BLOCKING_ENV).as_deref())
+}
+
+/// Any value other than empty, "0" or "false" turns a flag on
+pub(crate) fn is_set(value: Option<&OsStr>) -> bool {
+    value.is_some_and(|v| !v.is_empty() && v != "0" && v != "false")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_values() {
+        assert!(!is_set(None));
+        assert!(!is_set(Some(OsStr::new(""))));
+        assert!(!is_set(Some(OsStr::new("0"))));
+        assert!(!is_set(Some(OsStr::new("false"))));
+        assert!(is_set(Some(OsStr::new("1"))));
+        assert!(is_set(Some(OsStr::new("true"))));
+    }
+
+    #[test]
+    fn backend_selection() {
+        let value = |v: &'static str| Some(OsStr::new(v));
+
+        assert_eq!(backend_from(None, None).unwrap(), None);
+        assert_eq!(backend_from(None, value("1")).unwrap(), Some(Backend::Headless));
+        assert_eq!(backend_from(value(""), value("1")).unwrap(), Some(Backend::Headless));
+        assert_eq!(backend_from(value("Headless"), None).unwrap(), Some(Backend::Headless));
+        // The backend variable wins over the headless flag
+        assert_eq!(backend_from(value("native"), value("1")).unwrap(), Some(Backend::Native));
+        assert!(matches!(
+            backend_from(value("x11"), None),
+            Err(Error::InvalidEnvVar(BACKEND_ENV, _))
+        ));
+    }
+}
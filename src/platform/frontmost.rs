@@ -0,0 +1,259 @@
+//! Detection of the frontmost app, for app-scoped hotkeys
+//!
+//! Asking the OS for the frontmost app takes too long for a hook callback,
+//! which runs for every keystroke. While a listener runs, it watches for
+//! foreground changes instead, and the hooks read the app it last saw.
+
+use std::sync::{PoisonError, RwLock};
+
+/// The frontmost app as of the last foreground change, while any listener
+/// is watching for them
+static WATCHED: RwLock<Watched> = RwLock::new(Watched { watchers: 0, app: None });
+
+struct Watched {
+    /// Listeners keeping `app` up to date
+    watchers: usize,
+    app: Option<String>,
+}
+
+/// Whether [`frontmost_app`] can tell apps apart on this platform
+pub(crate) const SUPPORTED: bool = cfg!(any(target_os = "macos", target_os = "windows"));
+
+/// Identify the app that has keyboard focus
+///
+/// On macOS this is the bundle identifier, such as `com.apple.Safari`. On
+/// Windows it's the file name of the foreground window's executable, such as
+/// `Code.exe`. Returns `None` if there is no focused app or it can't be
+/// identified, and always on Linux, where no portable way to ask exists.
+pub fn frontmost_app() -> Option<String> {
+    imp::frontmost_app()
+}
+
+/// Check an app identifier against the one reported by [`frontmost_app`]
+///
+/// Case-insensitive, since Windows file names are.
+pub(crate) fn is_app(frontmost: &str, app: &str) -> bool {
+    frontmost.eq_ignore_ascii_case(app)
+}
+
+/// Check whether the frontmost app is one of `apps`
+///
+/// Uses the app seen at the last foreground change while a listener is
+/// watching, and only asks the OS otherwise.
+pub(crate) fn is_frontmost<'a>(apps: impl IntoIterator<Item = &'a String>) -> bool {
+    is_frontmost_in(&WATCHED, apps)
+}
+
+fn is_frontmost_in<'a>(watched: &RwLock<Watched>, apps: impl IntoIterator<Item = &'a String>) -> bool {
+    let watched = watched.read().unwrap_or_else(PoisonError::into_inner);
+    let matches = |frontmost: &str| apps.into_iter().any(|app| is_app(frontmost, app));
+    if watched.watchers > 0 {
+        return watched.app.as_deref().is_some_and(matches);
+    }
+    drop(watched);
+    frontmost_app().as_deref().is_some_and(matches)
+}
+
+/// Keeps the frontmost app up to date for [`is_frontmost`] until dropped
+///
+/// Must be dropped on the thread that created it, which has to run an event
+/// loop for the OS to deliver foreground changes: a message loop on Windows.
+/// macOS delivers them on the main thread's run loop, which NSWorkspace needs
+/// to track the frontmost app at all.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+pub(crate) struct Watch(#[allow(dead_code)] imp::Watch);
+
+/// Start watching for foreground changes on this thread
+///
+/// Returns `None` if the OS won't report them.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+pub(crate) fn watch() -> Option<Watch> {
+    let watch = imp::Watch::start()?;
+    let mut watched = WATCHED.write().unwrap_or_else(PoisonError::into_inner);
+    if watched.watchers == 0 {
+        watched.app = frontmost_app();
+    }
+    watched.watchers += 1;
+    Some(Watch(watch))
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+impl Drop for Watch {
+    fn drop(&mut self) {
+        let mut watched = WATCHED.write().unwrap_or_else(PoisonError::into_inner);
+        watched.watchers -= 1;
+        if watched.watchers == 0 {
+            watched.app = None;
+        }
+    }
+}
+
+/// Record a foreground change, called by the OS notification
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn foreground_changed() {
+    let app = frontmost_app();
+    let mut watched = WATCHED.write().unwrap_or_else(PoisonError::into_inner);
+    if watched.watchers > 0 {
+        watched.app = app;
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ptr::NonNull;
+
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, ProtocolObject};
+    use objc2_app_kit::{NSWorkspace, NSWorkspaceDidActivateApplicationNotification};
+    use objc2_foundation::{NSNotification, NSObjectProtocol};
+
+    pub fn frontmost_app() -> Option<String> {
+        objc2::rc::autoreleasepool(|_| {
+            let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+            app.bundleIdentifier().map(|id| id.to_string())
+        })
+    }
+
+    /// An observer of NSWorkspaceDidActivateApplicationNotification
+    pub struct Watch(Retained<ProtocolObject<dyn NSObjectProtocol>>);
+
+    impl Watch {
+        pub fn start() -> Option<Self> {
+            let block = RcBlock::new(|_: NonNull<NSNotification>| super::foreground_changed());
+            let center = NSWorkspace::sharedWorkspace().notificationCenter();
+            // Without a queue, the block runs on the thread posting the
+            // notification
+            let observer = unsafe {
+                center.addObserverForName_object_queue_usingBlock(
+                    Some(NSWorkspaceDidActivateApplicationNotification),
+                    None,
+                    None,
+                    &block,
+                )
+            };
+            Some(Self(observer))
+        }
+    }
+
+    impl Drop for Watch {
+        fn drop(&mut self) {
+            let center = NSWorkspace::sharedWorkspace().notificationCenter();
+            let observer: &AnyObject = (*self.0).as_ref();
+            unsafe { center.removeObserver(observer) };
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::Foundation::{HMODULE, HWND};
+    use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowThreadProcessId, EVENT_SYSTEM_FOREGROUND,
+        WINEVENT_OUTOFCONTEXT,
+    };
+
+    pub fn frontmost_app() -> Option<String> {
+        unsafe {
+            let window = GetForegroundWindow();
+            if window.0.is_null() {
+                return None;
+            }
+            let mut pid = 0;
+            GetWindowThreadProcessId(window, Some(&mut pid));
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+
+            let mut path = [0u16; 1024];
+            let mut len = path.len() as u32;
+            let queried = QueryFullProcessImageNameW(
+                process,
+                PROCESS_NAME_WIN32,
+                PWSTR(path.as_mut_ptr()),
+                &mut len,
+            );
+            let _ = CloseHandle(process);
+            queried.ok()?;
+
+            let path = String::from_utf16_lossy(&path[..len as usize]);
+            path.rsplit('\\').next().map(str::to_string)
+        }
+    }
+
+    /// An EVENT_SYSTEM_FOREGROUND hook, delivered through the message loop of
+    /// the thread that installed it
+    pub struct Watch(HWINEVENTHOOK);
+
+    impl Watch {
+        pub fn start() -> Option<Self> {
+            let hook = unsafe {
+                SetWinEventHook(
+                    EVENT_SYSTEM_FOREGROUND,
+                    EVENT_SYSTEM_FOREGROUND,
+                    HMODULE::default(),
+                    Some(on_foreground),
+                    0,
+                    0,
+                    WINEVENT_OUTOFCONTEXT,
+                )
+            };
+            (!hook.is_invalid()).then_some(Self(hook))
+        }
+    }
+
+    impl Drop for Watch {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = UnhookWinEvent(self.0);
+            }
+        }
+    }
+
+    unsafe extern "system" fn on_foreground(
+        _hook: HWINEVENTHOOK,
+        _event: u32,
+        _window: HWND,
+        _object: i32,
+        _child: i32,
+        _thread: u32,
+        _time: u32,
+    ) {
+        super::foreground_changed();
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod imp {
+    pub fn frontmost_app() -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apps_match_ignoring_case() {
+        assert!(is_app("Code.exe", "code.EXE"));
+        assert!(is_app("com.apple.Safari", "com.apple.Safari"));
+        assert!(!is_app("com.apple.Safari", "com.apple.SafariTechnologyPreview"));
+    }
+
+    #[test]
+    fn watched_app_used_while_watching() {
+        let apps = ["Code.exe".to_string()];
+        let watched = RwLock::new(Watched { watchers: 1, app: Some("code.EXE".to_string()) });
+        assert!(is_frontmost_in(&watched, &apps));
+
+        let unwatched = RwLock::new(Watched { watchers: 0, app: None });
+        let asked = frontmost_app().is_some_and(|app| is_app(&app, "Code.exe"));
+        assert_eq!(is_frontmost_in(&unwatched, &apps), asked);
+    }
+}
@@ -0,0 +1,76 @@
+//! Headless backend for CI and container environments
+//!
+//! Enabled by setting the `HANDY_KEYS_HEADLESS` environment variable (or
+//! `HANDY_KEYS_BACKEND=headless`, see [`crate::platform::env`]). No OS
+//! hooks are installed and no permissions are needed, so listeners and
+//! managers can be created without a display server or accessibility access.
+//! No key events are produced.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::channel::{self, Receiver};
+use crate::error::Result;
+use crate::platform::env::{self, Backend};
+use crate::platform::state::{BlockingHotkeys, ListenerConfig};
+use crate::platform::threads::{self, Handle};
+use crate::types::KeyEvent;
+
+/// Environment variable that selects the headless backend
+pub const HEADLESS_ENV: &str = "HANDY_KEYS_HEADLESS";
+
+/// State for a running headless listener
+pub struct HeadlessListenerState {
+    pub event_receiver: Receiver<KeyEvent>,
+    pub thread_handle: Option<Handle>,
+    pub running: Arc<AtomicBool>,
+    pub blocking_hotkeys: Option<BlockingHotkeys>,
+}
+
+/// Returns `true` if the headless backend is selected
+pub fn is_enabled() -> bool {
+    matches!(env::backend(), Ok(Some(Backend::Headless)))
+}
+
+/// Spawn a listener that produces no events
+pub(crate) fn spawn(config: ListenerConfig) -> Result<HeadlessListenerState> {
+    let (tx, rx) = channel::channel();
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = Arc::clone(&running);
+
+    // Hold the sender until the listener is dropped, so receivers time out
+    // instead of seeing a disconnected listener
+    let handle = threads::spawn(&running, move || {
+        let _event_sender = tx;
+        while thread_running.load(Ordering::SeqCst) {
+            thread::sleep(Duration::from_millis(100));
+        }
+    })?;
+
+    Ok(HeadlessListenerState {
+        event_receiver: rx,
+        thread_handle: Some(handle),
+        running,
+        blocking_hotkeys: config.blocking_hotkeys,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_listener_stays_connected() {
+        let state = spawn(ListenerConfig::default()).unwrap();
+
+        assert!(matches!(
+            state.event_receiver.recv_timeout(Duration::from_millis(10)),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout)
+        ));
+
+        state.running.store(false, Ordering::SeqCst);
+        state.thread_handle.unwrap().join();
+    }
+}
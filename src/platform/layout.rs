@@ -0,0 +1,281 @@
+//! Lookup of the key that types a character in the active keyboard layout
+
+use crate::platform::headless;
+use crate::types::Key;
+
+/// Keys that type a character with Shift on a US layout
+const US_SHIFTED: &[(char, Key)] = &[
+    ('~', Key::Grave),
+    ('!', Key::Num1),
+    ('@', Key::Num2),
+    ('#', Key::Num3),
+    ('$', Key::Num4),
+    ('%', Key::Num5),
+    ('^', Key::Num6),
+    ('&', Key::Num7),
+    ('*', Key::Num8),
+    ('(', Key::Num9),
+    (')', Key::Num0),
+    ('_', Key::Minus),
+    ('+', Key::Equal),
+    ('{', Key::LeftBracket),
+    ('}', Key::RightBracket),
+    ('|', Key::Backslash),
+    (':', Key::Semicolon),
+    ('"', Key::Quote),
+    ('<', Key::Comma),
+    ('>', Key::Period),
+    ('?', Key::Slash),
+];
+
+/// The key that types `c` in the active keyboard layout, and whether it
+/// needs Shift
+///
+/// Keys are those the listener reports, so positions on macOS and Linux and
+/// virtual keys on Windows. A US layout is assumed where the layout can't be
+/// read.
+pub(crate) fn key_for_char(c: char) -> Option<(Key, bool)> {
+    if headless::is_enabled() {
+        return us_key_for_char(c);
+    }
+    imp::key_for_char(c).unwrap_or_else(|| us_key_for_char(c))
+}
+
+/// The key that types `c` on a US layout, and whether it needs Shift
+fn us_key_for_char(c: char) -> Option<(Key, bool)> {
+    if let Some(&(_, key)) = US_SHIFTED.iter().find(|(shifted, _)| *shifted == c) {
+        return Some((key, true));
+    }
+    match c {
+        ' ' => Some((Key::Space, false)),
+        'a'..='z' | '0'..='9' | '-' | '=' | '[' | ']' | '\\' | ';' | '\'' | ',' | '.' | '/'
+        | '`' => Some((c.to_string().parse().ok()?, false)),
+        _ => None,
+    }
+}
+
+// Each `key_for_char` returns `None` if the layout can't be read, and
+// `Some(None)` if no key types the character.
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ffi::c_void;
+
+    use crate::platform::macos::keycode::keycode_to_key;
+    use crate::types::Key;
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        static kTISPropertyUnicodeKeyLayoutData: *const c_void;
+        fn TISCopyCurrentKeyboardLayoutInputSource() -> *mut c_void;
+        fn TISGetInputSourceProperty(source: *mut c_void, key: *const c_void) -> *const c_void;
+        fn LMGetKbdType() -> u8;
+        fn UCKeyTranslate(
+            layout: *const u8,
+            keycode: u16,
+            action: u16,
+            modifier_state: u32,
+            keyboard_type: u32,
+            options: u32,
+            dead_key_state: *mut u32,
+            max_len: usize,
+            len: *mut usize,
+            chars: *mut u16,
+        ) -> i32;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFDataGetBytePtr(data: *const c_void) -> *const u8;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    extern "C" {
+        fn pthread_main_np() -> i32;
+    }
+
+    const KEY_ACTION_DOWN: u16 = 0;
+    /// `shiftKey` of `EventModifiers`, shifted right by 8 as `UCKeyTranslate`
+    /// expects
+    const SHIFT_KEY_STATE: u32 = 0x02;
+    const NO_DEAD_KEYS: u32 = 1;
+
+    pub fn key_for_char(c: char) -> Option<Option<(Key, bool)>> {
+        // Text Input Sources may only be used on the main thread
+        if unsafe { pthread_main_np() } == 0 {
+            return None;
+        }
+        let mut expected = [0u16; 2];
+        let expected = c.encode_utf16(&mut expected);
+
+        unsafe {
+            let source = TISCopyCurrentKeyboardLayoutInputSource();
+            if source.is_null() {
+                return None;
+            }
+            let data = TISGetInputSourceProperty(source, kTISPropertyUnicodeKeyLayoutData);
+            let found = (!data.is_null()).then(|| {
+                let layout = CFDataGetBytePtr(data);
+                let keyboard_type = LMGetKbdType() as u32;
+                let types = |keycode: u16, modifier_state: u32| {
+                    let mut dead_key_state = 0;
+                    let mut chars = [0u16; 4];
+                    let mut len = 0;
+                    let status = UCKeyTranslate(
+                        layout,
+                        keycode,
+                        KEY_ACTION_DOWN,
+                        modifier_state,
+                        keyboard_type,
+                        NO_DEAD_KEYS,
+                        &mut dead_key_state,
+                        chars.len(),
+                        &mut len,
+                        chars.as_mut_ptr(),
+                    );
+                    status == 0 && chars[..len] == *expected
+                };
+                [(0, false), (SHIFT_KEY_STATE, true)]
+                    .into_iter()
+                    .find_map(|(modifier_state, shift)| {
+                        (0..0x80)
+                            .filter(|&keycode| types(keycode, modifier_state))
+                            .filter_map(keycode_to_key)
+                            .find(|key| !is_keypad(*key))
+                            .map(|key| (key, shift))
+                    })
+            });
+            CFRelease(source);
+            found
+        }
+    }
+
+    /// Whether a key is on the numeric keypad, which types the same characters as
+    /// keys on the main keyboard
+    fn is_keypad(key: Key) -> bool {
+        matches!(
+            key,
+            Key::Keypad0
+                | Key::Keypad1
+                | Key::Keypad2
+                | Key::Keypad3
+                | Key::Keypad4
+                | Key::Keypad5
+                | Key::Keypad6
+                | Key::Keypad7
+                | Key::Keypad8
+                | Key::Keypad9
+                | Key::KeypadDecimal
+                | Key::KeypadMultiply
+                | Key::KeypadPlus
+                | Key::KeypadClear
+                | Key::KeypadDivide
+                | Key::KeypadEnter
+                | Key::KeypadMinus
+                | Key::KeypadEquals
+        )
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyboardLayout, VkKeyScanExW};
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    use crate::platform::windows::vk_to_key;
+    use crate::types::Key;
+
+    pub fn key_for_char(c: char) -> Option<Option<(Key, bool)>> {
+        let mut utf16 = [0u16; 2];
+        let &mut [unit] = c.encode_utf16(&mut utf16) else {
+            return Some(None);
+        };
+        let scan = unsafe {
+            let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), None);
+            VkKeyScanExW(unit, GetKeyboardLayout(thread_id))
+        };
+        if scan == -1 {
+            return Some(None);
+        }
+        let [vk_code, shift_state] = (scan as u16).to_le_bytes();
+        // Characters that need Ctrl or Alt too, like AltGr ones, can't be
+        // typed with Shift alone
+        if shift_state & !1 != 0 {
+            return Some(None);
+        }
+        Some(vk_to_key(vk_code.into(), false).map(|key| (key, shift_state & 1 != 0)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use rdev::{EventType, Keyboard, KeyboardState};
+
+    use crate::platform::linux::keycode::{rdev_key_to_key, unknown_keycode_to_key};
+    use crate::types::Key;
+
+    /// Keys that type characters, as rdev names them
+    const CHARACTER_KEYS: &[rdev::Key] = &[
+        rdev::Key::KeyA, rdev::Key::KeyB, rdev::Key::KeyC, rdev::Key::KeyD, rdev::Key::KeyE,
+        rdev::Key::KeyF, rdev::Key::KeyG, rdev::Key::KeyH, rdev::Key::KeyI, rdev::Key::KeyJ,
+        rdev::Key::KeyK, rdev::Key::KeyL, rdev::Key::KeyM, rdev::Key::KeyN, rdev::Key::KeyO,
+        rdev::Key::KeyP, rdev::Key::KeyQ, rdev::Key::KeyR, rdev::Key::KeyS, rdev::Key::KeyT,
+        rdev::Key::KeyU, rdev::Key::KeyV, rdev::Key::KeyW, rdev::Key::KeyX, rdev::Key::KeyY,
+        rdev::Key::KeyZ,
+        rdev::Key::Num1, rdev::Key::Num2, rdev::Key::Num3, rdev::Key::Num4, rdev::Key::Num5,
+        rdev::Key::Num6, rdev::Key::Num7, rdev::Key::Num8, rdev::Key::Num9, rdev::Key::Num0,
+        rdev::Key::Minus, rdev::Key::Equal, rdev::Key::LeftBracket, rdev::Key::RightBracket,
+        rdev::Key::BackSlash, rdev::Key::SemiColon, rdev::Key::Quote, rdev::Key::Comma,
+        rdev::Key::Dot, rdev::Key::Slash, rdev::Key::BackQuote, rdev::Key::IntlBackslash,
+        rdev::Key::Space,
+        // Ro and Yen on JIS keyboards
+        rdev::Key::Unknown(97), rdev::Key::Unknown(132),
+    ];
+
+    pub fn key_for_char(c: char) -> Option<Option<(Key, bool)>> {
+        let mut keyboard = Keyboard::new()?;
+        let expected = c.to_string();
+        for shift in [false, true] {
+            keyboard.reset();
+            if shift {
+                keyboard.add(&EventType::KeyPress(rdev::Key::ShiftLeft));
+            }
+            for &rdev_key in CHARACTER_KEYS {
+                if keyboard.add(&EventType::KeyPress(rdev_key)).as_deref() == Some(expected.as_str()) {
+                    let key = match rdev_key {
+                        rdev::Key::Unknown(code) => unknown_keycode_to_key(code),
+                        rdev_key => rdev_key_to_key(rdev_key),
+                    };
+                    return Some(key.map(|key| (key, shift)));
+                }
+            }
+        }
+        Some(None)
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+mod imp {
+    use crate::types::Key;
+
+    pub fn key_for_char(_c: char) -> Option<Option<(Key, bool)>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn us_layout_characters() {
+        assert_eq!(us_key_for_char('a'), Some((Key::A, false)));
+        assert_eq!(us_key_for_char('7'), Some((Key::Num7, false)));
+        assert_eq!(us_key_for_char(' '), Some((Key::Space, false)));
+        assert_eq!(us_key_for_char('/'), Some((Key::Slash, false)));
+        assert_eq!(us_key_for_char('?'), Some((Key::Slash, true)));
+        assert_eq!(us_key_for_char('~'), Some((Key::Grave, true)));
+        assert_eq!(us_key_for_char('é'), None);
+        assert_eq!(us_key_for_char('§'), None);
+    }
+}
@@ -0,0 +1,367 @@
+//! A minimal D-Bus client, just enough to follow a signal on the system bus
+//!
+//! Covers only what handy-keys needs. It connects over a Unix socket with
+//! `EXTERNAL` authentication. It calls methods of the bus itself, with at most
+//! one string argument. It reads back messages whose header fields are
+//! strings, object paths, signatures or integers.
+
+use std::io::{self, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+/// Where the system bus listens unless `DBUS_SYSTEM_BUS_ADDRESS` says otherwise
+const DEFAULT_SYSTEM_BUS: &str = "/var/run/dbus/system_bus_socket";
+/// The largest message the protocol allows
+const MAX_MESSAGE_LEN: usize = 1 << 27;
+/// How long to wait for the bus to answer a call
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Message types
+const METHOD_CALL: u8 = 1;
+const METHOD_RETURN: u8 = 2;
+pub(crate) const SIGNAL: u8 = 4;
+
+// Header fields
+const PATH: u8 = 1;
+const INTERFACE: u8 = 2;
+const MEMBER: u8 = 3;
+const REPLY_SERIAL: u8 = 5;
+const DESTINATION: u8 = 6;
+const SIGNATURE: u8 = 8;
+
+/// A message read from the bus
+pub(crate) struct Message {
+    pub kind: u8,
+    pub member: Option<String>,
+    reply_serial: Option<u32>,
+    signature: Option<String>,
+    big_endian: bool,
+    body: Vec<u8>,
+}
+
+impl Message {
+    /// The body's first value, if it's a boolean
+    pub fn first_bool(&self) -> Option<bool> {
+        if !self.signature.as_deref()?.starts_with('b') {
+            return None;
+        }
+        let mut cursor = Cursor { bytes: &self.body, pos: 0, big_endian: self.big_endian };
+        cursor.u32().map(|value| value != 0)
+    }
+}
+
+/// An authenticated connection to the system bus
+pub(crate) struct Connection {
+    stream: UnixStream,
+    serial: u32,
+}
+
+impl Connection {
+    /// Connect to the system bus and register with it
+    pub fn system() -> io::Result<Self> {
+        let address = std::env::var("DBUS_SYSTEM_BUS_ADDRESS").ok();
+        let path = match address.as_deref() {
+            Some(address) => socket_path(address).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Unsupported, format!("unsupported D-Bus address: {address}"))
+            })?,
+            None => DEFAULT_SYSTEM_BUS,
+        };
+        let stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        authenticate(&stream)?;
+
+        let mut connection = Self { stream, serial: 0 };
+        connection.call("Hello", None)?;
+        Ok(connection)
+    }
+
+    /// Call a method of the bus itself and wait for its reply
+    pub fn call(&mut self, member: &str, arg: Option<&str>) -> io::Result<Message> {
+        self.serial += 1;
+        let serial = self.serial;
+        self.stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        (&self.stream).write_all(&method_call(serial, member, arg))?;
+        loop {
+            let message = read_message(&mut &self.stream)?;
+            // Anything else, such as the NameAcquired signal after Hello, is
+            // of no interest this early
+            if message.reply_serial != Some(serial) {
+                continue;
+            }
+            return match message.kind {
+                METHOD_RETURN => Ok(message),
+                _ => Err(io::Error::other(format!("D-Bus call to {member} failed"))),
+            };
+        }
+    }
+
+    /// Wait for the next message, however long that takes
+    pub fn receive(&mut self) -> io::Result<Message> {
+        self.stream.set_read_timeout(None)?;
+        read_message(&mut &self.stream)
+    }
+
+    /// Another handle to the socket, to shut it down from another thread
+    pub fn try_clone_socket(&self) -> io::Result<UnixStream> {
+        self.stream.try_clone()
+    }
+}
+
+/// The socket path of a `unix:path=...` bus address
+///
+/// Addresses can list several alternatives; the first Unix path is used.
+fn socket_path(address: &str) -> Option<&str> {
+    address.split(';').find_map(|entry| {
+        entry
+            .strip_prefix("unix:")?
+            .split(',')
+            .find_map(|pair| pair.strip_prefix("path="))
+    })
+}
+
+/// Authenticate as this process's user, which the bus checks against the
+/// socket's peer credentials
+fn authenticate(mut stream: &UnixStream) -> io::Result<()> {
+    let uid = std::fs::metadata("/proc/self")?.uid();
+    let hex: String = uid.to_string().bytes().map(|byte| format!("{byte:02x}")).collect();
+    stream.write_all(format!("\0AUTH EXTERNAL {hex}\r\n").as_bytes())?;
+
+    // Read a byte at a time, so nothing after the line is consumed
+    let mut line = Vec::new();
+    let mut byte = [0; 1];
+    while !line.ends_with(b"\r\n") {
+        stream.read_exact(&mut byte)?;
+        line.push(byte[0]);
+        if line.len() > 512 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "D-Bus authentication reply too long"));
+        }
+    }
+    if !line.starts_with(b"OK ") {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "D-Bus authentication rejected"));
+    }
+    stream.write_all(b"BEGIN\r\n")
+}
+
+/// Encode a call to a method of the bus itself
+fn method_call(serial: u32, member: &str, arg: Option<&str>) -> Vec<u8> {
+    let mut fields = vec![
+        (PATH, "o", "/org/freedesktop/DBus"),
+        (INTERFACE, "s", "org.freedesktop.DBus"),
+        (MEMBER, "s", member),
+        (DESTINATION, "s", "org.freedesktop.DBus"),
+    ];
+    let mut body = Encoder::default();
+    if let Some(arg) = arg {
+        fields.push((SIGNATURE, "g", "s"));
+        body.string(arg);
+    }
+    encode(METHOD_CALL, serial, &fields, &body.bytes)
+}
+
+/// Encode a little-endian message with string-like header fields
+fn encode(kind: u8, serial: u32, fields: &[(u8, &str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut message = Encoder::default();
+    message.bytes.extend_from_slice(&[b'l', kind, 0, 1]);
+    message.u32(body.len() as u32);
+    message.u32(serial);
+
+    let length_at = message.bytes.len();
+    message.u32(0);
+    message.align(8);
+    let fields_start = message.bytes.len();
+    for &(code, signature, value) in fields {
+        message.align(8);
+        message.bytes.push(code);
+        message.signature(signature);
+        match signature {
+            "g" => message.signature(value),
+            _ => message.string(value),
+        }
+    }
+    let fields_len = (message.bytes.len() - fields_start) as u32;
+    message.bytes[length_at..length_at + 4].copy_from_slice(&fields_len.to_le_bytes());
+
+    message.align(8);
+    message.bytes.extend_from_slice(body);
+    message.bytes
+}
+
+/// Read one message off the stream
+fn read_message(reader: &mut impl Read) -> io::Result<Message> {
+    let mut fixed = [0; 16];
+    reader.read_exact(&mut fixed)?;
+    let big_endian = match fixed[0] {
+        b'l' => false,
+        b'B' => true,
+        _ => return Err(invalid("unknown byte order")),
+    };
+    let word = |at: usize| {
+        let bytes = [fixed[at], fixed[at + 1], fixed[at + 2], fixed[at + 3]];
+        (if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }) as usize
+    };
+    let (body_len, fields_len) = (word(4), word(12));
+    if body_len + fields_len > MAX_MESSAGE_LEN {
+        return Err(invalid("message too long"));
+    }
+
+    let header_len = (16 + fields_len).next_multiple_of(8);
+    let mut header = fixed.to_vec();
+    header.resize(header_len, 0);
+    reader.read_exact(&mut header[16..])?;
+    let mut body = vec![0; body_len];
+    reader.read_exact(&mut body)?;
+
+    let mut message = Message {
+        kind: fixed[1],
+        member: None,
+        reply_serial: None,
+        signature: None,
+        big_endian,
+        body,
+    };
+    let mut cursor = Cursor { bytes: &header[..16 + fields_len], pos: 16, big_endian };
+    while cursor.pos < cursor.bytes.len() {
+        cursor.align(8);
+        let code = cursor.u8().ok_or_else(|| invalid("truncated header"))?;
+        let signature = cursor.signature().ok_or_else(|| invalid("truncated header"))?;
+        let value = match signature.as_str() {
+            "s" | "o" => cursor.string(),
+            "g" => cursor.signature(),
+            "u" => cursor.u32().map(|value| value.to_string()),
+            _ => return Err(invalid("unsupported header field type")),
+        }
+        .ok_or_else(|| invalid("truncated header"))?;
+        match code {
+            MEMBER => message.member = Some(value),
+            REPLY_SERIAL => message.reply_serial = value.parse().ok(),
+            SIGNATURE => message.signature = Some(value),
+            _ => {}
+        }
+    }
+    Ok(message)
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("malformed D-Bus message: {reason}"))
+}
+
+/// Builds a little-endian message, aligning values from its start
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn align(&mut self, to: usize) {
+        self.bytes.resize(self.bytes.len().next_multiple_of(to), 0);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes.extend_from_slice(value.as_bytes());
+        self.bytes.push(0);
+    }
+
+    fn signature(&mut self, value: &str) {
+        self.bytes.push(value.len() as u8);
+        self.bytes.extend_from_slice(value.as_bytes());
+        self.bytes.push(0);
+    }
+}
+
+/// Reads values out of a message, in its byte order
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl Cursor<'_> {
+    fn align(&mut self, to: usize) {
+        self.pos = self.pos.next_multiple_of(to);
+    }
+
+    fn take(&mut self, len: usize) -> Option<&[u8]> {
+        let bytes = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.align(4);
+        let bytes: [u8; 4] = self.take(4)?.try_into().ok()?;
+        Some(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+
+    /// A string or object path, followed by a nul
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        let value = String::from_utf8(self.take(len)?.to_vec()).ok();
+        self.take(1)?;
+        value
+    }
+
+    fn signature(&mut self) -> Option<String> {
+        let len = self.u8()? as usize;
+        let value = String::from_utf8(self.take(len)?.to_vec()).ok();
+        self.take(1)?;
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn socket_path_takes_the_first_unix_path() {
+        assert_eq!(socket_path("unix:path=/run/dbus/system_bus_socket"), Some("/run/dbus/system_bus_socket"));
+        assert_eq!(socket_path("tcp:host=localhost;unix:guid=00,path=/tmp/bus"), Some("/tmp/bus"));
+        assert_eq!(socket_path("unix:abstract=/tmp/dbus-XYZ"), None);
+    }
+
+    #[test]
+    fn reads_back_what_it_encodes() {
+        // PrepareForSleep(false), as logind sends it after a resume
+        let fields = [
+            (PATH, "o", "/org/freedesktop/login1"),
+            (INTERFACE, "s", "org.freedesktop.login1.Manager"),
+            (MEMBER, "s", "PrepareForSleep"),
+            (SIGNATURE, "g", "b"),
+        ];
+        let bytes = encode(SIGNAL, 7, &fields, &0u32.to_le_bytes());
+        let message = read_message(&mut bytes.as_slice()).unwrap();
+        assert_eq!(message.kind, SIGNAL);
+        assert_eq!(message.member.as_deref(), Some("PrepareForSleep"));
+        assert_eq!(message.first_bool(), Some(false));
+
+        let call = method_call(3, "AddMatch", Some("type='signal'"));
+        let message = read_message(&mut call.as_slice()).unwrap();
+        assert_eq!(message.kind, METHOD_CALL);
+        assert_eq!(message.member.as_deref(), Some("AddMatch"));
+        assert_eq!(message.first_bool(), None);
+    }
+
+    #[test]
+    fn reads_big_endian_replies() {
+        // A method return for serial 2 with body `true`
+        let mut bytes = vec![b'B', METHOD_RETURN, 0, 1, 0, 0, 0, 4, 0, 0, 0, 9, 0, 0, 0, 15];
+        bytes.extend_from_slice(&[REPLY_SERIAL, 1, b'u', 0, 0, 0, 0, 2]);
+        bytes.extend_from_slice(&[SIGNATURE, 1, b'g', 0, 1, b'b', 0, 0]);
+        bytes.extend_from_slice(&[0, 0, 0, 1]);
+        let message = read_message(&mut bytes.as_slice()).unwrap();
+        assert_eq!(message.kind, METHOD_RETURN);
+        assert_eq!(message.reply_serial, Some(2));
+        assert_eq!(message.first_bool(), Some(true));
+    }
+}
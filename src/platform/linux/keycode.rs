@@ -72,6 +72,9 @@ pub fn rdev_key_to_key(key: rdev::Key) -> Option<Key> {
         RK::End => Some(Key::End),
         RK::PageUp => Some(Key::PageUp),
         RK::PageDown => Some(Key::PageDown),
+        RK::PrintScreen => Some(Key::PrintScreen),
+        RK::Insert => Some(Key::Insert),
+        RK::Pause => Some(Key::Pause),
 
         // Arrow keys
         RK::LeftArrow => Some(Key::LeftArrow),
@@ -112,6 +115,7 @@ pub fn rdev_key_to_key(key: rdev::Key) -> Option<Key> {
 
         // Lock keys
         RK::CapsLock => Some(Key::CapsLock),
+        RK::IntlBackslash => Some(Key::Section),
         RK::ScrollLock => Some(Key::ScrollLock),
         RK::NumLock => Some(Key::NumLock),
 
@@ -119,6 +123,31 @@ pub fn rdev_key_to_key(key: rdev::Key) -> Option<Key> {
     }
 }
 
+/// Identify a key rdev reports as `Unknown`, from its X11 keycode
+///
+/// rdev has no variants for media keys, the Menu key or most Japanese and
+/// Korean keys, so the X11 listener passes them on as raw keycodes. The evdev grab used for blocking hotkeys drops them
+/// before they get here, so they can't be blocked.
+pub fn unknown_keycode_to_key(code: u32) -> Option<Key> {
+    match code {
+        97 => Some(Key::Ro),              // backslash/underscore (JIS)
+        101 => Some(Key::Kana),           // Hiragana_Katakana
+        121 => Some(Key::VolumeMute),     // XF86AudioMute
+        122 => Some(Key::VolumeDown),     // XF86AudioLowerVolume
+        123 => Some(Key::VolumeUp),       // XF86AudioRaiseVolume
+        130 => Some(Key::Hangul),         // Hangul
+        131 => Some(Key::Hanja),          // Hangul_Hanja
+        132 => Some(Key::Yen),            // backslash/bar (JIS)
+        135 => Some(Key::ContextMenu),    // Menu
+        171 => Some(Key::NextTrack),      // XF86AudioNext
+        172 => Some(Key::PlayPause),      // XF86AudioPlay
+        173 => Some(Key::PreviousTrack),  // XF86AudioPrev
+        232 => Some(Key::BrightnessDown), // XF86MonBrightnessDown
+        233 => Some(Key::BrightnessUp),   // XF86MonBrightnessUp
+        _ => None,
+    }
+}
+
 /// Convert an rdev modifier key to our Modifiers type
 pub fn rdev_key_to_modifier(key: rdev::Key) -> Option<Modifiers> {
     use rdev::Key as RK;
@@ -131,6 +160,22 @@ pub fn rdev_key_to_modifier(key: rdev::Key) -> Option<Modifiers> {
     }
 }
 
+/// The side flag of an rdev modifier key, like `Modifiers::R_OPT` for AltGr
+pub fn rdev_key_to_side(key: rdev::Key) -> Option<Modifiers> {
+    use rdev::Key as RK;
+    match key {
+        RK::ShiftLeft => Some(Modifiers::L_SHIFT),
+        RK::ShiftRight => Some(Modifiers::R_SHIFT),
+        RK::ControlLeft => Some(Modifiers::L_CTRL),
+        RK::ControlRight => Some(Modifiers::R_CTRL),
+        RK::Alt => Some(Modifiers::L_OPT),
+        RK::AltGr => Some(Modifiers::R_OPT),
+        RK::MetaLeft => Some(Modifiers::L_CMD),
+        RK::MetaRight => Some(Modifiers::R_CMD),
+        _ => None,
+    }
+}
+
 /// Convert rdev::Button to our Key type
 pub fn rdev_button_to_key(button: rdev::Button) -> Option<Key> {
     use rdev::Button as RB;
@@ -5,127 +5,225 @@
 //! When dropped, the listener stops processing events. The underlying thread
 //! remains alive (rdev limitation) but becomes idle because rdev::grab()
 //! blocks indefinitely and cannot be interrupted.
+//!
+//! # Grab conflicts
+//!
+//! rdev grabs the input devices, which fails if another process (such as
+//! another hotkey daemon) already grabbed them. rdev gives no sign that a grab
+//! succeeded, only that it stopped, so the listener is created without
+//! waiting. A grab that fails, at startup or later, stops the listener, and
+//! its `recv` methods return [`Error::GrabFailed`](crate::Error::GrabFailed) within a second. Managers
+//! keep retrying until the conflicting grab goes away.
+//!
+//! # Sessions
+//!
+//! Input is ignored (and passed through) while the process's logind session
+//! is inactive, for example after switching VT. Held modifiers are released
+//! when the session goes inactive, so hotkeys don't stay pressed, and both
+//! changes are reported as [`SystemEvent`]s.
 
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{self, Receiver};
-use std::sync::{Arc, Mutex};
-use std::thread::{self, JoinHandle};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
 
+use crate::channel::{self, Receiver};
 use crate::error::Result;
-use crate::platform::state::{BlockingHotkeys, ListenerState};
-use crate::types::{KeyEvent, Modifiers};
+use crate::platform::state::{BlockingHotkeys, ListenerConfig, ListenerState, SystemEvent, WakeDetector};
+use crate::platform::threads::{self, Handle};
+use crate::types::KeyEvent;
 
-use super::keycode::{rdev_button_to_key, rdev_key_to_key, rdev_key_to_modifier, update_modifiers};
+use super::keycode::{
+    rdev_button_to_key, rdev_key_to_key, rdev_key_to_modifier, rdev_key_to_side,
+    unknown_keycode_to_key, update_modifiers,
+};
+use super::session;
 use crate::types::Key;
 
 /// Internal listener state returned to KeyboardListener
 pub(crate) struct LinuxListenerState {
     pub event_receiver: Receiver<KeyEvent>,
-    pub thread_handle: Option<JoinHandle<()>>,
+    pub thread_handle: Option<Handle>,
     pub running: Arc<AtomicBool>,
     pub blocking_hotkeys: Option<BlockingHotkeys>,
+    /// Why the grab stopped, once it has
+    pub grab_failure: Arc<OnceLock<String>>,
 }
 
+/// How often the watchdog checks for stuck modifiers, system wake and
+/// session changes
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Spawn an rdev-based keyboard listener for Linux
-pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<LinuxListenerState> {
-    let (tx, rx) = mpsc::channel();
-    let state = Arc::new(Mutex::new(ListenerState::new(tx, blocking_hotkeys.clone())));
+pub(crate) fn spawn(config: ListenerConfig) -> Result<LinuxListenerState> {
+    let (tx, rx) = channel::channel();
+    let state = Arc::new(Mutex::new(ListenerState::new(tx, &config)));
     let running = Arc::new(AtomicBool::new(true));
+    let session_active = Arc::new(AtomicBool::new(session::is_active().unwrap_or(true)));
+
+    // rdev::grab() never returns control to us, so stuck modifiers, wake from
+    // sleep and session changes are checked from a separate thread. rdev can't
+    // query the OS modifier state, so modifiers are assumed released.
+    let watchdog_state = Arc::clone(&state);
+    let watchdog_running = Arc::clone(&running);
+    let watchdog_session_active = Arc::clone(&session_active);
+    threads::spawn(&running, move || {
+        let mut wake_detector = WakeDetector::new();
+        while watchdog_running.load(Ordering::SeqCst) {
+            thread::sleep(WATCHDOG_INTERVAL);
+            let active = session::is_active().unwrap_or(true);
+            let was_active = watchdog_session_active.swap(active, Ordering::SeqCst);
+            if let Ok(mut state) = watchdog_state.lock() {
+                // Key ups are lost while another session has the keyboard,
+                // just like while asleep
+                let woke = wake_detector.tick();
+                if woke || (was_active && !active) {
+                    state.resync_after_wake(None);
+                }
+                if woke {
+                    state.report_system_event(SystemEvent::Woke);
+                }
+                if was_active != active {
+                    state.report_system_event(if active {
+                        SystemEvent::SessionActive
+                    } else {
+                        SystemEvent::SessionInactive
+                    });
+                }
+                state.release_stuck_modifiers(|| None);
+            }
+        }
+    })?;
 
+    let grab_failure = Arc::new(OnceLock::new());
+    let thread_grab_failure = Arc::clone(&grab_failure);
+    let grab_running = Arc::clone(&running);
     let thread_state = Arc::clone(&state);
     let thread_running = Arc::clone(&running);
+    let thread_session_active = Arc::clone(&session_active);
 
-    let handle = thread::spawn(move || {
+    let handle = threads::spawn_prioritized(&running, config.thread_priority, move || {
         let callback = move |event: rdev::Event| -> Option<rdev::Event> {
             // Check if we should stop processing events
             if !thread_running.load(Ordering::SeqCst) {
                 return Some(event);
             }
 
+            // Keys typed into another session aren't ours
+            if !thread_session_active.load(Ordering::SeqCst) {
+                return Some(event);
+            }
+
             let mut should_block = false;
 
-            if let Ok(mut state) = thread_state.lock() {
+            if let Ok(mut guard) = thread_state.lock() {
+                let state = &mut *guard;
+                let _timer = state.time_callback();
+                state.note_input();
+
                 match event.event_type {
                     rdev::EventType::KeyPress(rdev_key) => {
                         if let Some(changed_modifier) = rdev_key_to_modifier(rdev_key) {
-                            let prev_mods = state.current_modifiers;
+                            let prev_mods = state.current_modifiers | state.held_sides();
                             state.current_modifiers =
                                 update_modifiers(state.current_modifiers, rdev_key, true);
+                            state.update_sides(rdev_key_to_side(rdev_key), true);
+                            let sides = state.held_sides();
 
                             // Emit modifier change event
-                            if state.current_modifiers != prev_mods {
+                            if state.current_modifiers | sides != prev_mods {
                                 // Check if this modifier-only combo should be blocked
-                                should_block = state.should_block(state.current_modifiers, None);
+                                should_block =
+                                    state.should_block(state.current_modifiers | sides, None);
 
-                                let _ = state.event_sender.send(KeyEvent {
+                                let _ = state.send(KeyEvent {
                                     modifiers: state.current_modifiers,
                                     key: None,
                                     is_key_down: true,
                                     changed_modifier: Some(changed_modifier),
+                                    sides,
+                                    hook: None,
                                 });
                             }
-                        } else if let Some(key) = rdev_key_to_key(rdev_key) {
+                        } else if let Some(key) = known_or_other(state, rdev_key) {
                             // Check if this should be blocked
-                            should_block = state.should_block(state.current_modifiers, Some(key));
+                            let sides = state.held_sides();
+                            let modifiers = state.current_modifiers;
+                            should_block = state.should_block_key_down(modifiers | sides, key);
 
-                            let _ = state.event_sender.send(KeyEvent {
+                            let _ = state.send(KeyEvent {
                                 modifiers: state.current_modifiers,
                                 key: Some(key),
                                 is_key_down: true,
                                 changed_modifier: None,
+                                sides,
+                                hook: None,
                             });
                         }
                     }
                     rdev::EventType::KeyRelease(rdev_key) => {
                         if let Some(changed_modifier) = rdev_key_to_modifier(rdev_key) {
-                            let prev_mods = state.current_modifiers;
+                            let prev_mods = state.current_modifiers | state.held_sides();
                             state.current_modifiers =
                                 update_modifiers(state.current_modifiers, rdev_key, false);
+                            state.update_sides(rdev_key_to_side(rdev_key), false);
+                            let sides = state.held_sides();
 
                             // Emit modifier change event
-                            if state.current_modifiers != prev_mods {
-                                let _ = state.event_sender.send(KeyEvent {
+                            if state.current_modifiers | sides != prev_mods {
+                                let _ = state.send(KeyEvent {
                                     modifiers: state.current_modifiers,
                                     key: None,
                                     is_key_down: false,
                                     changed_modifier: Some(changed_modifier),
+                                    sides,
+                                    hook: None,
                                 });
                             }
-                        } else if let Some(key) = rdev_key_to_key(rdev_key) {
+                        } else if let Some(key) = known_or_other(state, rdev_key) {
                             // Block key up if we blocked key down (to be consistent)
-                            should_block = state.should_block(state.current_modifiers, Some(key));
+                            let sides = state.held_sides();
+                            let modifiers = state.current_modifiers;
+                            should_block = state.should_block_key_up(modifiers | sides, key);
 
-                            let _ = state.event_sender.send(KeyEvent {
+                            let _ = state.send(KeyEvent {
                                 modifiers: state.current_modifiers,
                                 key: Some(key),
                                 is_key_down: false,
                                 changed_modifier: None,
+                                sides,
+                                hook: None,
                             });
                         }
                     }
-                    rdev::EventType::ButtonPress(button) => {
+                    rdev::EventType::ButtonPress(button)
+                    | rdev::EventType::ButtonRelease(button) => {
                         if let Some(key) = rdev_button_to_key(button) {
+                            let is_down =
+                                matches!(event.event_type, rdev::EventType::ButtonPress(_));
+                            let modifiers = state.current_modifiers;
+                            let sides = state.held_sides();
                             // Only report left/right clicks when modifiers are held
                             let is_common = matches!(key, Key::MouseLeft | Key::MouseRight);
-                            if !is_common || !state.current_modifiers.is_empty() {
-                                let _ = state.event_sender.send(KeyEvent {
-                                    modifiers: state.current_modifiers,
-                                    key: Some(key),
-                                    is_key_down: true,
-                                    changed_modifier: None,
-                                });
-                            }
-                        }
-                    }
-                    rdev::EventType::ButtonRelease(button) => {
-                        if let Some(key) = rdev_button_to_key(button) {
-                            let is_common = matches!(key, Key::MouseLeft | Key::MouseRight);
-                            if !is_common || !state.current_modifiers.is_empty() {
-                                let _ = state.event_sender.send(KeyEvent {
-                                    modifiers: state.current_modifiers,
+                            let report = !is_common || !modifiers.is_empty();
+
+                            should_block = if !report {
+                                // An unreported release still completes a blocked press
+                                !is_down && state.release_blocked_key(key)
+                            } else if is_down {
+                                state.should_block_key_down(modifiers | sides, key)
+                            } else {
+                                state.should_block_key_up(modifiers | sides, key)
+                            };
+
+                            if report {
+                                let _ = state.send(KeyEvent {
+                                    modifiers,
                                     key: Some(key),
-                                    is_key_down: false,
+                                    is_key_down: is_down,
                                     changed_modifier: None,
+                                    sides,
+                                    hook: None,
                                 });
                             }
                         }
@@ -141,16 +239,36 @@ pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<LinuxLi
             }
         };
 
-        // Start grabbing - this blocks indefinitely
-        if let Err(e) = rdev::grab(callback) {
-            eprintln!("rdev grab error: {:?}", e);
-        }
-    });
+        // Start grabbing - this blocks indefinitely unless the grab fails
+        let failure = match rdev::grab(callback) {
+            Ok(()) => "grab ended unexpectedly".to_string(),
+            Err(e) => format!("{:?}", e),
+        };
+        let _ = thread_grab_failure.set(failure);
+        // Stopping the watchdog drops the last event sender, which wakes
+        // `recv` to report the failure
+        grab_running.store(false, Ordering::SeqCst);
+    })
+    // Stop the watchdog
+    .inspect_err(|_| running.store(false, Ordering::SeqCst))?;
+    threads::pin();
 
     Ok(LinuxListenerState {
         event_receiver: rx,
         thread_handle: Some(handle),
         running,
-        blocking_hotkeys,
+        blocking_hotkeys: config.blocking_hotkeys,
+        grab_failure,
     })
 }
+
+/// Identify a non-modifier key, falling back to [`Key::Other`] for keys rdev
+/// couldn't name
+fn known_or_other(state: &mut ListenerState, rdev_key: rdev::Key) -> Option<Key> {
+    match rdev_key {
+        rdev::Key::Unknown(code) => {
+            Some(unknown_keycode_to_key(code).unwrap_or_else(|| state.unknown_key(code)))
+        }
+        rdev_key => rdev_key_to_key(rdev_key),
+    }
+}
@@ -1,4 +1,6 @@
 //! Linux platform support using rdev
 
+pub(crate) mod dbus;
 pub(crate) mod keycode;
 pub(crate) mod listener;
+pub(crate) mod session;
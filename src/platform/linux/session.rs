@@ -0,0 +1,86 @@
+//! logind session tracking
+//!
+//! rdev grabs every input device on the machine, whichever session is in
+//! front. After a VT switch, or from a remote login, the keys it sees belong
+//! to someone else, so the listener ignores them while its own session isn't
+//! the active local one.
+//!
+//! On multi-seat systems the grab still covers the keyboards of every seat,
+//! as rdev has no per-device filtering, so keys typed on another seat reach
+//! the listener while this session is active.
+
+use std::fs;
+use std::path::Path;
+
+/// Where logind publishes the state of each session
+const SESSIONS_DIR: &str = "/run/systemd/sessions";
+
+/// Check whether this process's session is the active local session
+///
+/// Returns `None` when there is no logind session to check against (no
+/// `XDG_SESSION_ID`, or logind isn't running), in which case the caller should
+/// assume it's active.
+pub(crate) fn is_active() -> Option<bool> {
+    let id = std::env::var("XDG_SESSION_ID").ok()?;
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+    read_active(&Path::new(SESSIONS_DIR).join(id))
+}
+
+fn read_active(path: &Path) -> Option<bool> {
+    parse_active(&fs::read_to_string(path).ok()?)
+}
+
+/// Read the active state out of a logind session file
+///
+/// Remote sessions never own the local keyboard, so they're never active.
+fn parse_active(contents: &str) -> Option<bool> {
+    let mut active = None;
+    let mut remote = false;
+    for line in contents.lines() {
+        match line.split_once('=') {
+            Some(("ACTIVE", value)) => active = parse_bool(value),
+            Some(("REMOTE", value)) => remote = parse_bool(value).unwrap_or(false),
+            _ => {}
+        }
+    }
+    active.map(|active| active && !remote)
+}
+
+/// Parse a boolean the way systemd writes them
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim() {
+        "1" | "yes" | "true" | "on" => Some(true),
+        "0" | "no" | "false" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_and_inactive_sessions() {
+        assert_eq!(parse_active("# comment\nUSER=1000\nACTIVE=1\nREMOTE=0\n"), Some(true));
+        assert_eq!(parse_active("ACTIVE=yes\n"), Some(true));
+        assert_eq!(parse_active("ACTIVE=0\nREMOTE=0\n"), Some(false));
+        assert_eq!(parse_active("ACTIVE=no\n"), Some(false));
+    }
+
+    #[test]
+    fn remote_sessions_are_never_active() {
+        assert_eq!(parse_active("ACTIVE=1\nREMOTE=1\n"), Some(false));
+        assert_eq!(parse_active("REMOTE=yes\nACTIVE=yes\n"), Some(false));
+    }
+
+    #[test]
+    fn unknown_state() {
+        assert_eq!(parse_active(""), None);
+        assert_eq!(parse_active("REMOTE=0\n"), None);
+        assert_eq!(parse_active("ACTIVE=maybe\n"), None);
+        assert_eq!(parse_active("\u{0}\u{1}garbage without separators"), None);
+        assert_eq!(read_active(Path::new("/nonexistent/handy-keys-session")), None);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::types::{Key, Modifiers};
-use objc2_core_graphics::CGEventFlags;
+use objc2_core_graphics::{CGEventFlags, CGEventSource, CGEventSourceStateID};
 
 /// macOS virtual key code type
 pub type CGKeyCode = u16;
@@ -116,6 +116,22 @@ mod keycodes {
     pub const RIGHT_ARROW: u16 = 0x7C;
     pub const DOWN_ARROW: u16 = 0x7D;
     pub const UP_ARROW: u16 = 0x7E;
+    // Where Insert is on PC keyboards
+    pub const HELP: u16 = 0x72;
+    pub const CONTEXTUAL_MENU: u16 = 0x6E;
+
+    // Keys on ISO and JIS keyboards
+    pub const ISO_SECTION: u16 = 0x0A;
+    pub const JIS_YEN: u16 = 0x5D;
+    pub const JIS_EISU: u16 = 0x66;
+    pub const JIS_KANA: u16 = 0x68;
+    pub const JIS_UNDERSCORE: u16 = 0x5E; // Ro
+
+    // Volume keys of non-Apple keyboards. Apple keyboards send media and
+    // brightness keys as system-defined events, which the key tap doesn't see.
+    pub const VOLUME_UP: u16 = 0x48;
+    pub const VOLUME_DOWN: u16 = 0x49;
+    pub const MUTE: u16 = 0x4A;
 }
 
 /// Convert a macOS virtual keycode to a Key enum
@@ -187,6 +203,8 @@ pub fn keycode_to_key(keycode: CGKeyCode) -> Option<Key> {
         keycodes::END => Some(Key::End),
         keycodes::PAGE_UP => Some(Key::PageUp),
         keycodes::PAGE_DOWN => Some(Key::PageDown),
+        keycodes::HELP => Some(Key::Insert),
+        keycodes::CONTEXTUAL_MENU => Some(Key::ContextMenu),
         keycodes::LEFT_ARROW => Some(Key::LeftArrow),
         keycodes::RIGHT_ARROW => Some(Key::RightArrow),
         keycodes::UP_ARROW => Some(Key::UpArrow),
@@ -221,6 +239,14 @@ pub fn keycode_to_key(keycode: CGKeyCode) -> Option<Key> {
         keycodes::KEYPAD_MINUS => Some(Key::KeypadMinus),
         keycodes::KEYPAD_EQUALS => Some(Key::KeypadEquals),
         keycodes::CAPS_LOCK => Some(Key::CapsLock),
+        keycodes::ISO_SECTION => Some(Key::Section),
+        keycodes::JIS_YEN => Some(Key::Yen),
+        keycodes::JIS_EISU => Some(Key::Eisu),
+        keycodes::JIS_KANA => Some(Key::Kana),
+        keycodes::JIS_UNDERSCORE => Some(Key::Ro),
+        keycodes::VOLUME_UP => Some(Key::VolumeUp),
+        keycodes::VOLUME_DOWN => Some(Key::VolumeDown),
+        keycodes::MUTE => Some(Key::VolumeMute),
         _ => None,
     }
 }
@@ -259,3 +285,31 @@ pub fn flags_to_modifiers(flags: CGEventFlags) -> Modifiers {
 
     mods
 }
+
+/// Device-dependent bits of `CGEventFlags` telling which modifier keys are
+/// held, from IOKit's `NX_DEVICE*KEYMASK`
+const DEVICE_SIDES: [(u64, Modifiers); 8] = [
+    (0x0001, Modifiers::L_CTRL),
+    (0x0002, Modifiers::L_SHIFT),
+    (0x0004, Modifiers::R_SHIFT),
+    (0x0008, Modifiers::L_CMD),
+    (0x0010, Modifiers::R_CMD),
+    (0x0020, Modifiers::L_OPT),
+    (0x0040, Modifiers::R_OPT),
+    (0x2000, Modifiers::R_CTRL),
+];
+
+/// Side flags of the modifier keys that `flags` reports as held
+pub fn flags_to_sides(flags: CGEventFlags) -> Modifiers {
+    DEVICE_SIDES
+        .iter()
+        .filter(|(mask, _)| flags.0 & mask != 0)
+        .fold(Modifiers::empty(), |sides, (_, side)| sides | *side)
+}
+
+/// Read the modifiers the OS currently reports as held
+pub fn current_modifiers() -> Modifiers {
+    flags_to_modifiers(CGEventSource::flags_state(
+        CGEventSourceStateID::CombinedSessionState,
+    ))
+}
@@ -3,9 +3,8 @@
 use std::ffi::c_void;
 use std::ptr::NonNull;
 use std::sync::atomic::AtomicBool;
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::mpsc::{self, Sender};
 use std::sync::{Arc, Mutex};
-use std::thread::{self, JoinHandle};
 
 use objc2_core_foundation::{CFMachPort, CFRetained, CFRunLoop, CFRunLoopSource};
 use objc2_core_graphics::{
@@ -13,29 +12,37 @@ use objc2_core_graphics::{
     CGEventTapOptions, CGEventTapPlacement, CGEventTapProxy, CGEventType,
 };
 
+use crate::channel::{self, Receiver};
 use crate::error::{Error, Result};
-use crate::platform::state::{BlockingHotkeys, ListenerState};
+use crate::platform::state::{
+    BlockingHotkeys, ListenerConfig, ListenerState, SystemEvent, TapLocation, TapPlacement,
+    WakeDetector,
+};
+use crate::platform::frontmost;
+use crate::platform::threads::{self, Handle};
 use crate::types::{Key, KeyEvent};
 
-use super::keycode::{flags_to_modifiers, keycode_to_key, keycode_to_modifier};
+use super::keycode::{
+    current_modifiers, flags_to_modifiers, flags_to_sides, keycode_to_key, keycode_to_modifier,
+};
 use super::permissions::check_accessibility;
 
 /// Internal listener state returned to KeyboardListener
 pub(crate) struct MacOSListenerState {
     pub event_receiver: Receiver<KeyEvent>,
-    pub thread_handle: Option<JoinHandle<()>>,
+    pub thread_handle: Option<Handle>,
     pub running: Arc<AtomicBool>,
     pub blocking_hotkeys: Option<BlockingHotkeys>,
 }
 
 /// Spawn a macOS keyboard listener using CGEventTap
-pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<MacOSListenerState> {
+pub(crate) fn spawn(config: ListenerConfig) -> Result<MacOSListenerState> {
     if !check_accessibility() {
         return Err(Error::AccessibilityNotGranted);
     }
 
-    let (tx, rx) = mpsc::channel();
-    let state = Arc::new(Mutex::new(ListenerState::new(tx, blocking_hotkeys.clone())));
+    let (tx, rx) = channel::channel();
+    let state = Arc::new(Mutex::new(ListenerState::new(tx, &config)));
     let running = Arc::new(AtomicBool::new(true));
 
     // Channel to communicate event tap creation success/failure
@@ -43,10 +50,11 @@ pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<MacOSLi
 
     let thread_state = Arc::clone(&state);
     let thread_running = Arc::clone(&running);
+    let (location, placement) = (config.tap_location, config.tap_placement);
 
-    let handle = thread::spawn(move || {
-        run_event_tap(thread_state, thread_running, init_tx);
-    });
+    let handle = threads::spawn_prioritized(&running, config.thread_priority, move || {
+        run_event_tap(thread_state, thread_running, location, placement, init_tx);
+    })?;
 
     // Wait for the event tap to be created
     match init_rx.recv() {
@@ -67,7 +75,7 @@ pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<MacOSLi
         event_receiver: rx,
         thread_handle: Some(handle),
         running,
-        blocking_hotkeys,
+        blocking_hotkeys: config.blocking_hotkeys,
     })
 }
 
@@ -86,10 +94,14 @@ unsafe extern "C-unwind" fn event_tap_callback(
     let cg_event = event.as_ref();
     let flags = CGEvent::flags(Some(cg_event));
     let modifiers = flags_to_modifiers(flags);
+    let sides = flags_to_sides(flags).sides_of(modifiers);
 
     let mut should_block = false;
 
     if let Ok(mut state) = state.lock() {
+        let _timer = state.time_callback();
+        state.note_input();
+
         match event_type {
             CGEventType::KeyDown => {
                 let keycode =
@@ -101,19 +113,22 @@ unsafe extern "C-unwind" fn event_tap_callback(
                 // Skip special function key events (e.g., F3 triggering Mission Control).
                 // These have MaskSecondaryFn set but use special keycodes (like 0xA0)
                 // that we don't recognize. Without this check, they'd be reported as
-                // "Fn pressed" with no key.
+                // unknown keys.
                 if key.is_none() && flags.contains(CGEventFlags::MaskSecondaryFn) {
                     return event.as_ptr();
                 }
+                let key = key.unwrap_or_else(|| state.unknown_key(keycode.into()));
 
                 // Check if this should be blocked
-                should_block = state.should_block(modifiers, key);
+                should_block = state.should_block_key_down(modifiers | sides, key);
 
-                let _ = state.event_sender.send(KeyEvent {
+                let _ = state.send(KeyEvent {
                     modifiers,
-                    key,
+                    key: Some(key),
                     is_key_down: true,
                     changed_modifier: None,
+                    sides,
+                    hook: None,
                 });
             }
             CGEventType::KeyUp => {
@@ -127,15 +142,19 @@ unsafe extern "C-unwind" fn event_tap_callback(
                 if key.is_none() && flags.contains(CGEventFlags::MaskSecondaryFn) {
                     return event.as_ptr();
                 }
+                let key = key.unwrap_or_else(|| state.unknown_key(keycode.into()));
 
-                // Block key up if we blocked key down (to be consistent)
-                should_block = state.should_block(modifiers, key);
+                // Block key up if we blocked key down (to be consistent), even
+                // when a modifier such as Opt was released before the key
+                should_block = state.should_block_key_up(modifiers | sides, key);
 
-                let _ = state.event_sender.send(KeyEvent {
+                let _ = state.send(KeyEvent {
                     modifiers,
-                    key,
+                    key: Some(key),
                     is_key_down: false,
                     changed_modifier: None,
+                    sides,
+                    hook: None,
                 });
             }
             CGEventType::FlagsChanged => {
@@ -149,8 +168,9 @@ unsafe extern "C-unwind" fn event_tap_callback(
                 // as FlagsChanged but isn't a traditional modifier
                 let lock_key = keycode_to_key(keycode);
 
-                let prev_mods = state.current_modifiers;
+                let prev_mods = state.current_modifiers | state.held_sides();
                 state.current_modifiers = modifiers;
+                state.current_sides = sides;
 
                 // Handle lock keys specially - they come through FlagsChanged
                 // but don't change our tracked modifier state
@@ -159,107 +179,95 @@ unsafe extern "C-unwind" fn event_tap_callback(
                     // or just emit both down and up on each press
                     let is_key_down = flags.contains(CGEventFlags::MaskAlphaShift);
 
-                    should_block = state.should_block(modifiers, Some(key));
+                    should_block = state.should_block(modifiers | sides, Some(key));
 
-                    let _ = state.event_sender.send(KeyEvent {
+                    let _ = state.send(KeyEvent {
                         modifiers,
                         key: Some(key),
                         is_key_down,
                         changed_modifier: None,
+                        sides,
+                        hook: None,
                     });
-                } else if modifiers != prev_mods {
+                } else if modifiers | sides != prev_mods {
                     // Regular modifier key - only emit if modifiers actually changed
                     // Determine press vs release by checking which bits changed
-                    let gained = modifiers.bits() & !prev_mods.bits();
+                    let gained = (modifiers | sides).bits() & !prev_mods.bits();
                     // A key is down if we gained any modifier bits
                     let is_key_down = gained != 0;
 
                     // Check if this modifier-only combo should be blocked
                     if is_key_down {
-                        should_block = state.should_block(modifiers, None);
+                        should_block = state.should_block(modifiers | sides, None);
                     }
 
-                    let _ = state.event_sender.send(KeyEvent {
+                    let _ = state.send(KeyEvent {
                         modifiers,
                         key: None,
                         is_key_down,
                         changed_modifier,
+                        sides,
+                        hook: None,
                     });
                 }
             }
             // Mouse button events
-            // Only report left/right clicks when modifiers are held (to avoid noise)
-            CGEventType::LeftMouseDown if !modifiers.is_empty() => {
-                let _ = state.event_sender.send(KeyEvent {
-                    modifiers,
-                    key: Some(Key::MouseLeft),
-                    is_key_down: true,
-                    changed_modifier: None,
-                });
-            }
-            CGEventType::LeftMouseUp if !modifiers.is_empty() => {
-                let _ = state.event_sender.send(KeyEvent {
-                    modifiers,
-                    key: Some(Key::MouseLeft),
-                    is_key_down: false,
-                    changed_modifier: None,
-                });
-            }
-            CGEventType::RightMouseDown if !modifiers.is_empty() => {
-                let _ = state.event_sender.send(KeyEvent {
-                    modifiers,
-                    key: Some(Key::MouseRight),
-                    is_key_down: true,
-                    changed_modifier: None,
-                });
-            }
-            CGEventType::RightMouseUp if !modifiers.is_empty() => {
-                let _ = state.event_sender.send(KeyEvent {
-                    modifiers,
-                    key: Some(Key::MouseRight),
-                    is_key_down: false,
-                    changed_modifier: None,
-                });
-            }
-            // Pass through unmodified left/right clicks
             CGEventType::LeftMouseDown
             | CGEventType::LeftMouseUp
             | CGEventType::RightMouseDown
-            | CGEventType::RightMouseUp => {}
-            CGEventType::OtherMouseDown => {
-                let button_number =
-                    CGEvent::integer_value_field(Some(cg_event), CGEventField::MouseEventButtonNumber);
-                let key = match button_number {
-                    2 => Some(Key::MouseMiddle),
-                    3 => Some(Key::MouseX1),
-                    4 => Some(Key::MouseX2),
-                    _ => None, // Unknown button
-                };
-                if let Some(key) = key {
-                    let _ = state.event_sender.send(KeyEvent {
-                        modifiers,
-                        key: Some(key),
-                        is_key_down: true,
-                        changed_modifier: None,
-                    });
-                }
-            }
-            CGEventType::OtherMouseUp => {
-                let button_number =
-                    CGEvent::integer_value_field(Some(cg_event), CGEventField::MouseEventButtonNumber);
-                let key = match button_number {
-                    2 => Some(Key::MouseMiddle),
-                    3 => Some(Key::MouseX1),
-                    4 => Some(Key::MouseX2),
-                    _ => None,
+            | CGEventType::RightMouseUp
+            | CGEventType::OtherMouseDown
+            | CGEventType::OtherMouseUp => {
+                let is_down = matches!(
+                    event_type,
+                    CGEventType::LeftMouseDown
+                        | CGEventType::RightMouseDown
+                        | CGEventType::OtherMouseDown
+                );
+                let key = match event_type {
+                    CGEventType::LeftMouseDown | CGEventType::LeftMouseUp => Some(Key::MouseLeft),
+                    CGEventType::RightMouseDown | CGEventType::RightMouseUp => {
+                        Some(Key::MouseRight)
+                    }
+                    _ => {
+                        let button_number = CGEvent::integer_value_field(
+                            Some(cg_event),
+                            CGEventField::MouseEventButtonNumber,
+                        );
+                        match button_number {
+                            2 => Some(Key::MouseMiddle),
+                            3 => Some(Key::MouseX1),
+                            4 => Some(Key::MouseX2),
+                            _ => None, // Unknown button
+                        }
+                    }
                 };
+
                 if let Some(key) = key {
-                    let _ = state.event_sender.send(KeyEvent {
-                        modifiers,
-                        key: Some(key),
-                        is_key_down: false,
-                        changed_modifier: None,
-                    });
+                    // Only report left/right clicks when modifiers are held (to avoid noise).
+                    // Middle and X buttons are always reported.
+                    let is_common = matches!(key, Key::MouseLeft | Key::MouseRight);
+                    let report = !is_common || !modifiers.is_empty();
+
+                    should_block = if !report {
+                        // An unreported release still completes a blocked press
+                        !is_down && state.release_blocked_key(key)
+                    } else if is_down {
+                        state.should_block_key_down(modifiers | sides, key)
+                    } else {
+                        state.should_block_key_up(modifiers | sides, key)
+                    };
+
+                    if report {
+                        let _ = state.send(KeyEvent {
+                            modifiers,
+                            key: Some(key),
+                            is_key_down: is_down,
+                            changed_modifier: None,
+                            sides,
+                            hook: None,
+                        });
+                    }
                 }
             }
             _ => {}
@@ -275,10 +283,27 @@ unsafe extern "C-unwind" fn event_tap_callback(
     }
 }
 
+fn tap_location(location: TapLocation) -> CGEventTapLocation {
+    match location {
+        TapLocation::Hid => CGEventTapLocation::HIDEventTap,
+        TapLocation::Session => CGEventTapLocation::SessionEventTap,
+        TapLocation::AnnotatedSession => CGEventTapLocation::AnnotatedSessionEventTap,
+    }
+}
+
+fn tap_placement(placement: TapPlacement) -> CGEventTapPlacement {
+    match placement {
+        TapPlacement::HeadInsert => CGEventTapPlacement::HeadInsertEventTap,
+        TapPlacement::TailAppend => CGEventTapPlacement::TailAppendEventTap,
+    }
+}
+
 /// Run the event tap in a dedicated thread
 fn run_event_tap(
     state: Arc<Mutex<ListenerState>>,
     running: Arc<AtomicBool>,
+    location: TapLocation,
+    placement: TapPlacement,
     init_tx: Sender<std::result::Result<(), String>>,
 ) {
     // Event types we want to monitor
@@ -301,8 +326,8 @@ fn run_event_tap(
     // Use Default mode (not ListenOnly) to enable optional event blocking
     let tap: Option<CFRetained<CFMachPort>> = unsafe {
         CGEvent::tap_create(
-            CGEventTapLocation::SessionEventTap,
-            CGEventTapPlacement::HeadInsertEventTap,
+            tap_location(location),
+            tap_placement(placement),
             CGEventTapOptions::Default,
             event_mask,
             callback,
@@ -364,7 +389,12 @@ fn run_event_tap(
     // Signal successful initialization
     let _ = init_tx.send(Ok(()));
 
+    // Lets the tap callback check excluded and app-scoped hotkeys without
+    // asking NSWorkspace on every event
+    let _foreground = frontmost::watch();
+
     // Run the loop
+    let mut wake_detector = WakeDetector::new();
     while running.load(std::sync::atomic::Ordering::SeqCst) {
         // Run for a short interval, then check if we should stop
         CFRunLoop::run_in_mode(
@@ -372,6 +402,19 @@ fn run_event_tap(
             0.1, // 100ms timeout
             true,
         );
+
+        // The system disables taps that time out or that sleep interrupts
+        if !CGEvent::tap_is_enabled(&tap) {
+            CGEvent::tap_enable(&tap, true);
+        }
+
+        if let Ok(mut state) = state.lock() {
+            if wake_detector.tick() {
+                state.resync_after_wake(Some(current_modifiers()));
+                state.report_system_event(SystemEvent::Woke);
+            }
+            state.release_stuck_modifiers(|| Some(current_modifiers()));
+        }
     }
 
     // Cleanup
@@ -6,8 +6,10 @@ extern "C" {
 }
 
 /// Check if the current process has accessibility permissions
+///
+/// Always `true` when the headless backend is enabled with `HANDY_KEYS_HEADLESS`.
 pub fn check_accessibility() -> bool {
-    unsafe { AXIsProcessTrusted() }
+    crate::platform::headless::is_enabled() || unsafe { AXIsProcessTrusted() }
 }
 
 /// Open System Settings to the Accessibility privacy panel
@@ -1,6 +1,14 @@
 //! Platform-specific keyboard utilities
 
+pub(crate) mod env;
+pub(crate) mod frontmost;
+pub(crate) mod headless;
+pub(crate) mod layout;
+pub(crate) mod power;
+pub(crate) mod priority;
+pub(crate) mod readiness;
 pub(crate) mod state;
+pub(crate) mod threads;
 
 #[cfg(target_os = "macos")]
 pub mod macos;
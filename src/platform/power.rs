@@ -0,0 +1,324 @@
+//! Notifications of the system resuming from sleep
+//!
+//! Windows and macOS report resumes through a callback, on a thread of their
+//! own, which sets a flag the listener loops check on their next tick. On
+//! Linux, a thread follows logind's `PrepareForSleep` signal on the system bus
+//! and sets the same flag. Without a system bus or logind there's no watch,
+//! and [`WakeDetector`](super::state::WakeDetector) falls back to comparing
+//! clocks.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Records resumes reported by the OS until dropped
+pub(crate) struct Watch {
+    // Dropped first, so the OS stops using `woke` before it's freed
+    _registration: imp::Registration,
+    woke: Arc<AtomicBool>,
+}
+
+impl Watch {
+    /// Start watching for resumes
+    ///
+    /// Returns `None` if the OS won't report them.
+    pub fn start() -> Option<Self> {
+        let woke = Arc::new(AtomicBool::new(false));
+        let registration = imp::Registration::start(Arc::clone(&woke))?;
+        Some(Self {
+            _registration: registration,
+            woke,
+        })
+    }
+
+    /// Check whether the system resumed since the last call
+    pub fn take_wake(&self) -> bool {
+        self.woke.swap(false, Ordering::SeqCst)
+    }
+
+    /// Whether resumes are still being reported
+    ///
+    /// Only a Linux watch can stop, when its bus connection closes.
+    pub fn is_watching(&self) -> bool {
+        self._registration.is_watching()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ffi::{c_char, c_void};
+    use std::ptr;
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    // Messages from IOKit/IOMessage.h
+    const CAN_SYSTEM_SLEEP: u32 = 0xE000_0270;
+    const SYSTEM_WILL_SLEEP: u32 = 0xE000_0280;
+    const SYSTEM_HAS_POWERED_ON: u32 = 0xE000_0300;
+
+    type NotificationPort = *mut c_void;
+    type PowerCallback = unsafe extern "C" fn(*mut c_void, u32, u32, *mut c_void);
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IORegisterForSystemPower(
+            refcon: *mut c_void,
+            port: *mut NotificationPort,
+            callback: PowerCallback,
+            notifier: *mut u32,
+        ) -> u32;
+        fn IODeregisterForSystemPower(notifier: *mut u32) -> i32;
+        fn IOAllowPowerChange(root_port: u32, notification_id: isize) -> i32;
+        fn IOServiceClose(connect: u32) -> i32;
+        fn IONotificationPortSetDispatchQueue(port: NotificationPort, queue: *mut c_void);
+        fn IONotificationPortDestroy(port: NotificationPort);
+    }
+
+    extern "C" {
+        fn dispatch_queue_create(label: *const c_char, attr: *mut c_void) -> *mut c_void;
+        fn dispatch_sync_f(queue: *mut c_void, context: *mut c_void, work: extern "C" fn(*mut c_void));
+        fn dispatch_release(object: *mut c_void);
+    }
+
+    struct Context {
+        woke: Arc<AtomicBool>,
+        /// The root power domain connection, to acknowledge sleep through
+        root_port: AtomicU32,
+    }
+
+    /// An `IORegisterForSystemPower` registration, delivered on a private
+    /// serial dispatch queue so no run loop is needed
+    pub struct Registration {
+        context: Box<Context>,
+        port: NotificationPort,
+        notifier: u32,
+        queue: *mut c_void,
+    }
+
+    impl Registration {
+        pub fn start(woke: Arc<AtomicBool>) -> Option<Self> {
+            let context = Box::new(Context {
+                woke,
+                root_port: AtomicU32::new(0),
+            });
+            let mut port = ptr::null_mut();
+            let mut notifier = 0;
+            let root_port = unsafe {
+                IORegisterForSystemPower(
+                    &*context as *const Context as *mut c_void,
+                    &mut port,
+                    on_power_change,
+                    &mut notifier,
+                )
+            };
+            if root_port == 0 {
+                return None;
+            }
+            // Messages are only delivered once the port has a queue
+            context.root_port.store(root_port, Ordering::SeqCst);
+            let queue = unsafe { dispatch_queue_create(c"handy-keys.power".as_ptr(), ptr::null_mut()) };
+            unsafe { IONotificationPortSetDispatchQueue(port, queue) };
+            Some(Self {
+                context,
+                port,
+                notifier,
+                queue,
+            })
+        }
+
+        pub fn is_watching(&self) -> bool {
+            true
+        }
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            unsafe {
+                IODeregisterForSystemPower(&mut self.notifier);
+                IOServiceClose(self.context.root_port.load(Ordering::SeqCst));
+                IONotificationPortDestroy(self.port);
+                // The queue is serial, so once an empty block has run on it no
+                // callback is still using the context freed after this
+                dispatch_sync_f(self.queue, ptr::null_mut(), drained);
+                dispatch_release(self.queue);
+            }
+        }
+    }
+
+    extern "C" fn drained(_context: *mut c_void) {}
+
+    unsafe extern "C" fn on_power_change(
+        refcon: *mut c_void,
+        _service: u32,
+        message: u32,
+        argument: *mut c_void,
+    ) {
+        let context = &*(refcon as *const Context);
+        match message {
+            // Sleep waits up to 30 seconds for every registered client to
+            // acknowledge it
+            CAN_SYSTEM_SLEEP | SYSTEM_WILL_SLEEP => {
+                IOAllowPowerChange(context.root_port.load(Ordering::SeqCst), argument as isize);
+            }
+            SYSTEM_HAS_POWERED_ON => context.woke.store(true, Ordering::SeqCst),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use windows::Win32::Foundation::{ERROR_SUCCESS, HANDLE};
+    use windows::Win32::System::Power::{
+        PowerRegisterSuspendResumeNotification, PowerUnregisterSuspendResumeNotification,
+        DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS, HPOWERNOTIFY,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC};
+
+    /// A `PowerRegisterSuspendResumeNotification` callback, the
+    /// `WM_POWERBROADCAST` events of processes without a window
+    pub struct Registration {
+        handle: HPOWERNOTIFY,
+        _parameters: Box<DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS>,
+        _woke: Arc<AtomicBool>,
+    }
+
+    impl Registration {
+        pub fn start(woke: Arc<AtomicBool>) -> Option<Self> {
+            let mut parameters = Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+                Callback: Some(on_power_change),
+                Context: Arc::as_ptr(&woke) as *mut c_void,
+            });
+            let mut handle = ptr::null_mut();
+            let result = unsafe {
+                PowerRegisterSuspendResumeNotification(
+                    DEVICE_NOTIFY_CALLBACK,
+                    HANDLE(&mut *parameters as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as *mut c_void),
+                    &mut handle,
+                )
+            };
+            (result == ERROR_SUCCESS).then(|| Self {
+                handle: HPOWERNOTIFY(handle),
+                _parameters: parameters,
+                _woke: woke,
+            })
+        }
+
+        pub fn is_watching(&self) -> bool {
+            true
+        }
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = PowerUnregisterSuspendResumeNotification(self.handle);
+            }
+        }
+    }
+
+    unsafe extern "system" fn on_power_change(
+        context: *const c_void,
+        event: u32,
+        _setting: *const c_void,
+    ) -> u32 {
+        // Sent on every resume, unlike PBT_APMRESUMESUSPEND which follows it
+        // only when a user woke the system
+        if event == PBT_APMRESUMEAUTOMATIC {
+            (*(context as *const AtomicBool)).store(true, Ordering::SeqCst);
+        }
+        ERROR_SUCCESS.0
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::net::Shutdown;
+    use std::os::unix::net::UnixStream;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use crate::platform::linux::dbus::{Connection, SIGNAL};
+    use crate::platform::threads::{self, Handle};
+
+    const LOGIND: &str = "org.freedesktop.login1";
+    const MATCH_RULE: &str = "type='signal',sender='org.freedesktop.login1',\
+        path='/org/freedesktop/login1',interface='org.freedesktop.login1.Manager',\
+        member='PrepareForSleep'";
+
+    /// A subscription to logind's `PrepareForSleep` signal, followed on a
+    /// thread of its own
+    pub struct Registration {
+        socket: UnixStream,
+        running: Arc<AtomicBool>,
+        watching: Arc<AtomicBool>,
+        thread: Option<Handle>,
+    }
+
+    impl Registration {
+        pub fn start(woke: Arc<AtomicBool>) -> Option<Self> {
+            let mut connection = Connection::system().ok()?;
+            // The bus accepts the match without logind, but nothing would
+            // ever be reported
+            if !connection.call("NameHasOwner", Some(LOGIND)).ok()?.first_bool()? {
+                return None;
+            }
+            connection.call("AddMatch", Some(MATCH_RULE)).ok()?;
+            let socket = connection.try_clone_socket().ok()?;
+
+            let running = Arc::new(AtomicBool::new(true));
+            let watching = Arc::new(AtomicBool::new(true));
+            let thread = threads::spawn(&running, {
+                let running = Arc::clone(&running);
+                let watching = Arc::clone(&watching);
+                move || {
+                    follow(connection, &running, &woke);
+                    watching.store(false, Ordering::SeqCst);
+                }
+            })
+            .ok()?;
+            Some(Self {
+                socket,
+                running,
+                watching,
+                thread: Some(thread),
+            })
+        }
+
+        pub fn is_watching(&self) -> bool {
+            self.watching.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            self.running.store(false, Ordering::SeqCst);
+            // Wakes the thread from its read
+            let _ = self.socket.shutdown(Shutdown::Both);
+            if let Some(thread) = self.thread.take() {
+                thread.join();
+            }
+        }
+    }
+
+    /// Set `woke` each time logind reports a resume, until the connection
+    /// closes or `running` is cleared
+    fn follow(mut connection: Connection, running: &AtomicBool, woke: &AtomicBool) {
+        while running.load(Ordering::SeqCst) {
+            let Ok(message) = connection.receive() else {
+                return;
+            };
+            // Sent with `true` before sleeping and `false` after resuming
+            if message.kind == SIGNAL
+                && message.member.as_deref() == Some("PrepareForSleep")
+                && message.first_bool() == Some(false)
+            {
+                woke.store(true, Ordering::SeqCst);
+            }
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Raising the scheduling priority of the current thread
+
+use crate::error::Result;
+
+use super::state::ThreadPriority;
+
+/// Run the current thread at `priority`
+pub(crate) fn apply(priority: ThreadPriority) -> Result<()> {
+    match priority {
+        ThreadPriority::Normal => Ok(()),
+        ThreadPriority::High => imp::raise(),
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod imp {
+    use windows::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_TIME_CRITICAL,
+    };
+
+    use crate::error::{Error, Result};
+
+    pub fn raise() -> Result<()> {
+        unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_TIME_CRITICAL) }
+            .map_err(|e| Error::ThreadPriority(e.to_string()))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ffi::c_int;
+
+    use crate::error::{Error, Result};
+
+    /// `QOS_CLASS_USER_INTERACTIVE` from `<sys/qos.h>`
+    const QOS_CLASS_USER_INTERACTIVE: u32 = 0x21;
+
+    extern "C" {
+        fn pthread_set_qos_class_self_np(qos_class: u32, relative_priority: c_int) -> c_int;
+    }
+
+    pub fn raise() -> Result<()> {
+        match unsafe { pthread_set_qos_class_self_np(QOS_CLASS_USER_INTERACTIVE, 0) } {
+            0 => Ok(()),
+            errno => Err(Error::ThreadPriority(
+                std::io::Error::from_raw_os_error(errno).to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::ffi::{c_int, c_ulong};
+
+    use crate::error::{Error, Result};
+
+    /// `SCHED_FIFO` from `<sched.h>`
+    const SCHED_FIFO: c_int = 1;
+
+    /// The lowest real-time priority, which still runs ahead of every
+    /// normal thread
+    const PRIORITY: c_int = 1;
+
+    #[repr(C)]
+    struct SchedParam {
+        sched_priority: c_int,
+    }
+
+    extern "C" {
+        fn pthread_self() -> c_ulong;
+        fn pthread_setschedparam(thread: c_ulong, policy: c_int, param: *const SchedParam) -> c_int;
+    }
+
+    pub fn raise() -> Result<()> {
+        let param = SchedParam {
+            sched_priority: PRIORITY,
+        };
+        match unsafe { pthread_setschedparam(pthread_self(), SCHED_FIFO, &param) } {
+            0 => Ok(()),
+            errno => Err(Error::ThreadPriority(format!(
+                "SCHED_FIFO not permitted ({}); grant CAP_SYS_NICE or raise RLIMIT_RTPRIO",
+                std::io::Error::from_raw_os_error(errno)
+            ))),
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+mod imp {
+    use crate::error::{Error, Result};
+
+    pub fn raise() -> Result<()> {
+        Err(Error::ThreadPriority("not supported on this platform".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normal_priority_is_a_no_op() {
+        assert!(apply(ThreadPriority::Normal).is_ok());
+    }
+}
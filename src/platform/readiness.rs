@@ -0,0 +1,120 @@
+//! OS objects that become ready when events are queued, for consumers that
+//! wait with `poll`, `epoll` or `WaitForMultipleObjects`
+
+use crate::error::Result;
+
+/// A readiness flag the OS can wait on
+///
+/// On Unix, the read end of a socket pair that has data while the flag is
+/// set. On Windows, a manual-reset event.
+pub(crate) struct Readiness {
+    #[cfg(unix)]
+    reader: std::os::unix::net::UnixStream,
+    #[cfg(unix)]
+    writer: std::os::unix::net::UnixStream,
+    /// Kept as a raw handle so it can be shared between threads
+    #[cfg(windows)]
+    event: isize,
+}
+
+#[cfg(unix)]
+impl Readiness {
+    pub fn new() -> Result<Self> {
+        let (reader, writer) = std::os::unix::net::UnixStream::pair()?;
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+        Ok(Self { reader, writer })
+    }
+
+    pub fn set(&self) {
+        use std::io::Write;
+
+        // A full buffer means it's ready already
+        let _ = (&self.writer).write(&[1]);
+    }
+
+    pub fn clear(&self) {
+        use std::io::Read;
+
+        let mut buf = [0; 64];
+        while matches!((&self.reader).read(&mut buf), Ok(n) if n > 0) {}
+    }
+
+    pub fn fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        use std::os::fd::AsFd;
+
+        self.reader.as_fd()
+    }
+
+    #[cfg(test)]
+    pub fn is_set(&self) -> bool {
+        use std::io::Read;
+
+        let set = matches!((&self.reader).read(&mut [0]), Ok(1));
+        if set {
+            self.set();
+        }
+        set
+    }
+}
+
+#[cfg(windows)]
+impl Readiness {
+    pub fn new() -> Result<Self> {
+        use windows::core::PCWSTR;
+        use windows::Win32::System::Threading::CreateEventW;
+
+        let event = unsafe { CreateEventW(None, true, false, PCWSTR::null()) }.map_err(|e| {
+            crate::error::Error::Platform(format!("failed to create readiness event: {e}"))
+        })?;
+        Ok(Self {
+            event: event.0 as isize,
+        })
+    }
+
+    pub fn set(&self) {
+        use windows::Win32::Foundation::HANDLE;
+        use windows::Win32::System::Threading::SetEvent;
+
+        let _ = unsafe { SetEvent(HANDLE(self.event as _)) };
+    }
+
+    pub fn clear(&self) {
+        use windows::Win32::Foundation::HANDLE;
+        use windows::Win32::System::Threading::ResetEvent;
+
+        let _ = unsafe { ResetEvent(HANDLE(self.event as _)) };
+    }
+
+    pub fn handle(&self) -> std::os::windows::io::BorrowedHandle<'_> {
+        // The event lives as long as `self`
+        unsafe { std::os::windows::io::BorrowedHandle::borrow_raw(self.event as _) }
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Readiness {
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::{CloseHandle, HANDLE};
+
+        let _ = unsafe { CloseHandle(HANDLE(self.event as _)) };
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_clear() {
+        let readiness = Readiness::new().unwrap();
+        assert!(!readiness.is_set());
+
+        readiness.set();
+        readiness.set();
+        assert!(readiness.is_set());
+
+        readiness.clear();
+        assert!(!readiness.is_set());
+    }
+}
@@ -1,40 +1,1475 @@
 //! Shared state for platform-specific keyboard listeners
 
-use std::collections::HashSet;
-use std::sync::mpsc::Sender;
-use std::sync::{Arc, Mutex};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{SendError, Sender};
 
-use crate::types::{Hotkey, Key, KeyEvent, Modifiers};
+use crate::channel;
+use crate::platform::env::Backend;
+use crate::platform::frontmost;
+use crate::platform::power;
+use crate::sink::SharedSink;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::types::{HookDecision, Hotkey, Key, KeyEvent, Modifiers};
 
 /// Hotkeys that should be blocked when triggered
 pub type BlockingHotkeys = Arc<Mutex<HashSet<Hotkey>>>;
 
+/// When blocking all keys ends, if it's on
+pub(crate) type SuppressAll = Arc<Mutex<Option<Instant>>>;
+
+/// Where a listener records [`HookDiagnostics`]
+pub(crate) type SharedDiagnostics = Arc<Mutex<HookDiagnostics>>;
+
+/// A predicate deciding whether a hotkey is active, see
+/// `HotkeyManager::register_guarded`
+///
+/// The predicate is the app's code, so it never runs in the hook callback:
+/// [`refresh`](Self::refresh) runs it on the manager's event thread, and the
+/// callback and the engine read its last answer.
+#[derive(Clone)]
+pub(crate) struct Guard(Arc<GuardState>);
+
+struct GuardState {
+    predicate: Box<dyn Fn() -> bool + Send + Sync>,
+    allows: AtomicBool,
+}
+
+impl Guard {
+    /// Create a guard, running the predicate once for its first answer
+    pub fn new(predicate: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        let allows = AtomicBool::new(predicate());
+        Self(Arc::new(GuardState {
+            predicate: Box::new(predicate),
+            allows,
+        }))
+    }
+
+    /// Run the predicate again, keeping its answer for [`allows`](Self::allows)
+    pub fn refresh(&self) {
+        self.0.allows.store((self.0.predicate)(), Ordering::Relaxed);
+    }
+
+    /// The predicate's answer as of the last refresh
+    pub fn allows(&self) -> bool {
+        self.0.allows.load(Ordering::Relaxed)
+    }
+}
+
+impl std::fmt::Debug for Guard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Guard")
+    }
+}
+
+/// Guards of blocked combos, which are only blocked while their guard allows
+pub(crate) type HotkeyGuards = Arc<Mutex<HashMap<Hotkey, Guard>>>;
+
+/// Blocking hotkeys that are also blocked with extra modifiers held, see
+/// `HotkeyManager::register_loose`
+pub(crate) type LooseHotkeys = Arc<Mutex<LooseSet>>;
+
+/// Loose hotkeys by key, so the callback only compares the few with the
+/// key of the event
+#[derive(Debug, Default)]
+pub(crate) struct LooseSet(HashMap<Option<Key>, HashSet<Hotkey>>);
+
+impl LooseSet {
+    pub fn insert(&mut self, hotkey: Hotkey) {
+        self.0.entry(hotkey.key).or_default().insert(hotkey);
+    }
+
+    pub fn remove(&mut self, hotkey: &Hotkey) {
+        if let Some(hotkeys) = self.0.get_mut(&hotkey.key) {
+            hotkeys.remove(hotkey);
+            if hotkeys.is_empty() {
+                self.0.remove(&hotkey.key);
+            }
+        }
+    }
+
+    /// The loose hotkeys that `combo` presses
+    fn matching<'a>(&'a self, combo: &'a Hotkey) -> impl Iterator<Item = &'a Hotkey> {
+        self.0
+            .get(&combo.key)
+            .into_iter()
+            .flatten()
+            .filter(|loose| loose.matches_loosely(combo))
+    }
+}
+
+impl Extend<Hotkey> for LooseSet {
+    fn extend<T: IntoIterator<Item = Hotkey>>(&mut self, hotkeys: T) {
+        for hotkey in hotkeys {
+            self.insert(hotkey);
+        }
+    }
+}
+
+/// The blocking hotkeys, their guards and loose hotkeys of each manager
+/// sharing a listener, see `HotkeyManagerBuilder::shared_listener`
+pub(crate) type BlockingMembers = Arc<Mutex<Vec<(BlockingHotkeys, HotkeyGuards, LooseHotkeys)>>>;
+
+/// Apps in which no hotkey is matched or blocked, see
+/// `HotkeyManager::set_excluded_apps`
+///
+/// The hook callback decides whether a key down came in an excluded app and
+/// passes the decision on in [`KeyEvent::hook`], so a key the callback let
+/// through is never pressed and one it blocked is never skipped.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExcludedApps {
+    apps: Arc<RwLock<Vec<String>>>,
+}
+
+impl ExcludedApps {
+    pub fn set(&self, apps: Vec<String>) {
+        if let Ok(mut excluded) = self.apps.write() {
+            *excluded = apps;
+        }
+    }
+
+    /// Whether any app is excluded
+    pub fn is_empty(&self) -> bool {
+        self.apps.read().map_or(true, |apps| apps.is_empty())
+    }
+
+    /// Check whether the frontmost app is excluded
+    ///
+    /// Reads the app a running listener saw at the last foreground change,
+    /// see [`frontmost::is_frontmost`], so the hook callbacks and the engine
+    /// decide alike and neither asks the OS per event.
+    pub fn frontmost_excluded(&self) -> bool {
+        let Ok(excluded) = self.apps.read() else {
+            return false;
+        };
+        !excluded.is_empty() && frontmost::is_frontmost(excluded.iter())
+    }
+}
+
+/// How long an OS callback may take to handle one event
+///
+/// Callbacks run on the OS's input path, which waits for them: Windows drops
+/// hooks that miss its timeout, macOS disables slow event taps, and every
+/// keystroke is delayed by the time spent. So callbacks only track modifiers,
+/// look up the blocking set and queue the event. Matching hotkeys, running
+/// user callbacks and anything else slow happens on the receiving thread.
+pub(crate) const CALLBACK_BUDGET: Duration = Duration::from_millis(1);
+
+/// How well the OS hook is keeping up
+///
+/// Latency is only measured on Windows, where other low-level hooks
+/// (AutoHotkey, antivirus software) run before ours and can delay events or
+/// stop them from arriving. Windows can't tell a hook its position in the
+/// chain, so the time between an event's timestamp and our callback is
+/// measured instead. Latency that grows over time means hooks ahead of ours
+/// are slow; see [`KeyboardListenerBuilder::hook_reinstall_interval`](crate::KeyboardListenerBuilder::hook_reinstall_interval).
+///
+/// The time our own callback takes is measured on every platform.
+/// [`lost_releases`](Self::lost_releases) is counted by `HotkeyManager`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HookDiagnostics {
+    /// Key and mouse events seen by the hook
+    pub events: u64,
+    /// Latency of the most recent event
+    pub last_latency: Duration,
+    /// Highest latency seen
+    pub max_latency: Duration,
+    /// Latency of all events added up
+    pub total_latency: Duration,
+    /// How often the hooks were reinstalled
+    pub reinstalls: u64,
+    /// Longest time our callback took to handle an event
+    pub max_callback_time: Duration,
+    /// Events our callback took longer than 1ms to handle
+    pub budget_overruns: u64,
+    /// Hotkey presses whose key up was lost and that were released before
+    /// being pressed again, see
+    /// [`HotkeyManagerBuilder::repair_lost_releases`](crate::HotkeyManagerBuilder::repair_lost_releases)
+    pub lost_releases: u64,
+}
+
+impl HookDiagnostics {
+    /// Average latency per event
+    pub fn average_latency(&self) -> Duration {
+        match self.events {
+            0 => Duration::ZERO,
+            events => Duration::from_nanos((self.total_latency.as_nanos() / u128::from(events)) as u64),
+        }
+    }
+
+    fn record(&mut self, latency: Duration) {
+        self.events += 1;
+        self.last_latency = latency;
+        self.max_latency = self.max_latency.max(latency);
+        self.total_latency += latency;
+    }
+
+    fn record_callback(&mut self, time: Duration) {
+        self.max_callback_time = self.max_callback_time.max(time);
+        if time > CALLBACK_BUDGET {
+            self.budget_overruns += 1;
+        }
+    }
+}
+
+/// Measures an OS callback against [`CALLBACK_BUDGET`]
+///
+/// The time is recorded when the timer is dropped, so it covers every way out
+/// of the callback.
+pub(crate) struct CallbackTimer {
+    started: Instant,
+    diagnostics: SharedDiagnostics,
+}
+
+impl Drop for CallbackTimer {
+    fn drop(&mut self) {
+        // A panic can't unwind out of an OS callback, so overruns are counted
+        // rather than asserted
+        if let Ok(mut diagnostics) = self.diagnostics.lock() {
+            diagnostics.record_callback(self.started.elapsed());
+        }
+    }
+}
+
+/// How key events are identified as a [`Key`]
+///
+/// Only affects Windows. macOS and Linux always identify keys by position.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum KeyMapping {
+    /// Use the key the active keyboard layout reports (Windows virtual keys)
+    #[default]
+    Layout,
+    /// Use the key's physical position, named after a US keyboard
+    ///
+    /// For example, `Key::Num1` is the key left of `Key::Num2` on every
+    /// layout, including AZERTY where it types "&" unshifted.
+    Physical,
+}
+
+/// Where the event tap sits in the chain of taps at its location
+///
+/// Only affects macOS. Matters when other apps, such as Karabiner-Elements or
+/// Rectangle, also tap keyboard events and may consume them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TapPlacement {
+    /// Run before taps already installed at the same location
+    ///
+    /// Hotkeys are seen, and blocked, before other apps can act on them.
+    #[default]
+    HeadInsert,
+    /// Run after taps already installed at the same location
+    ///
+    /// Other apps see events first and can consume them, so a hotkey they
+    /// also use never reaches this listener. Use it to defer to them.
+    TailAppend,
+}
+
+/// Which stage of macOS event delivery the event tap observes
+///
+/// Only affects macOS. Taps at an earlier stage see events before those at a
+/// later one, whatever their [`TapPlacement`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TapLocation {
+    /// Where events enter the window server, before any session tap
+    ///
+    /// Sees events ahead of apps that tap at the session level, but also
+    /// before the system has applied its own handling. Remapping done by a
+    /// virtual keyboard driver, like Karabiner-Elements', is already applied.
+    Hid,
+    /// Where events enter the login session
+    #[default]
+    Session,
+    /// Where session events are about to be delivered to an app
+    ///
+    /// Sees events only after every session tap passed them on, so this
+    /// listener yields to all of them.
+    AnnotatedSession,
+}
+
+/// Scheduling priority of the thread running the OS hook or event tap
+///
+/// The OS waits for the hook before delivering each event, and drops or
+/// bypasses hooks that take too long. A higher priority keeps the hook
+/// responsive while the system is under heavy load.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ThreadPriority {
+    /// The priority threads start with
+    #[default]
+    Normal,
+    /// Time-critical priority on Windows, the user-interactive QoS class on
+    /// macOS, and `SCHED_FIFO` on Linux
+    ///
+    /// On Linux, this needs `CAP_SYS_NICE` or a real-time rlimit
+    /// (`RLIMIT_RTPRIO`); without one, starting the listener fails with
+    /// [`Error::ThreadPriority`](crate::Error::ThreadPriority).
+    High,
+}
+
+/// A change in the system around the listener, see
+/// [`KeyboardListenerBuilder::report_system_events`](crate::KeyboardListenerBuilder::report_system_events)
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemEvent {
+    /// This process's session became the active local session again, so
+    /// input is seen once more (Linux)
+    SessionActive,
+    /// Another session took over the keyboard, e.g. after a VT switch, so
+    /// input is ignored until this one is active again (Linux)
+    ///
+    /// Held modifiers are released, and managers release pressed hotkeys.
+    SessionInactive,
+    /// The system woke from sleep
+    ///
+    /// Key ups are often lost while asleep, so held modifiers are resynced
+    /// and managers release pressed hotkeys.
+    Woke,
+}
+
+/// Options for spawning a platform listener
+#[derive(Clone, Default)]
+pub struct ListenerConfig {
+    /// Hotkeys to block (if any)
+    pub blocking_hotkeys: Option<BlockingHotkeys>,
+    /// Release modifiers that appear held for this long without any other input
+    pub modifier_timeout: Option<Duration>,
+    /// How key events are identified
+    pub key_mapping: KeyMapping,
+    /// Where to report keycodes that have no [`Key`] variant (if anywhere)
+    pub unknown_keys: Option<Sender<u32>>,
+    /// Where to report [`SystemEvent`]s (if anywhere)
+    pub system_events: Option<Sender<SystemEvent>>,
+    /// Ignore input for this long after the listener starts
+    pub arm_delay: Duration,
+    /// Block every key until this deadline (if set)
+    pub suppress_all: SuppressAll,
+    /// Drop key downs repeated within this window (zero to keep all)
+    pub dedupe_window: Duration,
+    /// Where to record how the hook performs
+    pub diagnostics: SharedDiagnostics,
+    /// Guards of blocking hotkeys (if any)
+    pub guards: Option<HotkeyGuards>,
+    /// Blocking hotkeys that are also blocked with extra modifiers (if any)
+    pub loose: Option<LooseHotkeys>,
+    /// More hotkeys to block, of the managers sharing the listener (if any)
+    pub members: Option<BlockingMembers>,
+    /// Apps in which nothing is blocked
+    pub excluded_apps: ExcludedApps,
+    /// Don't block simple hotkeys while the user is typing (if set)
+    pub typing: Option<TypingDetector>,
+    /// Where events go instead of the listener's receiver (if anywhere)
+    pub sink: Option<SharedSink<KeyEvent>>,
+    /// Reinstall the OS hooks this often (if set), only on Windows
+    pub hook_reinstall_interval: Option<Duration>,
+    /// Where the event tap runs relative to other taps, only on macOS
+    pub tap_placement: TapPlacement,
+    /// Which stage of event delivery the event tap observes, only on macOS
+    pub tap_location: TapLocation,
+    /// Merge modifier changes arriving within this window (if set), applied
+    /// by `KeyboardListener` as events are received
+    pub coalesce_window: Option<Duration>,
+    /// Where events come from, unless the environment says otherwise
+    pub backend: Backend,
+    /// Priority of the thread running the OS hook
+    pub thread_priority: ThreadPriority,
+}
+
 /// Internal state shared with platform-specific event callbacks
 pub struct ListenerState {
-    pub event_sender: Sender<KeyEvent>,
+    event_sender: channel::Sender<KeyEvent>,
     /// Track which modifiers are currently held
     pub current_modifiers: Modifiers,
+    /// Side bits of the modifier keys held, where the platform reports them,
+    /// see [`update_sides`](Self::update_sides)
+    pub current_sides: Modifiers,
     /// Hotkeys to block (if any)
     pub blocking_hotkeys: Option<BlockingHotkeys>,
+    /// Keys whose key down was blocked and whose key up must be blocked too
+    blocked_keys: HashSet<Key>,
+    /// See [`ListenerConfig::modifier_timeout`]
+    modifier_timeout: Option<Duration>,
+    /// When the last input event was seen
+    last_input: Instant,
+    /// See [`ListenerConfig::unknown_keys`]
+    unknown_keys: Option<Sender<u32>>,
+    /// Unknown keycodes already reported
+    reported_unknown_keys: HashSet<u32>,
+    /// When an unknown keycode was last reported
+    last_unknown_report: Option<Instant>,
+    /// See [`ListenerConfig::system_events`]
+    system_events: Option<Sender<SystemEvent>>,
+    /// When events start being delivered and blocked, see [`ListenerConfig::arm_delay`]
+    armed_at: Instant,
+    /// See [`ListenerConfig::suppress_all`]
+    suppress_all: SuppressAll,
+    /// See [`ListenerConfig::dedupe_window`]
+    dedupe_window: Duration,
+    /// When each key last went down, and whether it has gone up since
+    last_key_downs: HashMap<Key, (Instant, bool)>,
+    /// Keys whose next key up pairs with a dropped duplicate key down
+    deduped_keys: HashSet<Key>,
+    /// See [`ListenerConfig::diagnostics`]
+    diagnostics: SharedDiagnostics,
+    /// See [`ListenerConfig::guards`]
+    guards: Option<HotkeyGuards>,
+    /// See [`ListenerConfig::loose`]
+    loose: Option<LooseHotkeys>,
+    /// See [`ListenerConfig::members`]
+    members: Option<BlockingMembers>,
+    /// See [`ListenerConfig::excluded_apps`]
+    excluded_apps: ExcludedApps,
+    /// Whether the frontmost app was excluded when checking whether to block
+    /// the event being handled, and its key, for [`send`](Self::send) to pass on
+    exclusion: Cell<Option<(Option<Key>, bool)>>,
+    /// Whether the key down being handled was blocked, and its key, for
+    /// [`send`](Self::send) to pass on
+    blocked: Option<(Key, bool)>,
+    /// See [`ListenerConfig::typing`]
+    typing: Option<TypingDetector>,
+}
+
+/// The hotkeys that the keys of `hotkey` press: its modifiers with each
+/// subset of their side flags, starting with none
+fn side_variants(hotkey: &Hotkey) -> impl Iterator<Item = Hotkey> {
+    let sides = hotkey.modifiers.side_bits().bits();
+    let modifiers = hotkey.modifiers.without_sides();
+    let key = hotkey.key;
+    std::iter::successors(Some(0), move |&subset: &u32| {
+        (subset != sides).then(|| subset.wrapping_sub(sides) & sides)
+    })
+    .map(move |subset| Hotkey {
+        modifiers: modifiers | Modifiers::from_bits_retain(subset),
+        key,
+    })
+}
+
+/// If `hotkeys` has a hotkey that `hotkey` presses, or a loose hotkey that
+/// `hotkey` includes, that hotkey's guard in `guards` (if any)
+fn blocking_guard(
+    hotkeys: &BlockingHotkeys,
+    guards: Option<&HotkeyGuards>,
+    loose: Option<&LooseHotkeys>,
+    hotkey: &Hotkey,
+) -> Option<Option<Guard>> {
+    let blocked = {
+        let set = hotkeys.lock().ok()?;
+        if let Some(blocked) = side_variants(hotkey).find(|variant| set.contains(variant)) {
+            blocked
+        } else {
+            let loose = loose?.lock().ok()?;
+            let blocked = *loose.matching(hotkey).find(|loose| set.contains(loose))?;
+            blocked
+        }
+    };
+    Some(guards.and_then(|guards| guards.lock().ok()?.get(&blocked).cloned()))
 }
 
+/// Minimum time between two reports of unknown keycodes
+const UNKNOWN_KEY_REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Most unknown keycodes reported over a listener's lifetime
+const MAX_UNKNOWN_KEY_REPORTS: usize = 64;
+
 impl ListenerState {
-    pub fn new(event_sender: Sender<KeyEvent>, blocking_hotkeys: Option<BlockingHotkeys>) -> Self {
+    pub fn new(event_sender: channel::Sender<KeyEvent>, config: &ListenerConfig) -> Self {
         Self {
-            event_sender,
+            event_sender: event_sender.with_sink(config.sink.clone()),
             current_modifiers: Modifiers::empty(),
-            blocking_hotkeys,
+            current_sides: Modifiers::empty(),
+            blocking_hotkeys: config.blocking_hotkeys.clone(),
+            blocked_keys: HashSet::new(),
+            modifier_timeout: config.modifier_timeout,
+            last_input: Instant::now(),
+            unknown_keys: config.unknown_keys.clone(),
+            reported_unknown_keys: HashSet::new(),
+            last_unknown_report: None,
+            system_events: config.system_events.clone(),
+            armed_at: Instant::now() + config.arm_delay,
+            suppress_all: Arc::clone(&config.suppress_all),
+            dedupe_window: config.dedupe_window,
+            last_key_downs: HashMap::new(),
+            deduped_keys: HashSet::new(),
+            diagnostics: Arc::clone(&config.diagnostics),
+            guards: config.guards.clone(),
+            loose: config.loose.clone(),
+            members: config.members.clone(),
+            excluded_apps: config.excluded_apps.clone(),
+            exclusion: Cell::new(None),
+            blocked: None,
+            typing: config.typing.clone(),
         }
     }
 
-    /// Check if an event matches a blocking hotkey
-    pub fn should_block(&self, modifiers: Modifiers, key: Option<Key>) -> bool {
-        if let Some(ref hotkeys) = self.blocking_hotkeys {
-            if let Ok(set) = hotkeys.lock() {
-                let hotkey = Hotkey { modifiers, key };
-                return set.contains(&hotkey);
+    /// Whether every key is currently being blocked
+    ///
+    /// Only true until the deadline set by `KeyboardListener::set_suppress_all`,
+    /// so a caller that never turns suppression off can't mute the keyboard
+    /// for good.
+    pub fn is_suppressing_all(&self) -> bool {
+        self.suppress_all
+            .lock()
+            .map(|until| until.is_some_and(|until| Instant::now() < until))
+            .unwrap_or(false)
+    }
+
+    /// Whether the arm delay has passed
+    ///
+    /// Until then, events are neither delivered nor blocked, so keystrokes
+    /// already in flight when the listener starts (such as the Enter key up
+    /// from launching the app) can't trigger hotkeys. Modifiers are still
+    /// tracked.
+    pub fn is_armed(&self) -> bool {
+        Instant::now() >= self.armed_at
+    }
+
+    /// Deliver an event to the listener, unless it isn't armed yet or is a
+    /// duplicate (see [`ListenerConfig::dedupe_window`])
+    ///
+    /// Only delivery is affected: a duplicate is still blocked like the key
+    /// down it repeats, so the focused app sees the same events either way.
+    /// Key downs carry the callback's decisions in [`KeyEvent::hook`].
+    pub fn send(&mut self, mut event: KeyEvent) -> std::result::Result<(), SendError<KeyEvent>> {
+        let exclusion = self.exclusion.take();
+        let blocked = self.blocked.take();
+        let now = Instant::now();
+        if !self.is_armed() || self.is_duplicate_at(&event, now) {
+            return Ok(());
+        }
+        if event.is_key_down {
+            let excluded = match exclusion {
+                Some((checked, excluded)) if checked == event.key => excluded,
+                _ => self.excluded_apps.frontmost_excluded(),
+            };
+            let blocked = match blocked {
+                Some((checked, blocked)) if Some(checked) == event.key => Some(blocked),
+                _ => None,
+            };
+            event.hook = Some(HookDecision { blocked, excluded });
+        }
+        if let Some(typing) = &mut self.typing {
+            typing.record(&event, now);
+        }
+        self.event_sender.send(event)
+    }
+
+    /// Whether a key event repeats a key down from within the dedupe window
+    ///
+    /// A key down is a duplicate if the same key went down less than the
+    /// window ago, whether or not it went up in between. If it did, the key up
+    /// that follows the duplicate is dropped too, so keys stay paired.
+    fn is_duplicate_at(&mut self, event: &KeyEvent, now: Instant) -> bool {
+        let Some(key) = event.key else {
+            return false;
+        };
+        if self.dedupe_window.is_zero() {
+            return false;
+        }
+
+        if !event.is_key_down {
+            if self.deduped_keys.remove(&key) {
+                return true;
+            }
+            if let Some((_, released)) = self.last_key_downs.get_mut(&key) {
+                *released = true;
+            }
+            return false;
+        }
+
+        if let Some(&(at, released)) = self.last_key_downs.get(&key) {
+            if now.duration_since(at) < self.dedupe_window {
+                if released {
+                    self.deduped_keys.insert(key);
+                }
+                return true;
             }
         }
+        self.last_key_downs.insert(key, (now, false));
         false
     }
+
+    /// Identify a platform keycode that has no [`Key`] variant
+    ///
+    /// Each keycode is reported once, at most one every
+    /// [`UNKNOWN_KEY_REPORT_INTERVAL`]. A keycode dropped by the rate limit is
+    /// reported the next time it's seen.
+    pub fn unknown_key(&mut self, code: u32) -> Key {
+        self.report_unknown_key_at(code, Instant::now());
+        Key::Other(code)
+    }
+
+    /// Tell the app about a change in the system, if it asked
+    pub fn report_system_event(&self, event: SystemEvent) {
+        if let Some(sender) = &self.system_events {
+            let _ = sender.send(event);
+        }
+    }
+
+    fn report_unknown_key_at(&mut self, code: u32, now: Instant) {
+        let Some(sender) = &self.unknown_keys else {
+            return;
+        };
+        let due = self
+            .last_unknown_report
+            .is_none_or(|last| now.duration_since(last) >= UNKNOWN_KEY_REPORT_INTERVAL);
+        if due
+            && self.reported_unknown_keys.len() < MAX_UNKNOWN_KEY_REPORTS
+            && self.reported_unknown_keys.insert(code)
+        {
+            self.last_unknown_report = Some(now);
+            let _ = sender.send(code);
+        }
+    }
+
+    /// Record how long an event took to reach the hook
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn record_hook_latency(&self, latency: Duration) {
+        if let Ok(mut diagnostics) = self.diagnostics.lock() {
+            diagnostics.record(latency);
+        }
+    }
+
+    /// Start timing the callback handling the current event
+    pub fn time_callback(&self) -> CallbackTimer {
+        CallbackTimer {
+            started: Instant::now(),
+            diagnostics: Arc::clone(&self.diagnostics),
+        }
+    }
+
+    /// Record that the OS hooks were reinstalled
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn record_hook_reinstall(&self) {
+        if let Ok(mut diagnostics) = self.diagnostics.lock() {
+            diagnostics.reinstalls += 1;
+        }
+    }
+
+    /// Record that an input event was seen
+    pub fn note_input(&mut self) {
+        self.last_input = Instant::now();
+    }
+
+    /// Release modifiers that have appeared held for longer than the timeout
+    ///
+    /// `actual_modifiers` is only called once the timeout has expired. It should
+    /// return the modifiers the OS reports as held, or `None` if the platform
+    /// can't tell, in which case all modifiers are assumed released. A release
+    /// event is emitted for every modifier dropped.
+    pub fn release_stuck_modifiers(
+        &mut self,
+        actual_modifiers: impl FnOnce() -> Option<Modifiers>,
+    ) {
+        let Some(timeout) = self.modifier_timeout else {
+            return;
+        };
+        if self.current_modifiers.is_empty() || self.last_input.elapsed() < timeout {
+            return;
+        }
+        self.note_input();
+
+        self.resync_modifiers(actual_modifiers().unwrap_or(Modifiers::empty()));
+    }
+
+    /// Reset tracked state after the system wakes from sleep
+    ///
+    /// Key ups are often lost while the system sleeps, so modifiers are resynced
+    /// against `held` (or assumed released if `None`) and blocked keys are
+    /// forgotten.
+    pub fn resync_after_wake(&mut self, held: Option<Modifiers>) {
+        self.note_input();
+        self.blocked_keys.clear();
+        self.resync_modifiers(held.unwrap_or(Modifiers::empty()));
+    }
+
+    /// Drop tracked modifiers that aren't in `held`, emitting a release event
+    /// for each one
+    fn resync_modifiers(&mut self, held: Modifiers) {
+        for stuck in (self.current_modifiers - held).iter() {
+            self.current_modifiers.remove(stuck);
+            let _ = self.send(KeyEvent {
+                modifiers: self.current_modifiers,
+                key: None,
+                is_key_down: false,
+                changed_modifier: Some(stuck),
+                sides: self.held_sides(),
+                hook: None,
+            });
+        }
+    }
+
+    /// Track the side (like [`Modifiers::R_OPT`]) of a modifier key going
+    /// down or up, if the platform knows it
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    pub fn update_sides(&mut self, side: Option<Modifiers>, is_key_down: bool) {
+        if let Some(side) = side {
+            if is_key_down {
+                self.current_sides |= side.side_bits();
+            } else {
+                self.current_sides -= side.side_bits();
+            }
+        }
+    }
+
+    /// Side flags of the held modifiers, as tracked by
+    /// [`update_sides`](Self::update_sides)
+    pub fn held_sides(&self) -> Modifiers {
+        self.current_sides.sides_of(self.current_modifiers)
+    }
+
+    /// Check if an event matches a blocking hotkey whose guard (if any)
+    /// allows it, outside of excluded apps and typing
+    ///
+    /// `modifiers` has the side flags of the held modifiers where known, so
+    /// hotkeys that require a side are only blocked on that side.
+    pub fn should_block(&self, modifiers: Modifiers, key: Option<Key>) -> bool {
+        if !self.is_armed() {
+            return false;
+        }
+        let hotkey = Hotkey { modifiers, key };
+        // The guard (if any) of each blocking set that has the hotkey
+        let mut matches: Vec<Option<Guard>> = self
+            .blocking_hotkeys
+            .iter()
+            .filter_map(|hotkeys| {
+                blocking_guard(hotkeys, self.guards.as_ref(), self.loose.as_ref(), &hotkey)
+            })
+            .collect();
+        if let Some(members) = self.members.as_ref().and_then(|members| members.lock().ok()) {
+            matches.extend(members.iter().filter_map(|(hotkeys, guards, loose)| {
+                blocking_guard(hotkeys, Some(guards), Some(loose), &hotkey)
+            }));
+        }
+        if matches.is_empty() || self.frontmost_excluded(key) {
+            return false;
+        }
+        let typing = self.typing.as_ref().is_some_and(|typing| typing.is_typing(Instant::now()));
+        if typing && TypingDetector::suppresses(&hotkey) {
+            return false;
+        }
+        matches.into_iter().any(|guard| guard.is_none_or(|guard| guard.allows()))
+    }
+
+    /// Check whether the frontmost app is excluded, remembering the answer
+    /// for the event of `key` so the engine gets the same one
+    fn frontmost_excluded(&self, key: Option<Key>) -> bool {
+        let excluded = self.excluded_apps.frontmost_excluded();
+        self.exclusion.set(Some((key, excluded)));
+        excluded
+    }
+
+    /// Check if a key down should be blocked, remembering the key if so
+    ///
+    /// While suppressing all keys, every key except mouse buttons is blocked.
+    /// Autorepeats of a blocked key down are blocked until its key up, even if
+    /// the modifiers change or the hotkey is unregistered while it's held, so
+    /// repeats never leak to the focused app.
+    pub fn should_block_key_down(&mut self, modifiers: Modifiers, key: Key) -> bool {
+        if self.blocked_keys.contains(&key) {
+            self.blocked = Some((key, true));
+            return true;
+        }
+        let is_mouse_button = matches!(
+            key,
+            Key::MouseLeft | Key::MouseRight | Key::MouseMiddle | Key::MouseX1 | Key::MouseX2
+        );
+        let block = (self.is_armed() && !is_mouse_button && self.is_suppressing_all())
+            || self.should_block(modifiers, Some(key));
+        if block {
+            self.blocked_keys.insert(key);
+        }
+        if block || self.blocking_hotkeys.is_some() || self.members.is_some() {
+            self.blocked = Some((key, block));
+        }
+        block
+    }
+
+    /// Check if a key up should be blocked
+    ///
+    /// The key up of a blocked key down is always blocked, even if the modifiers
+    /// were released first (e.g. Opt released before K for a blocked Opt+K).
+    /// Otherwise the focused app would see an unpaired key up.
+    pub fn should_block_key_up(&mut self, modifiers: Modifiers, key: Key) -> bool {
+        self.release_blocked_key(key) || self.should_block(modifiers, Some(key))
+    }
+
+    /// Forget a blocked key down, returning whether the key was blocked
+    pub fn release_blocked_key(&mut self, key: Key) -> bool {
+        self.blocked_keys.remove(&key)
+    }
+}
+
+/// Detects typing from the rate of keystrokes that type text
+///
+/// The user counts as typing while at least `keys` letters, digits,
+/// punctuation or spaces, alone or with Shift, went down within the last
+/// `window`. Simple hotkeys are suppressed while typing, see
+/// `HotkeyManagerBuilder::suppress_while_typing`.
+#[derive(Debug, Clone)]
+pub(crate) struct TypingDetector {
+    keys: usize,
+    window: Duration,
+    /// Times of the most recent typing keystrokes, oldest first
+    recent: VecDeque<Instant>,
+}
+
+impl TypingDetector {
+    pub fn new(keys: usize, window: Duration) -> Self {
+        Self {
+            keys,
+            window,
+            recent: VecDeque::with_capacity(keys),
+        }
+    }
+
+    /// Record `event` if it's a typing keystroke
+    pub fn record(&mut self, event: &KeyEvent, now: Instant) {
+        let typed = event.is_key_down
+            && (event.modifiers - Modifiers::SHIFT).is_empty()
+            && event.key.is_some_and(types_text);
+        if !typed || self.keys == 0 {
+            return;
+        }
+        if self.recent.len() == self.keys {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(now);
+    }
+
+    /// Whether enough typing keystrokes came within the window before `now`
+    pub fn is_typing(&self, now: Instant) -> bool {
+        self.keys > 0
+            && self.recent.len() == self.keys
+            && self.recent.front().is_some_and(|first| now.duration_since(*first) <= self.window)
+    }
+
+    /// Whether a hotkey is simple enough to be typed by accident: one with
+    /// only modifiers, or with no modifiers but Shift
+    pub fn suppresses(hotkey: &Hotkey) -> bool {
+        hotkey.key.is_none() || (hotkey.modifiers.without_sides() - Modifiers::SHIFT).is_empty()
+    }
+}
+
+/// Whether a key types text
+fn types_text(key: Key) -> bool {
+    matches!(
+        key,
+        Key::A | Key::B | Key::C | Key::D | Key::E | Key::F | Key::G | Key::H | Key::I
+            | Key::J | Key::K | Key::L | Key::M | Key::N | Key::O | Key::P | Key::Q | Key::R
+            | Key::S | Key::T | Key::U | Key::V | Key::W | Key::X | Key::Y | Key::Z
+            | Key::Num0 | Key::Num1 | Key::Num2 | Key::Num3 | Key::Num4 | Key::Num5
+            | Key::Num6 | Key::Num7 | Key::Num8 | Key::Num9
+            | Key::Space | Key::Minus | Key::Equal | Key::LeftBracket | Key::RightBracket
+            | Key::Backslash | Key::Semicolon | Key::Quote | Key::Comma | Key::Period
+            | Key::Slash | Key::Grave
+    )
+}
+
+/// How far the wall clock must get ahead of the monotonic clock between
+/// ticks to count as a wake
+const WAKE_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Detects the system waking from sleep, for loops that tick every second or
+/// faster
+///
+/// Uses the OS resume notification while there is one. Otherwise, such as on
+/// Linux without logind, it compares clocks: [`Instant`] doesn't advance while the system
+/// sleeps but [`SystemTime`] does, so the wall clock getting ahead is time
+/// spent asleep. A thread stalled for a while moves both clocks and doesn't
+/// count. A large wall-clock adjustment still looks like a wake, which only
+/// causes a harmless resync.
+pub struct WakeDetector {
+    watch: Option<power::Watch>,
+    last_wall: SystemTime,
+    last_instant: Instant,
+}
+
+impl WakeDetector {
+    pub fn new() -> Self {
+        Self {
+            watch: power::Watch::start(),
+            last_wall: SystemTime::now(),
+            last_instant: Instant::now(),
+        }
+    }
+
+    /// Record a loop tick, returning `true` if the system slept since the last one
+    pub fn tick(&mut self) -> bool {
+        match &self.watch {
+            Some(watch) if watch.is_watching() => watch.take_wake(),
+            _ => self.tick_at(SystemTime::now(), Instant::now()),
+        }
+    }
+
+    fn tick_at(&mut self, wall: SystemTime, instant: Instant) -> bool {
+        let wall_gap = wall.duration_since(self.last_wall).unwrap_or(Duration::ZERO);
+        let awake = instant.saturating_duration_since(self.last_instant);
+        self.last_wall = wall;
+        self.last_instant = instant;
+        wall_gap.saturating_sub(awake) >= WAKE_THRESHOLD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::{channel, Receiver};
+    use std::sync::mpsc;
+
+    fn state_blocking(hotkey: Hotkey) -> ListenerState {
+        let (tx, _rx) = channel();
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(Arc::new(Mutex::new(HashSet::from([hotkey])))),
+            ..Default::default()
+        };
+        ListenerState::new(tx, &config)
+    }
+
+    #[test]
+    fn key_up_blocked_after_modifier_released_first() {
+        let mut state = state_blocking(Hotkey::new(Modifiers::OPT, Key::K).unwrap());
+
+        assert!(state.should_block_key_down(Modifiers::OPT, Key::K));
+        // Opt is released before K: the key up arrives without modifiers
+        assert!(state.should_block_key_up(Modifiers::empty(), Key::K));
+        // Once the pair is complete, a plain K passes through again
+        assert!(!state.should_block_key_down(Modifiers::empty(), Key::K));
+        assert!(!state.should_block_key_up(Modifiers::empty(), Key::K));
+    }
+
+    #[test]
+    fn unblocked_keys_pass_through() {
+        let mut state = state_blocking(Hotkey::new(Modifiers::OPT, Key::K).unwrap());
+
+        assert!(!state.should_block_key_down(Modifiers::OPT, Key::J));
+        assert!(!state.should_block_key_up(Modifiers::OPT, Key::J));
+    }
+
+    #[test]
+    fn non_blocking_listener_never_blocks() {
+        let (tx, _rx) = channel();
+        let mut state = ListenerState::new(tx, &ListenerConfig::default());
+
+        assert!(!state.should_block_key_down(Modifiers::OPT, Key::K));
+        assert!(!state.should_block_key_up(Modifiers::OPT, Key::K));
+    }
+
+    fn state_with_timeout(timeout: Option<Duration>) -> (ListenerState, Receiver<KeyEvent>) {
+        let (tx, rx) = channel();
+        let config = ListenerConfig {
+            modifier_timeout: timeout,
+            ..Default::default()
+        };
+        (ListenerState::new(tx, &config), rx)
+    }
+
+    #[test]
+    fn stuck_modifiers_resync_from_os_state() {
+        let (mut state, rx) = state_with_timeout(Some(Duration::ZERO));
+        state.current_modifiers = Modifiers::CMD | Modifiers::SHIFT;
+
+        state.release_stuck_modifiers(|| Some(Modifiers::SHIFT));
+
+        assert_eq!(state.current_modifiers, Modifiers::SHIFT);
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.modifiers, Modifiers::SHIFT);
+        assert_eq!(event.changed_modifier, Some(Modifiers::CMD));
+        assert!(!event.is_key_down);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn stuck_modifiers_assumed_released_without_os_state() {
+        let (mut state, rx) = state_with_timeout(Some(Duration::ZERO));
+        state.current_modifiers = Modifiers::CTRL | Modifiers::OPT;
+
+        state.release_stuck_modifiers(|| None);
+
+        assert!(state.current_modifiers.is_empty());
+        assert_eq!(rx.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn modifiers_kept_before_timeout() {
+        let (mut state, rx) = state_with_timeout(Some(Duration::from_secs(3600)));
+        state.current_modifiers = Modifiers::CMD;
+
+        state.release_stuck_modifiers(|| None);
+
+        assert_eq!(state.current_modifiers, Modifiers::CMD);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn modifiers_kept_without_timeout() {
+        let (mut state, rx) = state_with_timeout(None);
+        state.current_modifiers = Modifiers::CMD;
+
+        state.release_stuck_modifiers(|| None);
+
+        assert_eq!(state.current_modifiers, Modifiers::CMD);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn wake_resync_releases_modifiers_and_blocked_keys() {
+        let (tx, rx) = channel();
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(Arc::new(Mutex::new(HashSet::from([
+                Hotkey::new(Modifiers::OPT, Key::K).unwrap(),
+            ])))),
+            ..Default::default()
+        };
+        let mut state = ListenerState::new(tx, &config);
+        state.current_modifiers = Modifiers::OPT;
+        assert!(state.should_block_key_down(Modifiers::OPT, Key::K));
+
+        state.resync_after_wake(None);
+
+        assert!(state.current_modifiers.is_empty());
+        assert_eq!(rx.try_iter().count(), 1);
+        // The key up of K was lost during sleep, so a later K up passes through
+        assert!(!state.should_block_key_up(Modifiers::empty(), Key::K));
+    }
+
+    #[test]
+    fn wake_detected_when_wall_clock_gets_ahead() {
+        let (wall, instant) = (SystemTime::now(), Instant::now());
+        let mut detector = WakeDetector { watch: None, last_wall: wall, last_instant: instant };
+        let ms = Duration::from_millis;
+
+        assert!(!detector.tick_at(wall + ms(100), instant + ms(100)));
+        // Asleep for ten minutes
+        assert!(detector.tick_at(wall + ms(600_100), instant + ms(200)));
+        // A thread stalled for ten minutes isn't a wake
+        assert!(!detector.tick_at(wall + ms(1_200_100), instant + ms(600_200)));
+        // Nor is the clock going backwards
+        assert!(!detector.tick_at(wall, instant + ms(600_300)));
+    }
+
+    #[test]
+    fn unknown_keys_reported_once_and_rate_limited() {
+        let (tx, _rx) = channel();
+        let (unknown_tx, unknown_rx) = mpsc::channel();
+        let config = ListenerConfig {
+            unknown_keys: Some(unknown_tx),
+            ..Default::default()
+        };
+        let mut state = ListenerState::new(tx, &config);
+        let start = Instant::now();
+
+        state.report_unknown_key_at(0x5E, start);
+        state.report_unknown_key_at(0x5E, start + Duration::from_secs(1));
+        // Too soon after the first report, so dropped but not forgotten
+        state.report_unknown_key_at(0x5F, start + Duration::from_millis(10));
+        state.report_unknown_key_at(0x5F, start + Duration::from_secs(2));
+
+        assert_eq!(unknown_rx.try_iter().collect::<Vec<_>>(), [0x5E, 0x5F]);
+    }
+
+    #[test]
+    fn nothing_delivered_or_blocked_before_armed() {
+        let (tx, rx) = channel();
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(Arc::new(Mutex::new(HashSet::from([
+                Hotkey::new(Modifiers::OPT, Key::K).unwrap(),
+            ])))),
+            arm_delay: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let mut state = ListenerState::new(tx, &config);
+        let event = KeyEvent {
+            modifiers: Modifiers::OPT,
+            key: Some(Key::K),
+            is_key_down: true,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        };
+
+        assert!(!state.should_block_key_down(Modifiers::OPT, Key::K));
+        state.send(event).unwrap();
+        assert!(rx.try_recv().is_err());
+
+        state.armed_at = Instant::now();
+        assert!(state.should_block_key_down(Modifiers::OPT, Key::K));
+        state.send(event).unwrap();
+        assert_eq!(rx.try_recv().unwrap().key, Some(Key::K));
+    }
+
+    #[test]
+    fn repeats_of_blocked_key_blocked_until_key_up() {
+        let mut state = state_blocking(Hotkey::new(Modifiers::OPT, Key::K).unwrap());
+
+        assert!(state.should_block_key_down(Modifiers::OPT, Key::K));
+        assert!(state.should_block_key_down(Modifiers::OPT, Key::K));
+        // Opt released while K keeps repeating
+        assert!(state.should_block_key_down(Modifiers::empty(), Key::K));
+        assert!(state.should_block_key_up(Modifiers::empty(), Key::K));
+
+        // Once released, plain K passes through again
+        assert!(!state.should_block_key_down(Modifiers::empty(), Key::K));
+    }
+
+    #[test]
+    fn no_app_excluded_without_a_frontmost_app() {
+        let excluded = ExcludedApps::default();
+        assert!(!excluded.frontmost_excluded());
+
+        excluded.set(vec!["vmware.exe".to_string()]);
+        assert_eq!(
+            excluded.frontmost_excluded(),
+            frontmost::frontmost_app().is_some_and(|app| frontmost::is_app(&app, "vmware.exe"))
+        );
+    }
+
+    #[test]
+    fn key_downs_carry_the_block_decision() {
+        let (tx, rx) = channel();
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(Arc::new(Mutex::new(HashSet::from([
+                Hotkey::new(Modifiers::OPT, Key::K).unwrap(),
+            ])))),
+            ..Default::default()
+        };
+        let mut state = ListenerState::new(tx, &config);
+        let mut down = |key| {
+            let blocked = state.should_block_key_down(Modifiers::OPT, key);
+            state
+                .send(KeyEvent {
+                    modifiers: Modifiers::OPT,
+                    key: Some(key),
+                    is_key_down: true,
+                    changed_modifier: None,
+                    sides: Modifiers::empty(),
+                    hook: None,
+                })
+                .unwrap();
+            (blocked, rx.try_recv().unwrap().hook.and_then(|hook| hook.blocked))
+        };
+
+        assert_eq!(down(Key::K), (true, Some(true)));
+        assert_eq!(down(Key::J), (false, Some(false)));
+    }
+
+    #[test]
+    fn key_downs_carry_the_exclusion_decision() {
+        let (tx, rx) = channel();
+        let config = ListenerConfig {
+            excluded_apps: ExcludedApps::default(),
+            ..Default::default()
+        };
+        config.excluded_apps.set(vec!["vmware.exe".to_string()]);
+        let mut state = ListenerState::new(tx, &config);
+        let excluded = config.excluded_apps.frontmost_excluded();
+
+        let down = KeyEvent {
+            modifiers: Modifiers::CTRL,
+            key: Some(Key::K),
+            is_key_down: true,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        };
+        state.should_block_key_down(Modifiers::CTRL, Key::K);
+        state.send(down).unwrap();
+        state.send(KeyEvent { is_key_down: false, ..down }).unwrap();
+
+        let blocked = None;
+        assert_eq!(rx.try_recv().unwrap().hook, Some(HookDecision { blocked, excluded }));
+        assert_eq!(rx.try_recv().unwrap().hook, None);
+    }
+
+    #[test]
+    fn loose_hotkeys_blocked_with_extra_modifiers() {
+        let ctrl_c = Hotkey::new(Modifiers::CTRL, Key::C).unwrap();
+        let loose = LooseHotkeys::default();
+        let (tx, _rx) = channel();
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(Arc::new(Mutex::new(HashSet::from([ctrl_c])))),
+            loose: Some(Arc::clone(&loose)),
+            ..Default::default()
+        };
+        let state = ListenerState::new(tx, &config);
+        let ctrl_shift = Modifiers::CTRL | Modifiers::SHIFT;
+
+        assert!(!state.should_block(ctrl_shift, Some(Key::C)));
+        loose.lock().unwrap().insert(ctrl_c);
+        assert!(state.should_block(ctrl_shift, Some(Key::C)));
+        assert!(state.should_block(Modifiers::CTRL, Some(Key::C)));
+        assert!(!state.should_block(Modifiers::SHIFT, Some(Key::C)));
+    }
+
+    #[test]
+    fn sided_hotkeys_blocked_on_their_side() {
+        let right_alt = Hotkey::new(Modifiers::R_OPT, Key::K).unwrap();
+        let plain = Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, Key::K).unwrap();
+        let (tx, _rx) = channel();
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(Arc::new(Mutex::new(HashSet::from([right_alt, plain])))),
+            ..Default::default()
+        };
+        let mut state = ListenerState::new(tx, &config);
+
+        assert!(state.should_block(Modifiers::R_OPT, Some(Key::K)));
+        assert!(!state.should_block(Modifiers::L_OPT, Some(Key::K)));
+        assert!(!state.should_block(Modifiers::OPT, Some(Key::K)));
+        assert!(state.should_block(Modifiers::L_CTRL | Modifiers::R_SHIFT, Some(Key::K)));
+
+        // Only the sides of held modifiers count
+        state.current_modifiers = Modifiers::CTRL;
+        state.update_sides(Some(Modifiers::R_CTRL), true);
+        state.update_sides(Some(Modifiers::L_SHIFT), true);
+        assert_eq!(state.held_sides(), Modifiers::R_CTRL);
+        state.update_sides(Some(Modifiers::R_CTRL), false);
+        assert!(state.held_sides().is_empty());
+    }
+
+    #[test]
+    fn hotkeys_of_sharing_managers_blocked() {
+        let first = Hotkey::new(Modifiers::OPT, Key::K).unwrap();
+        let second = Hotkey::new(Modifiers::CTRL, Key::K).unwrap();
+        let members = BlockingMembers::default();
+        let (tx, _rx) = channel();
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(BlockingHotkeys::default()),
+            members: Some(Arc::clone(&members)),
+            ..Default::default()
+        };
+        let state = ListenerState::new(tx, &config);
+
+        members.lock().unwrap().push((
+            Arc::new(Mutex::new(HashSet::from([first]))),
+            HotkeyGuards::default(),
+            LooseHotkeys::default(),
+        ));
+        // A guard in one manager doesn't stop another from blocking the combo
+        members.lock().unwrap().push((
+            Arc::new(Mutex::new(HashSet::from([first, second]))),
+            Arc::new(Mutex::new(HashMap::from([
+                (first, Guard::new(|| false)),
+                (second, Guard::new(|| false)),
+            ]))),
+            LooseHotkeys::default(),
+        ));
+        assert!(state.should_block(Modifiers::OPT, Some(Key::K)));
+        assert!(!state.should_block(Modifiers::CTRL, Some(Key::K)));
+
+        members.lock().unwrap().remove(0);
+        assert!(!state.should_block(Modifiers::OPT, Some(Key::K)));
+    }
+
+    #[test]
+    fn guarded_hotkeys_blocked_only_while_allowed() {
+        let hotkey = Hotkey::new(Modifiers::OPT, Key::K).unwrap();
+        let modal = Arc::new(AtomicBool::new(false));
+        let guard_modal = Arc::clone(&modal);
+        let (tx, _rx) = channel();
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(Arc::new(Mutex::new(HashSet::from([hotkey])))),
+            guards: Some(Arc::new(Mutex::new(HashMap::from([(
+                hotkey,
+                Guard::new(move || !guard_modal.load(Ordering::SeqCst)),
+            )])))),
+            ..Default::default()
+        };
+        let state = ListenerState::new(tx, &config);
+
+        assert!(state.should_block(Modifiers::OPT, Some(Key::K)));
+        modal.store(true, Ordering::SeqCst);
+        // The callback only sees the change once the guard is refreshed
+        assert!(state.should_block(Modifiers::OPT, Some(Key::K)));
+        config.guards.as_ref().unwrap().lock().unwrap()[&hotkey].refresh();
+        assert!(!state.should_block(Modifiers::OPT, Some(Key::K)));
+    }
+
+    #[test]
+    fn repeats_blocked_after_hotkey_unregistered() {
+        let hotkey = Hotkey::new(Modifiers::OPT, Key::K).unwrap();
+        let mut state = state_blocking(hotkey);
+
+        assert!(state.should_block_key_down(Modifiers::OPT, Key::K));
+        if let Some(hotkeys) = &state.blocking_hotkeys {
+            hotkeys.lock().unwrap().remove(&hotkey);
+        }
+        assert!(state.should_block_key_down(Modifiers::OPT, Key::K));
+        assert!(state.should_block_key_up(Modifiers::OPT, Key::K));
+        assert!(!state.should_block_key_down(Modifiers::OPT, Key::K));
+    }
+
+    #[test]
+    fn suppress_all_blocks_keys_until_deadline() {
+        let (tx, _rx) = channel();
+        let config = ListenerConfig::default();
+        let mut state = ListenerState::new(tx, &config);
+
+        *config.suppress_all.lock().unwrap() = Some(Instant::now() + Duration::from_secs(60));
+        assert!(state.should_block_key_down(Modifiers::empty(), Key::A));
+        assert!(!state.should_block_key_down(Modifiers::empty(), Key::MouseLeft));
+
+        // The key up of a suppressed key down is blocked after suppression ends
+        *config.suppress_all.lock().unwrap() = None;
+        assert!(state.should_block_key_up(Modifiers::empty(), Key::A));
+        assert!(!state.should_block_key_down(Modifiers::empty(), Key::B));
+
+        // An expired deadline no longer blocks
+        *config.suppress_all.lock().unwrap() = Some(Instant::now());
+        assert!(!state.is_suppressing_all());
+    }
+
+    #[test]
+    fn duplicate_key_downs_dropped_within_window() {
+        let (tx, _rx) = channel();
+        let config = ListenerConfig {
+            dedupe_window: Duration::from_millis(5),
+            ..Default::default()
+        };
+        let mut state = ListenerState::new(tx, &config);
+        let down = |key| KeyEvent {
+            modifiers: Modifiers::empty(),
+            key: Some(key),
+            is_key_down: true,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        };
+        let up = |key| KeyEvent {
+            is_key_down: false,
+            ..down(key)
+        };
+        let start = Instant::now();
+        let ms = |n| start + Duration::from_millis(n);
+
+        // Repeated while held: only the repeat is dropped
+        assert!(!state.is_duplicate_at(&down(Key::A), ms(0)));
+        assert!(state.is_duplicate_at(&down(Key::A), ms(1)));
+        assert!(!state.is_duplicate_at(&up(Key::A), ms(2)));
+
+        // Bounced after the key up: the extra pair is dropped
+        assert!(!state.is_duplicate_at(&down(Key::B), ms(10)));
+        assert!(!state.is_duplicate_at(&up(Key::B), ms(11)));
+        assert!(state.is_duplicate_at(&down(Key::B), ms(12)));
+        assert!(state.is_duplicate_at(&up(Key::B), ms(13)));
+
+        // Other keys and later presses pass through
+        assert!(!state.is_duplicate_at(&down(Key::C), ms(13)));
+        assert!(!state.is_duplicate_at(&down(Key::B), ms(20)));
+    }
+
+    #[test]
+    fn key_downs_kept_without_dedupe_window() {
+        let (tx, rx) = channel();
+        let mut state = ListenerState::new(tx, &ListenerConfig::default());
+        let event = KeyEvent {
+            modifiers: Modifiers::empty(),
+            key: Some(Key::A),
+            is_key_down: true,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        };
+
+        state.send(event).unwrap();
+        state.send(event).unwrap();
+        assert_eq!(rx.try_iter().count(), 2);
+    }
+
+    #[test]
+    fn hook_latency_statistics() {
+        let mut diagnostics = HookDiagnostics::default();
+        assert_eq!(diagnostics.average_latency(), Duration::ZERO);
+
+        diagnostics.record(Duration::from_millis(2));
+        diagnostics.record(Duration::from_millis(10));
+        diagnostics.record(Duration::from_millis(3));
+
+        assert_eq!(diagnostics.events, 3);
+        assert_eq!(diagnostics.last_latency, Duration::from_millis(3));
+        assert_eq!(diagnostics.max_latency, Duration::from_millis(10));
+        assert_eq!(diagnostics.average_latency(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn callback_overruns_counted() {
+        let mut diagnostics = HookDiagnostics::default();
+        diagnostics.record_callback(Duration::from_micros(50));
+        diagnostics.record_callback(Duration::from_millis(3));
+        diagnostics.record_callback(CALLBACK_BUDGET);
+
+        assert_eq!(diagnostics.max_callback_time, Duration::from_millis(3));
+        assert_eq!(diagnostics.budget_overruns, 1);
+        // Callback times aren't hook latency
+        assert_eq!(diagnostics.events, 0);
+    }
+
+    #[test]
+    fn callback_work_within_budget() {
+        // Every event looks up the blocking and loose sets, so make them large
+        let hotkeys = |modifiers| {
+            (0..10_000).map(move |code| Hotkey { modifiers, key: Some(Key::Other(code)) })
+        };
+        let mut loose = LooseSet::default();
+        loose.extend(hotkeys(Modifiers::OPT));
+        let config = ListenerConfig {
+            blocking_hotkeys: Some(Arc::new(Mutex::new(
+                hotkeys(Modifiers::CTRL).chain(hotkeys(Modifiers::OPT)).collect(),
+            ))),
+            loose: Some(Arc::new(Mutex::new(loose))),
+            guards: Some(Arc::new(Mutex::new(
+                hotkeys(Modifiers::CTRL).map(|hotkey| (hotkey, Guard::new(|| true))).collect(),
+            ))),
+            dedupe_window: Duration::from_millis(5),
+            ..Default::default()
+        };
+        let (tx, rx) = channel();
+        let mut state = ListenerState::new(tx, &config);
+
+        // The fastest of a few batches, so a busy machine doesn't fail it
+        const BATCHES: u32 = 5;
+        const EVENTS: u32 = 1_000;
+        let per_event = (0..BATCHES)
+            .map(|batch| {
+                let started = Instant::now();
+                for code in batch * EVENTS..(batch + 1) * EVENTS {
+                    let key = Key::Other(code * 2);
+                    let modifiers = Modifiers::CTRL | Modifiers::OPT;
+                    for is_key_down in [true, false] {
+                        let _timer = state.time_callback();
+                        if is_key_down {
+                            state.should_block_key_down(modifiers, key);
+                        } else {
+                            state.should_block_key_up(modifiers, key);
+                        }
+                        state
+                            .send(KeyEvent {
+                                modifiers,
+                                key: Some(key),
+                                is_key_down,
+                                changed_modifier: None,
+                                sides: Modifiers::empty(),
+                                hook: None,
+                            })
+                            .unwrap();
+                    }
+                }
+                started.elapsed() / (2 * EVENTS)
+            })
+            .min()
+            .unwrap();
+
+        // Leave plenty of room for debug builds
+        assert!(
+            per_event < CALLBACK_BUDGET / 10,
+            "callback work took {per_event:?} per event"
+        );
+        assert_eq!(rx.try_iter().count(), (2 * BATCHES * EVENTS) as usize);
+    }
+
+    #[test]
+    fn unknown_keys_identified_without_reporting() {
+        let (tx, _rx) = channel();
+        let mut state = ListenerState::new(tx, &ListenerConfig::default());
+        assert_eq!(state.unknown_key(0x5E), Key::Other(0x5E));
+    }
 }
@@ -0,0 +1,303 @@
+//! Threads started by this crate, tracked so it can be unloaded safely
+//!
+//! A plugin host must not unload the library while any of its threads, or an
+//! OS hook calling into it, is still alive. Hooks only live as long as the
+//! thread that installed them, so every thread the crate starts goes through
+//! [`spawn`], which records it along with the flag that stops it. That lets
+//! [`prepare_unload`] stop and join all of them, even ones whose owner already
+//! gave up waiting.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, TryLockError};
+use std::thread::{self, JoinHandle, Thread};
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+use super::priority;
+use super::state::ThreadPriority;
+
+/// A thread's join handle, shared with the registry until it's joined
+type Slot = Arc<Mutex<Option<JoinHandle<()>>>>;
+
+/// How often a thread is checked for exit while joining with a deadline
+const JOIN_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    unloading: false,
+    pinned: false,
+    threads: Vec::new(),
+});
+
+struct Registry {
+    /// Set by [`prepare_unload`], after which no thread may start
+    unloading: bool,
+    /// Set once a thread that can never exit has started, see [`pin`]
+    pinned: bool,
+    threads: Vec<Entry>,
+}
+
+struct Entry {
+    slot: Slot,
+    running: Arc<AtomicBool>,
+    thread: Thread,
+}
+
+impl Registry {
+    /// Forget threads that have been joined, joining any that have exited
+    fn prune(&mut self) {
+        self.threads.retain(|entry| {
+            let Ok(mut slot) = entry.slot.try_lock() else {
+                return true;
+            };
+            let finished = slot.as_ref().is_none_or(JoinHandle::is_finished);
+            if let Some(handle) = slot.take_if(|_| finished) {
+                let _ = handle.join();
+            }
+            !finished
+        });
+    }
+}
+
+/// Start a thread that exits soon after `running` is cleared
+///
+/// Fails with [`Error::Unloading`] once [`prepare_unload`] has been called.
+pub(crate) fn spawn(
+    running: &Arc<AtomicBool>,
+    f: impl FnOnce() + Send + 'static,
+) -> Result<Handle> {
+    spawn_named(running, None, f)
+}
+
+/// [`spawn`] a thread with a name, if given
+pub(crate) fn spawn_named(
+    running: &Arc<AtomicBool>,
+    name: Option<String>,
+    f: impl FnOnce() + Send + 'static,
+) -> Result<Handle> {
+    let mut registry = REGISTRY.lock().map_err(|_| Error::MutexPoisoned)?;
+    if registry.unloading {
+        return Err(Error::Unloading);
+    }
+    registry.prune();
+
+    let mut builder = thread::Builder::new();
+    if let Some(name) = name {
+        builder = builder.name(name);
+    }
+    let handle = builder.spawn(f)?;
+    let thread = handle.thread().clone();
+    let slot = Arc::new(Mutex::new(Some(handle)));
+    registry.threads.push(Entry {
+        slot: Arc::clone(&slot),
+        running: Arc::clone(running),
+        thread: thread.clone(),
+    });
+    Ok(Handle { slot, thread })
+}
+
+/// [`spawn`] a thread that runs at `priority`
+///
+/// The priority is set before `f` runs, so a thread that installs an OS hook
+/// never does so if it can't be set. Fails with [`Error::ThreadPriority`] in
+/// that case.
+pub(crate) fn spawn_prioritized(
+    running: &Arc<AtomicBool>,
+    priority: ThreadPriority,
+    f: impl FnOnce() + Send + 'static,
+) -> Result<Handle> {
+    let (result_tx, result_rx) = mpsc::channel();
+    let handle = spawn(running, move || {
+        let result = priority::apply(priority);
+        let ok = result.is_ok();
+        let _ = result_tx.send(result);
+        if ok {
+            f();
+        }
+    })?;
+    result_rx
+        .recv()
+        .unwrap_or_else(|_| Err(Error::ThreadPriority("thread exited".to_string())))?;
+    Ok(handle)
+}
+
+/// Record that a thread which never exits has started
+///
+/// rdev's grab on Linux can't be stopped, so the library can't be unloaded
+/// once it runs.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) fn pin() {
+    if let Ok(mut registry) = REGISTRY.lock() {
+        registry.pinned = true;
+    }
+}
+
+/// Stop every thread and OS hook of this crate, so it can be unloaded
+///
+/// For hosts that load handy-keys as part of a plugin: call this before
+/// unloading the plugin. It stops all listeners and managers, including ones
+/// still owned by the plugin, and waits up to `timeout` for their threads to
+/// exit. Once it returns `Ok`, no thread or OS callback can run code from the
+/// library anymore. Creating a listener or manager afterwards fails with
+/// [`Error::Unloading`].
+///
+/// Note: On Linux, rdev's keyboard grab can't be removed, so this fails once
+/// a listener has been created there. The other threads are still told to stop.
+pub fn prepare_unload(timeout: Duration) -> Result<()> {
+    let deadline = Instant::now() + timeout;
+
+    let (slots, pinned) = {
+        let mut registry = REGISTRY.lock().map_err(|_| Error::MutexPoisoned)?;
+        registry.unloading = true;
+        for entry in &registry.threads {
+            entry.running.store(false, Ordering::SeqCst);
+            entry.thread.unpark();
+        }
+        let slots: Vec<Slot> = registry.threads.iter().map(|entry| Arc::clone(&entry.slot)).collect();
+        (slots, registry.pinned)
+    };
+
+    if pinned {
+        return Err(Error::Platform(
+            "rdev's keyboard grab can't be removed, so the library can't be unloaded".to_string(),
+        ));
+    }
+    // Joined without the registry lock, as exiting threads may need it
+    for slot in &slots {
+        join_slot(slot, deadline)?;
+    }
+    Ok(())
+}
+
+/// Join the thread in `slot`, giving up at `deadline`
+///
+/// The handle stays in the slot while the thread runs, so whoever joins next
+/// (usually [`prepare_unload`]) still can.
+fn join_slot(slot: &Slot, deadline: Instant) -> Result<()> {
+    loop {
+        match slot.try_lock() {
+            Ok(mut slot) => match slot.take_if(|handle| handle.is_finished()) {
+                Some(handle) => {
+                    return handle
+                        .join()
+                        .map_err(|_| Error::Platform("thread panicked while shutting down".to_string()));
+                }
+                None if slot.is_none() => return Ok(()),
+                None => {}
+            },
+            // Someone else is joining it
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Poisoned(_)) => return Err(Error::MutexPoisoned),
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::Timeout);
+        }
+        thread::sleep(JOIN_POLL_INTERVAL);
+    }
+}
+
+/// A thread started by [`spawn`]
+///
+/// Dropping the handle detaches the thread, though [`prepare_unload`] can
+/// still join it.
+pub(crate) struct Handle {
+    slot: Slot,
+    thread: Thread,
+}
+
+impl Handle {
+    /// Whether the thread has exited
+    pub fn is_finished(&self) -> bool {
+        self.slot
+            .lock()
+            .map(|slot| slot.as_ref().is_none_or(JoinHandle::is_finished))
+            .unwrap_or(true)
+    }
+
+    /// The thread's id
+    #[cfg_attr(target_os = "linux", allow(dead_code))]
+    pub fn id(&self) -> thread::ThreadId {
+        self.thread.id()
+    }
+
+    /// Whether this is the calling thread
+    pub fn is_current(&self) -> bool {
+        self.thread.id() == thread::current().id()
+    }
+
+    /// Wake the thread if it's parked, e.g. so it sees `running` cleared
+    pub fn unpark(&self) {
+        self.thread.unpark();
+    }
+
+    /// Wait for the thread to exit
+    pub fn join(self) {
+        if let Ok(mut slot) = self.slot.lock() {
+            if let Some(handle) = slot.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for the thread to exit
+    ///
+    /// Returns [`Error::Timeout`] if it's still running by then, in which case
+    /// it's left to finish in the background.
+    pub fn join_timeout(self, timeout: Duration) -> Result<()> {
+        join_slot(&self.slot, Instant::now() + timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn join_timeout_waits_for_exit() {
+        let running = Arc::new(AtomicBool::new(true));
+
+        let quick = spawn(&running, || thread::sleep(Duration::from_millis(10))).unwrap();
+        assert!(quick.join_timeout(Duration::from_secs(10)).is_ok());
+
+        let slow = spawn(&running, || thread::sleep(Duration::from_secs(1))).unwrap();
+        assert!(matches!(
+            slow.join_timeout(Duration::from_millis(10)),
+            Err(Error::Timeout)
+        ));
+    }
+
+    #[test]
+    fn named_threads() {
+        let running = Arc::new(AtomicBool::new(true));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = spawn_named(&running, Some("handy-keys-test".to_string()), move || {
+            let _ = tx.send(thread::current().name().map(str::to_string));
+        })
+        .unwrap();
+
+        assert_eq!(rx.recv().unwrap().as_deref(), Some("handy-keys-test"));
+        handle.join();
+    }
+
+    #[test]
+    fn handle_knows_its_thread() {
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = spawn(&running, || {}).unwrap();
+
+        assert!(!handle.is_current());
+        handle.join();
+    }
+
+    #[test]
+    fn prioritized_thread_runs_after_priority_is_set() {
+        let running = Arc::new(AtomicBool::new(true));
+        let (tx, rx) = std::sync::mpsc::channel();
+        let handle = spawn_prioritized(&running, ThreadPriority::Normal, move || {
+            let _ = tx.send(());
+        })
+        .unwrap();
+
+        assert!(rx.recv().is_ok());
+        handle.join();
+    }
+}
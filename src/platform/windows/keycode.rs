@@ -1,5 +1,10 @@
 //! Windows virtual key code conversion utilities
 
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, GetKeyboardLayout, MapVirtualKeyExW, MAPVK_VK_TO_VSC,
+};
+use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
 use crate::types::{Key, Modifiers};
 
 /// Windows Virtual Key codes
@@ -30,6 +35,10 @@ mod vk {
     pub const RIGHT: u16 = 0x27;
     pub const DOWN: u16 = 0x28;
     pub const DELETE: u16 = 0x2E;
+    pub const INSERT: u16 = 0x2D;
+    pub const SNAPSHOT: u16 = 0x2C; // Print Screen
+    pub const PAUSE: u16 = 0x13;
+    pub const APPS: u16 = 0x5D; // Menu
 
     // Numbers 0-9 are 0x30-0x39
     // Letters A-Z are 0x41-0x5A
@@ -97,6 +106,19 @@ mod vk {
     pub const OEM_5: u16 = 0xDC;      // \|
     pub const OEM_6: u16 = 0xDD;      // ]}
     pub const OEM_7: u16 = 0xDE;      // '"
+    pub const OEM_102: u16 = 0xE2;    // Extra key on ISO keyboards
+
+    // IME keys
+    pub const KANA: u16 = 0x15; // Also Hangul
+    pub const HANJA: u16 = 0x19; // Also Kanji
+
+    // Media keys
+    pub const VOLUME_MUTE: u16 = 0xAD;
+    pub const VOLUME_DOWN: u16 = 0xAE;
+    pub const VOLUME_UP: u16 = 0xAF;
+    pub const MEDIA_NEXT_TRACK: u16 = 0xB0;
+    pub const MEDIA_PREV_TRACK: u16 = 0xB1;
+    pub const MEDIA_PLAY_PAUSE: u16 = 0xB3;
 }
 
 /// Convert Windows virtual key code to Key
@@ -201,6 +223,10 @@ pub fn vk_to_key(vk_code: u16, is_extended: bool) -> Option<Key> {
         vk::UP => Some(Key::UpArrow),
         vk::RIGHT => Some(Key::RightArrow),
         vk::DOWN => Some(Key::DownArrow),
+        vk::INSERT => Some(Key::Insert),
+        vk::SNAPSHOT => Some(Key::PrintScreen),
+        vk::PAUSE => Some(Key::Pause),
+        vk::APPS => Some(Key::ContextMenu),
 
         // Punctuation (OEM keys - US layout)
         vk::OEM_1 => Some(Key::Semicolon),
@@ -220,10 +246,309 @@ pub fn vk_to_key(vk_code: u16, is_extended: bool) -> Option<Key> {
         vk::NUMLOCK => Some(Key::NumLock),
         vk::SCROLL => Some(Key::ScrollLock),
 
+        // International keys
+        vk::OEM_102 => Some(Key::Section),
+        vk::KANA => Some(Key::Kana),
+        vk::HANJA => Some(Key::Hanja),
+
+        // Media keys (Windows has no virtual keys for brightness)
+        vk::MEDIA_PLAY_PAUSE => Some(Key::PlayPause),
+        vk::MEDIA_NEXT_TRACK => Some(Key::NextTrack),
+        vk::MEDIA_PREV_TRACK => Some(Key::PreviousTrack),
+        vk::VOLUME_UP => Some(Key::VolumeUp),
+        vk::VOLUME_DOWN => Some(Key::VolumeDown),
+        vk::VOLUME_MUTE => Some(Key::VolumeMute),
+
         _ => None,
     }
 }
 
+/// Whether different physical keys share a virtual key, so only the scan code
+/// tells which one it is
+///
+/// Japanese and Korean keyboards report their IME keys with the same virtual
+/// keys, and the ISO extra key has the virtual key of the JIS Ro key.
+pub fn is_shared_vk(vk_code: u16) -> bool {
+    matches!(vk_code, vk::KANA | vk::HANJA | vk::OEM_102)
+}
+
+/// Convert a set 1 scan code to the Key at that physical position
+///
+/// Keys are named after their position on a US keyboard, whatever the active
+/// layout. The `is_extended` flag distinguishes e.g. the arrow keys from the
+/// numpad keys sharing their scan codes.
+pub fn scancode_to_key(scan_code: u32, is_extended: bool) -> Option<Key> {
+    let key = match (scan_code, is_extended) {
+        // Media keys share scan codes with letters, with the extended flag
+        (0x10, true) => Key::PreviousTrack,
+        (0x19, true) => Key::NextTrack,
+        (0x20, true) => Key::VolumeMute,
+        (0x22, true) => Key::PlayPause,
+        (0x2E, true) => Key::VolumeDown,
+        (0x30, true) => Key::VolumeUp,
+
+        (0x01, _) => Key::Escape,
+        (0x02, _) => Key::Num1,
+        (0x03, _) => Key::Num2,
+        (0x04, _) => Key::Num3,
+        (0x05, _) => Key::Num4,
+        (0x06, _) => Key::Num5,
+        (0x07, _) => Key::Num6,
+        (0x08, _) => Key::Num7,
+        (0x09, _) => Key::Num8,
+        (0x0A, _) => Key::Num9,
+        (0x0B, _) => Key::Num0,
+        (0x0C, _) => Key::Minus,
+        (0x0D, _) => Key::Equal,
+        (0x0E, _) => Key::Delete,
+        (0x0F, _) => Key::Tab,
+        (0x10, _) => Key::Q,
+        (0x11, _) => Key::W,
+        (0x12, _) => Key::E,
+        (0x13, _) => Key::R,
+        (0x14, _) => Key::T,
+        (0x15, _) => Key::Y,
+        (0x16, _) => Key::U,
+        (0x17, _) => Key::I,
+        (0x18, _) => Key::O,
+        (0x19, _) => Key::P,
+        (0x1A, _) => Key::LeftBracket,
+        (0x1B, _) => Key::RightBracket,
+        (0x1C, false) => Key::Return,
+        (0x1C, true) => Key::KeypadEnter,
+        (0x1E, _) => Key::A,
+        (0x1F, _) => Key::S,
+        (0x20, _) => Key::D,
+        (0x21, _) => Key::F,
+        (0x22, _) => Key::G,
+        (0x23, _) => Key::H,
+        (0x24, _) => Key::J,
+        (0x25, _) => Key::K,
+        (0x26, _) => Key::L,
+        (0x27, _) => Key::Semicolon,
+        (0x28, _) => Key::Quote,
+        (0x29, _) => Key::Grave,
+        (0x2B, _) => Key::Backslash,
+        (0x2C, _) => Key::Z,
+        (0x2D, _) => Key::X,
+        (0x2E, _) => Key::C,
+        (0x2F, _) => Key::V,
+        (0x30, _) => Key::B,
+        (0x31, _) => Key::N,
+        (0x32, _) => Key::M,
+        (0x33, _) => Key::Comma,
+        (0x34, _) => Key::Period,
+        (0x35, false) => Key::Slash,
+        (0x35, true) => Key::KeypadDivide,
+        (0x37, false) => Key::KeypadMultiply,
+        (0x37, true) => Key::PrintScreen,
+        (0x39, _) => Key::Space,
+        (0x3A, _) => Key::CapsLock,
+        (0x3B, _) => Key::F1,
+        (0x3C, _) => Key::F2,
+        (0x3D, _) => Key::F3,
+        (0x3E, _) => Key::F4,
+        (0x3F, _) => Key::F5,
+        (0x40, _) => Key::F6,
+        (0x41, _) => Key::F7,
+        (0x42, _) => Key::F8,
+        (0x43, _) => Key::F9,
+        (0x44, _) => Key::F10,
+        // Pause is sent with Num Lock's scan code, but not as an extended key
+        (0x45, false) => Key::Pause,
+        (0x45, true) => Key::NumLock,
+        (0x46, false) => Key::ScrollLock,
+        (0x47, false) => Key::Keypad7,
+        (0x47, true) => Key::Home,
+        (0x48, false) => Key::Keypad8,
+        (0x48, true) => Key::UpArrow,
+        (0x49, false) => Key::Keypad9,
+        (0x49, true) => Key::PageUp,
+        (0x4A, false) => Key::KeypadMinus,
+        (0x4B, false) => Key::Keypad4,
+        (0x4B, true) => Key::LeftArrow,
+        (0x4C, false) => Key::Keypad5,
+        (0x4D, false) => Key::Keypad6,
+        (0x4D, true) => Key::RightArrow,
+        (0x4E, false) => Key::KeypadPlus,
+        (0x4F, false) => Key::Keypad1,
+        (0x4F, true) => Key::End,
+        (0x50, false) => Key::Keypad2,
+        (0x50, true) => Key::DownArrow,
+        (0x51, false) => Key::Keypad3,
+        (0x51, true) => Key::PageDown,
+        (0x52, false) => Key::Keypad0,
+        (0x52, true) => Key::Insert,
+        (0x53, false) => Key::KeypadDecimal,
+        (0x53, true) => Key::ForwardDelete,
+        (0x57, _) => Key::F11,
+        (0x58, _) => Key::F12,
+        (0x59, false) => Key::KeypadEquals,
+        (0x64, _) => Key::F13,
+        (0x65, _) => Key::F14,
+        (0x66, _) => Key::F15,
+        (0x67, _) => Key::F16,
+        (0x68, _) => Key::F17,
+        (0x69, _) => Key::F18,
+        (0x6A, _) => Key::F19,
+        (0x6B, _) => Key::F20,
+        (0x5D, true) => Key::ContextMenu,
+        (0x56, _) => Key::Section,
+        (0x70, _) => Key::Kana,
+        (0x71, _) => Key::Hanja,
+        (0x72, _) => Key::Hangul,
+        (0x73, _) => Key::Ro,
+        (0x7D, _) => Key::Yen,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Look up the scan code of a virtual key in the foreground window's layout
+///
+/// Used for injected events, which often carry no scan code.
+pub fn vk_to_scancode(vk_code: u16) -> u32 {
+    unsafe {
+        let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), None);
+        let layout = GetKeyboardLayout(thread_id);
+        MapVirtualKeyExW(vk_code as u32, MAPVK_VK_TO_VSC, layout)
+    }
+}
+
+/// Convert Key to a Windows virtual key code
+///
+/// The inverse of [`vk_to_key`]. Numpad Enter maps to the same code as Return,
+/// since the two only differ by the extended flag.
+pub fn key_to_vk(key: Key) -> Option<u16> {
+    let vk_code = match key {
+        Key::A => 0x41,
+        Key::B => 0x42,
+        Key::C => 0x43,
+        Key::D => 0x44,
+        Key::E => 0x45,
+        Key::F => 0x46,
+        Key::G => 0x47,
+        Key::H => 0x48,
+        Key::I => 0x49,
+        Key::J => 0x4A,
+        Key::K => 0x4B,
+        Key::L => 0x4C,
+        Key::M => 0x4D,
+        Key::N => 0x4E,
+        Key::O => 0x4F,
+        Key::P => 0x50,
+        Key::Q => 0x51,
+        Key::R => 0x52,
+        Key::S => 0x53,
+        Key::T => 0x54,
+        Key::U => 0x55,
+        Key::V => 0x56,
+        Key::W => 0x57,
+        Key::X => 0x58,
+        Key::Y => 0x59,
+        Key::Z => 0x5A,
+
+        Key::Num0 => 0x30,
+        Key::Num1 => 0x31,
+        Key::Num2 => 0x32,
+        Key::Num3 => 0x33,
+        Key::Num4 => 0x34,
+        Key::Num5 => 0x35,
+        Key::Num6 => 0x36,
+        Key::Num7 => 0x37,
+        Key::Num8 => 0x38,
+        Key::Num9 => 0x39,
+
+        Key::Keypad0 => vk::NUMPAD0,
+        Key::Keypad1 => vk::NUMPAD1,
+        Key::Keypad2 => vk::NUMPAD2,
+        Key::Keypad3 => vk::NUMPAD3,
+        Key::Keypad4 => vk::NUMPAD4,
+        Key::Keypad5 => vk::NUMPAD5,
+        Key::Keypad6 => vk::NUMPAD6,
+        Key::Keypad7 => vk::NUMPAD7,
+        Key::Keypad8 => vk::NUMPAD8,
+        Key::Keypad9 => vk::NUMPAD9,
+        Key::KeypadMultiply => vk::MULTIPLY,
+        Key::KeypadPlus => vk::ADD,
+        Key::KeypadMinus => vk::SUBTRACT,
+        Key::KeypadDecimal => vk::DECIMAL,
+        Key::KeypadDivide => vk::DIVIDE,
+
+        Key::Return | Key::KeypadEnter => vk::RETURN,
+
+        Key::F1 => vk::F1,
+        Key::F2 => vk::F2,
+        Key::F3 => vk::F3,
+        Key::F4 => vk::F4,
+        Key::F5 => vk::F5,
+        Key::F6 => vk::F6,
+        Key::F7 => vk::F7,
+        Key::F8 => vk::F8,
+        Key::F9 => vk::F9,
+        Key::F10 => vk::F10,
+        Key::F11 => vk::F11,
+        Key::F12 => vk::F12,
+        Key::F13 => vk::F13,
+        Key::F14 => vk::F14,
+        Key::F15 => vk::F15,
+        Key::F16 => vk::F16,
+        Key::F17 => vk::F17,
+        Key::F18 => vk::F18,
+        Key::F19 => vk::F19,
+        Key::F20 => vk::F20,
+
+        Key::Delete => vk::BACK,
+        Key::ForwardDelete => vk::DELETE,
+        Key::Tab => vk::TAB,
+        Key::Escape => vk::ESCAPE,
+        Key::Space => vk::SPACE,
+        Key::PageUp => vk::PRIOR,
+        Key::PageDown => vk::NEXT,
+        Key::End => vk::END,
+        Key::Home => vk::HOME,
+        Key::LeftArrow => vk::LEFT,
+        Key::UpArrow => vk::UP,
+        Key::RightArrow => vk::RIGHT,
+        Key::DownArrow => vk::DOWN,
+        Key::Insert => vk::INSERT,
+        Key::PrintScreen => vk::SNAPSHOT,
+        Key::Pause => vk::PAUSE,
+        Key::ContextMenu => vk::APPS,
+
+        Key::Semicolon => vk::OEM_1,
+        Key::Equal => vk::OEM_PLUS,
+        Key::Comma => vk::OEM_COMMA,
+        Key::Minus => vk::OEM_MINUS,
+        Key::Period => vk::OEM_PERIOD,
+        Key::Slash => vk::OEM_2,
+        Key::Grave => vk::OEM_3,
+        Key::LeftBracket => vk::OEM_4,
+        Key::Backslash => vk::OEM_5,
+        Key::RightBracket => vk::OEM_6,
+        Key::Quote => vk::OEM_7,
+
+        Key::CapsLock => vk::CAPITAL,
+        Key::NumLock => vk::NUMLOCK,
+        Key::ScrollLock => vk::SCROLL,
+
+        Key::Section => vk::OEM_102,
+        Key::Kana | Key::Hangul => vk::KANA,
+        Key::Hanja => vk::HANJA,
+        Key::Ro => vk::OEM_102,
+
+        Key::PlayPause => vk::MEDIA_PLAY_PAUSE,
+        Key::NextTrack => vk::MEDIA_NEXT_TRACK,
+        Key::PreviousTrack => vk::MEDIA_PREV_TRACK,
+        Key::VolumeUp => vk::VOLUME_UP,
+        Key::VolumeDown => vk::VOLUME_DOWN,
+        Key::VolumeMute => vk::VOLUME_MUTE,
+
+        _ => return None,
+    };
+    Some(vk_code)
+}
+
 /// Convert Windows virtual key code to Modifier
 pub fn vk_to_modifier(vk_code: u16) -> Option<Modifiers> {
     match vk_code {
@@ -234,3 +559,39 @@ pub fn vk_to_modifier(vk_code: u16) -> Option<Modifiers> {
         _ => None,
     }
 }
+
+/// The side flag of a left or right modifier key, like `Modifiers::R_OPT`
+/// for `VK_RMENU`
+pub fn vk_to_side(vk_code: u16) -> Option<Modifiers> {
+    match vk_code {
+        vk::LSHIFT => Some(Modifiers::L_SHIFT),
+        vk::RSHIFT => Some(Modifiers::R_SHIFT),
+        vk::LCONTROL => Some(Modifiers::L_CTRL),
+        vk::RCONTROL => Some(Modifiers::R_CTRL),
+        vk::LMENU => Some(Modifiers::L_OPT),
+        vk::RMENU => Some(Modifiers::R_OPT),
+        vk::LWIN => Some(Modifiers::L_CMD),
+        vk::RWIN => Some(Modifiers::R_CMD),
+        _ => None,
+    }
+}
+
+/// Read the modifiers the OS currently reports as held
+pub fn current_modifiers() -> Modifiers {
+    let is_down = |vk_code: u16| (unsafe { GetAsyncKeyState(vk_code as i32) } as u16 & 0x8000) != 0;
+
+    let mut mods = Modifiers::empty();
+    if is_down(vk::SHIFT) {
+        mods |= Modifiers::SHIFT;
+    }
+    if is_down(vk::CONTROL) {
+        mods |= Modifiers::CTRL;
+    }
+    if is_down(vk::MENU) {
+        mods |= Modifiers::OPT;
+    }
+    if is_down(vk::LWIN) || is_down(vk::RWIN) {
+        mods |= Modifiers::CMD;
+    }
+    mods
+}
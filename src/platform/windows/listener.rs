@@ -1,68 +1,138 @@
 //! Windows low-level keyboard hook implementation
 
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{self, Sender};
-use std::sync::{Arc, Mutex};
-use std::thread::{self, JoinHandle};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::SystemInformation::GetTickCount;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+    VIRTUAL_KEY,
+};
 use windows::Win32::UI::WindowsAndMessaging::{
-    CallNextHookEx, DispatchMessageW, PeekMessageW, SetWindowsHookExW, TranslateMessage,
+    CallNextHookEx, DispatchMessageW, PeekMessageW, HHOOK, SetWindowsHookExW, TranslateMessage,
     UnhookWindowsHookEx, KBDLLHOOKSTRUCT, LLKHF_EXTENDED, MSLLHOOKSTRUCT, MSG, PM_REMOVE,
     WH_KEYBOARD_LL, WH_MOUSE_LL, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_LBUTTONUP,
     WM_MBUTTONDOWN, WM_MBUTTONUP, WM_QUIT, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SYSKEYDOWN,
     WM_SYSKEYUP, WM_XBUTTONDOWN, WM_XBUTTONUP,
 };
 
+use crate::channel;
 use crate::error::Result;
-use crate::platform::state::BlockingHotkeys;
-use crate::types::{Hotkey, Key, KeyEvent, Modifiers};
+use crate::platform::state::{
+    BlockingHotkeys, KeyMapping, ListenerConfig, ListenerState, SystemEvent, WakeDetector,
+};
+use crate::platform::frontmost;
+use crate::platform::threads;
+use crate::types::{Key, KeyEvent, Modifiers};
 
-use super::keycode::{vk_to_key, vk_to_modifier};
+use super::keycode::{
+    current_modifiers, is_shared_vk, scancode_to_key, vk_to_key, vk_to_modifier, vk_to_scancode,
+    vk_to_side,
+};
 
 /// Thread-local state for the keyboard hook callback.
 ///
 /// Windows low-level hooks require a callback function with a specific signature,
 /// so we use thread-local storage to access our state from within the callback.
 struct HookContext {
-    event_sender: Sender<KeyEvent>,
-    current_modifiers: Modifiers,
-    blocking_hotkeys: Option<BlockingHotkeys>,
+    state: ListenerState,
+    /// Whether the Start menu has already been masked for the current Win key press
+    win_menu_masked: bool,
+    key_mapping: KeyMapping,
+}
+
+impl HookContext {
+    /// Identify the key of a non-modifier keyboard event
+    fn key_for(&self, kb_struct: &KBDLLHOOKSTRUCT, is_extended: bool) -> Option<Key> {
+        let vk_code = kb_struct.vkCode as u16;
+        match self.key_mapping {
+            KeyMapping::Layout if !is_shared_vk(vk_code) => vk_to_key(vk_code, is_extended),
+            KeyMapping::Layout | KeyMapping::Physical => {
+                // Injected events often have no scan code
+                let scan_code = match kb_struct.scanCode {
+                    0 => vk_to_scancode(vk_code),
+                    scan_code => scan_code,
+                };
+                scancode_to_key(scan_code, is_extended)
+                    .or_else(|| vk_to_key(vk_code, is_extended))
+            }
+        }
+    }
 }
 
+/// Unassigned virtual key used to mask the Win key release.
+///
+/// Windows opens the Start menu when the Win key is released without any other
+/// key having been seen in between. When we block a Win-based hotkey, the OS
+/// never sees the other key, so we inject this no-op key while Win is held.
+const MENU_MASK_VK: u16 = 0xE8;
+
+/// Virtual keys that aren't real key presses, so aren't reported as unknown keys
+///
+/// Besides our own [`MENU_MASK_VK`], `VK_PACKET` carries characters injected
+/// by `SendInput`.
+const IGNORED_VKS: [u16; 2] = [MENU_MASK_VK, 0xE7];
+
 thread_local! {
     static HOOK_CONTEXT: std::cell::RefCell<Option<HookContext>> = const { std::cell::RefCell::new(None) };
 }
 
+/// Time since an event's timestamp, in `GetTickCount` milliseconds
+fn latency_since(time: u32) -> Duration {
+    Duration::from_millis(u64::from(unsafe { GetTickCount() }.wrapping_sub(time)))
+}
+
+/// Replace the hooks with new ones, which go to the head of the hook chain
+///
+/// Windows silently removes low-level hooks that time out, and hooks
+/// installed later by other apps run before ours.
+fn reinstall_hooks(kb_hook: &mut HHOOK, mouse_hook: &mut HHOOK) {
+    unsafe {
+        if let Ok(h) = SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook_proc), None, 0) {
+            let _ = UnhookWindowsHookEx(*kb_hook);
+            *kb_hook = h;
+        }
+        if let Ok(h) = SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), None, 0) {
+            let _ = UnhookWindowsHookEx(*mouse_hook);
+            *mouse_hook = h;
+        }
+    }
+}
+
 /// Internal listener state returned to KeyboardListener
 pub(crate) struct WindowsListenerState {
-    pub event_receiver: mpsc::Receiver<KeyEvent>,
-    pub thread_handle: Option<JoinHandle<()>>,
+    pub event_receiver: channel::Receiver<KeyEvent>,
+    pub thread_handle: Option<threads::Handle>,
     pub running: Arc<AtomicBool>,
     pub blocking_hotkeys: Option<BlockingHotkeys>,
 }
 
 /// Spawn a Windows low-level keyboard hook listener
-pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<WindowsListenerState> {
-    let (tx, rx) = mpsc::channel();
+pub(crate) fn spawn(config: ListenerConfig) -> Result<WindowsListenerState> {
+    let (tx, rx) = channel::channel();
     let running = Arc::new(AtomicBool::new(true));
     let thread_running = Arc::clone(&running);
-    let thread_blocking = blocking_hotkeys.clone();
+    let blocking_hotkeys = config.blocking_hotkeys.clone();
+    let reinstall_interval = config.hook_reinstall_interval;
+    let priority = config.thread_priority;
 
-    let handle = thread::spawn(move || {
+    let handle = threads::spawn_prioritized(&running, priority, move || {
         // Initialize thread-local hook context
         HOOK_CONTEXT.with(|ctx| {
             *ctx.borrow_mut() = Some(HookContext {
-                event_sender: tx,
-                current_modifiers: Modifiers::empty(),
-                blocking_hotkeys: thread_blocking,
+                state: ListenerState::new(tx, &config),
+                win_menu_masked: false,
+                key_mapping: config.key_mapping,
             });
         });
 
         // Install the low-level keyboard hook
         let kb_hook = unsafe { SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook_proc), None, 0) };
 
-        let kb_hook = match kb_hook {
+        let mut kb_hook = match kb_hook {
             Ok(h) => h,
             Err(e) => {
                 eprintln!("Failed to install keyboard hook: {:?}", e);
@@ -73,7 +143,7 @@ pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<Windows
         // Install the low-level mouse hook
         let mouse_hook = unsafe { SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook_proc), None, 0) };
 
-        let mouse_hook = match mouse_hook {
+        let mut mouse_hook = match mouse_hook {
             Ok(h) => h,
             Err(e) => {
                 eprintln!("Failed to install mouse hook: {:?}", e);
@@ -83,9 +153,15 @@ pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<Windows
             }
         };
 
+        // Lets the hook callbacks check excluded and app-scoped hotkeys
+        // without looking up the foreground process on every event
+        let foreground = frontmost::watch();
+
         // Message loop - required for low-level hooks to function
         // We use PeekMessage with a sleep to allow checking the running flag
         let mut msg = MSG::default();
+        let mut wake_detector = WakeDetector::new();
+        let mut installed_at = Instant::now();
         loop {
             // Check if we should stop
             if !thread_running.load(Ordering::SeqCst) {
@@ -103,11 +179,37 @@ pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<Windows
                 }
             }
 
+            let woke = wake_detector.tick();
+            // Hooks are likely to time out while resuming, so always
+            // reinstall them after a wake
+            let reinstall = woke
+                || reinstall_interval.is_some_and(|interval| installed_at.elapsed() >= interval);
+            if reinstall {
+                reinstall_hooks(&mut kb_hook, &mut mouse_hook);
+                installed_at = Instant::now();
+            }
+
+            HOOK_CONTEXT.with(|ctx| {
+                if let Some(ctx) = ctx.borrow_mut().as_mut() {
+                    if reinstall {
+                        ctx.state.record_hook_reinstall();
+                    }
+                    if woke {
+                        ctx.state.resync_after_wake(Some(current_modifiers()));
+                        ctx.win_menu_masked = false;
+                        ctx.state.report_system_event(SystemEvent::Woke);
+                    }
+                    ctx.state
+                        .release_stuck_modifiers(|| Some(current_modifiers()));
+                }
+            });
+
             // Sleep briefly to avoid busy-waiting while still being responsive
             thread::sleep(std::time::Duration::from_millis(10));
         }
 
         // Clean up the hooks
+        drop(foreground);
         unsafe {
             let _ = UnhookWindowsHookEx(kb_hook);
             let _ = UnhookWindowsHookEx(mouse_hook);
@@ -117,7 +219,7 @@ pub(crate) fn spawn(blocking_hotkeys: Option<BlockingHotkeys>) -> Result<Windows
         HOOK_CONTEXT.with(|ctx| {
             *ctx.borrow_mut() = None;
         });
-    });
+    })?;
 
     Ok(WindowsListenerState {
         event_receiver: rx,
@@ -147,8 +249,12 @@ unsafe extern "system" fn keyboard_hook_proc(
     HOOK_CONTEXT.with(|ctx_cell| {
         let mut ctx_ref = ctx_cell.borrow_mut();
         if let Some(ctx) = ctx_ref.as_mut() {
+            let _timer = ctx.state.time_callback();
+            ctx.state.note_input();
+
             // Extract key information from KBDLLHOOKSTRUCT
             let kb_struct = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+            ctx.state.record_hook_latency(latency_since(kb_struct.time));
             let vk_code = kb_struct.vkCode as u16;
             let is_extended = (kb_struct.flags.0 & LLKHF_EXTENDED.0) != 0;
 
@@ -156,46 +262,68 @@ unsafe extern "system" fn keyboard_hook_proc(
 
             // Check if this is a modifier key
             if let Some(modifier) = vk_to_modifier(vk_code) {
-                let prev_modifiers = ctx.current_modifiers;
+                let prev_modifiers = ctx.state.current_modifiers | ctx.state.held_sides();
 
                 // Update modifier state
                 if is_key_down {
-                    ctx.current_modifiers |= modifier;
+                    ctx.state.current_modifiers |= modifier;
                 } else {
-                    ctx.current_modifiers &= !modifier;
+                    ctx.state.current_modifiers &= !modifier;
                 }
+                ctx.state.update_sides(vk_to_side(vk_code), is_key_down);
+                let sides = ctx.state.held_sides();
 
                 // Only emit event if modifiers actually changed
-                if ctx.current_modifiers != prev_modifiers {
+                if ctx.state.current_modifiers | sides != prev_modifiers {
                     // Check if modifier-only combo should be blocked
-                    should_block = should_block_hotkey(
-                        &ctx.blocking_hotkeys,
-                        ctx.current_modifiers,
-                        None,
-                    );
-
-                    let _ = ctx.event_sender.send(KeyEvent {
-                        modifiers: ctx.current_modifiers,
+                    should_block = ctx.state.should_block(ctx.state.current_modifiers | sides, None);
+
+                    let _ = ctx.state.send(KeyEvent {
+                        modifiers: ctx.state.current_modifiers,
                         key: None,
                         is_key_down,
                         changed_modifier: Some(modifier),
+                        sides,
+                        hook: None,
                     });
                 }
-            } else if let Some(key) = vk_to_key(vk_code, is_extended) {
+
+                // Win released: the next Win press starts a fresh sequence
+                if !ctx.state.current_modifiers.contains(Modifiers::CMD) {
+                    ctx.win_menu_masked = false;
+                }
+            } else if let Some(key) = ctx.key_for(kb_struct, is_extended).or_else(|| {
+                let vk_code = kb_struct.vkCode as u16;
+                (!IGNORED_VKS.contains(&vk_code)).then(|| ctx.state.unknown_key(vk_code.into()))
+            }) {
                 // Regular key event
-                should_block = should_block_hotkey(
-                    &ctx.blocking_hotkeys,
-                    ctx.current_modifiers,
-                    Some(key),
-                );
-
-                let _ = ctx.event_sender.send(KeyEvent {
-                    modifiers: ctx.current_modifiers,
+                let modifiers = ctx.state.current_modifiers;
+                let sides = ctx.state.held_sides();
+                should_block = if is_key_down {
+                    ctx.state.should_block_key_down(modifiers | sides, key)
+                } else {
+                    ctx.state.should_block_key_up(modifiers | sides, key)
+                };
+
+                let _ = ctx.state.send(KeyEvent {
+                    modifiers: ctx.state.current_modifiers,
                     key: Some(key),
                     is_key_down,
                     changed_modifier: None,
+                    sides,
+                    hook: None,
                 });
             }
+
+            // Keep a blocked Win-based hotkey from opening the Start menu
+            if should_block
+                && is_key_down
+                && ctx.state.current_modifiers.contains(Modifiers::CMD)
+                && !ctx.win_menu_masked
+            {
+                ctx.win_menu_masked = true;
+                send_menu_mask_key();
+            }
         }
     });
 
@@ -221,21 +349,23 @@ unsafe extern "system" fn mouse_hook_proc(
         return CallNextHookEx(None, code, wparam, lparam);
     }
 
+    let mut should_block = false;
+
     // Process the mouse event
     HOOK_CONTEXT.with(|ctx_cell| {
         let mut ctx_ref = ctx_cell.borrow_mut();
         if let Some(ctx) = ctx_ref.as_mut() {
-            let mouse_struct = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            let _timer = ctx.state.time_callback();
+            ctx.state.note_input();
 
-            // Only report left/right clicks when modifiers are held (to avoid noise)
-            let has_modifiers = !ctx.current_modifiers.is_empty();
+            let mouse_struct = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            ctx.state.record_hook_latency(latency_since(mouse_struct.time));
 
             let (key, is_down) = match wparam.0 as u32 {
-                WM_LBUTTONDOWN if has_modifiers => (Some(Key::MouseLeft), true),
-                WM_LBUTTONUP if has_modifiers => (Some(Key::MouseLeft), false),
-                WM_RBUTTONDOWN if has_modifiers => (Some(Key::MouseRight), true),
-                WM_RBUTTONUP if has_modifiers => (Some(Key::MouseRight), false),
-                // Middle and X buttons always reported
+                WM_LBUTTONDOWN => (Some(Key::MouseLeft), true),
+                WM_LBUTTONUP => (Some(Key::MouseLeft), false),
+                WM_RBUTTONDOWN => (Some(Key::MouseRight), true),
+                WM_RBUTTONUP => (Some(Key::MouseRight), false),
                 WM_MBUTTONDOWN => (Some(Key::MouseMiddle), true),
                 WM_MBUTTONUP => (Some(Key::MouseMiddle), false),
                 WM_XBUTTONDOWN => {
@@ -265,31 +395,66 @@ unsafe extern "system" fn mouse_hook_proc(
             };
 
             if let Some(key) = key {
-                let _ = ctx.event_sender.send(KeyEvent {
-                    modifiers: ctx.current_modifiers,
-                    key: Some(key),
-                    is_key_down: is_down,
-                    changed_modifier: None,
-                });
+                // Only report left/right clicks when modifiers are held (to avoid noise).
+                // Middle and X buttons are always reported.
+                let modifiers = ctx.state.current_modifiers;
+                let sides = ctx.state.held_sides();
+                let is_common = matches!(key, Key::MouseLeft | Key::MouseRight);
+                let report = !is_common || !modifiers.is_empty();
+
+                should_block = if !report {
+                    // An unreported release still completes a blocked press
+                    !is_down && ctx.state.release_blocked_key(key)
+                } else if is_down {
+                    ctx.state.should_block_key_down(modifiers | sides, key)
+                } else {
+                    ctx.state.should_block_key_up(modifiers | sides, key)
+                };
+
+                if report {
+                    let _ = ctx.state.send(KeyEvent {
+                        modifiers,
+                        key: Some(key),
+                        is_key_down: is_down,
+                        changed_modifier: None,
+                        sides,
+                        hook: None,
+                    });
+                }
             }
         }
     });
 
-    // Always pass mouse events through (no blocking for mouse)
-    CallNextHookEx(None, code, wparam, lparam)
+    if should_block {
+        // Return non-zero to block the button event from propagating
+        LRESULT(1)
+    } else {
+        CallNextHookEx(None, code, wparam, lparam)
+    }
 }
 
-/// Check if a hotkey combination should be blocked
-fn should_block_hotkey(
-    blocking_hotkeys: &Option<BlockingHotkeys>,
-    modifiers: Modifiers,
-    key: Option<Key>,
-) -> bool {
-    if let Some(ref hotkeys) = blocking_hotkeys {
-        if let Ok(set) = hotkeys.lock() {
-            let hotkey = Hotkey { modifiers, key };
-            return set.contains(&hotkey);
-        }
+/// Inject a press and release of [`MENU_MASK_VK`]
+///
+/// The key is not mapped by `vk_to_key`/`vk_to_modifier`, so the injected
+/// events pass through our own hook without producing key events.
+fn send_menu_mask_key() {
+    let make_input = |flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(MENU_MASK_VK),
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    let inputs = [
+        make_input(KEYBD_EVENT_FLAGS(0)),
+        make_input(KEYEVENTF_KEYUP),
+    ];
+    unsafe {
+        SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
     }
-    false
 }
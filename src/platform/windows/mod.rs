@@ -3,4 +3,4 @@
 mod keycode;
 pub(crate) mod listener;
 
-pub(crate) use keycode::{vk_to_key, vk_to_modifier};
+pub(crate) use keycode::{key_to_vk, vk_to_key, vk_to_modifier};
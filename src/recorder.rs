@@ -0,0 +1,251 @@
+//! Recording a hotkey from the keyboard while a manager's hotkeys are paused
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::manager::HotkeyManager;
+use crate::types::{Hotkey, Key, KeyEvent, Modifiers};
+
+/// Where a manager's event loop copies key events while a
+/// [`HotkeyRecorder`] is open
+pub(crate) type RecordingTap = Arc<Mutex<Option<mpsc::Sender<KeyEvent>>>>;
+
+/// Result of feeding a key event to a [`Capture`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CaptureStep {
+    Pending,
+    Done(Hotkey),
+    Cancelled,
+}
+
+/// Turns the key events of a recording session into a hotkey
+///
+/// A key pressed with any held modifiers completes the hotkey. Modifiers
+/// released without a key complete a modifier-only hotkey of the most
+/// modifiers held at once. Escape on its own cancels.
+#[derive(Debug)]
+pub(crate) struct Capture {
+    held_modifiers: Modifiers,
+}
+
+impl Capture {
+    pub(crate) fn new() -> Self {
+        Self {
+            held_modifiers: Modifiers::empty(),
+        }
+    }
+
+    pub(crate) fn process(&mut self, event: &KeyEvent) -> CaptureStep {
+        match event.key {
+            // Plain clicks are the user interacting with the UI
+            Some(Key::MouseLeft | Key::MouseRight) if event.modifiers.is_empty() => {
+                CaptureStep::Pending
+            }
+            Some(Key::Escape) if event.is_key_down && event.modifiers.is_empty() => {
+                CaptureStep::Cancelled
+            }
+            Some(key) if event.is_key_down => match Hotkey::new(event.modifiers, key) {
+                Ok(hotkey) => CaptureStep::Done(hotkey),
+                Err(_) => CaptureStep::Pending,
+            },
+            Some(_) => CaptureStep::Pending,
+            None if event.is_key_down => {
+                self.held_modifiers |= event.modifiers;
+                CaptureStep::Pending
+            }
+            None if event.modifiers.is_empty() && !self.held_modifiers.is_empty() => {
+                match Hotkey::new(self.held_modifiers, None) {
+                    Ok(hotkey) => CaptureStep::Done(hotkey),
+                    Err(_) => CaptureStep::Pending,
+                }
+            }
+            None => CaptureStep::Pending,
+        }
+    }
+}
+
+/// A "change shortcut" session started by [`HotkeyManager::begin_recording`]
+///
+/// While it's open, the manager's hotkeys are suspended as with
+/// [`HotkeyManager::suspend`], so the combo being recorded doesn't also fire
+/// one of them, and the manager's key events are streamed to the recorder
+/// instead. Iterating yields the raw [`KeyEvent`]s until the user completes a
+/// combination, after which [`hotkey`](Self::hotkey) returns it.
+///
+/// A key pressed with any held modifiers completes the combination, as does
+/// letting go of held modifiers without pressing a key. Escape on its own
+/// cancels. Dropping the recorder resumes the hotkeys, unless the manager was
+/// already suspended before.
+///
+/// ```no_run
+/// use handy_keys::HotkeyManager;
+///
+/// let manager = HotkeyManager::new()?;
+/// manager.register("Ctrl+Alt+Space".parse()?)?;
+///
+/// let mut recorder = manager.begin_recording()?;
+/// for event in &mut recorder {
+///     println!("{:?} {:?}", event.modifiers, event.key);
+/// }
+/// match recorder.hotkey() {
+///     Some(hotkey) => println!("Recorded {hotkey}"),
+///     None => println!("Cancelled"),
+/// }
+/// # Ok::<(), handy_keys::Error>(())
+/// ```
+pub struct HotkeyRecorder<'a> {
+    manager: &'a HotkeyManager,
+    events: mpsc::Receiver<KeyEvent>,
+    capture: Capture,
+    outcome: CaptureStep,
+    /// Whether to resume the manager's hotkeys once done
+    resume: bool,
+}
+
+impl<'a> HotkeyRecorder<'a> {
+    pub(crate) fn new(
+        manager: &'a HotkeyManager,
+        events: mpsc::Receiver<KeyEvent>,
+        resume: bool,
+    ) -> Self {
+        Self {
+            manager,
+            events,
+            capture: Capture::new(),
+            outcome: CaptureStep::Pending,
+            resume,
+        }
+    }
+
+    /// Receive the next key event, waiting up to `timeout`
+    ///
+    /// Returns [`Error::Timeout`] if none came in time, and
+    /// [`Error::EventLoopNotRunning`] once the combination is complete or
+    /// recording was cancelled.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<KeyEvent> {
+        if self.is_finished() {
+            return Err(Error::EventLoopNotRunning);
+        }
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => Ok(self.process(event)),
+            Err(RecvTimeoutError::Timeout) => Err(Error::Timeout),
+            Err(RecvTimeoutError::Disconnected) => Err(Error::EventLoopNotRunning),
+        }
+    }
+
+    /// Receive the next key event without blocking
+    ///
+    /// Returns `None` if no event is available or recording is finished.
+    pub fn try_recv(&mut self) -> Option<KeyEvent> {
+        if self.is_finished() {
+            return None;
+        }
+        let event = self.events.try_recv().ok()?;
+        Some(self.process(event))
+    }
+
+    /// Whether the user completed a combination or cancelled
+    pub fn is_finished(&self) -> bool {
+        self.outcome != CaptureStep::Pending
+    }
+
+    /// Whether the user cancelled by pressing Escape
+    pub fn is_cancelled(&self) -> bool {
+        self.outcome == CaptureStep::Cancelled
+    }
+
+    /// The combination the user completed, if they did
+    pub fn hotkey(&self) -> Option<Hotkey> {
+        match self.outcome {
+            CaptureStep::Done(hotkey) => Some(hotkey),
+            _ => None,
+        }
+    }
+
+    /// Wait for the user to complete a combination and return it
+    ///
+    /// Returns `None` if they cancelled.
+    pub fn record(mut self) -> Option<Hotkey> {
+        self.by_ref().for_each(drop);
+        self.hotkey()
+    }
+
+    fn process(&mut self, event: KeyEvent) -> KeyEvent {
+        self.outcome = self.capture.process(&event);
+        event
+    }
+}
+
+impl Iterator for HotkeyRecorder<'_> {
+    type Item = KeyEvent;
+
+    fn next(&mut self) -> Option<KeyEvent> {
+        if self.is_finished() {
+            return None;
+        }
+        let event = self.events.recv().ok()?;
+        Some(self.process(event))
+    }
+}
+
+impl Drop for HotkeyRecorder<'_> {
+    fn drop(&mut self) {
+        self.manager.end_recording(self.resume);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(modifiers: Modifiers, key: Option<Key>, is_key_down: bool) -> KeyEvent {
+        KeyEvent {
+            modifiers,
+            key,
+            is_key_down,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        }
+    }
+
+    #[test]
+    fn key_with_modifiers_completes_capture() {
+        let mut capture = Capture::new();
+
+        assert_eq!(capture.process(&event(Modifiers::CMD, None, true)), CaptureStep::Pending);
+        assert_eq!(
+            capture.process(&event(Modifiers::CMD, Some(Key::K), true)),
+            CaptureStep::Done(Hotkey::new(Modifiers::CMD, Key::K).unwrap())
+        );
+    }
+
+    #[test]
+    fn released_modifiers_complete_modifier_only_capture() {
+        let mut capture = Capture::new();
+
+        capture.process(&event(Modifiers::CMD, None, true));
+        capture.process(&event(Modifiers::CMD | Modifiers::SHIFT, None, true));
+        assert_eq!(capture.process(&event(Modifiers::CMD, None, false)), CaptureStep::Pending);
+        assert_eq!(
+            capture.process(&event(Modifiers::empty(), None, false)),
+            CaptureStep::Done(Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, None).unwrap())
+        );
+    }
+
+    #[test]
+    fn escape_cancels_and_plain_clicks_are_ignored() {
+        let mut capture = Capture::new();
+
+        assert_eq!(
+            capture.process(&event(Modifiers::empty(), Some(Key::MouseLeft), true)),
+            CaptureStep::Pending
+        );
+        assert_eq!(
+            capture.process(&event(Modifiers::empty(), Some(Key::Escape), true)),
+            CaptureStep::Cancelled
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Out-of-band rescue for when blocked keys leave an app unusable
+//!
+//! A consumer that blocks the wrong keys can make its own UI, and sometimes
+//! the whole desktop, impossible to use. A rescue request comes from outside
+//! the process, so it works even then: the manager suspends every hotkey,
+//! which stops all blocking, and writes its state to a file for the bug
+//! report. The app can call [`HotkeyManager::resume`](crate::HotkeyManager::resume)
+//! once things are fixed.
+//!
+//! On Unix, the request is a signal. On Windows, it's the named event
+//! `HandyKeysRescue-<pid>`, which the `rescue` example sets.
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::error::Result;
+
+/// A way to request a rescue from outside the process
+pub(crate) struct Rescue {
+    trigger: Trigger,
+    /// Where the state is written on rescue
+    dump_path: PathBuf,
+}
+
+enum Trigger {
+    /// Set by the signal handler
+    #[cfg(unix)]
+    Signal {
+        requested: std::sync::Arc<std::sync::atomic::AtomicBool>,
+        id: signal_hook::SigId,
+    },
+    /// An auto-reset event, kept as a raw handle so it can be shared between
+    /// threads
+    #[cfg(windows)]
+    NamedEvent(isize),
+}
+
+impl Rescue {
+    /// Request a rescue whenever the process receives `signal`
+    #[cfg(unix)]
+    pub fn on_signal(signal: i32, dump_path: Option<PathBuf>) -> Result<Self> {
+        let requested = std::sync::Arc::default();
+        let id = signal_hook::flag::register(signal, std::sync::Arc::clone(&requested))?;
+        Ok(Self {
+            trigger: Trigger::Signal { requested, id },
+            dump_path: dump_path.unwrap_or_else(default_dump_path),
+        })
+    }
+
+    /// Request a rescue whenever the event `HandyKeysRescue-<pid>` is set
+    #[cfg(windows)]
+    pub fn on_named_event(dump_path: Option<PathBuf>) -> Result<Self> {
+        use windows::core::HSTRING;
+        use windows::Win32::System::Threading::CreateEventW;
+
+        let name = HSTRING::from(event_name(std::process::id()));
+        let event = unsafe { CreateEventW(None, false, false, &name) }.map_err(|e| {
+            crate::error::Error::Platform(format!("failed to create rescue event: {e}"))
+        })?;
+        Ok(Self {
+            trigger: Trigger::NamedEvent(event.0 as isize),
+            dump_path: dump_path.unwrap_or_else(default_dump_path),
+        })
+    }
+
+    /// Whether a rescue was requested since the last call
+    pub fn take_request(&self) -> bool {
+        match &self.trigger {
+            #[cfg(unix)]
+            Trigger::Signal { requested, .. } => {
+                requested.swap(false, std::sync::atomic::Ordering::SeqCst)
+            }
+            #[cfg(windows)]
+            Trigger::NamedEvent(event) => {
+                use windows::Win32::Foundation::{HANDLE, WAIT_OBJECT_0};
+                use windows::Win32::System::Threading::WaitForSingleObject;
+
+                // Waiting resets the event
+                unsafe { WaitForSingleObject(HANDLE(*event as _), 0) == WAIT_OBJECT_0 }
+            }
+        }
+    }
+
+    /// Write `dump` to the dump path, replacing an earlier dump
+    ///
+    /// The file is always created anew and, on Unix, only readable by this
+    /// user, so a symlink another user planted at the path is removed rather
+    /// than followed.
+    pub fn write_dump(&self, dump: &str) -> io::Result<()> {
+        match fs::remove_file(&self.dump_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options.open(&self.dump_path)?.write_all(dump.as_bytes())
+    }
+}
+
+impl fmt::Debug for Rescue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rescue")
+            .field("dump_path", &self.dump_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Rescue {
+    fn drop(&mut self) {
+        match &self.trigger {
+            #[cfg(unix)]
+            Trigger::Signal { id, .. } => {
+                signal_hook::low_level::unregister(*id);
+            }
+            #[cfg(windows)]
+            Trigger::NamedEvent(event) => {
+                use windows::Win32::Foundation::{CloseHandle, HANDLE};
+
+                let _ = unsafe { CloseHandle(HANDLE(*event as _)) };
+            }
+        }
+    }
+}
+
+/// Name of the event that rescues the process with ID `pid` on Windows
+#[cfg(windows)]
+fn event_name(pid: u32) -> String {
+    format!("HandyKeysRescue-{pid}")
+}
+
+/// `handy-keys-rescue-<pid>.txt` in the user's runtime directory
+/// (`$XDG_RUNTIME_DIR`), or the temp directory where there is none
+fn default_dump_path() -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(std::env::temp_dir);
+    dir.join(format!("handy-keys-rescue-{}.txt", std::process::id()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signal_requests_rescue() {
+        let rescue = Rescue::on_signal(signal_hook::consts::SIGUSR2, None).unwrap();
+        assert!(!rescue.take_request());
+
+        signal_hook::low_level::raise(signal_hook::consts::SIGUSR2).unwrap();
+        assert!(rescue.take_request());
+        // Each signal is one request
+        assert!(!rescue.take_request());
+        assert!(rescue
+            .dump_path
+            .ends_with(format!("handy-keys-rescue-{}.txt", std::process::id())));
+    }
+
+    #[test]
+    fn dump_replaces_symlinks_without_following_them() {
+        let dir = std::env::temp_dir().join(format!("handy-keys-rescue-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("target.txt");
+        let dump_path = dir.join("dump.txt");
+        fs::write(&target, "keep").unwrap();
+        std::os::unix::fs::symlink(&target, &dump_path).unwrap();
+
+        let rescue = Rescue::on_signal(signal_hook::consts::SIGUSR1, Some(dump_path.clone())).unwrap();
+        rescue.write_dump("first").unwrap();
+        rescue.write_dump("second").unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "keep");
+        assert_eq!(fs::read_to_string(&dump_path).unwrap(), "second");
+        let mode = std::os::unix::fs::PermissionsExt::mode(&fs::metadata(&dump_path).unwrap().permissions());
+        assert_eq!(mode & 0o777, 0o600);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
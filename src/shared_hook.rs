@@ -0,0 +1,189 @@
+//! One OS hook shared by several managers
+//!
+//! Managers built with
+//! [`shared_listener`](crate::HotkeyManagerBuilder::shared_listener) attach to
+//! a process-wide listener instead of installing their own hooks. A thread
+//! fans its events out to every attached manager, and the hook blocks the
+//! hotkeys of each of them. The hook is removed once the last one detaches.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::listener::{BlockingHotkeys, KeyboardListener, KeyboardListenerBuilder};
+use crate::platform::state::{BlockingMembers, HotkeyGuards, LooseHotkeys};
+use crate::platform::threads::{self, Handle};
+use crate::types::KeyEvent;
+
+/// Where each attached manager receives key events, by membership id
+type Senders = Arc<Mutex<Vec<(u64, mpsc::Sender<KeyEvent>)>>>;
+
+/// The hook managers attach to, while any is attached
+static HOOK: Mutex<Weak<SharedHook>> = Mutex::new(Weak::new());
+
+/// How long the fan-out thread waits for input before checking whether it
+/// should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The shared listener and the thread fanning out its events
+struct SharedHook {
+    senders: Senders,
+    /// The hotkeys each attached manager blocks
+    members: BlockingMembers,
+    next_id: AtomicU64,
+    thread_handle: Option<Handle>,
+    running: Arc<AtomicBool>,
+}
+
+impl SharedHook {
+    /// The current hook, or a new one installed with `builder`'s settings
+    fn get_or_install(builder: KeyboardListenerBuilder) -> Result<Arc<Self>> {
+        let mut hook = HOOK.lock().map_err(|_| Error::MutexPoisoned)?;
+        if let Some(existing) = hook.upgrade() {
+            return Ok(existing);
+        }
+
+        let members = BlockingMembers::default();
+        // The listener's own set stays empty, but makes it block
+        let listener = builder
+            .blocking(BlockingHotkeys::default())
+            .members(Arc::clone(&members))
+            .build()?;
+        let senders = Senders::default();
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread_senders = Arc::clone(&senders);
+        let thread_running = Arc::clone(&running);
+        let handle = threads::spawn(&running, move || {
+            Self::fan_out(listener, &thread_senders, &thread_running);
+        })?;
+
+        let installed = Arc::new(Self {
+            senders,
+            members,
+            next_id: AtomicU64::new(0),
+            thread_handle: Some(handle),
+            running,
+        });
+        *hook = Arc::downgrade(&installed);
+        Ok(installed)
+    }
+
+    /// Pass every key event to the attached managers until stopped
+    fn fan_out(
+        listener: KeyboardListener,
+        senders: &Senders,
+        running: &AtomicBool,
+    ) {
+        while running.load(Ordering::SeqCst) {
+            match listener.recv_timeout(POLL_INTERVAL) {
+                Ok(event) => {
+                    if let Ok(mut senders) = senders.lock() {
+                        senders.retain(|(_, sender)| sender.send(event).is_ok());
+                    }
+                }
+                Err(Error::Timeout) => {}
+                // Dropping the senders tells the managers the hook is gone
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl Drop for SharedHook {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.thread_handle.take() {
+            if !handle.is_current() {
+                handle.join();
+            }
+        }
+    }
+}
+
+/// A manager's attachment to the shared hook, detaching when dropped
+pub(crate) struct Membership {
+    hook: Arc<SharedHook>,
+    id: u64,
+    events: Receiver<KeyEvent>,
+    blocking_hotkeys: BlockingHotkeys,
+}
+
+impl Membership {
+    /// Attach to the shared hook, installing it with `builder`'s settings if
+    /// no manager is attached yet
+    ///
+    /// `blocking_hotkeys`, `guards` and `loose` are blocked as for a listener
+    /// of the manager's own.
+    pub fn join(
+        builder: KeyboardListenerBuilder,
+        blocking_hotkeys: &BlockingHotkeys,
+        guards: &HotkeyGuards,
+        loose: &LooseHotkeys,
+    ) -> Result<Self> {
+        let hook = SharedHook::get_or_install(builder)?;
+        let id = hook.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, events) = mpsc::channel();
+        hook.senders.lock().map_err(|_| Error::MutexPoisoned)?.push((id, tx));
+        hook.members
+            .lock()
+            .map_err(|_| Error::MutexPoisoned)?
+            .push((Arc::clone(blocking_hotkeys), Arc::clone(guards), Arc::clone(loose)));
+        Ok(Self {
+            hook,
+            id,
+            events,
+            blocking_hotkeys: Arc::clone(blocking_hotkeys),
+        })
+    }
+
+    /// Like [`KeyboardListener::recv_timeout`]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<KeyEvent> {
+        self.events.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => Error::Timeout,
+            RecvTimeoutError::Disconnected => Error::EventLoopNotRunning,
+        })
+    }
+}
+
+impl Drop for Membership {
+    fn drop(&mut self) {
+        if let Ok(mut senders) = self.hook.senders.lock() {
+            senders.retain(|(id, _)| *id != self.id);
+        }
+        if let Ok(mut members) = self.hook.members.lock() {
+            members.retain(|(hotkeys, _, _)| !Arc::ptr_eq(hotkeys, &self.blocking_hotkeys));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::listener::Backend;
+
+    #[test]
+    fn managers_share_one_hook_until_the_last_detaches() {
+        let listener = || KeyboardListener::builder().backend(Backend::Headless);
+        let first_set = BlockingHotkeys::default();
+        let second_set = BlockingHotkeys::default();
+
+        let join = |set| {
+            Membership::join(listener(), set, &HotkeyGuards::default(), &LooseHotkeys::default())
+        };
+
+        let first = join(&first_set).unwrap();
+        let second = join(&second_set).unwrap();
+        assert!(Arc::ptr_eq(&first.hook, &second.hook));
+        assert_eq!(first.hook.members.lock().unwrap().len(), 2);
+        assert!(matches!(first.recv_timeout(Duration::from_millis(10)), Err(Error::Timeout)));
+
+        drop(first);
+        assert_eq!(second.hook.members.lock().unwrap().len(), 1);
+        assert_eq!(second.hook.senders.lock().unwrap().len(), 1);
+        drop(second);
+        assert!(HOOK.lock().unwrap().upgrade().is_none());
+    }
+}
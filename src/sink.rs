@@ -0,0 +1,95 @@
+//! Delivering events to a channel of the app's choice
+
+use std::sync::Arc;
+
+/// A channel that receives events in place of the built-in one
+///
+/// Pass one to [`HotkeyManagerBuilder::event_sink`](crate::HotkeyManagerBuilder::event_sink)
+/// or [`KeyboardListenerBuilder::event_sink`](crate::KeyboardListenerBuilder::event_sink)
+/// to receive events on the app's own channel, without a thread that
+/// forwards them. Implemented for `std::sync::mpsc::Sender`, and for the
+/// senders of crossbeam-channel and flume with the `crossbeam` and `flume`
+/// features.
+///
+/// `send` runs on the thread delivering events, often inside the OS hook, so
+/// it must not block. Events a sink can't take right away, such as those sent
+/// to a full bounded channel, are given back and dropped.
+pub trait EventSink<T>: Send + Sync {
+    /// Deliver an event, giving it back if the receiving side is gone
+    fn send(&self, event: T) -> Result<(), T>;
+}
+
+impl<T: Send + 'static> EventSink<T> for std::sync::mpsc::Sender<T> {
+    fn send(&self, event: T) -> Result<(), T> {
+        std::sync::mpsc::Sender::send(self, event).map_err(|e| e.0)
+    }
+}
+
+/// Bounded channels that are full drop the event rather than block
+#[cfg(feature = "crossbeam")]
+impl<T: Send + 'static> EventSink<T> for crossbeam_channel::Sender<T> {
+    fn send(&self, event: T) -> Result<(), T> {
+        crossbeam_channel::Sender::try_send(self, event).map_err(|e| e.into_inner())
+    }
+}
+
+/// Bounded channels that are full drop the event rather than block
+#[cfg(feature = "flume")]
+impl<T: Send + 'static> EventSink<T> for flume::Sender<T> {
+    fn send(&self, event: T) -> Result<(), T> {
+        flume::Sender::try_send(self, event).map_err(|e| e.into_inner())
+    }
+}
+
+/// An [`EventSink`] shared by the senders of a channel
+pub(crate) struct SharedSink<T>(Arc<dyn EventSink<T>>);
+
+impl<T> SharedSink<T> {
+    pub fn new(sink: impl EventSink<T> + 'static) -> Self {
+        Self(Arc::new(sink))
+    }
+
+    pub fn send(&self, event: T) -> Result<(), T> {
+        self.0.send(event)
+    }
+}
+
+impl<T> Clone for SharedSink<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> std::fmt::Debug for SharedSink<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventSink")
+    }
+}
+
+#[cfg(all(test, any(feature = "crossbeam", feature = "flume")))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// Send into a channel that can never take the event, returning how long
+    /// the send took
+    fn time_full_send(sink: impl EventSink<u32>) -> Duration {
+        let started = Instant::now();
+        assert_eq!(sink.send(1), Err(1));
+        started.elapsed()
+    }
+
+    #[cfg(feature = "crossbeam")]
+    #[test]
+    fn full_crossbeam_channel_returns_event() {
+        let (tx, _rx) = crossbeam_channel::bounded(0);
+        assert!(time_full_send(tx) < Duration::from_millis(100));
+    }
+
+    #[cfg(feature = "flume")]
+    #[test]
+    fn full_flume_channel_returns_event() {
+        let (tx, _rx) = flume::bounded(0);
+        assert!(time_full_send(tx) < Duration::from_millis(100));
+    }
+}
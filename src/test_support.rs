@@ -0,0 +1,128 @@
+//! Proptest strategies and property tests for the core types
+
+use proptest::prelude::*;
+
+use crate::types::{Hotkey, Key, Modifiers};
+
+/// Every key, for strategies to pick from
+const ALL_KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K,
+    Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V,
+    Key::W, Key::X, Key::Y, Key::Z,
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4,
+    Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10,
+    Key::F11, Key::F12, Key::F13, Key::F14, Key::F15, Key::F16, Key::F17, Key::F18, Key::F19,
+    Key::F20,
+    Key::Space, Key::Return, Key::Tab, Key::Escape, Key::Delete, Key::ForwardDelete,
+    Key::Home, Key::End, Key::PageUp, Key::PageDown,
+    Key::PrintScreen, Key::Insert, Key::Pause, Key::ContextMenu,
+    Key::LeftArrow, Key::RightArrow, Key::UpArrow, Key::DownArrow,
+    Key::Minus, Key::Equal, Key::LeftBracket, Key::RightBracket, Key::Backslash,
+    Key::Semicolon, Key::Quote, Key::Comma, Key::Period, Key::Slash, Key::Grave,
+    Key::Keypad0, Key::Keypad1, Key::Keypad2, Key::Keypad3, Key::Keypad4,
+    Key::Keypad5, Key::Keypad6, Key::Keypad7, Key::Keypad8, Key::Keypad9,
+    Key::KeypadDecimal, Key::KeypadMultiply, Key::KeypadPlus, Key::KeypadClear,
+    Key::KeypadDivide, Key::KeypadEnter, Key::KeypadMinus, Key::KeypadEquals,
+    Key::CapsLock, Key::ScrollLock, Key::NumLock,
+    Key::Yen, Key::Eisu, Key::Kana, Key::Section, Key::Ro, Key::Hangul, Key::Hanja,
+    Key::PlayPause, Key::NextTrack, Key::PreviousTrack,
+    Key::VolumeUp, Key::VolumeDown, Key::VolumeMute, Key::BrightnessUp, Key::BrightnessDown,
+    Key::MouseLeft, Key::MouseRight, Key::MouseMiddle, Key::MouseX1, Key::MouseX2,
+];
+
+pub fn arb_key() -> impl Strategy<Value = Key> {
+    prop_oneof![
+        10 => proptest::sample::select(ALL_KEYS),
+        1 => any::<u32>().prop_map(Key::Other),
+    ]
+}
+
+/// Modifiers for either side, which every hotkey format can express
+pub fn arb_modifiers() -> impl Strategy<Value = Modifiers> {
+    arb_sided_modifiers().prop_map(Modifiers::without_sides)
+}
+
+/// Modifiers with side flags, but no side bits of modifiers that aren't set
+pub fn arb_sided_modifiers() -> impl Strategy<Value = Modifiers> {
+    any::<u32>()
+        .prop_map(Modifiers::from_bits_truncate)
+        .prop_map(|modifiers| modifiers.without_sides() | modifiers.sides_of(modifiers))
+}
+
+pub fn arb_hotkey() -> impl Strategy<Value = Hotkey> {
+    (arb_modifiers(), proptest::option::of(arb_key()))
+        .prop_filter_map("empty hotkey", |(modifiers, key)| Hotkey::new(modifiers, key).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PhysicalHotkey;
+
+    proptest! {
+        #[test]
+        fn hotkey_display_parses_back(hotkey in arb_hotkey()) {
+            prop_assert_eq!(hotkey.to_string().parse::<Hotkey>().unwrap(), hotkey);
+        }
+
+        #[test]
+        fn sided_hotkey_display_parses_back(
+            modifiers in arb_sided_modifiers(),
+            key in proptest::option::of(arb_key()),
+        ) {
+            if let Ok(hotkey) = Hotkey::new(modifiers, key) {
+                prop_assert_eq!(hotkey.to_string().parse::<Hotkey>().unwrap(), hotkey);
+            }
+        }
+
+        #[test]
+        fn lowercase_string_parses_back(hotkey in arb_hotkey()) {
+            prop_assert_eq!(hotkey.to_lowercase_string().parse::<Hotkey>().unwrap(), hotkey);
+        }
+
+        #[test]
+        fn display_is_a_normal_form(hotkey in arb_hotkey()) {
+            let normalized = hotkey.to_lowercase_string().parse::<Hotkey>().unwrap().to_string();
+            prop_assert_eq!(&normalized, &hotkey.to_string());
+            let renormalized = normalized.parse::<Hotkey>().unwrap().to_string();
+            prop_assert_eq!(renormalized, normalized);
+        }
+
+        #[test]
+        fn slug_parses_back(
+            modifiers in arb_sided_modifiers(),
+            key in proptest::option::of(arb_key()),
+        ) {
+            if let Ok(hotkey) = Hotkey::new(modifiers, key) {
+                let slug = hotkey.to_slug();
+                prop_assert!(slug.bytes().all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'-')));
+                prop_assert_eq!(Hotkey::from_slug(&slug).unwrap(), hotkey);
+            }
+        }
+
+        #[test]
+        fn key_display_parses_back(key in arb_key()) {
+            prop_assert_eq!(key.to_string().parse::<Key>().unwrap(), key);
+        }
+
+        #[test]
+        fn modifiers_display_parses_back(modifiers in arb_modifiers()) {
+            prop_assert_eq!(modifiers.to_string().parse::<Modifiers>().unwrap(), modifiers);
+        }
+
+        #[test]
+        fn hotkey_serde_round_trip(hotkey in arb_hotkey()) {
+            let json = serde_json::to_string(&hotkey).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Hotkey>(&json).unwrap(), hotkey);
+        }
+
+        #[test]
+        fn physical_hotkey_serde_round_trip(hotkey in arb_hotkey()) {
+            if let Ok(physical) = hotkey.to_physical() {
+                let json = serde_json::to_string(&physical).unwrap();
+                prop_assert_eq!(serde_json::from_str::<PhysicalHotkey>(&json).unwrap(), physical);
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Testing an app's hotkey wiring without input devices
+//!
+//! [`MockManager`] matches key events with the [`MatchEngine`] of a
+//! [`HotkeyManager`](crate::HotkeyManager), but installs no OS hooks and
+//! takes its events from the test. Bind hotkeys
+//! to the app's actions the way the app does, play scripted combos, and check
+//! which actions would run.
+//!
+//! ```
+//! use handy_keys::testing::MockManager;
+//!
+//! let mut manager = MockManager::new();
+//! manager.bind("Ctrl+Shift+P".parse()?, "command palette")?;
+//! manager.bind("Ctrl+S".parse()?, "save")?;
+//!
+//! assert_eq!(manager.play("Ctrl+S Ctrl+Shift+P")?, ["save", "command palette"]);
+//! assert!(manager.play("Ctrl+P")?.is_empty());
+//! # Ok::<(), handy_keys::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::engine::MatchEngine;
+use crate::types::{Hotkey, HotkeyEvent, HotkeyId, HotkeyState, KeyEvent, Modifiers};
+
+/// A hotkey manager fed by the test, with an action bound to each hotkey
+///
+/// Time only passes through [`advance`](Self::advance), so tests don't depend
+/// on how fast they run.
+pub struct MockManager<A> {
+    engine: MatchEngine,
+    actions: HashMap<HotkeyId, A>,
+    now: Instant,
+}
+
+impl<A: Clone> MockManager<A> {
+    /// Create a manager with no hotkeys
+    pub fn new() -> Self {
+        Self {
+            engine: MatchEngine::new(),
+            actions: HashMap::new(),
+            now: Instant::now(),
+        }
+    }
+
+    /// Register `hotkey`, running `action` when it's pressed
+    ///
+    /// Fails like [`HotkeyManager::register`](crate::HotkeyManager::register)
+    /// if the hotkey is already registered.
+    pub fn bind(&mut self, hotkey: Hotkey, action: A) -> Result<HotkeyId> {
+        let id = self.engine.register(hotkey)?;
+        self.actions.insert(id, action);
+        Ok(id)
+    }
+
+    /// Process a single key event, returning the hotkey events it causes
+    pub fn feed(&mut self, event: KeyEvent) -> Vec<HotkeyEvent> {
+        self.engine.process_event_at(&event, self.now)
+    }
+
+    /// Type `hotkey`: press its modifiers one by one, then tap its key and
+    /// release the modifiers, returning the actions that would run
+    pub fn press(&mut self, hotkey: Hotkey) -> Vec<A> {
+        let events: Vec<HotkeyEvent> = keystrokes(hotkey).into_iter().flat_map(|event| self.feed(event)).collect();
+        events
+            .into_iter()
+            .filter(|event| event.state == HotkeyState::Pressed)
+            .filter_map(|event| self.actions.get(&event.id).cloned())
+            .collect()
+    }
+
+    /// Type each hotkey of a space-separated script like "Ctrl+K Ctrl+B",
+    /// returning the actions that would run
+    pub fn play(&mut self, script: &str) -> Result<Vec<A>> {
+        let hotkeys = script
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<Hotkey>>>()?;
+        Ok(hotkeys.into_iter().flat_map(|hotkey| self.press(hotkey)).collect())
+    }
+
+    /// Let `duration` pass before the next event
+    pub fn advance(&mut self, duration: Duration) {
+        self.now += duration;
+    }
+}
+
+impl<A: Clone> Default for MockManager<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The key events of typing `hotkey`, holding its modifiers on the sides it
+/// requires
+fn keystrokes(hotkey: Hotkey) -> Vec<KeyEvent> {
+    let mut events = Vec::new();
+    let mut held = Modifiers::empty();
+    let modifiers: Vec<Modifiers> = hotkey.modifiers.without_sides().iter().collect();
+    for &modifier in &modifiers {
+        held |= modifier;
+        events.push(modifier_event(held, hotkey.modifiers, modifier, true));
+    }
+    if let Some(key) = hotkey.key {
+        for is_key_down in [true, false] {
+            events.push(KeyEvent {
+                modifiers: held,
+                key: Some(key),
+                is_key_down,
+                changed_modifier: None,
+                sides: hotkey.modifiers.sides_of(held),
+                hook: None,
+            });
+        }
+    }
+    for &modifier in modifiers.iter().rev() {
+        held -= modifier;
+        events.push(modifier_event(held, hotkey.modifiers, modifier, false));
+    }
+    events
+}
+
+fn modifier_event(
+    modifiers: Modifiers,
+    sides: Modifiers,
+    changed: Modifiers,
+    is_key_down: bool,
+) -> KeyEvent {
+    KeyEvent {
+        modifiers,
+        key: None,
+        is_key_down,
+        changed_modifier: Some(changed),
+        sides: sides.sides_of(modifiers),
+        hook: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Key;
+
+    #[test]
+    fn modifier_only_and_repeated_hotkeys() {
+        let mut manager = MockManager::new();
+        manager.bind("Cmd+Shift".parse().unwrap(), 1).unwrap();
+        manager.bind("Cmd+Shift+K".parse().unwrap(), 2).unwrap();
+        assert!(manager.bind("Shift+Cmd+K".parse().unwrap(), 3).is_err());
+
+        assert_eq!(manager.play("Cmd+Shift+K Cmd+Shift+K").unwrap(), [1, 2, 1, 2]);
+        assert_eq!(manager.press(Hotkey::new(Modifiers::empty(), Key::K).unwrap()), Vec::<i32>::new());
+        assert!(manager.play("Cmd+Nope").is_err());
+    }
+
+    #[test]
+    fn sided_hotkeys() {
+        let mut manager = MockManager::new();
+        manager.bind("RightAlt+K".parse().unwrap(), "right").unwrap();
+        manager.bind("Alt+K".parse().unwrap(), "either").unwrap();
+
+        assert_eq!(manager.play("RightAlt+K LeftAlt+K Alt+K").unwrap(), ["right", "either", "either"]);
+    }
+}
@@ -0,0 +1,279 @@
+//! Conversion of hotkeys to menu accelerators
+
+use crate::error::{Error, Result};
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+use super::modifiers::Modifiers;
+
+/// A macOS menu item key equivalent
+///
+/// Set `key` as the `NSMenuItem`'s `keyEquivalent` and `modifier_mask` as its
+/// `keyEquivalentModifierMask`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyEquivalent {
+    pub key: String,
+    /// `NSEventModifierFlags` bits
+    pub modifier_mask: usize,
+}
+
+/// `NSEventModifierFlags` values
+mod ns_flags {
+    pub const SHIFT: usize = 1 << 17;
+    pub const CONTROL: usize = 1 << 18;
+    pub const OPTION: usize = 1 << 19;
+    pub const COMMAND: usize = 1 << 20;
+    pub const NUMERIC_PAD: usize = 1 << 21;
+    pub const FUNCTION: usize = 1 << 23;
+}
+
+impl Hotkey {
+    /// Format hotkey as an accelerator string for `muda` and `tao` menus
+    ///
+    /// Returns an error for modifier-only hotkeys, mouse buttons, the Fn
+    /// modifier and modifier sides, which menus can't show.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::Hotkey;
+    ///
+    /// let hotkey: Hotkey = "Cmd+Shift+K".parse().unwrap();
+    /// assert_eq!(hotkey.to_accelerator_string().unwrap(), "Shift+Super+KeyK");
+    /// ```
+    pub fn to_accelerator_string(&self) -> Result<String> {
+        let key = self.accelerator_key()?;
+        if self.modifiers.contains(Modifiers::FN) || !self.modifiers.side_bits().is_empty() {
+            return Err(Error::NotAnAccelerator(self.to_string()));
+        }
+        // `muda` and `tao` name keys by their `KeyboardEvent.code`
+        let code = key.to_web_code().ok_or_else(|| Error::NotAnAccelerator(self.to_string()))?;
+
+        let mut parts = Vec::new();
+        if self.modifiers.contains(Modifiers::CTRL) {
+            parts.push("Ctrl");
+        }
+        if self.modifiers.contains(Modifiers::OPT) {
+            parts.push("Alt");
+        }
+        if self.modifiers.contains(Modifiers::SHIFT) {
+            parts.push("Shift");
+        }
+        if self.modifiers.contains(Modifiers::CMD) {
+            parts.push("Super");
+        }
+        parts.push(code);
+        Ok(parts.join("+"))
+    }
+
+    /// Convert hotkey to a macOS menu item key equivalent
+    ///
+    /// Returns an error for modifier-only hotkeys, mouse buttons and lock keys.
+    pub fn to_key_equivalent(&self) -> Result<KeyEquivalent> {
+        let key = self.accelerator_key()?;
+        let (equivalent, numeric_pad) =
+            key_equivalent(key).ok_or_else(|| Error::NotAnAccelerator(self.to_string()))?;
+
+        let mut modifier_mask = 0;
+        for (modifier, flag) in [
+            (Modifiers::SHIFT, ns_flags::SHIFT),
+            (Modifiers::CTRL, ns_flags::CONTROL),
+            (Modifiers::OPT, ns_flags::OPTION),
+            (Modifiers::CMD, ns_flags::COMMAND),
+            (Modifiers::FN, ns_flags::FUNCTION),
+        ] {
+            if self.modifiers.contains(modifier) {
+                modifier_mask |= flag;
+            }
+        }
+        if numeric_pad {
+            modifier_mask |= ns_flags::NUMERIC_PAD;
+        }
+
+        Ok(KeyEquivalent {
+            key: equivalent,
+            modifier_mask,
+        })
+    }
+
+    /// Convert hotkey to a Win32 accelerator table entry for command `cmd`
+    ///
+    /// Returns an error for modifier-only hotkeys, mouse buttons and hotkeys
+    /// using the Windows key, which accelerator tables don't support.
+    #[cfg(target_os = "windows")]
+    pub fn to_win32_accel(
+        &self,
+        cmd: u16,
+    ) -> Result<windows::Win32::UI::WindowsAndMessaging::ACCEL> {
+        use windows::Win32::UI::WindowsAndMessaging::{ACCEL, FALT, FCONTROL, FSHIFT, FVIRTKEY};
+
+        let key = self.accelerator_key()?;
+        if self.modifiers.intersects(Modifiers::CMD | Modifiers::FN) {
+            return Err(Error::NotAnAccelerator(self.to_string()));
+        }
+        let vk_code = crate::platform::windows::key_to_vk(key)
+            .ok_or_else(|| Error::NotAnAccelerator(self.to_string()))?;
+
+        let mut flags = FVIRTKEY;
+        if self.modifiers.contains(Modifiers::SHIFT) {
+            flags |= FSHIFT;
+        }
+        if self.modifiers.contains(Modifiers::CTRL) {
+            flags |= FCONTROL;
+        }
+        if self.modifiers.contains(Modifiers::OPT) {
+            flags |= FALT;
+        }
+
+        Ok(ACCEL {
+            fVirt: flags,
+            key: vk_code,
+            cmd,
+        })
+    }
+
+    /// The key of a hotkey used as an accelerator, which menus require
+    fn accelerator_key(&self) -> Result<Key> {
+        self.key
+            .ok_or_else(|| Error::NotAnAccelerator(self.to_string()))
+    }
+}
+
+/// The `NSMenuItem` key equivalent of a key, and whether it's on the numeric pad
+fn key_equivalent(key: Key) -> Option<(String, bool)> {
+    // Function key characters from NSEvent.h
+    let function_key = |code: u32| char::from_u32(code).map(String::from);
+
+    let equivalent = match key {
+        Key::Keypad0 => return Some(("0".into(), true)),
+        Key::Keypad1 => return Some(("1".into(), true)),
+        Key::Keypad2 => return Some(("2".into(), true)),
+        Key::Keypad3 => return Some(("3".into(), true)),
+        Key::Keypad4 => return Some(("4".into(), true)),
+        Key::Keypad5 => return Some(("5".into(), true)),
+        Key::Keypad6 => return Some(("6".into(), true)),
+        Key::Keypad7 => return Some(("7".into(), true)),
+        Key::Keypad8 => return Some(("8".into(), true)),
+        Key::Keypad9 => return Some(("9".into(), true)),
+        Key::KeypadDecimal => return Some((".".into(), true)),
+        Key::KeypadMultiply => return Some(("*".into(), true)),
+        Key::KeypadPlus => return Some(("+".into(), true)),
+        Key::KeypadDivide => return Some(("/".into(), true)),
+        Key::KeypadMinus => return Some(("-".into(), true)),
+        Key::KeypadEquals => return Some(("=".into(), true)),
+        Key::KeypadEnter => return Some(("\u{3}".into(), true)),
+        Key::KeypadClear => return function_key(0xF739).map(|s| (s, true)),
+
+        Key::Space => " ".into(),
+        Key::Return => "\r".into(),
+        Key::Tab => "\t".into(),
+        Key::Escape => "\u{1b}".into(),
+        Key::Delete => "\u{8}".into(),
+        Key::Minus => "-".into(),
+        Key::Equal => "=".into(),
+        Key::LeftBracket => "[".into(),
+        Key::RightBracket => "]".into(),
+        Key::Backslash => "\\".into(),
+        Key::Semicolon => ";".into(),
+        Key::Quote => "'".into(),
+        Key::Comma => ",".into(),
+        Key::Period => ".".into(),
+        Key::Slash => "/".into(),
+        Key::Grave => "`".into(),
+        Key::Yen => "¥".into(),
+        Key::Section => "§".into(),
+
+        Key::UpArrow => function_key(0xF700)?,
+        Key::DownArrow => function_key(0xF701)?,
+        Key::LeftArrow => function_key(0xF702)?,
+        Key::RightArrow => function_key(0xF703)?,
+        Key::ForwardDelete => function_key(0xF728)?,
+        Key::Home => function_key(0xF729)?,
+        Key::End => function_key(0xF72B)?,
+        Key::PageUp => function_key(0xF72C)?,
+        Key::PageDown => function_key(0xF72D)?,
+        Key::Insert => function_key(0xF727)?,
+        Key::PrintScreen => function_key(0xF72E)?,
+        Key::Pause => function_key(0xF730)?,
+        Key::ContextMenu => function_key(0xF735)?,
+
+        Key::F1 => function_key(0xF704)?,
+        Key::F2 => function_key(0xF705)?,
+        Key::F3 => function_key(0xF706)?,
+        Key::F4 => function_key(0xF707)?,
+        Key::F5 => function_key(0xF708)?,
+        Key::F6 => function_key(0xF709)?,
+        Key::F7 => function_key(0xF70A)?,
+        Key::F8 => function_key(0xF70B)?,
+        Key::F9 => function_key(0xF70C)?,
+        Key::F10 => function_key(0xF70D)?,
+        Key::F11 => function_key(0xF70E)?,
+        Key::F12 => function_key(0xF70F)?,
+        Key::F13 => function_key(0xF710)?,
+        Key::F14 => function_key(0xF711)?,
+        Key::F15 => function_key(0xF712)?,
+        Key::F16 => function_key(0xF713)?,
+        Key::F17 => function_key(0xF714)?,
+        Key::F18 => function_key(0xF715)?,
+        Key::F19 => function_key(0xF716)?,
+        Key::F20 => function_key(0xF717)?,
+
+        // Letters and digits: the lowercase character, with Shift in the mask
+        _ => {
+            let name = key.to_web_code()?;
+            let c = name.strip_prefix("Key").or_else(|| name.strip_prefix("Digit"))?;
+            c.to_lowercase()
+        }
+    };
+    Some((equivalent, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accelerator_string() {
+        let hotkey = Hotkey::new(Modifiers::CTRL | Modifiers::OPT, Key::Num1).unwrap();
+        assert_eq!(hotkey.to_accelerator_string().unwrap(), "Ctrl+Alt+Digit1");
+
+        let hotkey = Hotkey::new(Modifiers::empty(), Key::F5).unwrap();
+        assert_eq!(hotkey.to_accelerator_string().unwrap(), "F5");
+    }
+
+    #[test]
+    fn non_accelerators_are_rejected() {
+        let modifier_only = Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, None).unwrap();
+        let mouse = Hotkey::new(Modifiers::CMD, Key::MouseX1).unwrap();
+        let with_fn = Hotkey::new(Modifiers::FN, Key::K).unwrap();
+        let sided: Hotkey = "RightAlt+K".parse().unwrap();
+
+        for hotkey in [modifier_only, mouse, with_fn, sided] {
+            assert!(matches!(
+                hotkey.to_accelerator_string(),
+                Err(Error::NotAnAccelerator(_))
+            ));
+        }
+        assert!(modifier_only.to_key_equivalent().is_err());
+        assert!(mouse.to_key_equivalent().is_err());
+    }
+
+    #[test]
+    fn key_equivalent_letter() {
+        let hotkey = Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, Key::K).unwrap();
+        let equivalent = hotkey.to_key_equivalent().unwrap();
+
+        assert_eq!(equivalent.key, "k");
+        assert_eq!(equivalent.modifier_mask, ns_flags::COMMAND | ns_flags::SHIFT);
+    }
+
+    #[test]
+    fn key_equivalent_special_keys() {
+        let hotkey = Hotkey::new(Modifiers::CMD, Key::UpArrow).unwrap();
+        assert_eq!(hotkey.to_key_equivalent().unwrap().key, "\u{F700}");
+
+        let hotkey = Hotkey::new(Modifiers::CMD, Key::Keypad1).unwrap();
+        let equivalent = hotkey.to_key_equivalent().unwrap();
+        assert_eq!(equivalent.key, "1");
+        assert_eq!(equivalent.modifier_mask, ns_flags::COMMAND | ns_flags::NUMERIC_PAD);
+    }
+}
@@ -0,0 +1,154 @@
+//! Canonical forms of hotkeys that are pressed the same way
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+
+/// Keys the listener never reports on this platform, and the key it reports
+/// for them instead
+const PLATFORM_SYNONYMS: &[(Key, Key)] = &[
+    // Macs report Korean keys as the Japanese ones in the same positions, and
+    // have Clear where other keyboards have Num Lock
+    #[cfg(target_os = "macos")]
+    (Key::Hangul, Key::Kana),
+    #[cfg(target_os = "macos")]
+    (Key::Hanja, Key::Eisu),
+    #[cfg(target_os = "macos")]
+    (Key::NumLock, Key::KeypadClear),
+];
+
+/// Keypad keys and the main keyboard keys typing the same character
+const KEYPAD_KEYS: &[(Key, Key)] = &[
+    (Key::Keypad0, Key::Num0), (Key::Keypad1, Key::Num1), (Key::Keypad2, Key::Num2),
+    (Key::Keypad3, Key::Num3), (Key::Keypad4, Key::Num4), (Key::Keypad5, Key::Num5),
+    (Key::Keypad6, Key::Num6), (Key::Keypad7, Key::Num7), (Key::Keypad8, Key::Num8),
+    (Key::Keypad9, Key::Num9),
+    (Key::KeypadDecimal, Key::Period),
+    (Key::KeypadDivide, Key::Slash),
+    (Key::KeypadMinus, Key::Minus),
+    (Key::KeypadEquals, Key::Equal),
+    (Key::KeypadEnter, Key::Return),
+];
+
+/// Extra equivalences for [`Hotkey::canonicalize_with`]
+///
+/// Each one merges hotkeys that the listener tells apart, so two hotkeys with
+/// the same canonical form may still fire differently. They suit dedup of
+/// user configs where, say, `Ctrl+Keypad1` and `Ctrl+1` are meant as the
+/// same binding.
+///
+/// # Examples
+/// ```
+/// use handy_keys::{CanonicalizeOptions, Hotkey};
+///
+/// let options = CanonicalizeOptions::new().ignore_sides().keypad_as_main_keys();
+/// let a: Hotkey = "LeftCtrl+Keypad1".parse()?;
+/// let b: Hotkey = "Ctrl+1".parse()?;
+/// assert_eq!(a.canonicalize_with(options), b.canonicalize_with(options));
+/// # Ok::<(), handy_keys::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CanonicalizeOptions {
+    ignore_sides: bool,
+    keypad_as_main_keys: bool,
+}
+
+impl CanonicalizeOptions {
+    /// Options that only apply [`Hotkey::canonicalize`]'s equivalences
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop modifier sides, so `LeftCtrl+K` becomes `Ctrl+K`
+    pub fn ignore_sides(mut self) -> Self {
+        self.ignore_sides = true;
+        self
+    }
+
+    /// Replace keypad keys with the main keyboard key typing the same
+    /// character, so `Keypad1` becomes `1` and `KeypadEnter` becomes `Return`
+    ///
+    /// Keypad keys typing a shifted character, `Keypad*` and `Keypad+`, and
+    /// `KeypadClear` are kept.
+    pub fn keypad_as_main_keys(mut self) -> Self {
+        self.keypad_as_main_keys = true;
+        self
+    }
+}
+
+impl Hotkey {
+    /// The canonical form of this hotkey among those pressed the same way
+    ///
+    /// Parsing already maps alias spellings and platform names like Win,
+    /// Super and Meta to one [`Hotkey`] value. This also replaces keys the
+    /// platform reports as another key, e.g. `Hangul` with `Kana` on macOS,
+    /// so hotkeys that fire the same compare equal. The canonical string is
+    /// the canonical hotkey's `Display`.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::Hotkey;
+    ///
+    /// let a: Hotkey = "super+shift+esc".parse()?;
+    /// let b: Hotkey = "Shift + Win + Escape".parse()?;
+    /// assert_eq!(a.canonicalize(), b.canonicalize());
+    /// assert_eq!(a.canonicalize().to_string(), "Shift+Cmd+Escape");
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn canonicalize(&self) -> Hotkey {
+        self.canonicalize_with(CanonicalizeOptions::new())
+    }
+
+    /// The canonical form of this hotkey, also merging the hotkeys `options`
+    /// treats as equivalent
+    pub fn canonicalize_with(&self, options: CanonicalizeOptions) -> Hotkey {
+        let replaced = |table: &[(Key, Key)], key: Key| {
+            table.iter().find(|(from, _)| *from == key).map_or(key, |&(_, to)| to)
+        };
+        let mut key = self.key.map(|key| replaced(PLATFORM_SYNONYMS, key));
+        if options.keypad_as_main_keys {
+            key = key.map(|key| replaced(KEYPAD_KEYS, key));
+        }
+        let modifiers = if options.ignore_sides { self.modifiers.without_sides() } else { self.modifiers };
+        Hotkey { modifiers, key }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::arb_hotkey;
+    use proptest::prelude::*;
+
+    fn canonical(s: &str, options: CanonicalizeOptions) -> Hotkey {
+        s.parse::<Hotkey>().unwrap().canonicalize_with(options)
+    }
+
+    #[test]
+    fn options_merge_more_hotkeys() {
+        let all = CanonicalizeOptions::new().ignore_sides().keypad_as_main_keys();
+        assert_eq!(canonical("RCtrl+KeypadEnter", all), canonical("Ctrl+Return", all));
+        assert_eq!(canonical("Shift+Keypad-", all), canonical("Shift+Minus", all));
+        assert_ne!(canonical("Keypad*", all), canonical("8", all));
+
+        let default = CanonicalizeOptions::new();
+        assert_ne!(canonical("RCtrl+K", default), canonical("Ctrl+K", default));
+        assert_ne!(canonical("Keypad1", default), canonical("1", default));
+    }
+
+    #[test]
+    fn platform_synonyms() {
+        let hangul: Hotkey = "Ctrl+Hangul".parse().unwrap();
+        let kana: Hotkey = "Ctrl+Kana".parse().unwrap();
+        assert_eq!(hangul.canonicalize() == kana.canonicalize(), cfg!(target_os = "macos"));
+    }
+
+    proptest! {
+        #[test]
+        fn canonicalize_is_idempotent(hotkey in arb_hotkey()) {
+            let options = CanonicalizeOptions::new().ignore_sides().keypad_as_main_keys();
+            let canonical = hotkey.canonicalize_with(options);
+            prop_assert_eq!(canonical.canonicalize_with(options), canonical);
+            prop_assert_eq!(hotkey.canonicalize().canonicalize(), hotkey.canonicalize());
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Overlaps between hotkeys that make one fire while typing another
+
+use serde::{Deserialize, Serialize};
+
+use super::hotkey::{Hotkey, HotkeyId};
+
+/// How a hotkey overlaps with a registered one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ConflictKind {
+    /// Both are the same combo, so registering would fail
+    Duplicate,
+    /// The hotkey is modifier-only and is pressed on the way to typing the
+    /// registered one (e.g. `Cmd+Shift` before `Cmd+Shift+K`)
+    Prefix,
+    /// The registered hotkey is modifier-only and is pressed on the way to
+    /// typing this one (e.g. `Cmd` before `Cmd+K`)
+    Extends,
+}
+
+/// A registered hotkey that overlaps with another, see
+/// [`HotkeyManager::check_conflicts`](crate::HotkeyManager::check_conflicts)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Conflict {
+    /// ID of the registered hotkey
+    pub id: HotkeyId,
+    /// The registered combo it overlaps with
+    pub hotkey: Hotkey,
+    pub kind: ConflictKind,
+}
+
+impl Hotkey {
+    /// How this hotkey overlaps with `other`, if at all
+    ///
+    /// A modifier-only hotkey fires as soon as its modifiers are held, so it
+    /// overlaps with every other hotkey that includes those modifiers. Hotkeys
+    /// with a key only overlap if they are the same combo.
+    pub fn conflict_with(&self, other: &Hotkey) -> Option<ConflictKind> {
+        if self == other {
+            Some(ConflictKind::Duplicate)
+        } else if self.key.is_none() && other.modifiers.contains(self.modifiers) {
+            Some(ConflictKind::Prefix)
+        } else if other.key.is_none() && self.modifiers.contains(other.modifiers) {
+            Some(ConflictKind::Extends)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conflict(a: &str, b: &str) -> Option<ConflictKind> {
+        a.parse::<Hotkey>().unwrap().conflict_with(&b.parse().unwrap())
+    }
+
+    #[test]
+    fn conflict_kinds() {
+        assert_eq!(conflict("Cmd+K", "Cmd+K"), Some(ConflictKind::Duplicate));
+        assert_eq!(conflict("Cmd+Shift", "Cmd+Shift"), Some(ConflictKind::Duplicate));
+        assert_eq!(conflict("Cmd+Shift", "Cmd+Shift+K"), Some(ConflictKind::Prefix));
+        assert_eq!(conflict("Cmd", "Cmd+Shift"), Some(ConflictKind::Prefix));
+        assert_eq!(conflict("Cmd+K", "Cmd"), Some(ConflictKind::Extends));
+        assert_eq!(conflict("Cmd+Shift+Opt", "Cmd+Shift"), Some(ConflictKind::Extends));
+    }
+
+    #[test]
+    fn unrelated_hotkeys_dont_conflict() {
+        assert_eq!(conflict("Cmd+K", "Cmd+J"), None);
+        assert_eq!(conflict("Cmd+K", "Cmd+Shift+K"), None);
+        assert_eq!(conflict("Cmd+Shift", "Ctrl+K"), None);
+        assert_eq!(conflict("Cmd+Shift", "Cmd+K"), None);
+    }
+}
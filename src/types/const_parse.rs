@@ -0,0 +1,214 @@
+//! Hotkey parsing at compile time, for the [`hotkey!`](crate::hotkey) macro
+
+use super::hotkey::Hotkey;
+use super::key::{Key, KEY_NAMES};
+use super::modifiers::{Modifiers, MODIFIER_NAMES, SIDED, SIDE_PREFIXES, SIDE_SEPARATORS};
+
+/// Parse a hotkey string literal at compile time
+///
+/// Accepts the same names as `str::parse` does without a
+/// [default parser](crate::HotkeyParser::set_default), except
+/// `Other(<code>)` and `Raw(<code>)` keys. A typo fails the build instead of surfacing as a
+/// parse error at runtime.
+///
+/// # Examples
+/// ```
+/// use handy_keys::{hotkey, Hotkey, Key, Modifiers};
+///
+/// const PALETTE: Hotkey = hotkey!("Cmd+Shift+P");
+/// assert_eq!(PALETTE, Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, Key::P).unwrap());
+/// ```
+///
+/// ```compile_fail
+/// let typo = handy_keys::hotkey!("Cmd+Shfit+P");
+/// ```
+#[macro_export]
+macro_rules! hotkey {
+    ($hotkey:literal) => {{
+        const HOTKEY: $crate::Hotkey = $crate::Hotkey::parse_const($hotkey);
+        HOTKEY
+    }};
+}
+
+impl Hotkey {
+    /// Parse a hotkey in a const context, panicking if it's invalid
+    ///
+    /// Behind the [`hotkey!`](crate::hotkey) macro, which is usually more
+    /// convenient. Evaluated in a `const`, the panic is a compile error.
+    pub const fn parse_const(s: &str) -> Hotkey {
+        let s = s.as_bytes();
+        let mut modifiers = Modifiers::empty();
+        let mut key: Option<Key> = None;
+
+        let mut from = 0;
+        while from <= s.len() {
+            let (start, end, next) = part_at(s, from);
+            from = next;
+            if start == end {
+                continue;
+            }
+            if let Some(modifier) = modifier_named(s, start, end) {
+                modifiers = modifiers.union(modifier);
+                continue;
+            }
+
+            // "Keypad+" is split into "Keypad" and an empty part
+            let found = if eq_name(s, start, end, b"keypad") && from <= s.len() && is_empty_part(s, from) {
+                from = part_at(s, from).2;
+                Some(Key::KeypadPlus)
+            } else {
+                key_named(s, start, end)
+            };
+            match found {
+                None => panic!("unknown key or modifier in hotkey"),
+                Some(_) if key.is_some() => panic!("hotkey has more than one key"),
+                Some(found) => key = Some(found),
+            }
+        }
+
+        if modifiers.is_empty() && key.is_none() {
+            panic!("hotkey cannot be empty");
+        }
+        Hotkey { modifiers, key }
+    }
+}
+
+/// The part of `s` starting at `from`, trimmed, and where the next part
+/// starts (past the end of `s` if this is the last)
+const fn part_at(s: &[u8], from: usize) -> (usize, usize, usize) {
+    let mut end = from;
+    while end < s.len() && s[end] != b'+' {
+        end += 1;
+    }
+    let next = end + 1;
+    let mut start = from;
+    while start < end && s[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    while end > start && s[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    (start, end, next)
+}
+
+const fn is_empty_part(s: &[u8], from: usize) -> bool {
+    let (start, end, _) = part_at(s, from);
+    start == end
+}
+
+/// Whether `s[start..end]` is `name`, ignoring ASCII case
+const fn eq_name(s: &[u8], start: usize, end: usize, name: &[u8]) -> bool {
+    if end - start != name.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < name.len() {
+        if s[start + i].to_ascii_lowercase() != name[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn key_named(s: &[u8], start: usize, end: usize) -> Option<Key> {
+    let mut i = 0;
+    while i < KEY_NAMES.len() {
+        let (name, key) = KEY_NAMES[i];
+        if eq_name(s, start, end, name.as_bytes()) {
+            return Some(key);
+        }
+        i += 1;
+    }
+    None
+}
+
+const fn unsided_modifier_named(s: &[u8], start: usize, end: usize) -> Option<Modifiers> {
+    let mut i = 0;
+    while i < MODIFIER_NAMES.len() {
+        let (name, modifier) = MODIFIER_NAMES[i];
+        if eq_name(s, start, end, name.as_bytes()) {
+            return Some(modifier);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Like `Modifiers::parse_single`
+const fn modifier_named(s: &[u8], start: usize, end: usize) -> Option<Modifiers> {
+    if let Some(modifier) = unsided_modifier_named(s, start, end) {
+        return Some(modifier);
+    }
+    let mut p = 0;
+    while p < SIDE_PREFIXES.len() {
+        let (prefix, is_left) = SIDE_PREFIXES[p];
+        let prefix = prefix.as_bytes();
+        if end - start > prefix.len() && eq_name(s, start, start + prefix.len(), prefix) {
+            let mut rest = start + prefix.len();
+            while rest < end && is_side_separator(s[rest]) {
+                rest += 1;
+            }
+            if let Some(modifier) = unsided_modifier_named(s, rest, end) {
+                let mut i = 0;
+                while i < SIDED.len() {
+                    let (sided, left, right, _) = SIDED[i];
+                    if sided.bits() == modifier.bits() {
+                        return Some(if is_left { left } else { right });
+                    }
+                    i += 1;
+                }
+            }
+        }
+        p += 1;
+    }
+    None
+}
+
+const fn is_side_separator(byte: u8) -> bool {
+    let mut i = 0;
+    while i < SIDE_SEPARATORS.len() {
+        if SIDE_SEPARATORS[i] as u32 == byte as u32 {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::arb_hotkey;
+    use proptest::prelude::*;
+
+    #[test]
+    fn macro_matches_runtime_parsing() {
+        let cases = [
+            (hotkey!("Cmd+Shift+K"), "Cmd+Shift+K"),
+            (hotkey!("ctrl + alt + space"), "Ctrl+Alt+Space"),
+            (hotkey!("Ctrl+Keypad+"), "Ctrl+Keypad+"),
+            (hotkey!("RightAlt+LCtrl+F1"), "RightAlt+LCtrl+F1"),
+            (hotkey!("Hyper"), "Hyper"),
+            (hotkey!("§"), "Section"),
+        ];
+        for (parsed, s) in cases {
+            assert_eq!(parsed, s.parse::<Hotkey>().unwrap(), "{s}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "more than one key")]
+    fn rejects_two_keys() {
+        Hotkey::parse_const("Ctrl+A+B");
+    }
+
+    proptest! {
+        #[test]
+        fn displayed_hotkeys_parse_the_same(hotkey in arb_hotkey()) {
+            prop_assume!(!matches!(hotkey.key, Some(Key::Other(_))));
+            let s = hotkey.to_string();
+            prop_assert_eq!(Hotkey::parse_const(&s), s.parse::<Hotkey>().unwrap());
+        }
+    }
+}
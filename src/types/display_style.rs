@@ -0,0 +1,239 @@
+//! Platform conventions for showing hotkeys to users
+
+use serde::{Deserialize, Serialize};
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+use super::modifiers::Modifiers;
+
+/// How to format a hotkey for display, by platform convention
+///
+/// Each platform has its own modifier order and names, e.g. "⌃⌥⇧⌘" on macOS
+/// and "Ctrl+Alt+Shift+Win" on Windows. Apps showing shortcuts for another
+/// platform than the one they run on, such as a docs page, pick the style
+/// explicitly; [`Hotkey::to_symbol_string`] uses the current platform's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum DisplayStyle {
+    /// This crate's own format, as `Display` writes it, e.g. "Ctrl+Opt+Cmd+K"
+    ///
+    /// Keeps modifier sides and parses back to the same hotkey.
+    #[default]
+    Standard,
+    /// The standard format with Cmd, Ctrl, Opt and Shift together written as
+    /// "Hyper", e.g. "Hyper+K", for apps whose users think of Caps Lock remaps
+    /// that way
+    ///
+    /// Like [`Standard`](Self::Standard), keeps modifier sides and parses back
+    /// to the same hotkey. Modifiers with a side are spelled out.
+    Hyper,
+    /// macOS menu glyphs, e.g. "⌃⌥⇧⌘K" and "⌘↩"
+    MacOS,
+    /// Windows menu names, e.g. "Ctrl+Alt+Shift+Win+K" and "Ctrl+Del"
+    Windows,
+    /// Linux desktop names, e.g. "Ctrl+Alt+Shift+Super+K"
+    Linux,
+}
+
+impl DisplayStyle {
+    /// The style of the platform this runs on
+    pub fn platform() -> Self {
+        if cfg!(target_os = "macos") {
+            DisplayStyle::MacOS
+        } else if cfg!(target_os = "windows") {
+            DisplayStyle::Windows
+        } else {
+            DisplayStyle::Linux
+        }
+    }
+
+    /// Modifier names in the order this style writes them, and what goes
+    /// between names
+    fn modifiers(self) -> (&'static [(Modifiers, &'static str)], &'static str) {
+        match self {
+            DisplayStyle::Standard | DisplayStyle::Hyper => (&[], "+"),
+            DisplayStyle::MacOS => (
+                &[
+                    (Modifiers::FN, "fn"),
+                    (Modifiers::CTRL, "⌃"),
+                    (Modifiers::OPT, "⌥"),
+                    (Modifiers::SHIFT, "⇧"),
+                    (Modifiers::CMD, "⌘"),
+                ],
+                "",
+            ),
+            DisplayStyle::Windows => (
+                &[
+                    (Modifiers::CTRL, "Ctrl"),
+                    (Modifiers::OPT, "Alt"),
+                    (Modifiers::SHIFT, "Shift"),
+                    (Modifiers::CMD, "Win"),
+                ],
+                "+",
+            ),
+            DisplayStyle::Linux => (
+                &[
+                    (Modifiers::CTRL, "Ctrl"),
+                    (Modifiers::OPT, "Alt"),
+                    (Modifiers::SHIFT, "Shift"),
+                    (Modifiers::CMD, "Super"),
+                ],
+                "+",
+            ),
+        }
+    }
+
+    /// The name this style uses for a key, where it differs from [`Key`]'s
+    /// `Display`
+    fn key_name(self, key: Key) -> Option<&'static str> {
+        match self {
+            DisplayStyle::Standard | DisplayStyle::Hyper => None,
+            DisplayStyle::MacOS => mac_key_symbol(key),
+            DisplayStyle::Windows | DisplayStyle::Linux => pc_key_name(key),
+        }
+    }
+}
+
+impl Modifiers {
+    /// Format modifiers in the order and with the names of `style`
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::{DisplayStyle, Modifiers};
+    ///
+    /// let modifiers = Modifiers::CMD | Modifiers::CTRL;
+    /// assert_eq!(modifiers.to_styled_string(DisplayStyle::MacOS), "⌃⌘");
+    /// assert_eq!(modifiers.to_styled_string(DisplayStyle::Windows), "Ctrl+Win");
+    /// ```
+    pub fn to_styled_string(&self, style: DisplayStyle) -> String {
+        match style {
+            DisplayStyle::Standard => return self.to_string(),
+            DisplayStyle::Hyper if self.contains(Modifiers::HYPER) && self.side_bits().is_empty() => {
+                let rest = *self - Modifiers::HYPER;
+                return if rest.is_empty() { "Hyper".to_string() } else { format!("Hyper+{rest}") };
+            }
+            DisplayStyle::Hyper => return self.to_string(),
+            _ => {}
+        }
+        let (names, separator) = style.modifiers();
+        names
+            .iter()
+            .filter(|(modifier, _)| self.contains(*modifier))
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+impl Hotkey {
+    /// Format hotkey in the modifier order and with the modifier and key
+    /// names of `style`
+    ///
+    /// Modifiers come in the style's order whatever order the hotkey was
+    /// written in. Platform styles drop modifier sides, and the Windows and Linux styles drop Fn, which
+    /// their menus don't show.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::{DisplayStyle, Hotkey};
+    ///
+    /// let hotkey: Hotkey = "Cmd+Shift+Return".parse().unwrap();
+    /// assert_eq!(hotkey.to_styled_string(DisplayStyle::MacOS), "⇧⌘↩");
+    /// assert_eq!(hotkey.to_styled_string(DisplayStyle::Windows), "Shift+Win+Enter");
+    /// ```
+    pub fn to_styled_string(&self, style: DisplayStyle) -> String {
+        if style == DisplayStyle::Standard {
+            return self.to_string();
+        }
+        let mut result = self.modifiers.to_styled_string(style);
+        if let Some(key) = self.key {
+            if !result.is_empty() {
+                result.push_str(style.modifiers().1);
+            }
+            match style.key_name(key) {
+                Some(name) => result.push_str(name),
+                None => result.push_str(&key.to_string()),
+            }
+        }
+        result
+    }
+}
+
+/// The glyph macOS menus use for a key, if it has one
+fn mac_key_symbol(key: Key) -> Option<&'static str> {
+    match key {
+        Key::Return => Some("↩"),
+        Key::Tab => Some("⇥"),
+        Key::Escape => Some("⎋"),
+        Key::Delete => Some("⌫"),
+        Key::ForwardDelete => Some("⌦"),
+        Key::LeftArrow => Some("←"),
+        Key::RightArrow => Some("→"),
+        Key::UpArrow => Some("↑"),
+        Key::DownArrow => Some("↓"),
+        Key::PageUp => Some("⇞"),
+        Key::PageDown => Some("⇟"),
+        Key::Home => Some("↖"),
+        Key::End => Some("↘"),
+        Key::Space => Some("␣"),
+        Key::KeypadEnter => Some("⌤"),
+        Key::KeypadClear => Some("⌧"),
+        Key::CapsLock => Some("⇪"),
+        _ => None,
+    }
+}
+
+/// The name Windows and Linux menus use for a key, if it differs from
+/// [`Key`]'s `Display`
+fn pc_key_name(key: Key) -> Option<&'static str> {
+    match key {
+        Key::Return | Key::KeypadEnter => Some("Enter"),
+        Key::Escape => Some("Esc"),
+        Key::Delete => Some("Backspace"),
+        Key::ForwardDelete => Some("Del"),
+        Key::Insert => Some("Ins"),
+        Key::PageUp => Some("PgUp"),
+        Key::PageDown => Some("PgDn"),
+        Key::PrintScreen => Some("PrtSc"),
+        Key::ContextMenu => Some("Menu"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styled_strings() {
+        let hotkey: Hotkey = "Cmd+Shift+Opt+K".parse().unwrap();
+        let cases = [
+            (DisplayStyle::Standard, "Opt+Shift+Cmd+K"),
+            (DisplayStyle::MacOS, "⌥⇧⌘K"),
+            (DisplayStyle::Windows, "Alt+Shift+Win+K"),
+            (DisplayStyle::Linux, "Alt+Shift+Super+K"),
+        ];
+        for (style, expected) in cases {
+            assert_eq!(hotkey.to_styled_string(style), expected, "{style:?}");
+        }
+
+        let hyper: Hotkey = "Hyper+Fn+K".parse().unwrap();
+        assert_eq!(hyper.to_styled_string(DisplayStyle::Standard), "Ctrl+Opt+Shift+Cmd+Fn+K");
+        assert_eq!(hyper.to_styled_string(DisplayStyle::Hyper), "Hyper+Fn+K");
+        assert_eq!(hyper.to_styled_string(DisplayStyle::Hyper).parse::<Hotkey>().unwrap(), hyper);
+        assert_eq!(hotkey.to_styled_string(DisplayStyle::Hyper), "Opt+Shift+Cmd+K");
+
+        let sided: Hotkey = "RCtrl+Fn+Escape".parse().unwrap();
+        assert_eq!(sided.to_styled_string(DisplayStyle::Standard), "RightCtrl+Fn+Escape");
+        assert_eq!(sided.to_styled_string(DisplayStyle::MacOS), "fn⌃⎋");
+        assert_eq!(sided.to_styled_string(DisplayStyle::Linux), "Ctrl+Esc");
+        let sided_hyper: Hotkey = "Hyper+RCmd+K".parse().unwrap();
+        assert_eq!(sided_hyper.to_styled_string(DisplayStyle::Hyper), "Ctrl+Opt+Shift+RightCmd+K");
+    }
+
+    #[test]
+    fn styled_modifier_only() {
+        let hotkey = Hotkey::new(Modifiers::SHIFT | Modifiers::CMD, None).unwrap();
+        assert_eq!(hotkey.to_styled_string(DisplayStyle::Windows), "Shift+Win");
+        assert_eq!(hotkey.to_styled_string(DisplayStyle::MacOS), "⇧⌘");
+    }
+}
@@ -3,9 +3,11 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use crate::error::{Error, Result};
 
+use super::display_style::DisplayStyle;
 use super::key::Key;
 use super::modifiers::Modifiers;
 
@@ -54,6 +56,45 @@ impl Hotkey {
         Ok(Self { modifiers, key })
     }
 
+    /// Create a hotkey from the character its key types in the active
+    /// keyboard layout
+    ///
+    /// [`Key`]s name keys by their position on a US keyboard, so `Key::A` is
+    /// the key labeled "Q" on AZERTY and "Ф" on a Russian layout. This finds
+    /// the key that types `c` instead, adding Shift to the modifiers if the
+    /// layout needs it. Letters match either case.
+    ///
+    /// The layout is read when this is called, so hotkeys created this way
+    /// should be created again when the user switches layouts. On Windows the
+    /// key is the one reported with the default [`KeyMapping::Layout`]. Where
+    /// the layout can't be read, such as with the headless backend, on Linux
+    /// without an X server, or off the main thread on macOS, a US layout is
+    /// assumed.
+    ///
+    /// Returns [`Error::NoKeyForChar`] if no key types `c`, or only with
+    /// modifiers other than Shift, as for AltGr characters.
+    ///
+    /// [`KeyMapping::Layout`]: crate::KeyMapping::Layout
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use handy_keys::{Hotkey, Modifiers};
+    ///
+    /// // Ctrl plus whichever key types "é", e.g. "2" on French AZERTY
+    /// let hotkey = Hotkey::from_char('é', Modifiers::CTRL)?;
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn from_char(c: char, modifiers: Modifiers) -> Result<Self> {
+        let mut lowercase = c.to_lowercase();
+        let c = match (lowercase.next(), lowercase.next()) {
+            (Some(lower), None) => lower,
+            _ => c,
+        };
+        let (key, shift) = crate::platform::layout::key_for_char(c).ok_or(Error::NoKeyForChar(c))?;
+        let modifiers = if shift { modifiers | Modifiers::SHIFT } else { modifiers };
+        Self::new(modifiers, key)
+    }
+
     /// Format hotkey as lowercase string (e.g., "cmd+shift+k")
     ///
     /// This is useful for compatibility with systems that expect lowercase
@@ -103,6 +144,20 @@ impl Hotkey {
             parts.join("+")
         }
     }
+
+    /// Format hotkey the way the platform shows shortcuts in menus
+    ///
+    /// - macOS: modifier and key glyphs (e.g., "⌃⇧⌘K", "⌘↩", "⌥⌫")
+    /// - Windows: "Ctrl+Alt+Shift+Win+K", with the usual short names of keys
+    ///   like "Esc", "Del" and "PgUp"
+    /// - Linux: the same, with "Super" for the Cmd modifier
+    ///
+    /// Modifiers always come in the order shown, whatever order the hotkey
+    /// was written in. Use [`to_styled_string`](Self::to_styled_string) to
+    /// format for another platform.
+    pub fn to_symbol_string(&self) -> String {
+        self.to_styled_string(DisplayStyle::platform())
+    }
 }
 
 impl fmt::Display for Hotkey {
@@ -135,13 +190,43 @@ impl FromStr for Hotkey {
     /// let hotkey: Hotkey = "F1".parse().unwrap();  // Key only
     /// let hotkey: Hotkey = "Cmd+Shift".parse().unwrap();  // Modifiers only
     /// ```
+    ///
+    /// Also accepts the aliases of the parser set with
+    /// [`HotkeyParser::set_default`](crate::HotkeyParser::set_default).
     fn from_str(s: &str) -> Result<Self> {
+        super::parser::parse_default(s)
+    }
+}
+
+impl Hotkey {
+    /// Whether `combo` is this hotkey, possibly with extra modifiers held
+    pub(crate) fn matches_loosely(&self, combo: &Hotkey) -> bool {
+        self.key == combo.key && combo.modifiers.contains(self.modifiers)
+    }
+
+    /// Parse a hotkey, looking up modifier names with `modifier` and key names
+    /// with `key`
+    pub(crate) fn parse_with(
+        s: &str,
+        modifier: impl Fn(&str) -> Option<Modifiers>,
+        key_named: impl Fn(&str) -> Result<Key>,
+    ) -> Result<Self> {
         let s = s.trim();
         if s.is_empty() {
             return Err(Error::EmptyHotkey);
         }
 
-        let parts: Vec<&str> = s.split('+').map(|p| p.trim()).collect();
+        let mut parts: Vec<&str> = s.split('+').map(|p| p.trim()).collect();
+
+        // "Keypad+" is split into "Keypad" and an empty part; rejoin them
+        let mut i = 0;
+        while i + 1 < parts.len() {
+            if parts[i].eq_ignore_ascii_case("keypad") && parts[i + 1].is_empty() {
+                parts[i] = "keypad+";
+                parts.remove(i + 1);
+            }
+            i += 1;
+        }
 
         let mut modifiers = Modifiers::empty();
         let mut key: Option<Key> = None;
@@ -152,7 +237,7 @@ impl FromStr for Hotkey {
             }
 
             // Try to parse as modifier first
-            if let Some(m) = Modifiers::parse_single(part) {
+            if let Some(m) = modifier(part) {
                 modifiers |= m;
             } else {
                 // Not a modifier, must be a key
@@ -162,7 +247,7 @@ impl FromStr for Hotkey {
                         part
                     )));
                 }
-                key = Some(Key::from_str(part)?);
+                key = Some(key_named(part)?);
             }
         }
 
@@ -177,17 +262,41 @@ pub enum HotkeyState {
     Pressed,
     /// The hotkey was just released
     Released,
+    /// Every key of a released hotkey, its modifiers included, is back up
+    ///
+    /// Follows [`Released`](Self::Released), which comes as soon as the combo
+    /// is broken, e.g. while Shift of Cmd+Shift+K is still held. Only sent by
+    /// managers built with
+    /// [`all_released_events`](crate::HotkeyManagerBuilder::all_released_events).
+    AllReleased,
 }
 
 /// Event emitted when a hotkey is pressed or released
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HotkeyEvent {
     pub id: HotkeyId,
     pub state: HotkeyState,
+    /// The combo that triggered the event, which for a hotkey registered with
+    /// aliases is the alias that was pressed
+    pub hotkey: Hotkey,
+    /// Position of `hotkey` among the hotkey's combos, as returned by
+    /// `HotkeyManager::get_aliases`, so 0 for the main combo
+    pub alias: usize,
+    /// The hotkey's label, if it was given one with
+    /// `HotkeyManager::set_label`
+    #[serde(default)]
+    pub label: Option<Arc<str>>,
+}
+
+impl HotkeyEvent {
+    /// Whether the hotkey was triggered through an alias rather than its main combo
+    pub fn is_alias(&self) -> bool {
+        self.alias > 0
+    }
 }
 
 /// Event emitted during key recording
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct KeyEvent {
     pub modifiers: Modifiers,
     pub key: Option<Key>,
@@ -195,10 +304,46 @@ pub struct KeyEvent {
     /// For modifier-only events (FlagsChanged), indicates which modifier changed.
     /// `None` for regular key events.
     pub changed_modifier: Option<Modifiers>,
+    /// Side flags (like [`Modifiers::R_OPT`]) of the held modifiers whose key
+    /// the platform reports, empty where it doesn't
+    #[serde(default)]
+    pub sides: Modifiers,
+    /// What the listener's OS hook decided for a key down, `None` for key
+    /// ups and events that didn't come from a hook
+    #[serde(default)]
+    pub hook: Option<HookDecision>,
+}
+
+/// What a listener's OS hook decided for a key down, see [`KeyEvent::hook`]
+///
+/// The hook runs before the event is matched, and a
+/// [`MatchEngine`](crate::MatchEngine) follows its decision, so hotkeys are
+/// matched the way the hook saw them even if the settings change in between.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HookDecision {
+    /// Whether the key was blocked, `None` if the listener doesn't block
+    ///
+    /// A key that was let through doesn't press a hotkey that blocks, which
+    /// would otherwise happen if the hotkey was registered after the hook saw
+    /// the key.
+    pub blocked: Option<bool>,
+    /// Whether an app excluded with `HotkeyManager::set_excluded_apps` was
+    /// frontmost, in which case no hotkey is pressed
+    pub excluded: bool,
 }
 
 impl KeyEvent {
+    /// The held modifiers with their side flags, to match against hotkeys
+    /// that require a side
+    pub fn sided_modifiers(&self) -> Modifiers {
+        self.modifiers | self.sides
+    }
+
     /// Convert this key event to a hotkey definition
+    ///
+    /// The hotkey works with either side of each modifier. Use
+    /// [`sided_modifiers`](Self::sided_modifiers) for one that requires the
+    /// sides held.
     pub fn as_hotkey(&self) -> Result<Hotkey> {
         Hotkey::new(self.modifiers, self.key)
     }
@@ -283,4 +428,37 @@ mod tests {
         // Invalid: no modifiers and no key
         assert!(Hotkey::new(Modifiers::empty(), None).is_err());
     }
+
+    #[test]
+    fn hotkey_symbol_string() {
+        let symbols = |s: &str| s.parse::<Hotkey>().unwrap().to_symbol_string();
+        let hotkey = Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, Key::K).unwrap();
+        let modifier_only = Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, None).unwrap();
+
+        #[cfg(target_os = "macos")]
+        {
+            assert_eq!(hotkey.to_symbol_string(), "⇧⌘K");
+            assert_eq!(modifier_only.to_symbol_string(), "⌃⇧");
+            assert_eq!(symbols("Shift+Cmd+Return"), "⇧⌘↩");
+            assert_eq!(symbols("Opt+Backspace"), "⌥⌫");
+        }
+        #[cfg(not(target_os = "macos"))]
+        {
+            let cmd = if cfg!(target_os = "windows") { "Win" } else { "Super" };
+            assert_eq!(hotkey.to_symbol_string(), format!("Shift+{cmd}+K"));
+            assert_eq!(modifier_only.to_symbol_string(), "Ctrl+Shift");
+            assert_eq!(symbols("Shift+Ctrl+Return"), "Ctrl+Shift+Enter");
+            assert_eq!(symbols("Alt+Backspace"), "Alt+Backspace");
+            assert_eq!(symbols("Ctrl+Alt+Del"), "Ctrl+Alt+Del");
+        }
+    }
+
+    #[test]
+    fn parse_keypad_plus() {
+        let hotkey: Hotkey = "Ctrl+Keypad+".parse().unwrap();
+        assert_eq!(hotkey, Hotkey::new(Modifiers::CTRL, Key::KeypadPlus).unwrap());
+
+        let hotkey: Hotkey = "keypad+ + shift".parse().unwrap();
+        assert_eq!(hotkey, Hotkey::new(Modifiers::SHIFT, Key::KeypadPlus).unwrap());
+    }
 }
@@ -0,0 +1,128 @@
+//! Recorded input sequences (macros)
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::hotkey::KeyEvent;
+
+/// A single step of a macro: an input event and the delay before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MacroStep {
+    /// Time elapsed since the previous step (zero for the first step)
+    pub delay: Duration,
+    /// The key or mouse event
+    pub event: KeyEvent,
+}
+
+/// A sequence of timed key and mouse events
+///
+/// Macros are usually captured with [`MacroRecorder`] or
+/// [`KeyboardListener::record_macro`](crate::KeyboardListener::record_macro),
+/// and can be serialized for storage in a config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Macro {
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    /// Create an empty macro
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an event to the macro
+    pub fn push(&mut self, delay: Duration, event: KeyEvent) {
+        self.steps.push(MacroStep { delay, event });
+    }
+
+    /// Number of steps in the macro
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns `true` if the macro has no steps
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Total time the macro takes to play back
+    pub fn duration(&self) -> Duration {
+        self.steps.iter().map(|step| step.delay).sum()
+    }
+}
+
+/// Builds a [`Macro`] from a live stream of key events
+///
+/// Delays are measured between successive calls to [`record`](Self::record).
+#[derive(Debug, Default)]
+pub struct MacroRecorder {
+    recorded: Macro,
+    last_event: Option<Instant>,
+}
+
+impl MacroRecorder {
+    /// Create a new recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an event, timestamped now
+    pub fn record(&mut self, event: KeyEvent) {
+        self.record_at(Instant::now(), event);
+    }
+
+    /// Record an event with an explicit timestamp
+    pub fn record_at(&mut self, at: Instant, event: KeyEvent) {
+        let delay = self
+            .last_event
+            .map(|last| at.saturating_duration_since(last))
+            .unwrap_or(Duration::ZERO);
+        self.last_event = Some(at);
+        self.recorded.push(delay, event);
+    }
+
+    /// Finish recording and return the macro
+    pub fn finish(self) -> Macro {
+        self.recorded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Key, Modifiers};
+
+    fn key_down(key: Key) -> KeyEvent {
+        KeyEvent {
+            modifiers: Modifiers::empty(),
+            key: Some(key),
+            is_key_down: true,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        }
+    }
+
+    #[test]
+    fn recorder_measures_delays_between_events() {
+        let start = Instant::now();
+        let mut recorder = MacroRecorder::new();
+        recorder.record_at(start, key_down(Key::A));
+        recorder.record_at(start + Duration::from_millis(30), key_down(Key::B));
+        recorder.record_at(start + Duration::from_millis(50), key_down(Key::C));
+
+        let recorded = recorder.finish();
+        assert_eq!(recorded.len(), 3);
+        assert_eq!(recorded.steps[0].delay, Duration::ZERO);
+        assert_eq!(recorded.steps[1].delay, Duration::from_millis(30));
+        assert_eq!(recorded.steps[2].delay, Duration::from_millis(20));
+        assert_eq!(recorded.duration(), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn empty_macro() {
+        let recorded = MacroRecorder::new().finish();
+        assert!(recorded.is_empty());
+        assert_eq!(recorded.duration(), Duration::ZERO);
+    }
+}
@@ -33,6 +33,12 @@ pub enum Key {
     End,
     PageUp,
     PageDown,
+    PrintScreen,
+    Insert,
+    /// Pause/Break
+    Pause,
+    /// Menu key, which opens the context menu
+    ContextMenu,
 
     // Arrow keys
     LeftArrow,
@@ -70,6 +76,38 @@ pub enum Key {
     ScrollLock,
     NumLock,
 
+    // International keys
+    /// Yen key on Japanese (JIS) keyboards
+    Yen,
+    /// Eisu (alphanumeric) key on Japanese keyboards
+    Eisu,
+    /// Kana key on Japanese keyboards
+    Kana,
+    /// Extra key on ISO keyboards ("§" on Mac, next to left Shift elsewhere)
+    Section,
+    /// Ro key on Japanese (JIS) keyboards, left of right Shift
+    Ro,
+    /// Hangul (han/yeong) key on Korean keyboards
+    ///
+    /// Macs report it as [`Key::Kana`], which sits in the same position.
+    Hangul,
+    /// Hanja key on Korean keyboards
+    ///
+    /// Macs report it as [`Key::Eisu`], which sits in the same position.
+    Hanja,
+
+    // Media keys
+    PlayPause,
+    NextTrack,
+    PreviousTrack,
+    VolumeUp,
+    VolumeDown,
+    VolumeMute,
+    /// Display brightness up, only reported on Linux
+    BrightnessUp,
+    /// Display brightness down, only reported on Linux
+    BrightnessDown,
+
     // Mouse buttons
     MouseLeft,
     MouseRight,
@@ -78,6 +116,16 @@ pub enum Key {
     MouseX1,
     /// Extra button 2 (often "forward" on mice with side buttons)
     MouseX2,
+
+    /// A key with no variant of its own, identified by its platform keycode
+    ///
+    /// The code is a macOS virtual keycode, a Windows virtual-key code, or an
+    /// X11 keycode on Linux, so hotkeys using it only work on the platform
+    /// that reported it. Listeners deliver every key they can't name this
+    /// way, such as the extra keys of macro keyboards, so these can be
+    /// recorded and registered like any other. Parses from `Other(<code>)`,
+    /// or `Raw(<code>)`, with the code in hex or decimal.
+    Other(u32),
 }
 
 impl fmt::Display for Key {
@@ -149,6 +197,10 @@ impl fmt::Display for Key {
             Key::End => write!(f, "End"),
             Key::PageUp => write!(f, "PageUp"),
             Key::PageDown => write!(f, "PageDown"),
+            Key::PrintScreen => write!(f, "PrintScreen"),
+            Key::Insert => write!(f, "Insert"),
+            Key::Pause => write!(f, "Pause"),
+            Key::ContextMenu => write!(f, "ContextMenu"),
             Key::LeftArrow => write!(f, "Left"),
             Key::RightArrow => write!(f, "Right"),
             Key::UpArrow => write!(f, "Up"),
@@ -185,152 +237,490 @@ impl fmt::Display for Key {
             Key::CapsLock => write!(f, "CapsLock"),
             Key::ScrollLock => write!(f, "ScrollLock"),
             Key::NumLock => write!(f, "NumLock"),
+            Key::Yen => write!(f, "Yen"),
+            Key::Eisu => write!(f, "Eisu"),
+            Key::Kana => write!(f, "Kana"),
+            Key::Section => write!(f, "Section"),
+            Key::Ro => write!(f, "Ro"),
+            Key::Hangul => write!(f, "Hangul"),
+            Key::Hanja => write!(f, "Hanja"),
+            Key::PlayPause => write!(f, "PlayPause"),
+            Key::NextTrack => write!(f, "NextTrack"),
+            Key::PreviousTrack => write!(f, "PreviousTrack"),
+            Key::VolumeUp => write!(f, "VolumeUp"),
+            Key::VolumeDown => write!(f, "VolumeDown"),
+            Key::VolumeMute => write!(f, "VolumeMute"),
+            Key::BrightnessUp => write!(f, "BrightnessUp"),
+            Key::BrightnessDown => write!(f, "BrightnessDown"),
             Key::MouseLeft => write!(f, "MouseLeft"),
             Key::MouseRight => write!(f, "MouseRight"),
             Key::MouseMiddle => write!(f, "MouseMiddle"),
             Key::MouseX1 => write!(f, "MouseX1"),
             Key::MouseX2 => write!(f, "MouseX2"),
+            Key::Other(code) => write!(f, "Other({code:#X})"),
         }
     }
 }
 
+/// Every key name `FromStr` accepts besides `Other(<code>)` and
+/// `Raw(<code>)`, lowercase
+///
+/// Also used by [`Hotkey::parse_const`](super::Hotkey::parse_const), so both
+/// accept the same names.
+pub(crate) const KEY_NAMES: &[(&str, Key)] = &[
+    // Letters
+    ("a", Key::A),
+    ("b", Key::B),
+    ("c", Key::C),
+    ("d", Key::D),
+    ("e", Key::E),
+    ("f", Key::F),
+    ("g", Key::G),
+    ("h", Key::H),
+    ("i", Key::I),
+    ("j", Key::J),
+    ("k", Key::K),
+    ("l", Key::L),
+    ("m", Key::M),
+    ("n", Key::N),
+    ("o", Key::O),
+    ("p", Key::P),
+    ("q", Key::Q),
+    ("r", Key::R),
+    ("s", Key::S),
+    ("t", Key::T),
+    ("u", Key::U),
+    ("v", Key::V),
+    ("w", Key::W),
+    ("x", Key::X),
+    ("y", Key::Y),
+    ("z", Key::Z),
+
+    // Numbers
+    ("0", Key::Num0),
+    ("num0", Key::Num0),
+    ("1", Key::Num1),
+    ("num1", Key::Num1),
+    ("2", Key::Num2),
+    ("num2", Key::Num2),
+    ("3", Key::Num3),
+    ("num3", Key::Num3),
+    ("4", Key::Num4),
+    ("num4", Key::Num4),
+    ("5", Key::Num5),
+    ("num5", Key::Num5),
+    ("6", Key::Num6),
+    ("num6", Key::Num6),
+    ("7", Key::Num7),
+    ("num7", Key::Num7),
+    ("8", Key::Num8),
+    ("num8", Key::Num8),
+    ("9", Key::Num9),
+    ("num9", Key::Num9),
+
+    // Function keys
+    ("f1", Key::F1),
+    ("f2", Key::F2),
+    ("f3", Key::F3),
+    ("f4", Key::F4),
+    ("f5", Key::F5),
+    ("f6", Key::F6),
+    ("f7", Key::F7),
+    ("f8", Key::F8),
+    ("f9", Key::F9),
+    ("f10", Key::F10),
+    ("f11", Key::F11),
+    ("f12", Key::F12),
+    ("f13", Key::F13),
+    ("f14", Key::F14),
+    ("f15", Key::F15),
+    ("f16", Key::F16),
+    ("f17", Key::F17),
+    ("f18", Key::F18),
+    ("f19", Key::F19),
+    ("f20", Key::F20),
+
+    // Special keys
+    ("space", Key::Space),
+    (" ", Key::Space),
+    ("return", Key::Return),
+    ("enter", Key::Return),
+    ("tab", Key::Tab),
+    ("escape", Key::Escape),
+    ("esc", Key::Escape),
+    ("delete", Key::Delete),
+    ("backspace", Key::Delete),
+    ("forwarddelete", Key::ForwardDelete),
+    ("del", Key::ForwardDelete),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("printscreen", Key::PrintScreen),
+    ("prtsc", Key::PrintScreen),
+    ("print", Key::PrintScreen),
+    ("insert", Key::Insert),
+    ("ins", Key::Insert),
+    ("pause", Key::Pause),
+    ("break", Key::Pause),
+    ("contextmenu", Key::ContextMenu),
+    ("menu", Key::ContextMenu),
+    ("apps", Key::ContextMenu),
+
+    // Arrow keys
+    ("left", Key::LeftArrow),
+    ("leftarrow", Key::LeftArrow),
+    ("right", Key::RightArrow),
+    ("rightarrow", Key::RightArrow),
+    ("up", Key::UpArrow),
+    ("uparrow", Key::UpArrow),
+    ("down", Key::DownArrow),
+    ("downarrow", Key::DownArrow),
+
+    // Punctuation and symbols
+    ("-", Key::Minus),
+    ("minus", Key::Minus),
+    ("=", Key::Equal),
+    ("equal", Key::Equal),
+    ("equals", Key::Equal),
+    ("[", Key::LeftBracket),
+    ("leftbracket", Key::LeftBracket),
+    ("]", Key::RightBracket),
+    ("rightbracket", Key::RightBracket),
+    ("\\", Key::Backslash),
+    ("backslash", Key::Backslash),
+    (";", Key::Semicolon),
+    ("semicolon", Key::Semicolon),
+    ("'", Key::Quote),
+    ("quote", Key::Quote),
+    (",", Key::Comma),
+    ("comma", Key::Comma),
+    (".", Key::Period),
+    ("period", Key::Period),
+    ("/", Key::Slash),
+    ("slash", Key::Slash),
+    ("`", Key::Grave),
+    ("grave", Key::Grave),
+    ("backtick", Key::Grave),
+
+    // Keypad
+    ("keypad0", Key::Keypad0),
+    ("keypad1", Key::Keypad1),
+    ("keypad2", Key::Keypad2),
+    ("keypad3", Key::Keypad3),
+    ("keypad4", Key::Keypad4),
+    ("keypad5", Key::Keypad5),
+    ("keypad6", Key::Keypad6),
+    ("keypad7", Key::Keypad7),
+    ("keypad8", Key::Keypad8),
+    ("keypad9", Key::Keypad9),
+    ("keypad.", Key::KeypadDecimal),
+    ("keypaddecimal", Key::KeypadDecimal),
+    ("keypad*", Key::KeypadMultiply),
+    ("keypadmultiply", Key::KeypadMultiply),
+    ("keypad+", Key::KeypadPlus),
+    ("keypadplus", Key::KeypadPlus),
+    ("keypadclear", Key::KeypadClear),
+    ("keypad/", Key::KeypadDivide),
+    ("keypaddivide", Key::KeypadDivide),
+    ("keypadenter", Key::KeypadEnter),
+    ("keypad-", Key::KeypadMinus),
+    ("keypadminus", Key::KeypadMinus),
+    ("keypad=", Key::KeypadEquals),
+    ("keypadequals", Key::KeypadEquals),
+
+    // Lock keys
+    ("capslock", Key::CapsLock),
+    ("caps", Key::CapsLock),
+    ("scrolllock", Key::ScrollLock),
+    ("scroll", Key::ScrollLock),
+    ("numlock", Key::NumLock),
+
+    // International keys
+    ("yen", Key::Yen),
+    ("intlyen", Key::Yen),
+    ("¥", Key::Yen),
+    ("eisu", Key::Eisu),
+    ("英数", Key::Eisu),
+    ("kana", Key::Kana),
+    ("かな", Key::Kana),
+    ("section", Key::Section),
+    ("intlbackslash", Key::Section),
+    ("§", Key::Section),
+    ("ro", Key::Ro),
+    ("intlro", Key::Ro),
+    ("hangul", Key::Hangul),
+    ("hangeul", Key::Hangul),
+    ("한/영", Key::Hangul),
+    ("hanja", Key::Hanja),
+    ("한자", Key::Hanja),
+
+    // Media keys
+    ("playpause", Key::PlayPause),
+    ("mediaplaypause", Key::PlayPause),
+    ("nexttrack", Key::NextTrack),
+    ("mediatracknext", Key::NextTrack),
+    ("medianexttrack", Key::NextTrack),
+    ("previoustrack", Key::PreviousTrack),
+    ("prevtrack", Key::PreviousTrack),
+    ("mediatrackprevious", Key::PreviousTrack),
+    ("volumeup", Key::VolumeUp),
+    ("audiovolumeup", Key::VolumeUp),
+    ("volumedown", Key::VolumeDown),
+    ("audiovolumedown", Key::VolumeDown),
+    ("volumemute", Key::VolumeMute),
+    ("mute", Key::VolumeMute),
+    ("audiovolumemute", Key::VolumeMute),
+    ("brightnessup", Key::BrightnessUp),
+    ("brightnessdown", Key::BrightnessDown),
+
+    // Mouse buttons
+    ("mouseleft", Key::MouseLeft),
+    ("leftclick", Key::MouseLeft),
+    ("lmb", Key::MouseLeft),
+    ("mouse1", Key::MouseLeft),
+    ("mouseright", Key::MouseRight),
+    ("rightclick", Key::MouseRight),
+    ("rmb", Key::MouseRight),
+    ("mouse2", Key::MouseRight),
+    ("mousemiddle", Key::MouseMiddle),
+    ("middleclick", Key::MouseMiddle),
+    ("mmb", Key::MouseMiddle),
+    ("mouse3", Key::MouseMiddle),
+    ("mousex1", Key::MouseX1),
+    ("mouse4", Key::MouseX1),
+    ("back", Key::MouseX1),
+    ("xbutton1", Key::MouseX1),
+    ("mousex2", Key::MouseX2),
+    ("mouse5", Key::MouseX2),
+    ("forward", Key::MouseX2),
+    ("xbutton2", Key::MouseX2),
+];
+
 impl FromStr for Key {
     type Err = Error;
 
     /// Parse a key from its string representation (case-insensitive)
     fn from_str(s: &str) -> Result<Self> {
         let s = s.trim();
-        match s.to_lowercase().as_str() {
-            // Letters
-            "a" => Ok(Key::A),
-            "b" => Ok(Key::B),
-            "c" => Ok(Key::C),
-            "d" => Ok(Key::D),
-            "e" => Ok(Key::E),
-            "f" => Ok(Key::F),
-            "g" => Ok(Key::G),
-            "h" => Ok(Key::H),
-            "i" => Ok(Key::I),
-            "j" => Ok(Key::J),
-            "k" => Ok(Key::K),
-            "l" => Ok(Key::L),
-            "m" => Ok(Key::M),
-            "n" => Ok(Key::N),
-            "o" => Ok(Key::O),
-            "p" => Ok(Key::P),
-            "q" => Ok(Key::Q),
-            "r" => Ok(Key::R),
-            "s" => Ok(Key::S),
-            "t" => Ok(Key::T),
-            "u" => Ok(Key::U),
-            "v" => Ok(Key::V),
-            "w" => Ok(Key::W),
-            "x" => Ok(Key::X),
-            "y" => Ok(Key::Y),
-            "z" => Ok(Key::Z),
-
-            // Numbers
-            "0" | "num0" => Ok(Key::Num0),
-            "1" | "num1" => Ok(Key::Num1),
-            "2" | "num2" => Ok(Key::Num2),
-            "3" | "num3" => Ok(Key::Num3),
-            "4" | "num4" => Ok(Key::Num4),
-            "5" | "num5" => Ok(Key::Num5),
-            "6" | "num6" => Ok(Key::Num6),
-            "7" | "num7" => Ok(Key::Num7),
-            "8" | "num8" => Ok(Key::Num8),
-            "9" | "num9" => Ok(Key::Num9),
-
-            // Function keys
-            "f1" => Ok(Key::F1),
-            "f2" => Ok(Key::F2),
-            "f3" => Ok(Key::F3),
-            "f4" => Ok(Key::F4),
-            "f5" => Ok(Key::F5),
-            "f6" => Ok(Key::F6),
-            "f7" => Ok(Key::F7),
-            "f8" => Ok(Key::F8),
-            "f9" => Ok(Key::F9),
-            "f10" => Ok(Key::F10),
-            "f11" => Ok(Key::F11),
-            "f12" => Ok(Key::F12),
-            "f13" => Ok(Key::F13),
-            "f14" => Ok(Key::F14),
-            "f15" => Ok(Key::F15),
-            "f16" => Ok(Key::F16),
-            "f17" => Ok(Key::F17),
-            "f18" => Ok(Key::F18),
-            "f19" => Ok(Key::F19),
-            "f20" => Ok(Key::F20),
-
-            // Special keys
-            "space" | " " => Ok(Key::Space),
-            "return" | "enter" => Ok(Key::Return),
-            "tab" => Ok(Key::Tab),
-            "escape" | "esc" => Ok(Key::Escape),
-            "delete" | "backspace" => Ok(Key::Delete),
-            "forwarddelete" | "del" => Ok(Key::ForwardDelete),
-            "home" => Ok(Key::Home),
-            "end" => Ok(Key::End),
-            "pageup" => Ok(Key::PageUp),
-            "pagedown" => Ok(Key::PageDown),
-
-            // Arrow keys
-            "left" | "leftarrow" => Ok(Key::LeftArrow),
-            "right" | "rightarrow" => Ok(Key::RightArrow),
-            "up" | "uparrow" => Ok(Key::UpArrow),
-            "down" | "downarrow" => Ok(Key::DownArrow),
-
-            // Punctuation and symbols
-            "-" | "minus" => Ok(Key::Minus),
-            "=" | "equal" | "equals" => Ok(Key::Equal),
-            "[" | "leftbracket" => Ok(Key::LeftBracket),
-            "]" | "rightbracket" => Ok(Key::RightBracket),
-            "\\" | "backslash" => Ok(Key::Backslash),
-            ";" | "semicolon" => Ok(Key::Semicolon),
-            "'" | "quote" => Ok(Key::Quote),
-            "," | "comma" => Ok(Key::Comma),
-            "." | "period" => Ok(Key::Period),
-            "/" | "slash" => Ok(Key::Slash),
-            "`" | "grave" | "backtick" => Ok(Key::Grave),
-
-            // Keypad
-            "keypad0" => Ok(Key::Keypad0),
-            "keypad1" => Ok(Key::Keypad1),
-            "keypad2" => Ok(Key::Keypad2),
-            "keypad3" => Ok(Key::Keypad3),
-            "keypad4" => Ok(Key::Keypad4),
-            "keypad5" => Ok(Key::Keypad5),
-            "keypad6" => Ok(Key::Keypad6),
-            "keypad7" => Ok(Key::Keypad7),
-            "keypad8" => Ok(Key::Keypad8),
-            "keypad9" => Ok(Key::Keypad9),
-            "keypad." | "keypaddecimal" => Ok(Key::KeypadDecimal),
-            "keypad*" | "keypadmultiply" => Ok(Key::KeypadMultiply),
-            "keypad+" | "keypadplus" => Ok(Key::KeypadPlus),
-            "keypadclear" => Ok(Key::KeypadClear),
-            "keypad/" | "keypaddivide" => Ok(Key::KeypadDivide),
-            "keypadenter" => Ok(Key::KeypadEnter),
-            "keypad-" | "keypadminus" => Ok(Key::KeypadMinus),
-            "keypad=" | "keypadequals" => Ok(Key::KeypadEquals),
-
-            // Lock keys
-            "capslock" | "caps" => Ok(Key::CapsLock),
-            "scrolllock" | "scroll" => Ok(Key::ScrollLock),
-            "numlock" => Ok(Key::NumLock),
-
-            // Mouse buttons
-            "mouseleft" | "leftclick" | "lmb" | "mouse1" => Ok(Key::MouseLeft),
-            "mouseright" | "rightclick" | "rmb" | "mouse2" => Ok(Key::MouseRight),
-            "mousemiddle" | "middleclick" | "mmb" | "mouse3" => Ok(Key::MouseMiddle),
-            "mousex1" | "mouse4" | "back" | "xbutton1" => Ok(Key::MouseX1),
-            "mousex2" | "mouse5" | "forward" | "xbutton2" => Ok(Key::MouseX2),
-
-            _ => Err(Error::UnknownKey(s.to_string())),
-        }
+        let name = s.to_lowercase();
+        KEY_NAMES
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, key)| *key)
+            .or_else(|| parse_other(&name))
+            .ok_or_else(|| Error::UnknownKey(s.to_string()))
     }
 }
 
+/// Parse a lowercased `other(<code>)` or `raw(<code>)` key, with the code in
+/// hex or decimal
+fn parse_other(s: &str) -> Option<Key> {
+    let code = s
+        .strip_prefix("other(")
+        .or_else(|| s.strip_prefix("raw("))?
+        .strip_suffix(')')?
+        .trim();
+    let code = match code.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => code.parse().ok()?,
+    };
+    Some(Key::Other(code))
+}
+
+/// Generate the conversions between named keys and their stable codes
+macro_rules! stable_codes {
+    ($($key:ident = $code:literal,)*) => {
+        impl Key {
+            /// Stable numeric code of the key, for storing events compactly
+            /// in logs and databases
+            ///
+            /// Each key's code is fixed, independent of the enum order, and
+            /// never changes or gets reused between versions, so codes can be
+            /// compared across crate versions. Every [`Key::Other`] has the
+            /// code [`ffi::KEY_OTHER`](crate::ffi::KEY_OTHER), so its platform
+            /// keycode must be stored separately.
+            ///
+            /// ```
+            /// use handy_keys::Key;
+            ///
+            /// assert_eq!(Key::A.code(), 0x0001);
+            /// assert_eq!(Key::from_code(Key::Space.code()), Some(Key::Space));
+            /// ```
+            pub fn code(&self) -> u16 {
+                match self {
+                    $(Key::$key => $code,)*
+                    Key::Other(_) => crate::ffi::KEY_OTHER,
+                }
+            }
+
+            /// The key with a stable code, see [`code`](Self::code)
+            ///
+            /// Returns `None` for [`ffi::KEY_NONE`](crate::ffi::KEY_NONE),
+            /// [`ffi::KEY_OTHER`](crate::ffi::KEY_OTHER), and codes this
+            /// version doesn't know, such as those of keys added later.
+            pub fn from_code(code: u16) -> Option<Self> {
+                match code {
+                    $($code => Some(Key::$key),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+// Stable codes of the named keys, see `Key::code`. Once released, an entry must
+// never change, and new keys get codes that were never used. 0 means no key.
+stable_codes! {
+    // Letters
+    A = 0x0001,
+    B = 0x0002,
+    C = 0x0003,
+    D = 0x0004,
+    E = 0x0005,
+    F = 0x0006,
+    G = 0x0007,
+    H = 0x0008,
+    I = 0x0009,
+    J = 0x000A,
+    K = 0x000B,
+    L = 0x000C,
+    M = 0x000D,
+    N = 0x000E,
+    O = 0x000F,
+    P = 0x0010,
+    Q = 0x0011,
+    R = 0x0012,
+    S = 0x0013,
+    T = 0x0014,
+    U = 0x0015,
+    V = 0x0016,
+    W = 0x0017,
+    X = 0x0018,
+    Y = 0x0019,
+    Z = 0x001A,
+
+    // Numbers
+    Num0 = 0x0020,
+    Num1 = 0x0021,
+    Num2 = 0x0022,
+    Num3 = 0x0023,
+    Num4 = 0x0024,
+    Num5 = 0x0025,
+    Num6 = 0x0026,
+    Num7 = 0x0027,
+    Num8 = 0x0028,
+    Num9 = 0x0029,
+
+    // Function keys
+    F1 = 0x0030,
+    F2 = 0x0031,
+    F3 = 0x0032,
+    F4 = 0x0033,
+    F5 = 0x0034,
+    F6 = 0x0035,
+    F7 = 0x0036,
+    F8 = 0x0037,
+    F9 = 0x0038,
+    F10 = 0x0039,
+    F11 = 0x003A,
+    F12 = 0x003B,
+    F13 = 0x003C,
+    F14 = 0x003D,
+    F15 = 0x003E,
+    F16 = 0x003F,
+    F17 = 0x0040,
+    F18 = 0x0041,
+    F19 = 0x0042,
+    F20 = 0x0043,
+
+    // Special keys
+    Space = 0x0050,
+    Return = 0x0051,
+    Tab = 0x0052,
+    Escape = 0x0053,
+    Delete = 0x0054,
+    ForwardDelete = 0x0055,
+    Home = 0x0056,
+    End = 0x0057,
+    PageUp = 0x0058,
+    PageDown = 0x0059,
+    PrintScreen = 0x005A,
+    Insert = 0x005B,
+    Pause = 0x005C,
+    ContextMenu = 0x005D,
+
+    // Arrow keys
+    LeftArrow = 0x0060,
+    RightArrow = 0x0061,
+    UpArrow = 0x0062,
+    DownArrow = 0x0063,
+
+    // Punctuation and symbols
+    Minus = 0x0070,
+    Equal = 0x0071,
+    LeftBracket = 0x0072,
+    RightBracket = 0x0073,
+    Backslash = 0x0074,
+    Semicolon = 0x0075,
+    Quote = 0x0076,
+    Comma = 0x0077,
+    Period = 0x0078,
+    Slash = 0x0079,
+    Grave = 0x007A,
+
+    // Keypad
+    Keypad0 = 0x0080,
+    Keypad1 = 0x0081,
+    Keypad2 = 0x0082,
+    Keypad3 = 0x0083,
+    Keypad4 = 0x0084,
+    Keypad5 = 0x0085,
+    Keypad6 = 0x0086,
+    Keypad7 = 0x0087,
+    Keypad8 = 0x0088,
+    Keypad9 = 0x0089,
+    KeypadDecimal = 0x008A,
+    KeypadMultiply = 0x008B,
+    KeypadPlus = 0x008C,
+    KeypadClear = 0x008D,
+    KeypadDivide = 0x008E,
+    KeypadEnter = 0x008F,
+    KeypadMinus = 0x0090,
+    KeypadEquals = 0x0091,
+
+    // Lock keys
+    CapsLock = 0x00A0,
+    ScrollLock = 0x00A1,
+    NumLock = 0x00A2,
+
+    // International keys
+    Yen = 0x00B0,
+    Eisu = 0x00B1,
+    Kana = 0x00B2,
+    Section = 0x00B3,
+    Ro = 0x00B4,
+    Hangul = 0x00B5,
+    Hanja = 0x00B6,
+
+    // Media keys
+    PlayPause = 0x00C0,
+    NextTrack = 0x00C1,
+    PreviousTrack = 0x00C2,
+    VolumeUp = 0x00C3,
+    VolumeDown = 0x00C4,
+    VolumeMute = 0x00C5,
+    BrightnessUp = 0x00C6,
+    BrightnessDown = 0x00C7,
+
+    // Mouse buttons
+    MouseLeft = 0x0100,
+    MouseRight = 0x0101,
+    MouseMiddle = 0x0102,
+    MouseX1 = 0x0103,
+    MouseX2 = 0x0104,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -366,6 +756,10 @@ mod tests {
         assert_eq!("esc".parse::<Key>().unwrap(), Key::Escape);
         assert_eq!("Delete".parse::<Key>().unwrap(), Key::Delete);
         assert_eq!("backspace".parse::<Key>().unwrap(), Key::Delete);
+        assert_eq!("PrintScreen".parse::<Key>().unwrap(), Key::PrintScreen);
+        assert_eq!("ins".parse::<Key>().unwrap(), Key::Insert);
+        assert_eq!("break".parse::<Key>().unwrap(), Key::Pause);
+        assert_eq!("menu".parse::<Key>().unwrap(), Key::ContextMenu);
     }
 
     #[test]
@@ -388,12 +782,41 @@ mod tests {
         assert_eq!("`".parse::<Key>().unwrap(), Key::Grave);
     }
 
+    #[test]
+    fn parse_international_keys() {
+        assert_eq!("IntlBackslash".parse::<Key>().unwrap(), Key::Section);
+        assert_eq!("¥".parse::<Key>().unwrap(), Key::Yen);
+        assert_eq!("IntlRo".parse::<Key>().unwrap(), Key::Ro);
+        assert_eq!("한/영".parse::<Key>().unwrap(), Key::Hangul);
+        assert_eq!("hanja".parse::<Key>().unwrap(), Key::Hanja);
+    }
+
+    #[test]
+    fn parse_media_keys() {
+        assert_eq!("PlayPause".parse::<Key>().unwrap(), Key::PlayPause);
+        assert_eq!("MediaTrackNext".parse::<Key>().unwrap(), Key::NextTrack);
+        assert_eq!("prevtrack".parse::<Key>().unwrap(), Key::PreviousTrack);
+        assert_eq!("AudioVolumeUp".parse::<Key>().unwrap(), Key::VolumeUp);
+        assert_eq!("mute".parse::<Key>().unwrap(), Key::VolumeMute);
+        assert_eq!(Key::VolumeMute.to_string(), "VolumeMute");
+    }
+
     #[test]
     fn parse_unknown_key_fails() {
         assert!("unknown".parse::<Key>().is_err());
         assert!("".parse::<Key>().is_err());
     }
 
+    #[test]
+    fn parse_other_keys() {
+        assert_eq!("Other(0x5E)".parse::<Key>().unwrap(), Key::Other(0x5E));
+        assert_eq!("other(94)".parse::<Key>().unwrap(), Key::Other(0x5E));
+        assert_eq!("Raw(0x5E)".parse::<Key>().unwrap(), Key::Other(0x5E));
+        assert_eq!(Key::Other(0x5E).to_string(), "Other(0x5E)");
+        assert!("other()".parse::<Key>().is_err());
+        assert!("other(0xZZ)".parse::<Key>().is_err());
+    }
+
     #[test]
     fn key_display_roundtrip() {
         // Test that parsing the display output gives the same key
@@ -408,4 +831,32 @@ mod tests {
             assert_eq!(parsed, key, "Roundtrip failed for {:?}", key);
         }
     }
+
+    #[test]
+    fn stable_codes_pinned() {
+        // These must never change
+        assert_eq!(Key::A.code(), 0x0001);
+        assert_eq!(Key::Num0.code(), 0x0020);
+        assert_eq!(Key::F20.code(), 0x0043);
+        assert_eq!(Key::Space.code(), 0x0050);
+        assert_eq!(Key::ContextMenu.code(), 0x005D);
+        assert_eq!(Key::Grave.code(), 0x007A);
+        assert_eq!(Key::KeypadEquals.code(), 0x0091);
+        assert_eq!(Key::Section.code(), 0x00B3);
+        assert_eq!(Key::Hanja.code(), 0x00B6);
+        assert_eq!(Key::BrightnessDown.code(), 0x00C7);
+        assert_eq!(Key::MouseX2.code(), 0x0104);
+        assert_eq!(Key::Other(0x5E).code(), crate::ffi::KEY_OTHER);
+    }
+
+    #[test]
+    fn stable_codes_roundtrip() {
+        let keys: Vec<Key> = (0..=u16::MAX).filter_map(Key::from_code).collect();
+        for key in &keys {
+            assert_eq!(Key::from_code(key.code()), Some(*key));
+        }
+        assert_eq!(keys.len(), 126);
+        assert_eq!(Key::from_code(crate::ffi::KEY_NONE), None);
+        assert_eq!(Key::from_code(crate::ffi::KEY_OTHER), None);
+    }
 }
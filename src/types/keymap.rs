@@ -0,0 +1,151 @@
+//! Named, grouped hotkeys for rendering "keyboard shortcuts" help screens
+
+use serde::{Deserialize, Serialize};
+
+use super::hotkey::Hotkey;
+
+/// A hotkey and the action it performs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeymapEntry {
+    /// Section the entry is listed under (e.g., "Editing")
+    pub group: String,
+    /// What the hotkey does (e.g., "Open command palette")
+    pub description: String,
+    pub hotkey: Hotkey,
+}
+
+/// A list of an app's hotkeys, renderable as a cheat sheet
+///
+/// Hotkeys are rendered with [`Hotkey::to_symbol_string`], so the cheat sheet
+/// matches how the platform displays shortcuts. Groups appear in the order
+/// they were first added.
+///
+/// # Examples
+/// ```
+/// use handy_keys::{Hotkey, Keymap};
+///
+/// let mut keymap = Keymap::new();
+/// keymap.add("General", "Start dictation", "Ctrl+Space".parse::<Hotkey>().unwrap());
+///
+/// let markdown = keymap.to_markdown();
+/// assert!(markdown.starts_with("## General"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keymap {
+    pub entries: Vec<KeymapEntry>,
+}
+
+impl Keymap {
+    /// Create an empty keymap
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hotkey under a group
+    pub fn add(&mut self, group: impl Into<String>, description: impl Into<String>, hotkey: Hotkey) {
+        self.entries.push(KeymapEntry {
+            group: group.into(),
+            description: description.into(),
+            hotkey,
+        });
+    }
+
+    /// Entries grouped by [`KeymapEntry::group`], in first-seen order
+    fn groups(&self) -> Vec<(&str, Vec<&KeymapEntry>)> {
+        let mut groups: Vec<(&str, Vec<&KeymapEntry>)> = Vec::new();
+        for entry in &self.entries {
+            match groups.iter_mut().find(|(name, _)| *name == entry.group) {
+                Some((_, entries)) => entries.push(entry),
+                None => groups.push((&entry.group, vec![entry])),
+            }
+        }
+        groups
+    }
+
+    /// Render the keymap as Markdown, one table per group
+    pub fn to_markdown(&self) -> String {
+        let escape = |s: &str| s.replace('|', "\\|");
+
+        let mut out = String::new();
+        for (group, entries) in self.groups() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("## {}\n\n", group));
+            out.push_str("| Shortcut | Action |\n");
+            out.push_str("|----------|--------|\n");
+            for entry in entries {
+                out.push_str(&format!(
+                    "| `{}` | {} |\n",
+                    entry.hotkey.to_symbol_string(),
+                    escape(&entry.description)
+                ));
+            }
+        }
+        out
+    }
+
+    /// Render the keymap as an HTML fragment, one table per group
+    pub fn to_html(&self) -> String {
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+
+        let mut out = String::new();
+        for (group, entries) in self.groups() {
+            out.push_str(&format!("<h2>{}</h2>\n<table>\n", escape(group)));
+            for entry in entries {
+                out.push_str(&format!(
+                    "  <tr><td><kbd>{}</kbd></td><td>{}</td></tr>\n",
+                    escape(&entry.hotkey.to_symbol_string()),
+                    escape(&entry.description)
+                ));
+            }
+            out.push_str("</table>\n");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Key, Modifiers};
+
+    fn keymap() -> Keymap {
+        let mut keymap = Keymap::new();
+        keymap.add("General", "Start <dictation>", Hotkey::new(Modifiers::CTRL, Key::Space).unwrap());
+        keymap.add("Editing", "Undo | redo", Hotkey::new(Modifiers::CTRL, Key::Z).unwrap());
+        keymap.add("General", "Quit", Hotkey::new(Modifiers::CTRL, Key::Q).unwrap());
+        keymap
+    }
+
+    #[test]
+    fn markdown_groups_in_first_seen_order() {
+        let markdown = keymap().to_markdown();
+
+        let general = markdown.find("## General").unwrap();
+        let editing = markdown.find("## Editing").unwrap();
+        assert!(general < editing);
+        // Quit is listed under General even though it was added after Editing
+        assert!(markdown.find("Quit").unwrap() < editing);
+        assert!(markdown.contains("Undo \\| redo"));
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let html = keymap().to_html();
+
+        assert!(html.contains("Start &lt;dictation&gt;"));
+        assert_eq!(html.matches("<table>").count(), 2);
+    }
+
+    #[test]
+    fn empty_keymap_renders_nothing() {
+        assert_eq!(Keymap::new().to_markdown(), "");
+        assert_eq!(Keymap::new().to_html(), "");
+    }
+}
@@ -0,0 +1,217 @@
+//! The string format of the `hotkey` and `tauri-hotkey` crates
+//!
+//! Projects moving off those crates have user bindings stored as strings like
+//! `"CTRL+SHIFT+Q"`. The format differs from ours in its key names, most
+//! notably `DELETE` meaning forward delete and `BACKSPACE` meaning
+//! [`Key::Delete`].
+
+use crate::error::{Error, Result};
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+use super::modifiers::Modifiers;
+
+/// Legacy modifier names, the first of each being the one written
+const LEGACY_MODIFIERS: &[(Modifiers, &[&str])] = &[
+    (Modifiers::CTRL, &["CTRL", "CONTROL"]),
+    (Modifiers::OPT, &["ALT", "OPTION"]),
+    (Modifiers::SHIFT, &["SHIFT"]),
+    (Modifiers::CMD, &["SUPER", "CMD", "COMMAND", "META", "WIN"]),
+];
+
+/// Names of the modifier that is Cmd on macOS and Ctrl elsewhere
+const CMD_OR_CTRL: &[&str] = &["CMDORCTRL", "CMDORCONTROL", "COMMANDORCTRL", "COMMANDORCONTROL"];
+
+/// Legacy key names that differ from ours, the first of each being the one
+/// written
+///
+/// Letters, digits and F-keys are written as their uppercased name.
+const LEGACY_KEYS: &[(Key, &[&str])] = &[
+    (Key::Space, &["SPACE", "SPACEBAR"]),
+    (Key::Return, &["ENTER", "RETURN"]),
+    (Key::Tab, &["TAB"]),
+    (Key::Escape, &["ESCAPE", "ESC"]),
+    (Key::Delete, &["BACKSPACE"]),
+    (Key::ForwardDelete, &["DELETE", "DEL"]),
+    (Key::Home, &["HOME"]),
+    (Key::End, &["END"]),
+    (Key::PageUp, &["PAGEUP"]),
+    (Key::PageDown, &["PAGEDOWN"]),
+    (Key::PrintScreen, &["PRINTSCREEN"]),
+    (Key::Insert, &["INSERT"]),
+    (Key::Pause, &["PAUSE"]),
+    (Key::LeftArrow, &["LEFT"]),
+    (Key::RightArrow, &["RIGHT"]),
+    (Key::UpArrow, &["UP"]),
+    (Key::DownArrow, &["DOWN"]),
+    (Key::CapsLock, &["CAPSLOCK"]),
+    (Key::NumLock, &["NUMLOCK"]),
+    (Key::ScrollLock, &["SCROLLLOCK"]),
+    (Key::Minus, &["MINUS", "-"]),
+    (Key::Equal, &["EQUAL", "="]),
+    (Key::LeftBracket, &["OPENBRACKET", "["]),
+    (Key::RightBracket, &["CLOSEBRACKET", "]"]),
+    (Key::Backslash, &["BACKSLASH", "\\"]),
+    (Key::Semicolon, &["SEMICOLON", ";"]),
+    (Key::Quote, &["QUOTE", "'"]),
+    (Key::Comma, &["COMMA", ","]),
+    (Key::Period, &["PERIOD", "."]),
+    (Key::Slash, &["SLASH", "/"]),
+    (Key::Grave, &["BACKQUOTE", "GRAVE", "`"]),
+    (Key::Keypad0, &["NUMPAD0"]),
+    (Key::Keypad1, &["NUMPAD1"]),
+    (Key::Keypad2, &["NUMPAD2"]),
+    (Key::Keypad3, &["NUMPAD3"]),
+    (Key::Keypad4, &["NUMPAD4"]),
+    (Key::Keypad5, &["NUMPAD5"]),
+    (Key::Keypad6, &["NUMPAD6"]),
+    (Key::Keypad7, &["NUMPAD7"]),
+    (Key::Keypad8, &["NUMPAD8"]),
+    (Key::Keypad9, &["NUMPAD9"]),
+    (Key::KeypadMultiply, &["MULTIPLY"]),
+    (Key::KeypadPlus, &["ADD"]),
+    (Key::KeypadMinus, &["SUBTRACT"]),
+    (Key::KeypadDecimal, &["DECIMAL"]),
+    (Key::KeypadDivide, &["DIVIDE"]),
+];
+
+impl Hotkey {
+    /// Parse a hotkey in the format of the `hotkey` and `tauri-hotkey` crates
+    ///
+    /// Names are case-insensitive. `CmdOrCtrl` means Cmd on macOS and Ctrl
+    /// elsewhere. Every hotkey must have a key.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::{Hotkey, Key, Modifiers};
+    ///
+    /// let hotkey = Hotkey::from_legacy_string("CTRL+SHIFT+Q").unwrap();
+    /// assert_eq!(hotkey, Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, Key::Q).unwrap());
+    /// assert_eq!(Hotkey::from_legacy_string("ALT+DELETE").unwrap().key, Some(Key::ForwardDelete));
+    /// ```
+    pub fn from_legacy_string(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidHotkeyFormat(s.to_string());
+
+        let parts: Vec<String> = s.split('+').map(|part| part.trim().to_uppercase()).collect();
+        let (key, modifier_names) = parts.split_last().ok_or_else(invalid)?;
+        let mut modifiers = Modifiers::empty();
+        for name in modifier_names {
+            modifiers |= legacy_modifier(name).ok_or_else(|| Error::UnknownModifier(name.clone()))?;
+        }
+        let key = legacy_key(key).ok_or_else(|| Error::UnknownKey(key.clone()))?;
+
+        Hotkey::new(modifiers, key)
+    }
+
+    /// Format hotkey in the format of the `hotkey` and `tauri-hotkey` crates
+    ///
+    /// Returns an error for hotkeys that format can't express: modifier-only
+    /// ones, the Fn modifier, mouse buttons and keys it has no name for.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::Hotkey;
+    ///
+    /// let hotkey: Hotkey = "Shift+Ctrl+Backspace".parse().unwrap();
+    /// assert_eq!(hotkey.to_legacy_string().unwrap(), "CTRL+SHIFT+BACKSPACE");
+    /// ```
+    pub fn to_legacy_string(&self) -> Result<String> {
+        let unsupported = || Error::InvalidHotkeyFormat(format!("{} has no legacy form", self));
+
+        if self.modifiers.contains(Modifiers::FN) {
+            return Err(unsupported());
+        }
+        let key = self.key.and_then(legacy_key_name).ok_or_else(unsupported)?;
+
+        let mut parts: Vec<String> = LEGACY_MODIFIERS
+            .iter()
+            .filter(|(modifier, _)| self.modifiers.contains(*modifier))
+            .map(|(_, names)| names[0].to_string())
+            .collect();
+        parts.push(key);
+        Ok(parts.join("+"))
+    }
+}
+
+fn legacy_modifier(name: &str) -> Option<Modifiers> {
+    if CMD_OR_CTRL.contains(&name) {
+        return Some(if cfg!(target_os = "macos") { Modifiers::CMD } else { Modifiers::CTRL });
+    }
+    LEGACY_MODIFIERS
+        .iter()
+        .find(|(_, names)| names.contains(&name))
+        .map(|(modifier, _)| *modifier)
+}
+
+fn legacy_key(name: &str) -> Option<Key> {
+    if let Some((key, _)) = LEGACY_KEYS.iter().find(|(_, names)| names.contains(&name)) {
+        return Some(*key);
+    }
+    name.parse::<Key>().ok().filter(|key| is_plain_key(*key))
+}
+
+fn legacy_key_name(key: Key) -> Option<String> {
+    if let Some((_, names)) = LEGACY_KEYS.iter().find(|(k, _)| *k == key) {
+        return Some(names[0].to_string());
+    }
+    is_plain_key(key).then(|| key.to_string().to_uppercase())
+}
+
+/// Whether a key is written the same in the legacy format as in ours: letters,
+/// digits and F-keys
+pub(super) fn is_plain_key(key: Key) -> bool {
+    matches!(
+        key,
+        Key::A | Key::B | Key::C | Key::D | Key::E | Key::F | Key::G | Key::H | Key::I
+            | Key::J | Key::K | Key::L | Key::M | Key::N | Key::O | Key::P | Key::Q | Key::R
+            | Key::S | Key::T | Key::U | Key::V | Key::W | Key::X | Key::Y | Key::Z
+            | Key::Num0 | Key::Num1 | Key::Num2 | Key::Num3 | Key::Num4 | Key::Num5
+            | Key::Num6 | Key::Num7 | Key::Num8 | Key::Num9
+            | Key::F1 | Key::F2 | Key::F3 | Key::F4 | Key::F5 | Key::F6 | Key::F7 | Key::F8
+            | Key::F9 | Key::F10 | Key::F11 | Key::F12 | Key::F13 | Key::F14 | Key::F15
+            | Key::F16 | Key::F17 | Key::F18 | Key::F19 | Key::F20
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::arb_hotkey;
+    use proptest::prelude::*;
+
+    #[test]
+    fn parses_legacy_names() {
+        let cases = [
+            ("CTRL+SHIFT+Q", Modifiers::CTRL | Modifiers::SHIFT, Key::Q),
+            ("control+alt+spacebar", Modifiers::CTRL | Modifiers::OPT, Key::Space),
+            ("SUPER+BACKSPACE", Modifiers::CMD, Key::Delete),
+            ("ALT+DELETE", Modifiers::OPT, Key::ForwardDelete),
+            ("SHIFT+NUMPAD5", Modifiers::SHIFT, Key::Keypad5),
+            ("F12", Modifiers::empty(), Key::F12),
+        ];
+        for (s, modifiers, key) in cases {
+            assert_eq!(Hotkey::from_legacy_string(s).unwrap(), Hotkey::new(modifiers, key).unwrap(), "{s}");
+        }
+
+        let cmd_or_ctrl = Hotkey::from_legacy_string("CmdOrCtrl+S").unwrap();
+        assert_eq!(cmd_or_ctrl.modifiers.contains(Modifiers::CMD), cfg!(target_os = "macos"));
+        assert!(Hotkey::from_legacy_string("CTRL+SHIFT").is_err());
+        assert!(Hotkey::from_legacy_string("HYPER+Q").is_err());
+    }
+
+    #[test]
+    fn rejects_hotkeys_without_a_legacy_form() {
+        assert!("Cmd+Shift".parse::<Hotkey>().unwrap().to_legacy_string().is_err());
+        assert!("Fn+F1".parse::<Hotkey>().unwrap().to_legacy_string().is_err());
+        assert!("Ctrl+MouseLeft".parse::<Hotkey>().unwrap().to_legacy_string().is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn legacy_strings_roundtrip(hotkey in arb_hotkey()) {
+            if let Ok(s) = hotkey.to_legacy_string() {
+                prop_assert_eq!(Hotkey::from_legacy_string(&s).unwrap(), hotkey);
+            }
+        }
+    }
+}
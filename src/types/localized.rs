@@ -0,0 +1,210 @@
+//! Key names translated for display
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+
+/// Translated key names of a language, for keys whose name isn't the same as
+/// in English
+type KeyNames = &'static [(Key, &'static str)];
+
+/// Translated modifier names of a language, by their English name
+type ModifierNames = &'static [(&'static str, &'static str)];
+
+/// Bundled languages by ISO 639-1 code, named as on their keyboards
+const LOCALES: &[(&str, KeyNames, ModifierNames)] = &[
+    ("de", GERMAN_KEYS, &[("Ctrl", "Strg"), ("Shift", "Umschalt")]),
+    ("fr", FRENCH_KEYS, &[("Shift", "Maj")]),
+    ("es", SPANISH_KEYS, &[("Shift", "Mayús")]),
+    ("it", ITALIAN_KEYS, &[("Shift", "Maiusc")]),
+    ("pt", PORTUGUESE_KEYS, &[]),
+];
+
+const GERMAN_KEYS: KeyNames = &[
+    (Key::Return, "Eingabe"),
+    (Key::Space, "Leertaste"),
+    (Key::Escape, "Esc"),
+    (Key::Delete, "Rücktaste"),
+    (Key::ForwardDelete, "Entf"),
+    (Key::Insert, "Einfg"),
+    (Key::Home, "Pos1"),
+    (Key::End, "Ende"),
+    (Key::PageUp, "Bild auf"),
+    (Key::PageDown, "Bild ab"),
+    (Key::LeftArrow, "Links"),
+    (Key::RightArrow, "Rechts"),
+    (Key::UpArrow, "Oben"),
+    (Key::DownArrow, "Unten"),
+    (Key::CapsLock, "Feststelltaste"),
+    (Key::NumLock, "Num"),
+    (Key::ScrollLock, "Rollen"),
+    (Key::PrintScreen, "Druck"),
+];
+
+const FRENCH_KEYS: KeyNames = &[
+    (Key::Return, "Entrée"),
+    (Key::Space, "Espace"),
+    (Key::Escape, "Échap"),
+    (Key::Delete, "Retour arrière"),
+    (Key::ForwardDelete, "Suppr"),
+    (Key::Insert, "Inser"),
+    (Key::Home, "Origine"),
+    (Key::End, "Fin"),
+    (Key::PageUp, "Page préc."),
+    (Key::PageDown, "Page suiv."),
+    (Key::LeftArrow, "Gauche"),
+    (Key::RightArrow, "Droite"),
+    (Key::UpArrow, "Haut"),
+    (Key::DownArrow, "Bas"),
+    (Key::CapsLock, "Verr. maj."),
+    (Key::NumLock, "Verr. num."),
+    (Key::ScrollLock, "Arrêt défil."),
+    (Key::PrintScreen, "Impr. écran"),
+];
+
+const SPANISH_KEYS: KeyNames = &[
+    (Key::Return, "Intro"),
+    (Key::Space, "Espacio"),
+    (Key::Escape, "Esc"),
+    (Key::Delete, "Retroceso"),
+    (Key::ForwardDelete, "Supr"),
+    (Key::Home, "Inicio"),
+    (Key::End, "Fin"),
+    (Key::PageUp, "Re Pág"),
+    (Key::PageDown, "Av Pág"),
+    (Key::LeftArrow, "Izquierda"),
+    (Key::RightArrow, "Derecha"),
+    (Key::UpArrow, "Arriba"),
+    (Key::DownArrow, "Abajo"),
+    (Key::CapsLock, "Bloq Mayús"),
+    (Key::NumLock, "Bloq Num"),
+    (Key::ScrollLock, "Bloq Despl"),
+    (Key::PrintScreen, "Impr Pant"),
+    (Key::Pause, "Pausa"),
+];
+
+const ITALIAN_KEYS: KeyNames = &[
+    (Key::Return, "Invio"),
+    (Key::Space, "Spazio"),
+    (Key::Escape, "Esc"),
+    (Key::ForwardDelete, "Canc"),
+    (Key::Insert, "Ins"),
+    (Key::End, "Fine"),
+    (Key::PageUp, "Pag su"),
+    (Key::PageDown, "Pag giù"),
+    (Key::LeftArrow, "Sinistra"),
+    (Key::RightArrow, "Destra"),
+    (Key::UpArrow, "Su"),
+    (Key::DownArrow, "Giù"),
+    (Key::CapsLock, "Bloc Maiusc"),
+    (Key::NumLock, "Bloc Num"),
+    (Key::ScrollLock, "Bloc Scorr"),
+    (Key::PrintScreen, "Stamp"),
+    (Key::Pause, "Pausa"),
+];
+
+const PORTUGUESE_KEYS: KeyNames = &[
+    (Key::Space, "Espaço"),
+    (Key::LeftArrow, "Esquerda"),
+    (Key::RightArrow, "Direita"),
+    (Key::UpArrow, "Cima"),
+    (Key::DownArrow, "Baixo"),
+    (Key::Pause, "Pausa"),
+];
+
+/// The bundled language of a locale like `"de"`, `"fr-CA"` or `"pt_BR.UTF-8"`
+fn language(locale: &str) -> Option<(KeyNames, ModifierNames)> {
+    let language = locale.split(['-', '_', '.']).next()?;
+    LOCALES
+        .iter()
+        .find(|(code, _, _)| code.eq_ignore_ascii_case(language))
+        .map(|&(_, keys, modifiers)| (keys, modifiers))
+}
+
+impl Key {
+    /// The name of this key in the language of `locale`, as printed on that
+    /// language's keyboards
+    ///
+    /// `locale` is a BCP 47 or POSIX tag like `"de"`, `"fr-CA"` or
+    /// `"pt_BR.UTF-8"`; only its language is used. German, French, Spanish,
+    /// Italian and Portuguese are bundled. Other languages, and keys a
+    /// language names as in English, fall back to the `Display` name.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::Key;
+    ///
+    /// assert_eq!(Key::Space.display_localized("de-DE"), "Leertaste");
+    /// assert_eq!(Key::Return.display_localized("fr"), "Entrée");
+    /// assert_eq!(Key::Return.display_localized("ja"), "Return");
+    /// ```
+    pub fn display_localized(&self, locale: &str) -> String {
+        language(locale)
+            .and_then(|(keys, _)| keys.iter().find(|(key, _)| key == self))
+            .map_or_else(|| self.to_string(), |(_, name)| name.to_string())
+    }
+}
+
+impl Hotkey {
+    /// Format hotkey like `Display` does, with key and modifier names in the
+    /// language of `locale`
+    ///
+    /// Names are translated as by [`Key::display_localized`]. Modifiers are
+    /// translated where that language's keyboards label them differently,
+    /// like "Strg" for Ctrl in German; ones with a side keep their English
+    /// name.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::Hotkey;
+    ///
+    /// let hotkey: Hotkey = "Ctrl+Shift+Space".parse().unwrap();
+    /// assert_eq!(hotkey.to_localized_string("de"), "Strg+Umschalt+Leertaste");
+    /// ```
+    pub fn to_localized_string(&self, locale: &str) -> String {
+        let modifier_names = language(locale).map_or(&[][..], |(_, modifiers)| modifiers);
+        let modifiers = self.modifiers.to_string();
+        let mut parts: Vec<String> = modifiers
+            .split('+')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                modifier_names
+                    .iter()
+                    .find(|(english, _)| *english == part)
+                    .map_or(part, |(_, name)| name)
+                    .to_string()
+            })
+            .collect();
+        if let Some(key) = &self.key {
+            parts.push(key.display_localized(locale));
+        }
+        if parts.is_empty() {
+            return self.to_string();
+        }
+        parts.join("+")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localized_names() {
+        assert_eq!(Key::Return.display_localized("fr-CA"), "Entrée");
+        assert_eq!(Key::Space.display_localized("pt_BR.UTF-8"), "Espaço");
+        assert_eq!(Key::Delete.display_localized("ES"), "Retroceso");
+        assert_eq!(Key::K.display_localized("de"), "K");
+        assert_eq!(Key::Space.display_localized("en-US"), "Space");
+        assert_eq!(Key::Space.display_localized(""), "Space");
+    }
+
+    #[test]
+    fn localized_hotkeys() {
+        let hotkey: Hotkey = "Ctrl+Shift+Left".parse().unwrap();
+        assert_eq!(hotkey.to_localized_string("fr"), "Ctrl+Maj+Gauche");
+        assert_eq!(hotkey.to_localized_string("nl"), hotkey.to_string());
+
+        let sided: Hotkey = "LeftShift+Ctrl".parse().unwrap();
+        assert_eq!(sided.to_localized_string("de"), "Strg+LeftShift");
+    }
+}
@@ -1,9 +1,34 @@
 //! Core types for keyboard shortcuts
 
+mod accelerator;
+mod canonical;
+mod conflict;
+mod display_style;
+mod const_parse;
 mod hotkey;
+mod input_macro;
 mod key;
+mod keymap;
+mod legacy;
+mod localized;
 mod modifiers;
+mod parser;
+mod scancode;
+mod sequence;
+mod slug;
+mod vscode;
+mod web_code;
 
-pub use hotkey::{Hotkey, HotkeyEvent, HotkeyId, HotkeyState, KeyEvent};
+pub use accelerator::KeyEquivalent;
+pub use canonical::CanonicalizeOptions;
+pub use conflict::{Conflict, ConflictKind};
+pub use display_style::DisplayStyle;
+pub use hotkey::{Hotkey, HookDecision, HotkeyEvent, HotkeyId, HotkeyState, KeyEvent};
+pub use input_macro::{Macro, MacroRecorder, MacroStep};
 pub use key::Key;
+pub use keymap::{Keymap, KeymapEntry};
 pub use modifiers::Modifiers;
+pub use parser::HotkeyParser;
+pub(crate) use parser::current as current_parser;
+pub use scancode::{PhysicalHotkey, Scancode};
+pub use sequence::HotkeySequence;
@@ -9,7 +9,11 @@ use crate::error::{Error, Result};
 
 bitflags! {
     /// Modifier keys for hotkey combinations
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    ///
+    /// The `L_` and `R_` flags include their modifier and make a hotkey
+    /// require its key on that side, like `R_OPT` for "Right Alt only".
+    /// Hotkeys with the plain flags work with either side.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
     #[serde(transparent)]
     pub struct Modifiers: u32 {
         /// Command key (macOS) / Windows key (Windows) / Super key (Linux)
@@ -22,44 +26,150 @@ bitflags! {
         const OPT = 1 << 3;
         /// Function key (macOS)
         const FN = 1 << 4;
+        /// Hyper: Command, Control, Option and Shift together, which power
+        /// users often map Caps Lock to
+        const HYPER = Self::CMD.bits() | Self::CTRL.bits() | Self::OPT.bits() | Self::SHIFT.bits();
+
+        /// Left Command / Windows / Super key, for hotkeys that require it
+        const L_CMD = Self::CMD.bits() | 1 << 8;
+        /// Right Command / Windows / Super key
+        const R_CMD = Self::CMD.bits() | 1 << 9;
+        /// Left Shift key
+        const L_SHIFT = Self::SHIFT.bits() | 1 << 10;
+        /// Right Shift key
+        const R_SHIFT = Self::SHIFT.bits() | 1 << 11;
+        /// Left Control key
+        const L_CTRL = Self::CTRL.bits() | 1 << 12;
+        /// Right Control key
+        const R_CTRL = Self::CTRL.bits() | 1 << 13;
+        /// Left Option / Alt key
+        const L_OPT = Self::OPT.bits() | 1 << 14;
+        /// Right Option / Alt key (AltGr on many layouts)
+        const R_OPT = Self::OPT.bits() | 1 << 15;
     }
 }
 
+/// The modifiers that have a key on each side, with their left and right
+/// flags and display names
+pub(crate) const SIDED: [(Modifiers, Modifiers, Modifiers, &str); 4] = [
+    (Modifiers::CTRL, Modifiers::L_CTRL, Modifiers::R_CTRL, "Ctrl"),
+    (Modifiers::OPT, Modifiers::L_OPT, Modifiers::R_OPT, "Opt"),
+    (Modifiers::SHIFT, Modifiers::L_SHIFT, Modifiers::R_SHIFT, "Shift"),
+    (Modifiers::CMD, Modifiers::L_CMD, Modifiers::R_CMD, "Cmd"),
+];
+
+/// Every modifier name `FromStr` accepts without a side, lowercase
+///
+/// Also used by [`Hotkey::parse_const`](super::Hotkey::parse_const), so both
+/// accept the same names.
+pub(crate) const MODIFIER_NAMES: &[(&str, Modifiers)] = &[
+    ("cmd", Modifiers::CMD),
+    ("command", Modifiers::CMD),
+    ("meta", Modifiers::CMD),
+    ("super", Modifiers::CMD),
+    ("win", Modifiers::CMD),
+    ("windows", Modifiers::CMD),
+    ("shift", Modifiers::SHIFT),
+    ("ctrl", Modifiers::CTRL),
+    ("control", Modifiers::CTRL),
+    ("opt", Modifiers::OPT),
+    ("option", Modifiers::OPT),
+    ("alt", Modifiers::OPT),
+    ("fn", Modifiers::FN),
+    ("function", Modifiers::FN),
+    ("hyper", Modifiers::HYPER),
+];
+
+/// Prefixes that give a modifier name a side, and whether it's the left one
+pub(crate) const SIDE_PREFIXES: [(&str, bool); 4] =
+    [("left", true), ("right", false), ("l", true), ("r", false)];
+
+/// What may separate a side prefix from the modifier name, as in "Right Alt"
+pub(crate) const SIDE_SEPARATORS: [char; 3] = [' ', '_', '-'];
+
+/// The bits of the modifiers themselves, without side flags
+const WITHOUT_SIDES: u32 = 0x1F;
+
 impl fmt::Display for Modifiers {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut parts = Vec::new();
-        if self.contains(Modifiers::CTRL) {
-            parts.push("Ctrl");
-        }
-        if self.contains(Modifiers::OPT) {
-            parts.push("Opt");
-        }
-        if self.contains(Modifiers::SHIFT) {
-            parts.push("Shift");
-        }
-        if self.contains(Modifiers::CMD) {
-            parts.push("Cmd");
+        for (modifier, left, right, name) in SIDED {
+            match (self.contains(left), self.contains(right)) {
+                (false, false) if self.contains(modifier) => parts.push(name.to_string()),
+                (false, false) => {}
+                (left, right) => {
+                    if left {
+                        parts.push(format!("Left{name}"));
+                    }
+                    if right {
+                        parts.push(format!("Right{name}"));
+                    }
+                }
+            }
         }
         if self.contains(Modifiers::FN) {
-            parts.push("Fn");
+            parts.push("Fn".to_string());
         }
         write!(f, "{}", parts.join("+"))
     }
 }
 
 impl Modifiers {
+    /// These modifiers without side flags, e.g. `OPT` for `R_OPT`
+    pub fn without_sides(self) -> Modifiers {
+        Modifiers::from_bits_retain(self.bits() & WITHOUT_SIDES)
+    }
+
+    /// Whether `held` presses a hotkey with these modifiers: the same
+    /// modifiers, held on the sides these require
+    ///
+    /// `held` has the side flags of the modifiers whose key is known, like
+    /// [`KeyEvent::sided_modifiers`](crate::KeyEvent::sided_modifiers).
+    pub fn matched_by(self, held: Modifiers) -> bool {
+        self.without_sides() == held.without_sides() && held.contains(self)
+    }
+
+    /// Just the side bits of these modifiers, which aren't valid flags alone
+    pub(crate) fn side_bits(self) -> Modifiers {
+        Modifiers::from_bits_retain(self.bits() & !WITHOUT_SIDES)
+    }
+
+    /// The side flags in `self` of the modifiers in `held`
+    ///
+    /// Side bits of released modifiers are ignored, so a tracker only has to
+    /// clear a key's side bit when it goes up.
+    pub(crate) fn sides_of(self, held: Modifiers) -> Modifiers {
+        SIDED
+            .iter()
+            .flat_map(|&(modifier, left, right, _)| [(modifier, left), (modifier, right)])
+            .filter(|&(modifier, side)| {
+                held.contains(modifier) && self.contains(side.side_bits())
+            })
+            .fold(Modifiers::empty(), |sides, (_, side)| sides | side)
+    }
+
     /// Parse a single modifier name (case-insensitive)
+    ///
+    /// Names with a side, like "RightAlt", "Right Alt" or "RAlt", give its
+    /// side flag.
     pub(crate) fn parse_single(s: &str) -> Option<Modifiers> {
-        match s.to_lowercase().as_str() {
-            "cmd" | "command" | "meta" | "super" | "win" | "windows" => Some(Modifiers::CMD),
-            "shift" => Some(Modifiers::SHIFT),
-            "ctrl" | "control" => Some(Modifiers::CTRL),
-            "opt" | "option" | "alt" => Some(Modifiers::OPT),
-            "fn" | "function" => Some(Modifiers::FN),
-            _ => None,
-        }
+        let name = s.to_lowercase();
+        Self::parse_unsided(&name).or_else(|| {
+            SIDE_PREFIXES.into_iter().find_map(|(prefix, is_left)| {
+                let rest = name.strip_prefix(prefix)?.trim_start_matches(SIDE_SEPARATORS);
+                let modifier = Self::parse_unsided(rest)?;
+                let (_, left, right, _) = SIDED.into_iter().find(|sided| sided.0 == modifier)?;
+                Some(if is_left { left } else { right })
+            })
+        })
     }
 
+    fn parse_unsided(name: &str) -> Option<Modifiers> {
+        MODIFIER_NAMES
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, modifier)| *modifier)
+    }
 }
 
 impl FromStr for Modifiers {
@@ -90,6 +200,7 @@ impl FromStr for Modifiers {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::Hotkey;
 
     #[test]
     fn parse_single_modifiers() {
@@ -143,4 +254,42 @@ mod tests {
         assert_eq!(format!("{}", Modifiers::SHIFT), "Shift");
         assert_eq!(format!("{}", Modifiers::CMD | Modifiers::SHIFT), "Shift+Cmd");
     }
+
+    #[test]
+    fn hyper() {
+        let hotkey: Hotkey = "Hyper+K".parse().unwrap();
+        let all_four = Modifiers::CMD | Modifiers::CTRL | Modifiers::OPT | Modifiers::SHIFT;
+        assert_eq!(hotkey.modifiers, all_four);
+        assert_eq!(hotkey.to_string(), "Ctrl+Opt+Shift+Cmd+K");
+        assert_eq!("hyper+fn".parse::<Modifiers>().unwrap(), Modifiers::HYPER | Modifiers::FN);
+    }
+
+    #[test]
+    fn sided_modifiers() {
+        assert_eq!("RightAlt".parse::<Modifiers>().unwrap(), Modifiers::R_OPT);
+        assert_eq!("right alt".parse::<Modifiers>().unwrap(), Modifiers::R_OPT);
+        assert_eq!("LCtrl+rshift".parse::<Modifiers>().unwrap(), Modifiers::L_CTRL | Modifiers::R_SHIFT);
+        assert!("RightFn".parse::<Modifiers>().is_err());
+
+        let both = Modifiers::L_SHIFT | Modifiers::R_SHIFT | Modifiers::CMD;
+        assert_eq!(both.to_string(), "LeftShift+RightShift+Cmd");
+        assert_eq!(both.to_string().parse::<Modifiers>().unwrap(), both);
+        assert_eq!(Modifiers::R_OPT.without_sides(), Modifiers::OPT);
+    }
+
+    #[test]
+    fn side_matching() {
+        let held = Modifiers::CTRL | Modifiers::R_OPT;
+        assert!(Modifiers::R_OPT.union(Modifiers::CTRL).matched_by(held));
+        assert!((Modifiers::CTRL | Modifiers::OPT).matched_by(held));
+        assert!(!(Modifiers::CTRL | Modifiers::L_OPT).matched_by(held));
+        assert!(!Modifiers::R_OPT.matched_by(held));
+        // Without known sides, only hotkeys for either side match
+        assert!(!Modifiers::R_OPT.matched_by(Modifiers::OPT));
+
+        // Side bits of released modifiers are dropped
+        let tracked = Modifiers::R_OPT | Modifiers::L_SHIFT;
+        assert_eq!(tracked.sides_of(Modifiers::OPT | Modifiers::CMD), Modifiers::R_OPT);
+        assert_eq!(tracked.side_bits().sides_of(Modifiers::SHIFT), Modifiers::L_SHIFT);
+    }
 }
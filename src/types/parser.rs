@@ -0,0 +1,210 @@
+//! Hotkey parsing with localized and custom modifier and key names
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{PoisonError, RwLock};
+
+use crate::error::Result;
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+use super::modifiers::Modifiers;
+
+/// The parser `Hotkey::from_str` uses, if one was set
+static DEFAULT: RwLock<Option<HotkeyParser>> = RwLock::new(None);
+
+/// Modifier names of non-English keyboards and tools, lowercase
+const LOCALIZED_MODIFIERS: &[(&str, Modifiers)] = &[
+    // German
+    ("strg", Modifiers::CTRL),
+    ("steuerung", Modifiers::CTRL),
+    ("umschalt", Modifiers::SHIFT),
+    ("umsch", Modifiers::SHIFT),
+    ("wahl", Modifiers::OPT),
+    ("wahltaste", Modifiers::OPT),
+    ("befehl", Modifiers::CMD),
+    // French
+    ("maj", Modifiers::SHIFT),
+    ("majuscule", Modifiers::SHIFT),
+    ("commande", Modifiers::CMD),
+    // Spanish
+    ("mayús", Modifiers::SHIFT),
+    ("mayus", Modifiers::SHIFT),
+    ("opción", Modifiers::OPT),
+    ("comando", Modifiers::CMD),
+    // Italian
+    ("maiusc", Modifiers::SHIFT),
+    // Nordic
+    ("skift", Modifiers::SHIFT),
+];
+
+/// Parses hotkeys with extra modifier and key names
+///
+/// [`Hotkey`]'s `FromStr` only accepts English modifier names and the built-in
+/// key names. A parser also accepts the names in its tables, such as those
+/// used in configs written by non-English users, imported from other tools,
+/// or an app's own vocabulary like "Hyper". Make it the default with
+/// [`set_default`](Self::set_default) to have `FromStr` accept them too.
+///
+/// # Examples
+/// ```
+/// use handy_keys::{Hotkey, HotkeyParser, Key, Modifiers};
+///
+/// let hyper = Modifiers::CTRL | Modifiers::OPT | Modifiers::SHIFT | Modifiers::CMD;
+/// let parser = HotkeyParser::localized().modifier_alias("Hyper", hyper);
+/// assert_eq!(parser.parse("Strg+Umschalt+K")?, "Ctrl+Shift+K".parse::<Hotkey>()?);
+/// assert_eq!(parser.parse("Maj+F1")?, "Shift+F1".parse::<Hotkey>()?);
+/// assert_eq!(parser.parse("Hyper+K")?, "Ctrl+Opt+Shift+Cmd+K".parse::<Hotkey>()?);
+///
+/// let parser = parser.key_alias("Launch", Key::F13);
+/// assert_eq!(parser.parse("Hyper+Launch")?.key, Some(Key::F13));
+/// # Ok::<(), handy_keys::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HotkeyParser {
+    /// Extra modifier names, lowercase
+    aliases: HashMap<String, Modifiers>,
+    /// Extra key names, lowercase
+    key_aliases: HashMap<String, Key>,
+}
+
+impl HotkeyParser {
+    /// Create a parser that accepts the same names as `FromStr`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a parser that also accepts the built-in localized modifier
+    /// names, like German "Strg" and "Umschalt" or French "Maj"
+    pub fn localized() -> Self {
+        LOCALIZED_MODIFIERS
+            .iter()
+            .fold(Self::new(), |parser, (name, modifier)| parser.modifier_alias(name, *modifier))
+    }
+
+    /// Accept `name` (case-insensitive) for `modifiers`
+    ///
+    /// Replaces an alias of the same name. English names always keep their
+    /// meaning, except "Hyper", which an alias may redefine.
+    pub fn modifier_alias(mut self, name: &str, modifiers: Modifiers) -> Self {
+        self.aliases.insert(name.trim().to_lowercase(), modifiers);
+        self
+    }
+
+    /// Accept `name` (case-insensitive) for `key`
+    ///
+    /// Replaces an alias of the same name. Built-in key names always keep
+    /// their meaning.
+    pub fn key_alias(mut self, name: &str, key: Key) -> Self {
+        self.key_aliases.insert(name.trim().to_lowercase(), key);
+        self
+    }
+
+    /// Parse a hotkey like `Hotkey::from_str`, with the extra names
+    pub fn parse(&self, s: &str) -> Result<Hotkey> {
+        Hotkey::parse_with(
+            s,
+            |part| {
+                let alias = || self.aliases.get(&part.to_lowercase()).copied();
+                match Modifiers::parse_single(part) {
+                    // Hyper names a combination rather than a key, so an
+                    // app's own meaning for it wins
+                    Some(Modifiers::HYPER) => alias().or(Some(Modifiers::HYPER)),
+                    Some(modifier) => Some(modifier),
+                    None => alias(),
+                }
+            },
+            |part| {
+                Key::from_str(part).or_else(|e| self.key_aliases.get(&part.to_lowercase()).copied().ok_or(e))
+            },
+        )
+    }
+
+    /// Make `Hotkey::from_str`, and so `str::parse`, use `parser`
+    ///
+    /// Lets apps define their names once at startup instead of passing a
+    /// parser to every place that parses hotkeys. `None` restores the
+    /// built-in names.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::{Hotkey, HotkeyParser, Key};
+    ///
+    /// HotkeyParser::set_default(Some(HotkeyParser::new().key_alias("Launch", Key::F13)));
+    /// assert_eq!("Ctrl+Launch".parse::<Hotkey>()?.key, Some(Key::F13));
+    /// # Ok::<(), handy_keys::Error>(())
+    /// ```
+    pub fn set_default(parser: Option<HotkeyParser>) {
+        *DEFAULT.write().unwrap_or_else(PoisonError::into_inner) = parser;
+    }
+}
+
+/// A copy of the default parser, or a parser with the built-in names if
+/// none is set
+pub(crate) fn current() -> HotkeyParser {
+    DEFAULT.read().unwrap_or_else(PoisonError::into_inner).clone().unwrap_or_default()
+}
+
+/// Parse with the default parser, or the built-in names if none is set
+pub(crate) fn parse_default(s: &str) -> Result<Hotkey> {
+    match DEFAULT.read().unwrap_or_else(PoisonError::into_inner).as_ref() {
+        Some(parser) => parser.parse(s),
+        None => Hotkey::parse_with(s, Modifiers::parse_single, Key::from_str),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localized_names() {
+        let parser = HotkeyParser::localized();
+        let cases = [
+            ("Strg+Alt+Entf", None),
+            ("STRG+UMSCHALT+K", Some(Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, Key::K))),
+            ("Ctrl+Maj+S", Some(Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, Key::S))),
+            ("Mayús+Opción+Space", Some(Hotkey::new(Modifiers::SHIFT | Modifiers::OPT, Key::Space))),
+            ("Befehl+Umschalt", Some(Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, None))),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(parser.parse(s).ok(), expected.map(Result::unwrap), "{s}");
+        }
+    }
+
+    #[test]
+    fn only_enabled_names_accepted() {
+        assert!(HotkeyParser::new().parse("Strg+K").is_err());
+        assert!("Strg+K".parse::<Hotkey>().is_err());
+
+        let parser = HotkeyParser::new().modifier_alias("Hyper", Modifiers::CTRL | Modifiers::OPT);
+        assert_eq!(parser.parse("hyper+k").unwrap().modifiers, Modifiers::CTRL | Modifiers::OPT);
+        // English names can't be redefined
+        let parser = HotkeyParser::new().modifier_alias("Shift", Modifiers::CTRL);
+        assert_eq!(parser.parse("Shift+K").unwrap().modifiers, Modifiers::SHIFT);
+    }
+
+    #[test]
+    fn key_aliases() {
+        let parser = HotkeyParser::new().key_alias("Launch", Key::F13).key_alias("Space", Key::F14);
+        assert_eq!(parser.parse("Cmd+LAUNCH").unwrap(), Hotkey::new(Modifiers::CMD, Key::F13).unwrap());
+        // Built-in key names can't be redefined
+        assert_eq!(parser.parse("Space").unwrap().key, Some(Key::Space));
+        assert!(parser.parse("Launch+Launch").is_err());
+    }
+
+    #[test]
+    fn default_parser_used_by_from_str() {
+        // Names no other test parses, as the default is process-wide
+        assert!("Hypermod+Launchpadkey".parse::<Hotkey>().is_err());
+        HotkeyParser::set_default(Some(
+            HotkeyParser::new()
+                .modifier_alias("Hypermod", Modifiers::CTRL | Modifiers::OPT)
+                .key_alias("Launchpadkey", Key::F13),
+        ));
+        let parsed = "Hypermod+Launchpadkey".parse::<Hotkey>();
+        HotkeyParser::set_default(None);
+        assert_eq!(parsed.unwrap(), Hotkey::new(Modifiers::CTRL | Modifiers::OPT, Key::F13).unwrap());
+        assert!("Hypermod+Launchpadkey".parse::<Hotkey>().is_err());
+    }
+}
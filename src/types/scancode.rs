@@ -0,0 +1,235 @@
+//! Layout-independent physical key positions
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+use super::modifiers::Modifiers;
+
+/// The physical position of a key, as a USB HID keyboard usage ID
+///
+/// Unlike a [`Key`], a scancode identifies where a key sits on the keyboard
+/// rather than what it types, so it is the same on every platform and layout.
+/// For example, the key typing "Z" on a German QWERTZ keyboard has the scancode
+/// of [`Key::Y`], because that's the key in the same position on a US keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Scancode(pub u16);
+
+/// Scancodes of keys, named after their position on a US ANSI keyboard
+const SCANCODES: &[(Key, u16)] = &[
+    (Key::A, 0x04), (Key::B, 0x05), (Key::C, 0x06), (Key::D, 0x07),
+    (Key::E, 0x08), (Key::F, 0x09), (Key::G, 0x0A), (Key::H, 0x0B),
+    (Key::I, 0x0C), (Key::J, 0x0D), (Key::K, 0x0E), (Key::L, 0x0F),
+    (Key::M, 0x10), (Key::N, 0x11), (Key::O, 0x12), (Key::P, 0x13),
+    (Key::Q, 0x14), (Key::R, 0x15), (Key::S, 0x16), (Key::T, 0x17),
+    (Key::U, 0x18), (Key::V, 0x19), (Key::W, 0x1A), (Key::X, 0x1B),
+    (Key::Y, 0x1C), (Key::Z, 0x1D),
+
+    (Key::Num1, 0x1E), (Key::Num2, 0x1F), (Key::Num3, 0x20), (Key::Num4, 0x21),
+    (Key::Num5, 0x22), (Key::Num6, 0x23), (Key::Num7, 0x24), (Key::Num8, 0x25),
+    (Key::Num9, 0x26), (Key::Num0, 0x27),
+
+    (Key::Return, 0x28),
+    (Key::Escape, 0x29),
+    (Key::Delete, 0x2A),
+    (Key::Tab, 0x2B),
+    (Key::Space, 0x2C),
+    (Key::Minus, 0x2D),
+    (Key::Equal, 0x2E),
+    (Key::LeftBracket, 0x2F),
+    (Key::RightBracket, 0x30),
+    (Key::Backslash, 0x31),
+    (Key::Semicolon, 0x33),
+    (Key::Quote, 0x34),
+    (Key::Grave, 0x35),
+    (Key::Comma, 0x36),
+    (Key::Period, 0x37),
+    (Key::Slash, 0x38),
+    (Key::CapsLock, 0x39),
+
+    (Key::F1, 0x3A), (Key::F2, 0x3B), (Key::F3, 0x3C), (Key::F4, 0x3D),
+    (Key::F5, 0x3E), (Key::F6, 0x3F), (Key::F7, 0x40), (Key::F8, 0x41),
+    (Key::F9, 0x42), (Key::F10, 0x43), (Key::F11, 0x44), (Key::F12, 0x45),
+
+    (Key::PrintScreen, 0x46),
+    (Key::ScrollLock, 0x47),
+    (Key::Pause, 0x48),
+    (Key::Insert, 0x49),
+    (Key::Home, 0x4A),
+    (Key::PageUp, 0x4B),
+    (Key::ForwardDelete, 0x4C),
+    (Key::End, 0x4D),
+    (Key::PageDown, 0x4E),
+    (Key::RightArrow, 0x4F),
+    (Key::LeftArrow, 0x50),
+    (Key::DownArrow, 0x51),
+    (Key::UpArrow, 0x52),
+
+    // Mac keyboards have Clear where other keyboards have Num Lock
+    #[cfg(target_os = "macos")]
+    (Key::KeypadClear, 0x53),
+    (Key::NumLock, 0x53),
+    (Key::KeypadDivide, 0x54),
+    (Key::KeypadMultiply, 0x55),
+    (Key::KeypadMinus, 0x56),
+    (Key::KeypadPlus, 0x57),
+    (Key::KeypadEnter, 0x58),
+    (Key::Keypad1, 0x59), (Key::Keypad2, 0x5A), (Key::Keypad3, 0x5B),
+    (Key::Keypad4, 0x5C), (Key::Keypad5, 0x5D), (Key::Keypad6, 0x5E),
+    (Key::Keypad7, 0x5F), (Key::Keypad8, 0x60), (Key::Keypad9, 0x61),
+    (Key::Keypad0, 0x62),
+    (Key::KeypadDecimal, 0x63),
+    (Key::KeypadEquals, 0x67),
+
+    (Key::F13, 0x68), (Key::F14, 0x69), (Key::F15, 0x6A), (Key::F16, 0x6B),
+    (Key::F17, 0x6C), (Key::F18, 0x6D), (Key::F19, 0x6E), (Key::F20, 0x6F),
+
+    (Key::Section, 0x64),
+    (Key::ContextMenu, 0x65),
+    (Key::Ro, 0x87),
+    (Key::Yen, 0x89),
+    // Korean keyboards have Hangul and Hanja in these positions, so those
+    // keys have the same scancodes
+    (Key::Kana, 0x90),
+    (Key::Eisu, 0x91),
+
+    // Only the volume keys are on the keyboard usage page, the other media
+    // keys are consumer controls
+    (Key::VolumeMute, 0x7F),
+    (Key::VolumeUp, 0x80),
+    (Key::VolumeDown, 0x81),
+];
+
+impl Key {
+    /// The physical position of this key, or `None` for mouse buttons
+    pub fn scancode(&self) -> Option<Scancode> {
+        SCANCODES
+            .iter()
+            .find(|(key, _)| key == self)
+            .map(|&(_, code)| Scancode(code))
+    }
+
+    /// The key at a physical position, if it's one this crate knows
+    pub fn from_scancode(scancode: Scancode) -> Option<Key> {
+        SCANCODES
+            .iter()
+            .find(|&&(_, code)| code == scancode.0)
+            .map(|&(key, _)| key)
+    }
+}
+
+/// A hotkey stored by physical key position, for persisting bindings
+///
+/// A [`Hotkey`] saved on one keyboard layout and loaded on another may end up
+/// on a different physical key. Storing a `PhysicalHotkey` instead keeps the
+/// binding in the same place, while the caption preserves what the user saw
+/// when the hotkey was recorded.
+///
+/// # Examples
+/// ```
+/// use handy_keys::{Hotkey, PhysicalHotkey};
+///
+/// let hotkey: Hotkey = "cmd+shift+k".parse().unwrap();
+/// let stored = hotkey.to_physical().unwrap();
+/// assert_eq!(stored.to_hotkey().unwrap(), hotkey);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PhysicalHotkey {
+    pub modifiers: Modifiers,
+    pub scancode: Option<Scancode>,
+    /// How the hotkey was displayed when it was recorded
+    pub caption: String,
+}
+
+impl PhysicalHotkey {
+    /// Create a physical hotkey with a custom caption
+    ///
+    /// Use this when the caption shown to the user differs from the hotkey's
+    /// [`Display`](std::fmt::Display) output, e.g. a layout-specific character.
+    /// Returns an error if the hotkey's key is a mouse button.
+    pub fn new(hotkey: Hotkey, caption: impl Into<String>) -> Result<Self> {
+        let scancode = match hotkey.key {
+            Some(key) => Some(key.scancode().ok_or(Error::NoScancode(key))?),
+            None => None,
+        };
+        Ok(Self {
+            modifiers: hotkey.modifiers,
+            scancode,
+            caption: caption.into(),
+        })
+    }
+
+    /// Convert back to a hotkey for the key at the stored position
+    pub fn to_hotkey(&self) -> Result<Hotkey> {
+        let key = match self.scancode {
+            Some(scancode) => Some(
+                Key::from_scancode(scancode)
+                    .ok_or_else(|| Error::UnknownKey(format!("scancode {:#04x}", scancode.0)))?,
+            ),
+            None => None,
+        };
+        Hotkey::new(self.modifiers, key)
+    }
+}
+
+impl Hotkey {
+    /// Convert to a [`PhysicalHotkey`], captioned with this hotkey's display string
+    ///
+    /// Returns an error if the key is a mouse button.
+    pub fn to_physical(&self) -> Result<PhysicalHotkey> {
+        PhysicalHotkey::new(*self, self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scancodes_round_trip() {
+        for &(key, code) in SCANCODES {
+            assert_eq!(key.scancode(), Some(Scancode(code)));
+            #[cfg(not(target_os = "macos"))]
+            assert_eq!(Key::from_scancode(Scancode(code)), Some(key));
+        }
+    }
+
+    #[test]
+    fn mouse_buttons_have_no_scancode() {
+        assert_eq!(Key::MouseLeft.scancode(), None);
+        let hotkey = Hotkey::new(Modifiers::CMD, Key::MouseX1).unwrap();
+        assert!(matches!(hotkey.to_physical(), Err(Error::NoScancode(Key::MouseX1))));
+    }
+
+    #[test]
+    fn physical_hotkey_keeps_caption() {
+        // Recorded on QWERTZ, where the key in the Y position types Z
+        let hotkey = Hotkey::new(Modifiers::CTRL, Key::Y).unwrap();
+        let stored = PhysicalHotkey::new(hotkey, "Ctrl+Z").unwrap();
+
+        assert_eq!(stored.caption, "Ctrl+Z");
+        assert_eq!(stored.scancode, Some(Scancode(0x1C)));
+        assert_eq!(stored.to_hotkey().unwrap(), hotkey);
+    }
+
+    #[test]
+    fn modifier_only_physical_hotkey() {
+        let hotkey = Hotkey::new(Modifiers::CMD | Modifiers::SHIFT, None).unwrap();
+        let stored = hotkey.to_physical().unwrap();
+        assert_eq!(stored.scancode, None);
+        assert_eq!(stored.to_hotkey().unwrap(), hotkey);
+    }
+
+    #[test]
+    fn unknown_scancode_is_an_error() {
+        let stored = PhysicalHotkey {
+            modifiers: Modifiers::CMD,
+            scancode: Some(Scancode(0xFFFF)),
+            caption: String::new(),
+        };
+        assert!(matches!(stored.to_hotkey(), Err(Error::UnknownKey(_))));
+    }
+}
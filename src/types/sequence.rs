@@ -0,0 +1,143 @@
+//! Multi-chord key sequences such as `Ctrl+K Ctrl+B`
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+
+use super::hotkey::Hotkey;
+
+/// Hotkeys typed one after the other, like Emacs and VS Code leader keys
+///
+/// Every chord must have a key, since modifiers alone are pressed on the way
+/// to typing the next chord.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Hotkey>", into = "Vec<Hotkey>")]
+pub struct HotkeySequence {
+    chords: Vec<Hotkey>,
+}
+
+impl HotkeySequence {
+    /// Create a sequence from its chords
+    ///
+    /// Returns an error if there are no chords or one of them has no key.
+    pub fn new(chords: Vec<Hotkey>) -> Result<Self> {
+        if chords.is_empty() {
+            return Err(Error::InvalidHotkeyFormat("Empty key sequence".to_string()));
+        }
+        if let Some(chord) = chords.iter().find(|chord| chord.key.is_none()) {
+            return Err(Error::InvalidHotkeyFormat(format!(
+                "Chord '{}' of a key sequence has no key",
+                chord
+            )));
+        }
+        Ok(Self { chords })
+    }
+
+    /// The chords, in the order they are typed
+    pub fn chords(&self) -> &[Hotkey] {
+        &self.chords
+    }
+
+    /// The chord that starts the sequence
+    pub fn first(&self) -> Hotkey {
+        self.chords[0]
+    }
+}
+
+impl TryFrom<Vec<Hotkey>> for HotkeySequence {
+    type Error = Error;
+
+    fn try_from(chords: Vec<Hotkey>) -> Result<Self> {
+        Self::new(chords)
+    }
+}
+
+impl From<HotkeySequence> for Vec<Hotkey> {
+    fn from(sequence: HotkeySequence) -> Self {
+        sequence.chords
+    }
+}
+
+impl fmt::Display for HotkeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chord) in self.chords.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", chord)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HotkeySequence {
+    type Err = Error;
+
+    /// Parse a sequence from chords separated by spaces or commas, like
+    /// "Ctrl+K Ctrl+B" or "Ctrl+K, Ctrl+B"
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::HotkeySequence;
+    ///
+    /// let sequence: HotkeySequence = "Ctrl+K Ctrl+B".parse().unwrap();
+    /// assert_eq!(sequence.chords().len(), 2);
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let chords = if s.contains(',') {
+            s.split(',').map(str::parse).collect::<Result<Vec<Hotkey>>>()?
+        } else {
+            // Spaces may also surround a `+` within a chord
+            let mut parts: Vec<String> = Vec::new();
+            for token in s.split_whitespace() {
+                match parts.last_mut() {
+                    Some(part) if part.ends_with('+') || token.starts_with('+') => {
+                        part.push_str(token)
+                    }
+                    _ => parts.push(token.to_string()),
+                }
+            }
+            parts.iter().map(|part| part.parse()).collect::<Result<Vec<Hotkey>>>()?
+        };
+        Self::new(chords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Key, Modifiers};
+
+    #[test]
+    fn parse_and_display() {
+        let sequence: HotkeySequence = "Ctrl+K Ctrl+B".parse().unwrap();
+        assert_eq!(
+            sequence.chords(),
+            [
+                Hotkey::new(Modifiers::CTRL, Key::K).unwrap(),
+                Hotkey::new(Modifiers::CTRL, Key::B).unwrap(),
+            ]
+        );
+        assert_eq!(sequence.to_string().parse::<HotkeySequence>().unwrap(), sequence);
+
+        assert_eq!("Ctrl+K, Ctrl+B".parse::<HotkeySequence>().unwrap(), sequence);
+        assert_eq!("Ctrl + K  Ctrl +B".parse::<HotkeySequence>().unwrap(), sequence);
+    }
+
+    #[test]
+    fn rejects_empty_and_modifier_only_chords() {
+        assert!("".parse::<HotkeySequence>().is_err());
+        assert!("Ctrl+K Ctrl".parse::<HotkeySequence>().is_err());
+        assert!(HotkeySequence::new(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let sequence: HotkeySequence = "Ctrl+K Ctrl+B".parse().unwrap();
+        let json = serde_json::to_string(&sequence).unwrap();
+        assert_eq!(serde_json::from_str::<HotkeySequence>(&json).unwrap(), sequence);
+        assert!(serde_json::from_str::<HotkeySequence>("[]").is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! URL- and filesystem-safe hotkey identifiers
+
+use crate::error::{Error, Result};
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+use super::modifiers::{Modifiers, SIDED};
+
+/// Slug names of modifiers, in the order they appear in a slug
+///
+/// A modifier required on one side is written with that side's name instead
+/// of its own, e.g. `ropt` for Right Alt.
+const MODIFIER_SLUGS: &[(Modifiers, &str)] = &[
+    (Modifiers::L_CTRL, "lctrl"),
+    (Modifiers::R_CTRL, "rctrl"),
+    (Modifiers::CTRL, "ctrl"),
+    (Modifiers::L_OPT, "lopt"),
+    (Modifiers::R_OPT, "ropt"),
+    (Modifiers::OPT, "opt"),
+    (Modifiers::L_SHIFT, "lshift"),
+    (Modifiers::R_SHIFT, "rshift"),
+    (Modifiers::SHIFT, "shift"),
+    (Modifiers::L_CMD, "lcmd"),
+    (Modifiers::R_CMD, "rcmd"),
+    (Modifiers::CMD, "cmd"),
+    (Modifiers::FN, "fn"),
+];
+
+/// Slug names of keys
+///
+/// These are part of the slug format and must never change. Names only use
+/// `[a-z0-9]`, so `-` can separate the parts of a slug.
+const KEY_SLUGS: &[(Key, &str)] = &[
+    (Key::A, "a"), (Key::B, "b"), (Key::C, "c"), (Key::D, "d"), (Key::E, "e"),
+    (Key::F, "f"), (Key::G, "g"), (Key::H, "h"), (Key::I, "i"), (Key::J, "j"),
+    (Key::K, "k"), (Key::L, "l"), (Key::M, "m"), (Key::N, "n"), (Key::O, "o"),
+    (Key::P, "p"), (Key::Q, "q"), (Key::R, "r"), (Key::S, "s"), (Key::T, "t"),
+    (Key::U, "u"), (Key::V, "v"), (Key::W, "w"), (Key::X, "x"), (Key::Y, "y"),
+    (Key::Z, "z"),
+
+    (Key::Num0, "0"), (Key::Num1, "1"), (Key::Num2, "2"), (Key::Num3, "3"),
+    (Key::Num4, "4"), (Key::Num5, "5"), (Key::Num6, "6"), (Key::Num7, "7"),
+    (Key::Num8, "8"), (Key::Num9, "9"),
+
+    (Key::F1, "f1"), (Key::F2, "f2"), (Key::F3, "f3"), (Key::F4, "f4"),
+    (Key::F5, "f5"), (Key::F6, "f6"), (Key::F7, "f7"), (Key::F8, "f8"),
+    (Key::F9, "f9"), (Key::F10, "f10"), (Key::F11, "f11"), (Key::F12, "f12"),
+    (Key::F13, "f13"), (Key::F14, "f14"), (Key::F15, "f15"), (Key::F16, "f16"),
+    (Key::F17, "f17"), (Key::F18, "f18"), (Key::F19, "f19"), (Key::F20, "f20"),
+
+    (Key::Space, "space"),
+    (Key::Return, "return"),
+    (Key::Tab, "tab"),
+    (Key::Escape, "escape"),
+    (Key::Delete, "delete"),
+    (Key::ForwardDelete, "forwarddelete"),
+    (Key::Home, "home"),
+    (Key::End, "end"),
+    (Key::PageUp, "pageup"),
+    (Key::PageDown, "pagedown"),
+    (Key::PrintScreen, "printscreen"),
+    (Key::Insert, "insert"),
+    (Key::Pause, "pause"),
+    (Key::ContextMenu, "contextmenu"),
+
+    (Key::LeftArrow, "left"),
+    (Key::RightArrow, "right"),
+    (Key::UpArrow, "up"),
+    (Key::DownArrow, "down"),
+
+    (Key::Minus, "minus"),
+    (Key::Equal, "equal"),
+    (Key::LeftBracket, "leftbracket"),
+    (Key::RightBracket, "rightbracket"),
+    (Key::Backslash, "backslash"),
+    (Key::Semicolon, "semicolon"),
+    (Key::Quote, "quote"),
+    (Key::Comma, "comma"),
+    (Key::Period, "period"),
+    (Key::Slash, "slash"),
+    (Key::Grave, "grave"),
+
+    (Key::Keypad0, "keypad0"), (Key::Keypad1, "keypad1"), (Key::Keypad2, "keypad2"),
+    (Key::Keypad3, "keypad3"), (Key::Keypad4, "keypad4"), (Key::Keypad5, "keypad5"),
+    (Key::Keypad6, "keypad6"), (Key::Keypad7, "keypad7"), (Key::Keypad8, "keypad8"),
+    (Key::Keypad9, "keypad9"),
+    (Key::KeypadDecimal, "keypaddecimal"),
+    (Key::KeypadMultiply, "keypadmultiply"),
+    (Key::KeypadPlus, "keypadplus"),
+    (Key::KeypadClear, "keypadclear"),
+    (Key::KeypadDivide, "keypaddivide"),
+    (Key::KeypadEnter, "keypadenter"),
+    (Key::KeypadMinus, "keypadminus"),
+    (Key::KeypadEquals, "keypadequals"),
+
+    (Key::CapsLock, "capslock"),
+    (Key::ScrollLock, "scrolllock"),
+    (Key::NumLock, "numlock"),
+
+    (Key::Yen, "yen"),
+    (Key::Eisu, "eisu"),
+    (Key::Kana, "kana"),
+    (Key::Section, "section"),
+    (Key::Ro, "ro"),
+    (Key::Hangul, "hangul"),
+    (Key::Hanja, "hanja"),
+
+    (Key::PlayPause, "playpause"),
+    (Key::NextTrack, "nexttrack"),
+    (Key::PreviousTrack, "previoustrack"),
+    (Key::VolumeUp, "volumeup"),
+    (Key::VolumeDown, "volumedown"),
+    (Key::VolumeMute, "volumemute"),
+    (Key::BrightnessUp, "brightnessup"),
+    (Key::BrightnessDown, "brightnessdown"),
+
+    (Key::MouseLeft, "mouseleft"),
+    (Key::MouseRight, "mouseright"),
+    (Key::MouseMiddle, "mousemiddle"),
+    (Key::MouseX1, "mousex1"),
+    (Key::MouseX2, "mousex2"),
+];
+
+/// Prefix of the slug of a [`Key::Other`], followed by its code in hex
+const OTHER_PREFIX: &str = "other";
+
+impl Hotkey {
+    /// Format hotkey as a stable identifier safe for URLs and file names
+    ///
+    /// Slugs only contain `[a-z0-9-]`, list modifiers in a fixed order and are
+    /// the same on every platform, e.g. `"ctrl-shift-k"`. They won't change
+    /// between versions, so they can be used as config keys, in deep links
+    /// and as telemetry labels.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::Hotkey;
+    ///
+    /// let hotkey: Hotkey = "Shift+Ctrl+K".parse().unwrap();
+    /// assert_eq!(hotkey.to_slug(), "ctrl-shift-k");
+    /// assert_eq!(Hotkey::from_slug("ctrl-shift-k").unwrap(), hotkey);
+    /// ```
+    pub fn to_slug(&self) -> String {
+        let mut parts: Vec<String> = MODIFIER_SLUGS
+            .iter()
+            .filter(|(modifier, _)| {
+                self.modifiers.contains(*modifier)
+                    && (!modifier.side_bits().is_empty()
+                        || (self.modifiers.side_bits() & side_bits_of(*modifier)).is_empty())
+            })
+            .map(|(_, name)| name.to_string())
+            .collect();
+        if let Some(key) = self.key {
+            parts.push(key_slug(key));
+        }
+        parts.join("-")
+    }
+
+    /// Parse a hotkey from a slug made by [`to_slug`](Self::to_slug)
+    pub fn from_slug(slug: &str) -> Result<Self> {
+        let invalid = || Error::InvalidHotkeyFormat(slug.to_string());
+
+        let mut modifiers = Modifiers::empty();
+        let mut key = None;
+        let mut parts = slug.split('-').peekable();
+        while let Some(part) = parts.next() {
+            if let Some(&(modifier, _)) = MODIFIER_SLUGS.iter().find(|(_, name)| *name == part) {
+                // A modifier is named once, either with or without its sides
+                let unsided = modifier.without_sides();
+                let named = modifiers.contains(unsided)
+                    && (modifier == unsided
+                        || (modifiers.side_bits() & side_bits_of(modifier)).is_empty()
+                        || modifiers.contains(modifier));
+                if named {
+                    return Err(invalid());
+                }
+                modifiers |= modifier;
+            } else if parts.peek().is_none() {
+                key = Some(key_from_slug(part).ok_or_else(invalid)?);
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Hotkey::new(modifiers, key)
+    }
+}
+
+/// The side bits of both sides of `modifier`, empty for Fn
+fn side_bits_of(modifier: Modifiers) -> Modifiers {
+    SIDED
+        .iter()
+        .find(|(unsided, _, _, _)| *unsided == modifier.without_sides())
+        .map_or(Modifiers::empty(), |&(_, left, right, _)| (left | right).side_bits())
+}
+
+fn key_slug(key: Key) -> String {
+    if let Key::Other(code) = key {
+        return format!("{OTHER_PREFIX}{code:x}");
+    }
+    KEY_SLUGS
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, name)| name.to_string())
+        .expect("every named key has a slug")
+}
+
+fn key_from_slug(slug: &str) -> Option<Key> {
+    if let Some(&(key, _)) = KEY_SLUGS.iter().find(|(_, name)| *name == slug) {
+        return Some(key);
+    }
+    let hex = slug.strip_prefix(OTHER_PREFIX)?;
+    if hex.is_empty() || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok().map(Key::Other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slug(s: &str) -> String {
+        s.parse::<Hotkey>().unwrap().to_slug()
+    }
+
+    #[test]
+    fn slug_format() {
+        assert_eq!(slug("Cmd+Shift+Ctrl+Opt+Fn+K"), "ctrl-opt-shift-cmd-fn-k");
+        assert_eq!(slug("Ctrl+-"), "ctrl-minus");
+        assert_eq!(slug("Keypad+"), "keypadplus");
+        assert_eq!(slug("Cmd+Shift"), "shift-cmd");
+        assert_eq!(slug("Other(0x5E)"), "other5e");
+        assert_eq!(slug("RightAlt+K"), "ropt-k");
+        assert_eq!(slug("LCtrl+RCtrl+Shift+K"), "lctrl-rctrl-shift-k");
+        assert_ne!(slug("RightAlt+K"), slug("Alt+K"));
+    }
+
+    #[test]
+    fn parse_slug() {
+        assert_eq!(Hotkey::from_slug("shift-cmd").unwrap(), "Cmd+Shift".parse().unwrap());
+        assert_eq!(Hotkey::from_slug("other5e").unwrap().key, Some(Key::Other(0x5E)));
+    }
+
+    #[test]
+    fn invalid_slugs_rejected() {
+        for slug in ["", "k-ctrl", "ctrl-ctrl-k", "ctrl--k", "Ctrl-K", "ctrl-", "other", "other+5", "a-b", "ctrl-lctrl-k",
+            "lctrl-ctrl-k", "ropt-ropt-k"] {
+            assert!(Hotkey::from_slug(slug).is_err(), "{slug}");
+        }
+    }
+
+    #[test]
+    fn slug_names_are_unique_and_safe() {
+        for (i, (_, name)) in KEY_SLUGS.iter().enumerate() {
+            assert!(name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit()));
+            assert!(!name.starts_with(OTHER_PREFIX));
+            assert!(KEY_SLUGS[i + 1..].iter().all(|(_, other)| other != name));
+            assert!(MODIFIER_SLUGS.iter().all(|(_, modifier)| modifier != name));
+        }
+    }
+
+    #[test]
+    fn every_key_has_a_slug() {
+        // key_slug expects this
+        for key in (0..=u16::MAX).filter_map(Key::from_code) {
+            assert!(KEY_SLUGS.iter().any(|(k, _)| *k == key), "{key:?}");
+            assert_eq!(key_from_slug(&key_slug(key)), Some(key));
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! The keybinding format of VS Code's `keybindings.json`
+//!
+//! Bindings look like `"ctrl+shift+k"`, with chords separated by spaces as in
+//! `"ctrl+k ctrl+s"`. A key in brackets, like `"cmd+[Minus]"`, is a scan code
+//! named after its `KeyboardEvent.code`.
+
+use crate::error::{Error, Result};
+
+use super::hotkey::Hotkey;
+use super::key::Key;
+use super::legacy::is_plain_key;
+use super::modifiers::Modifiers;
+use super::sequence::HotkeySequence;
+use super::web_code::WEB_CODES;
+
+/// VS Code modifier names
+///
+/// `cmd`, `win` and `meta` are the same modifier on macOS, Windows and Linux.
+const VSCODE_MODIFIERS: &[(&str, Modifiers)] = &[
+    ("ctrl", Modifiers::CTRL),
+    ("shift", Modifiers::SHIFT),
+    ("alt", Modifiers::OPT),
+    ("cmd", Modifiers::CMD),
+    ("win", Modifiers::CMD),
+    ("meta", Modifiers::CMD),
+];
+
+/// VS Code key names that differ from ours
+///
+/// Letters, digits and F-keys are written as their lowercased name.
+const VSCODE_KEYS: &[(&str, Key)] = &[
+    ("space", Key::Space),
+    ("enter", Key::Return),
+    ("tab", Key::Tab),
+    ("escape", Key::Escape),
+    ("backspace", Key::Delete),
+    ("delete", Key::ForwardDelete),
+    ("insert", Key::Insert),
+    ("home", Key::Home),
+    ("end", Key::End),
+    ("pageup", Key::PageUp),
+    ("pagedown", Key::PageDown),
+    ("left", Key::LeftArrow),
+    ("right", Key::RightArrow),
+    ("up", Key::UpArrow),
+    ("down", Key::DownArrow),
+    ("pausebreak", Key::Pause),
+    ("printscreen", Key::PrintScreen),
+    ("contextmenu", Key::ContextMenu),
+    ("capslock", Key::CapsLock),
+    ("numlock", Key::NumLock),
+    ("scrolllock", Key::ScrollLock),
+    ("-", Key::Minus),
+    ("=", Key::Equal),
+    ("[", Key::LeftBracket),
+    ("]", Key::RightBracket),
+    ("\\", Key::Backslash),
+    (";", Key::Semicolon),
+    ("'", Key::Quote),
+    (",", Key::Comma),
+    (".", Key::Period),
+    ("/", Key::Slash),
+    ("`", Key::Grave),
+    ("oem_102", Key::Section),
+    ("numpad0", Key::Keypad0),
+    ("numpad1", Key::Keypad1),
+    ("numpad2", Key::Keypad2),
+    ("numpad3", Key::Keypad3),
+    ("numpad4", Key::Keypad4),
+    ("numpad5", Key::Keypad5),
+    ("numpad6", Key::Keypad6),
+    ("numpad7", Key::Keypad7),
+    ("numpad8", Key::Keypad8),
+    ("numpad9", Key::Keypad9),
+    ("numpad_multiply", Key::KeypadMultiply),
+    ("numpad_add", Key::KeypadPlus),
+    ("numpad_subtract", Key::KeypadMinus),
+    ("numpad_decimal", Key::KeypadDecimal),
+    ("numpad_divide", Key::KeypadDivide),
+    ("audiovolumemute", Key::VolumeMute),
+    ("audiovolumeup", Key::VolumeUp),
+    ("audiovolumedown", Key::VolumeDown),
+    ("mediaplaypause", Key::PlayPause),
+    ("mediatracknext", Key::NextTrack),
+    ("mediatrackprevious", Key::PreviousTrack),
+];
+
+impl Hotkey {
+    /// Parse a single-chord binding from VS Code's `keybindings.json`
+    ///
+    /// Names are case-insensitive, and modifiers may be joined with `-` as
+    /// well as `+`. Returns an error for bindings with more than one chord,
+    /// which [`HotkeySequence::from_vscode_string`] parses.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::{Hotkey, Key, Modifiers};
+    ///
+    /// let hotkey = Hotkey::from_vscode_string("ctrl+shift+k").unwrap();
+    /// assert_eq!(hotkey, Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, Key::K).unwrap());
+    /// assert_eq!(Hotkey::from_vscode_string("cmd+[Minus]").unwrap().key, Some(Key::Minus));
+    /// ```
+    pub fn from_vscode_string(s: &str) -> Result<Self> {
+        match s.split_whitespace().collect::<Vec<_>>()[..] {
+            [chord] => vscode_chord(chord),
+            [] => Err(Error::EmptyHotkey),
+            _ => Err(Error::InvalidHotkeyFormat(format!("'{}' has more than one chord", s.trim()))),
+        }
+    }
+}
+
+impl HotkeySequence {
+    /// Parse a binding from VS Code's `keybindings.json`, whose chords are
+    /// separated by spaces, like `"ctrl+k ctrl+s"`
+    ///
+    /// Chords are parsed like [`Hotkey::from_vscode_string`] does. A binding
+    /// with one chord is a sequence of one.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::HotkeySequence;
+    ///
+    /// let sequence = HotkeySequence::from_vscode_string("ctrl+k ctrl+s").unwrap();
+    /// assert_eq!(sequence, "Ctrl+K Ctrl+S".parse().unwrap());
+    /// ```
+    pub fn from_vscode_string(s: &str) -> Result<Self> {
+        let chords = s.split_whitespace().map(vscode_chord).collect::<Result<Vec<_>>>()?;
+        HotkeySequence::new(chords)
+    }
+}
+
+/// Parse one chord, reading modifiers off the front like VS Code does so that
+/// `ctrl+-` and `ctrl--` are both Ctrl and Minus
+fn vscode_chord(chord: &str) -> Result<Hotkey> {
+    let lower = chord.to_lowercase();
+    let mut rest = lower.as_str();
+    let mut modifiers = Modifiers::empty();
+    while let Some((modifier, after)) = VSCODE_MODIFIERS.iter().find_map(|&(name, modifier)| {
+        let after = rest.strip_prefix(name)?;
+        let after = after.strip_prefix('+').or_else(|| after.strip_prefix('-'))?;
+        Some((modifier, after))
+    }) {
+        modifiers |= modifier;
+        rest = after;
+    }
+
+    let key = vscode_key(rest).ok_or_else(|| Error::UnknownKey(rest.to_string()))?;
+    Hotkey::new(modifiers, key)
+}
+
+fn vscode_key(name: &str) -> Option<Key> {
+    if let Some(code) = name.strip_prefix('[').and_then(|name| name.strip_suffix(']')) {
+        return WEB_CODES
+            .iter()
+            .find(|(_, web_code)| web_code.eq_ignore_ascii_case(code))
+            .map(|&(key, _)| key);
+    }
+    if let Some(&(_, key)) = VSCODE_KEYS.iter().find(|(vscode_name, _)| *vscode_name == name) {
+        return Some(key);
+    }
+    name.parse::<Key>().ok().filter(|key| is_plain_key(*key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_vscode_bindings() {
+        let cases = [
+            ("ctrl+shift+k", Modifiers::CTRL | Modifiers::SHIFT, Key::K),
+            ("Ctrl+Alt+Delete", Modifiers::CTRL | Modifiers::OPT, Key::ForwardDelete),
+            ("cmd+backspace", Modifiers::CMD, Key::Delete),
+            ("ctrl+-", Modifiers::CTRL, Key::Minus),
+            ("ctrl--", Modifiers::CTRL, Key::Minus),
+            ("shift-alt-down", Modifiers::SHIFT | Modifiers::OPT, Key::DownArrow),
+            ("meta+[Minus]", Modifiers::CMD, Key::Minus),
+            ("win+[numpadenter]", Modifiers::CMD, Key::KeypadEnter),
+            ("numpad_add", Modifiers::empty(), Key::KeypadPlus),
+            ("f12", Modifiers::empty(), Key::F12),
+        ];
+        for (s, modifiers, key) in cases {
+            assert_eq!(Hotkey::from_vscode_string(s).unwrap(), Hotkey::new(modifiers, key).unwrap(), "{s}");
+        }
+    }
+
+    #[test]
+    fn parses_vscode_chords() {
+        let sequence = HotkeySequence::from_vscode_string("ctrl+k  ctrl+shift+s").unwrap();
+        assert_eq!(sequence, "Ctrl+K Ctrl+Shift+S".parse().unwrap());
+        assert_eq!(HotkeySequence::from_vscode_string("f1").unwrap().chords().len(), 1);
+
+        assert!(matches!(Hotkey::from_vscode_string("ctrl+k ctrl+s"), Err(Error::InvalidHotkeyFormat(_))));
+        assert!(HotkeySequence::from_vscode_string("").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_vscode_keys() {
+        for s in ["ctrl+", "hyper+k", "ctrl+[Nope]", "ctrl+shift", "ctrl+keypad"] {
+            assert!(matches!(Hotkey::from_vscode_string(s), Err(Error::UnknownKey(_))), "{s}");
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Conversion between keys and W3C `KeyboardEvent.code` names
+
+use super::key::Key;
+
+/// `KeyboardEvent.code` names of keys
+///
+/// Where two keys share a name, the first is the one a name converts to.
+pub(super) const WEB_CODES: &[(Key, &str)] = &[
+    (Key::A, "KeyA"), (Key::B, "KeyB"), (Key::C, "KeyC"), (Key::D, "KeyD"),
+    (Key::E, "KeyE"), (Key::F, "KeyF"), (Key::G, "KeyG"), (Key::H, "KeyH"),
+    (Key::I, "KeyI"), (Key::J, "KeyJ"), (Key::K, "KeyK"), (Key::L, "KeyL"),
+    (Key::M, "KeyM"), (Key::N, "KeyN"), (Key::O, "KeyO"), (Key::P, "KeyP"),
+    (Key::Q, "KeyQ"), (Key::R, "KeyR"), (Key::S, "KeyS"), (Key::T, "KeyT"),
+    (Key::U, "KeyU"), (Key::V, "KeyV"), (Key::W, "KeyW"), (Key::X, "KeyX"),
+    (Key::Y, "KeyY"), (Key::Z, "KeyZ"),
+
+    (Key::Num0, "Digit0"), (Key::Num1, "Digit1"), (Key::Num2, "Digit2"),
+    (Key::Num3, "Digit3"), (Key::Num4, "Digit4"), (Key::Num5, "Digit5"),
+    (Key::Num6, "Digit6"), (Key::Num7, "Digit7"), (Key::Num8, "Digit8"),
+    (Key::Num9, "Digit9"),
+
+    (Key::F1, "F1"), (Key::F2, "F2"), (Key::F3, "F3"), (Key::F4, "F4"),
+    (Key::F5, "F5"), (Key::F6, "F6"), (Key::F7, "F7"), (Key::F8, "F8"),
+    (Key::F9, "F9"), (Key::F10, "F10"), (Key::F11, "F11"), (Key::F12, "F12"),
+    (Key::F13, "F13"), (Key::F14, "F14"), (Key::F15, "F15"), (Key::F16, "F16"),
+    (Key::F17, "F17"), (Key::F18, "F18"), (Key::F19, "F19"), (Key::F20, "F20"),
+
+    (Key::Space, "Space"),
+    (Key::Return, "Enter"),
+    (Key::Tab, "Tab"),
+    (Key::Escape, "Escape"),
+    (Key::Delete, "Backspace"),
+    (Key::ForwardDelete, "Delete"),
+    (Key::Home, "Home"),
+    (Key::End, "End"),
+    (Key::PageUp, "PageUp"),
+    (Key::PageDown, "PageDown"),
+    (Key::PrintScreen, "PrintScreen"),
+    (Key::Insert, "Insert"),
+    (Key::Pause, "Pause"),
+    (Key::ContextMenu, "ContextMenu"),
+    (Key::LeftArrow, "ArrowLeft"),
+    (Key::RightArrow, "ArrowRight"),
+    (Key::UpArrow, "ArrowUp"),
+    (Key::DownArrow, "ArrowDown"),
+
+    (Key::Minus, "Minus"),
+    (Key::Equal, "Equal"),
+    (Key::LeftBracket, "BracketLeft"),
+    (Key::RightBracket, "BracketRight"),
+    (Key::Backslash, "Backslash"),
+    (Key::Semicolon, "Semicolon"),
+    (Key::Quote, "Quote"),
+    (Key::Comma, "Comma"),
+    (Key::Period, "Period"),
+    (Key::Slash, "Slash"),
+    (Key::Grave, "Backquote"),
+
+    (Key::Keypad0, "Numpad0"), (Key::Keypad1, "Numpad1"), (Key::Keypad2, "Numpad2"),
+    (Key::Keypad3, "Numpad3"), (Key::Keypad4, "Numpad4"), (Key::Keypad5, "Numpad5"),
+    (Key::Keypad6, "Numpad6"), (Key::Keypad7, "Numpad7"), (Key::Keypad8, "Numpad8"),
+    (Key::Keypad9, "Numpad9"),
+    (Key::KeypadDecimal, "NumpadDecimal"),
+    (Key::KeypadMultiply, "NumpadMultiply"),
+    (Key::KeypadPlus, "NumpadAdd"),
+    (Key::KeypadDivide, "NumpadDivide"),
+    (Key::KeypadEnter, "NumpadEnter"),
+    (Key::KeypadMinus, "NumpadSubtract"),
+    (Key::KeypadEquals, "NumpadEqual"),
+    // Mac keyboards have Clear where other keyboards have Num Lock, and
+    // browsers name it after Num Lock
+    #[cfg(target_os = "macos")]
+    (Key::KeypadClear, "NumLock"),
+    (Key::NumLock, "NumLock"),
+    (Key::KeypadClear, "NumLock"),
+
+    (Key::CapsLock, "CapsLock"),
+    (Key::ScrollLock, "ScrollLock"),
+
+    (Key::Section, "IntlBackslash"),
+    (Key::Ro, "IntlRo"),
+    (Key::Yen, "IntlYen"),
+    // Korean keyboards have Hangul and Hanja in these positions, so those
+    // keys have the same names
+    (Key::Kana, "Lang1"),
+    (Key::Eisu, "Lang2"),
+    (Key::Hangul, "Lang1"),
+    (Key::Hanja, "Lang2"),
+
+    (Key::PlayPause, "MediaPlayPause"),
+    (Key::NextTrack, "MediaTrackNext"),
+    (Key::PreviousTrack, "MediaTrackPrevious"),
+    (Key::VolumeUp, "AudioVolumeUp"),
+    (Key::VolumeDown, "AudioVolumeDown"),
+    (Key::VolumeMute, "AudioVolumeMute"),
+];
+
+impl Key {
+    /// The W3C `KeyboardEvent.code` name of this key, e.g. `"KeyA"` or
+    /// `"NumpadEnter"`
+    ///
+    /// Returns `None` for mouse buttons and keys browsers have no name for,
+    /// such as the brightness keys.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::Key;
+    ///
+    /// assert_eq!(Key::Num1.to_web_code(), Some("Digit1"));
+    /// assert_eq!(Key::Delete.to_web_code(), Some("Backspace"));
+    /// ```
+    pub fn to_web_code(&self) -> Option<&'static str> {
+        WEB_CODES
+            .iter()
+            .find(|(key, _)| key == self)
+            .map(|&(_, code)| code)
+    }
+
+    /// The key with a W3C `KeyboardEvent.code` name, as a browser reports
+    /// it in a `keydown` event
+    ///
+    /// Names are case-sensitive. Modifier keys, like `"ShiftLeft"`, have no
+    /// [`Key`] and return `None`; read them from the event's `ctrlKey`,
+    /// `altKey`, `shiftKey` and `metaKey` instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use handy_keys::{Hotkey, Key, Modifiers};
+    ///
+    /// let key = Key::from_web_code("KeyK").unwrap();
+    /// let hotkey = Hotkey::new(Modifiers::CTRL | Modifiers::SHIFT, key).unwrap();
+    /// assert_eq!(hotkey.to_string(), "Ctrl+Shift+K");
+    /// ```
+    pub fn from_web_code(code: &str) -> Option<Key> {
+        WEB_CODES
+            .iter()
+            .find(|&&(_, name)| name == code)
+            .map(|&(key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn web_codes_round_trip() {
+        for &(key, code) in WEB_CODES {
+            assert_eq!(key.to_web_code(), Some(code));
+            if !matches!(key, Key::KeypadClear | Key::Hangul | Key::Hanja) {
+                #[cfg(not(target_os = "macos"))]
+                assert_eq!(Key::from_web_code(code), Some(key), "{code}");
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_web_codes() {
+        assert_eq!(Key::from_web_code("ShiftLeft"), None);
+        assert_eq!(Key::from_web_code("keya"), None);
+        assert_eq!(Key::MouseLeft.to_web_code(), None);
+        assert_eq!(Key::BrightnessUp.to_web_code(), None);
+    }
+}
@@ -0,0 +1,404 @@
+//! Conversion of key events to winit types (requires the `winit` feature)
+
+use winit::event::ElementState;
+use winit::keyboard::{
+    Key as WinitKey, KeyCode, ModifiersState, NamedKey, NativeKey, NativeKeyCode, PhysicalKey,
+};
+
+use crate::types::{Key, KeyEvent, Modifiers};
+
+/// A [`KeyEvent`] in winit's vocabulary
+///
+/// Lets apps that already handle `winit::event::KeyEvent`s reuse that code for
+/// global events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WinitKeyEvent {
+    /// The key as a US layout would produce it
+    ///
+    /// handy-keys doesn't know the active layout, so this is a best guess.
+    /// Prefer `physical_key` for matching.
+    pub logical_key: WinitKey,
+    pub physical_key: PhysicalKey,
+    /// The held modifiers (winit has no Fn modifier, so it's dropped)
+    pub modifiers: ModifiersState,
+    pub state: ElementState,
+}
+
+impl KeyEvent {
+    /// Convert this event to winit keyboard types
+    ///
+    /// Returns `None` for mouse buttons, which aren't keyboard events in winit.
+    pub fn to_winit(&self) -> Option<WinitKeyEvent> {
+        let (logical_key, physical_key) = match (self.key, self.changed_modifier) {
+            (Some(key), _) => (logical_key(key, self.modifiers)?, physical_key(key)?),
+            (None, Some(modifier)) => modifier_keys(modifier),
+            (None, None) => (
+                WinitKey::Unidentified(NativeKey::Unidentified),
+                PhysicalKey::Unidentified(NativeKeyCode::Unidentified),
+            ),
+        };
+
+        Some(WinitKeyEvent {
+            logical_key,
+            physical_key,
+            modifiers: modifiers_state(self.modifiers),
+            state: if self.is_key_down {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            },
+        })
+    }
+}
+
+fn modifiers_state(modifiers: Modifiers) -> ModifiersState {
+    let mut state = ModifiersState::empty();
+    for (modifier, flag) in [
+        (Modifiers::SHIFT, ModifiersState::SHIFT),
+        (Modifiers::CTRL, ModifiersState::CONTROL),
+        (Modifiers::OPT, ModifiersState::ALT),
+        (Modifiers::CMD, ModifiersState::SUPER),
+    ] {
+        if modifiers.contains(modifier) {
+            state |= flag;
+        }
+    }
+    state
+}
+
+/// The keys of a modifier, reported as the left-hand one
+fn modifier_keys(modifier: Modifiers) -> (WinitKey, PhysicalKey) {
+    let (named, code) = if modifier.contains(Modifiers::CMD) {
+        (NamedKey::Super, KeyCode::SuperLeft)
+    } else if modifier.contains(Modifiers::CTRL) {
+        (NamedKey::Control, KeyCode::ControlLeft)
+    } else if modifier.contains(Modifiers::OPT) {
+        (NamedKey::Alt, KeyCode::AltLeft)
+    } else if modifier.contains(Modifiers::SHIFT) {
+        (NamedKey::Shift, KeyCode::ShiftLeft)
+    } else {
+        (NamedKey::Fn, KeyCode::Fn)
+    };
+    (WinitKey::Named(named), PhysicalKey::Code(code))
+}
+
+/// The platform's native code for [`Key::Other`]
+fn native_key(code: u32) -> NativeKey {
+    if cfg!(target_os = "macos") {
+        NativeKey::MacOS(code as u16)
+    } else if cfg!(target_os = "windows") {
+        NativeKey::Windows(code as u16)
+    } else {
+        NativeKey::Unidentified
+    }
+}
+
+fn logical_key(key: Key, modifiers: Modifiers) -> Option<WinitKey> {
+    if let Some(character) = us_character(key) {
+        let character = if modifiers.contains(Modifiers::SHIFT) {
+            character.to_ascii_uppercase()
+        } else {
+            character
+        };
+        return Some(WinitKey::Character(character.to_string().into()));
+    }
+
+    let named = match key {
+        Key::F1 => NamedKey::F1,
+        Key::F2 => NamedKey::F2,
+        Key::F3 => NamedKey::F3,
+        Key::F4 => NamedKey::F4,
+        Key::F5 => NamedKey::F5,
+        Key::F6 => NamedKey::F6,
+        Key::F7 => NamedKey::F7,
+        Key::F8 => NamedKey::F8,
+        Key::F9 => NamedKey::F9,
+        Key::F10 => NamedKey::F10,
+        Key::F11 => NamedKey::F11,
+        Key::F12 => NamedKey::F12,
+        Key::F13 => NamedKey::F13,
+        Key::F14 => NamedKey::F14,
+        Key::F15 => NamedKey::F15,
+        Key::F16 => NamedKey::F16,
+        Key::F17 => NamedKey::F17,
+        Key::F18 => NamedKey::F18,
+        Key::F19 => NamedKey::F19,
+        Key::F20 => NamedKey::F20,
+        Key::Space => NamedKey::Space,
+        Key::Return | Key::KeypadEnter => NamedKey::Enter,
+        Key::Tab => NamedKey::Tab,
+        Key::Escape => NamedKey::Escape,
+        Key::Delete => NamedKey::Backspace,
+        Key::ForwardDelete => NamedKey::Delete,
+        Key::Home => NamedKey::Home,
+        Key::End => NamedKey::End,
+        Key::PageUp => NamedKey::PageUp,
+        Key::PageDown => NamedKey::PageDown,
+        Key::PrintScreen => NamedKey::PrintScreen,
+        Key::Insert => NamedKey::Insert,
+        Key::Pause => NamedKey::Pause,
+        Key::ContextMenu => NamedKey::ContextMenu,
+        Key::LeftArrow => NamedKey::ArrowLeft,
+        Key::RightArrow => NamedKey::ArrowRight,
+        Key::UpArrow => NamedKey::ArrowUp,
+        Key::DownArrow => NamedKey::ArrowDown,
+        Key::KeypadClear => NamedKey::Clear,
+        Key::CapsLock => NamedKey::CapsLock,
+        Key::ScrollLock => NamedKey::ScrollLock,
+        Key::NumLock => NamedKey::NumLock,
+        Key::Eisu => NamedKey::Eisu,
+        Key::Kana => NamedKey::KanaMode,
+        Key::Hangul => NamedKey::HangulMode,
+        Key::Hanja => NamedKey::HanjaMode,
+        Key::PlayPause => NamedKey::MediaPlayPause,
+        Key::NextTrack => NamedKey::MediaTrackNext,
+        Key::PreviousTrack => NamedKey::MediaTrackPrevious,
+        Key::VolumeUp => NamedKey::AudioVolumeUp,
+        Key::VolumeDown => NamedKey::AudioVolumeDown,
+        Key::VolumeMute => NamedKey::AudioVolumeMute,
+        Key::BrightnessUp => NamedKey::BrightnessUp,
+        Key::BrightnessDown => NamedKey::BrightnessDown,
+        Key::Other(code) => return Some(WinitKey::Unidentified(native_key(code))),
+        _ => return None,
+    };
+    Some(WinitKey::Named(named))
+}
+
+/// The character a key types on a US layout without modifiers
+fn us_character(key: Key) -> Option<char> {
+    let character = match key {
+        Key::A => 'a',
+        Key::B => 'b',
+        Key::C => 'c',
+        Key::D => 'd',
+        Key::E => 'e',
+        Key::F => 'f',
+        Key::G => 'g',
+        Key::H => 'h',
+        Key::I => 'i',
+        Key::J => 'j',
+        Key::K => 'k',
+        Key::L => 'l',
+        Key::M => 'm',
+        Key::N => 'n',
+        Key::O => 'o',
+        Key::P => 'p',
+        Key::Q => 'q',
+        Key::R => 'r',
+        Key::S => 's',
+        Key::T => 't',
+        Key::U => 'u',
+        Key::V => 'v',
+        Key::W => 'w',
+        Key::X => 'x',
+        Key::Y => 'y',
+        Key::Z => 'z',
+        Key::Num0 | Key::Keypad0 => '0',
+        Key::Num1 | Key::Keypad1 => '1',
+        Key::Num2 | Key::Keypad2 => '2',
+        Key::Num3 | Key::Keypad3 => '3',
+        Key::Num4 | Key::Keypad4 => '4',
+        Key::Num5 | Key::Keypad5 => '5',
+        Key::Num6 | Key::Keypad6 => '6',
+        Key::Num7 | Key::Keypad7 => '7',
+        Key::Num8 | Key::Keypad8 => '8',
+        Key::Num9 | Key::Keypad9 => '9',
+        Key::Minus | Key::KeypadMinus => '-',
+        Key::Equal | Key::KeypadEquals => '=',
+        Key::LeftBracket => '[',
+        Key::RightBracket => ']',
+        Key::Backslash => '\\',
+        Key::Semicolon => ';',
+        Key::Quote => '\'',
+        Key::Comma => ',',
+        Key::Period | Key::KeypadDecimal => '.',
+        Key::Slash | Key::KeypadDivide => '/',
+        Key::Grave => '`',
+        Key::KeypadMultiply => '*',
+        Key::KeypadPlus => '+',
+        Key::Yen => '¥',
+        Key::Section => '§',
+        _ => return None,
+    };
+    Some(character)
+}
+
+fn physical_key(key: Key) -> Option<PhysicalKey> {
+    let code = match key {
+        Key::A => KeyCode::KeyA,
+        Key::B => KeyCode::KeyB,
+        Key::C => KeyCode::KeyC,
+        Key::D => KeyCode::KeyD,
+        Key::E => KeyCode::KeyE,
+        Key::F => KeyCode::KeyF,
+        Key::G => KeyCode::KeyG,
+        Key::H => KeyCode::KeyH,
+        Key::I => KeyCode::KeyI,
+        Key::J => KeyCode::KeyJ,
+        Key::K => KeyCode::KeyK,
+        Key::L => KeyCode::KeyL,
+        Key::M => KeyCode::KeyM,
+        Key::N => KeyCode::KeyN,
+        Key::O => KeyCode::KeyO,
+        Key::P => KeyCode::KeyP,
+        Key::Q => KeyCode::KeyQ,
+        Key::R => KeyCode::KeyR,
+        Key::S => KeyCode::KeyS,
+        Key::T => KeyCode::KeyT,
+        Key::U => KeyCode::KeyU,
+        Key::V => KeyCode::KeyV,
+        Key::W => KeyCode::KeyW,
+        Key::X => KeyCode::KeyX,
+        Key::Y => KeyCode::KeyY,
+        Key::Z => KeyCode::KeyZ,
+        Key::Num0 => KeyCode::Digit0,
+        Key::Num1 => KeyCode::Digit1,
+        Key::Num2 => KeyCode::Digit2,
+        Key::Num3 => KeyCode::Digit3,
+        Key::Num4 => KeyCode::Digit4,
+        Key::Num5 => KeyCode::Digit5,
+        Key::Num6 => KeyCode::Digit6,
+        Key::Num7 => KeyCode::Digit7,
+        Key::Num8 => KeyCode::Digit8,
+        Key::Num9 => KeyCode::Digit9,
+        Key::F1 => KeyCode::F1,
+        Key::F2 => KeyCode::F2,
+        Key::F3 => KeyCode::F3,
+        Key::F4 => KeyCode::F4,
+        Key::F5 => KeyCode::F5,
+        Key::F6 => KeyCode::F6,
+        Key::F7 => KeyCode::F7,
+        Key::F8 => KeyCode::F8,
+        Key::F9 => KeyCode::F9,
+        Key::F10 => KeyCode::F10,
+        Key::F11 => KeyCode::F11,
+        Key::F12 => KeyCode::F12,
+        Key::F13 => KeyCode::F13,
+        Key::F14 => KeyCode::F14,
+        Key::F15 => KeyCode::F15,
+        Key::F16 => KeyCode::F16,
+        Key::F17 => KeyCode::F17,
+        Key::F18 => KeyCode::F18,
+        Key::F19 => KeyCode::F19,
+        Key::F20 => KeyCode::F20,
+        Key::Space => KeyCode::Space,
+        Key::Return => KeyCode::Enter,
+        Key::Tab => KeyCode::Tab,
+        Key::Escape => KeyCode::Escape,
+        Key::Delete => KeyCode::Backspace,
+        Key::ForwardDelete => KeyCode::Delete,
+        Key::Home => KeyCode::Home,
+        Key::End => KeyCode::End,
+        Key::PageUp => KeyCode::PageUp,
+        Key::PageDown => KeyCode::PageDown,
+        Key::PrintScreen => KeyCode::PrintScreen,
+        Key::Insert => KeyCode::Insert,
+        Key::Pause => KeyCode::Pause,
+        Key::ContextMenu => KeyCode::ContextMenu,
+        Key::LeftArrow => KeyCode::ArrowLeft,
+        Key::RightArrow => KeyCode::ArrowRight,
+        Key::UpArrow => KeyCode::ArrowUp,
+        Key::DownArrow => KeyCode::ArrowDown,
+        Key::Minus => KeyCode::Minus,
+        Key::Equal => KeyCode::Equal,
+        Key::LeftBracket => KeyCode::BracketLeft,
+        Key::RightBracket => KeyCode::BracketRight,
+        Key::Backslash => KeyCode::Backslash,
+        Key::Semicolon => KeyCode::Semicolon,
+        Key::Quote => KeyCode::Quote,
+        Key::Comma => KeyCode::Comma,
+        Key::Period => KeyCode::Period,
+        Key::Slash => KeyCode::Slash,
+        Key::Grave => KeyCode::Backquote,
+        Key::Keypad0 => KeyCode::Numpad0,
+        Key::Keypad1 => KeyCode::Numpad1,
+        Key::Keypad2 => KeyCode::Numpad2,
+        Key::Keypad3 => KeyCode::Numpad3,
+        Key::Keypad4 => KeyCode::Numpad4,
+        Key::Keypad5 => KeyCode::Numpad5,
+        Key::Keypad6 => KeyCode::Numpad6,
+        Key::Keypad7 => KeyCode::Numpad7,
+        Key::Keypad8 => KeyCode::Numpad8,
+        Key::Keypad9 => KeyCode::Numpad9,
+        Key::KeypadDecimal => KeyCode::NumpadDecimal,
+        Key::KeypadMultiply => KeyCode::NumpadMultiply,
+        Key::KeypadPlus => KeyCode::NumpadAdd,
+        Key::KeypadClear => KeyCode::NumpadClear,
+        Key::KeypadDivide => KeyCode::NumpadDivide,
+        Key::KeypadEnter => KeyCode::NumpadEnter,
+        Key::KeypadMinus => KeyCode::NumpadSubtract,
+        Key::KeypadEquals => KeyCode::NumpadEqual,
+        Key::CapsLock => KeyCode::CapsLock,
+        Key::ScrollLock => KeyCode::ScrollLock,
+        Key::NumLock => KeyCode::NumLock,
+        Key::Yen => KeyCode::IntlYen,
+        Key::Kana => KeyCode::Lang1,
+        Key::Eisu => KeyCode::Lang2,
+        Key::Section => KeyCode::IntlBackslash,
+        Key::Ro => KeyCode::IntlRo,
+        Key::Hangul => KeyCode::Lang1,
+        Key::Hanja => KeyCode::Lang2,
+        Key::PlayPause => KeyCode::MediaPlayPause,
+        Key::NextTrack => KeyCode::MediaTrackNext,
+        Key::PreviousTrack => KeyCode::MediaTrackPrevious,
+        Key::VolumeUp => KeyCode::AudioVolumeUp,
+        Key::VolumeDown => KeyCode::AudioVolumeDown,
+        Key::VolumeMute => KeyCode::AudioVolumeMute,
+        Key::Other(code) if cfg!(target_os = "macos") => {
+            return Some(PhysicalKey::Unidentified(NativeKeyCode::MacOS(code as u16)))
+        }
+        // Other platforms report a virtual key or an rdev code, not a scancode
+        Key::Other(_) => return Some(PhysicalKey::Unidentified(NativeKeyCode::Unidentified)),
+        _ => return None,
+    };
+    Some(PhysicalKey::Code(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(modifiers: Modifiers, key: Option<Key>, is_key_down: bool) -> KeyEvent {
+        KeyEvent {
+            modifiers,
+            key,
+            is_key_down,
+            changed_modifier: None,
+            sides: Modifiers::empty(),
+            hook: None,
+        }
+    }
+
+    #[test]
+    fn key_event_converts() {
+        let winit = event(Modifiers::CMD | Modifiers::SHIFT, Some(Key::K), true)
+            .to_winit()
+            .unwrap();
+        assert_eq!(winit.logical_key, WinitKey::Character("K".into()));
+        assert_eq!(winit.physical_key, PhysicalKey::Code(KeyCode::KeyK));
+        assert_eq!(winit.modifiers, ModifiersState::SUPER | ModifiersState::SHIFT);
+        assert_eq!(winit.state, ElementState::Pressed);
+
+        let winit = event(Modifiers::empty(), Some(Key::LeftArrow), false)
+            .to_winit()
+            .unwrap();
+        assert_eq!(winit.logical_key, WinitKey::Named(NamedKey::ArrowLeft));
+        assert_eq!(winit.state, ElementState::Released);
+    }
+
+    #[test]
+    fn modifier_event_converts() {
+        let mut modifier_event = event(Modifiers::CTRL, None, true);
+        modifier_event.changed_modifier = Some(Modifiers::CTRL);
+        let winit = modifier_event.to_winit().unwrap();
+        assert_eq!(winit.logical_key, WinitKey::Named(NamedKey::Control));
+        assert_eq!(winit.physical_key, PhysicalKey::Code(KeyCode::ControlLeft));
+    }
+
+    #[test]
+    fn mouse_buttons_dont_convert() {
+        assert!(event(Modifiers::CMD, Some(Key::MouseLeft), true)
+            .to_winit()
+            .is_none());
+    }
+}